@@ -29,6 +29,76 @@ impl EventRecord {
     }
 }
 
+/// An [EventRecord] which owns its user data and extended data, so that it
+/// can outlive the trace callback and be sent to other threads.
+pub struct OwnedEventRecord {
+    record: EventRecord,
+    _user_data: Vec<u8>,
+    _extended_data: Vec<Etw::EVENT_HEADER_EXTENDED_DATA_ITEM>,
+    _extended_data_buffers: Vec<Vec<u8>>,
+}
+
+// The pointers in `record` only point into the heap buffers owned by this struct.
+unsafe impl Send for OwnedEventRecord {}
+
+impl OwnedEventRecord {
+    pub fn new(event: &EventRecord) -> Self {
+        let user_data = event.user_buffer().to_vec();
+        let extended_data_items: &[Etw::EVENT_HEADER_EXTENDED_DATA_ITEM] =
+            if event.ExtendedData.is_null() {
+                &[]
+            } else {
+                unsafe {
+                    std::slice::from_raw_parts(event.ExtendedData, event.ExtendedDataCount.into())
+                }
+            };
+        let extended_data_buffers: Vec<Vec<u8>> = extended_data_items
+            .iter()
+            .map(|item| {
+                if item.DataPtr == 0 {
+                    return Vec::new();
+                }
+                unsafe {
+                    std::slice::from_raw_parts(item.DataPtr as *const u8, item.DataSize.into())
+                }
+                .to_vec()
+            })
+            .collect();
+        let mut extended_data = extended_data_items.to_vec();
+        for (item, buffer) in extended_data.iter_mut().zip(&extended_data_buffers) {
+            item.DataPtr = buffer.as_ptr() as u64;
+        }
+
+        let mut record = event.0;
+        record.UserContext = std::ptr::null_mut();
+        record.UserData = if user_data.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            user_data.as_ptr() as *mut _
+        };
+        record.ExtendedData = if extended_data.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            extended_data.as_mut_ptr()
+        };
+
+        OwnedEventRecord {
+            record: EventRecord(record),
+            _user_data: user_data,
+            _extended_data: extended_data,
+            _extended_data_buffers: extended_data_buffers,
+        }
+    }
+}
+
+impl Deref for OwnedEventRecord {
+    type Target = EventRecord;
+
+    fn deref(&self) -> &Self::Target {
+        &self.record
+    }
+}
+
 /// Newtype wrapper over an [EVENT_PROPERTY_INFO]
 ///
 /// [EVENT_PROPERTY_INFO]: https://microsoft.github.io/windows-docs-rs/doc/bindings/Windows/Win32/Etw/struct.EVENT_PROPERTY_INFO.html
@@ -10,19 +10,28 @@ use windows::Win32::System::Diagnostics::Etw::{
 
 use crate::parser::{Parser, ParserError, TryParse};
 use crate::schema::SchemaLocator;
-use crate::tdh_types::{PrimitiveDesc, PropertyDesc, TdhInType};
+use crate::tdh_types::{
+    PrimitiveDesc, PropertyDesc, PropertyFlags, PropertyLength, StructDesc, TdhInType,
+};
 use crate::traits::EncodeUtf16;
 
 #[macro_use]
 extern crate memoffset;
 
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::hash::BuildHasherDefault;
 use std::mem;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::ControlFlow;
 use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use etw_types::EventRecord;
+use etw_types::{EventRecord, OwnedEventRecord};
 use fxhash::FxHasher;
 use tdh_types::{Property, TdhOutType};
 use windows::Win32::System::Diagnostics::Etw;
@@ -72,6 +81,67 @@ impl std::ops::DerefMut for EventTraceLogfile {
     }
 }
 
+/// Information from the header of a trace file which is needed to interpret
+/// the raw event timestamps.
+///
+/// Trace files are opened with `PROCESS_TRACE_MODE_RAW_TIMESTAMP`, so
+/// `EventHeader.TimeStamp` contains a value in the units of the trace's clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    /// The clock used for the event timestamps: 1 for QPC, 2 for the system
+    /// time, 3 for the CPU cycle counter.
+    pub clock_type: u32,
+    /// The QPC frequency, in ticks per second.
+    pub perf_freq: u64,
+    /// The CPU speed, used for the cycle counter clock.
+    pub cpu_speed_mhz: u32,
+    /// The time at which the system was booted, as a FILETIME, i.e. in 100ns
+    /// units since January 1, 1601 (UTC).
+    pub boot_time: i64,
+}
+
+impl TraceContext {
+    const CLOCK_TYPE_SYSTEM_TIME: u32 = 2;
+    const CLOCK_TYPE_CPU_CYCLE_COUNTER: u32 = 3;
+
+    /// The FILETIME of the Unix epoch.
+    const UNIX_EPOCH_AS_FILETIME: i64 = 116_444_736_000_000_000;
+
+    fn from_logfile_header(header: &Etw::TRACE_LOGFILE_HEADER) -> Self {
+        TraceContext {
+            clock_type: header.ReservedFlags,
+            perf_freq: header.PerfFreq as u64,
+            cpu_speed_mhz: unsafe { header.Anonymous2.Anonymous.CpuSpeedInMHz },
+            boot_time: header.BootTime,
+        }
+    }
+
+    /// Converts a raw event timestamp into nanoseconds since the system was booted.
+    pub fn timestamp_to_nanos(&self, raw: i64) -> u64 {
+        let nanos: i128 = match self.clock_type {
+            Self::CLOCK_TYPE_SYSTEM_TIME => (raw as i128 - self.boot_time as i128) * 100,
+            Self::CLOCK_TYPE_CPU_CYCLE_COUNTER if self.cpu_speed_mhz != 0 => {
+                raw as i128 * 1000 / self.cpu_speed_mhz as i128
+            }
+            _ if self.perf_freq != 0 => raw as i128 * 1_000_000_000 / self.perf_freq as i128,
+            _ => 0,
+        };
+        u64::try_from(nanos).unwrap_or(0)
+    }
+
+    /// Converts a raw event timestamp into wall-clock time.
+    pub fn timestamp_to_system_time(&self, raw: i64) -> SystemTime {
+        let boot_time_nanos_since_unix_epoch =
+            (self.boot_time as i128 - Self::UNIX_EPOCH_AS_FILETIME as i128) * 100;
+        let boot_time = if boot_time_nanos_since_unix_epoch >= 0 {
+            UNIX_EPOCH + Duration::from_nanos(boot_time_nanos_since_unix_epoch as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_nanos((-boot_time_nanos_since_unix_epoch) as u64)
+        };
+        boot_time + Duration::from_nanos(self.timestamp_to_nanos(raw))
+    }
+}
+
 unsafe extern "system" fn trace_callback_thunk(event_record: *mut Etw::EVENT_RECORD) {
     let f: &mut &mut dyn FnMut(&EventRecord) = &mut *((*event_record).UserContext
         as *mut &mut dyn for<'a> std::ops::FnMut(&'a etw_types::EventRecord));
@@ -81,6 +151,30 @@ unsafe extern "system" fn trace_callback_thunk(event_record: *mut Etw::EVENT_REC
 pub fn open_trace<F: FnMut(&EventRecord)>(
     path: &Path,
     mut callback: F,
+) -> Result<(), std::io::Error> {
+    process_trace_file(path, &mut |e: &EventRecord, _: &TraceContext| {
+        callback(e);
+        ControlFlow::Continue(())
+    })
+}
+
+/// Like [`open_trace`], but also passes the [`TraceContext`] of the trace file
+/// to the callback, which can be used to convert the event timestamps.
+pub fn open_trace_with_context<F: FnMut(&EventRecord, &TraceContext)>(
+    path: &Path,
+    mut callback: F,
+) -> Result<(), std::io::Error> {
+    process_trace_file(path, &mut |e: &EventRecord, context: &TraceContext| {
+        callback(e, context);
+        ControlFlow::Continue(())
+    })
+}
+
+/// Calls `callback` for each event in the trace file at `path`, until the
+/// callback returns [`ControlFlow::Break`] or the end of the file is reached.
+fn process_trace_file(
+    path: &Path,
+    callback: &mut dyn FnMut(&EventRecord, &TraceContext) -> ControlFlow<()>,
 ) -> Result<(), std::io::Error> {
     let mut log_file = EventTraceLogfile::default();
 
@@ -91,17 +185,136 @@ pub fn open_trace<F: FnMut(&EventRecord)>(
     log_file.0.LogFileName = PWSTR(path.as_ptr() as *mut _);
     log_file.0.Anonymous1.ProcessTraceMode =
         Etw::PROCESS_TRACE_MODE_EVENT_RECORD | Etw::PROCESS_TRACE_MODE_RAW_TIMESTAMP;
-    let mut cb: &mut dyn FnMut(&EventRecord) = &mut callback;
+
+    // ProcessTrace keeps delivering the events which are already buffered after
+    // CloseTrace has been called, so we need to ignore those ourselves.
+    let session_handle = Cell::new(None);
+    let trace_context = Cell::new(None);
+    let stopped = Cell::new(false);
+    let mut stoppable_callback = |e: &EventRecord| {
+        if stopped.get() {
+            return;
+        }
+        let Some(context) = trace_context.get() else {
+            return;
+        };
+        if callback(e, &context).is_break() {
+            stopped.set(true);
+            if let Some(handle) = session_handle.get() {
+                unsafe {
+                    let _ = Etw::CloseTrace(handle);
+                }
+            }
+        }
+    };
+    let mut cb: &mut dyn FnMut(&EventRecord) = &mut stoppable_callback;
     log_file.0.Context = unsafe { std::mem::transmute(&mut cb) };
     log_file.0.Anonymous2.EventRecordCallback = Some(trace_callback_thunk);
 
-    let session_handle = unsafe { Etw::OpenTraceW(&mut *log_file) };
-    let result = unsafe { Etw::ProcessTrace(&[session_handle], None, None) };
+    let handle = unsafe { Etw::OpenTraceW(&mut *log_file) };
+    if handle.Value == INVALID_TRACE_HANDLE {
+        return Err(std::io::Error::last_os_error());
+    }
+    // OpenTrace has filled in the header.
+    trace_context.set(Some(TraceContext::from_logfile_header(
+        &log_file.0.LogfileHeader,
+    )));
+    session_handle.set(Some(handle));
+    let result = unsafe { Etw::ProcessTrace(&[handle], None, None) };
+    if stopped.get() {
+        // ProcessTrace reports an error if the trace was closed early.
+        return Ok(());
+    }
+    unsafe {
+        let _ = Etw::CloseTrace(handle);
+    }
     result
         .ok()
         .map_err(|e| std::io::Error::from_raw_os_error(e.code().0))
 }
 
+/// An iterator over the events in a trace file.
+///
+/// The file is processed on a separate thread, which is stopped when the
+/// `TraceReader` is dropped, so it's fine to stop iterating early.
+///
+/// # Example
+/// ```no_run
+/// use std::path::Path;
+/// use etw_reader::TraceReader;
+///
+/// let first_events: Vec<_> = TraceReader::open(Path::new("trace.etl"))
+///     .take(10)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// ```
+pub struct TraceReader {
+    receiver: Receiver<Result<OwnedEventRecord, std::io::Error>>,
+    context: Arc<OnceLock<TraceContext>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TraceReader {
+    /// The number of events which can be buffered before the processing
+    /// thread waits for the consumer.
+    const CHANNEL_CAPACITY: usize = 1024;
+
+    /// Opens the trace file at `path` and starts processing it.
+    ///
+    /// Errors, e.g. if the file can't be opened, are returned by the iterator.
+    pub fn open(path: &Path) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(Self::CHANNEL_CAPACITY);
+        let context = Arc::new(OnceLock::new());
+        let thread_context = context.clone();
+        let path = path.to_owned();
+        let thread = std::thread::spawn(move || {
+            let result =
+                process_trace_file(&path, &mut |e: &EventRecord, context: &TraceContext| {
+                    thread_context.get_or_init(|| *context);
+                    match sender.send(Ok(OwnedEventRecord::new(e))) {
+                        Ok(()) => ControlFlow::Continue(()),
+                        // The TraceReader was dropped.
+                        Err(_) => ControlFlow::Break(()),
+                    }
+                });
+            if let Err(e) = result {
+                let _ = sender.send(Err(e));
+            }
+        });
+        TraceReader {
+            receiver,
+            context,
+            thread: Some(thread),
+        }
+    }
+
+    /// Returns the [`TraceContext`] of the trace file, which can be used to
+    /// convert the event timestamps. This is available once the first event
+    /// has been returned.
+    pub fn context(&self) -> Option<TraceContext> {
+        self.context.get().copied()
+    }
+}
+
+impl Iterator for TraceReader {
+    type Item = Result<OwnedEventRecord, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for TraceReader {
+    fn drop(&mut self) {
+        // Disconnect the channel so that the processing thread stops at the next event.
+        let (_, disconnected_receiver) = mpsc::sync_channel(0);
+        drop(std::mem::replace(&mut self.receiver, disconnected_receiver));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
 /// Complete Trace Properties struct
 ///
 /// The [EventTraceProperties] struct contains the information about a tracing session, this struct
@@ -413,69 +626,17 @@ pub fn write_property(
             .unwrap();
         }
     } else {
-        let value = match &property.desc {
-            PropertyDesc::Primitive(desc) => {
-                // XXX: we should be using the out_type here instead of in_type
-                match desc.in_type {
-                    TdhInType::InTypeUnicodeString => {
-                        TryParse::<String>::try_parse(parser, &property.name)
-                    }
-                    TdhInType::InTypeAnsiString => {
-                        TryParse::<String>::try_parse(parser, &property.name)
-                    }
-                    TdhInType::InTypeBoolean => {
-                        TryParse::<bool>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeHexInt32 => {
-                        TryParse::<i32>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeUInt32 => {
-                        TryParse::<u32>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeUInt16 => {
-                        TryParse::<u16>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeUInt8 => {
-                        TryParse::<u8>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeInt8 => {
-                        TryParse::<i8>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeInt64 => {
-                        TryParse::<i64>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeUInt64 => {
-                        let i = TryParse::<u64>::try_parse(parser, &property.name);
-                        if desc.out_type == TdhOutType::OutTypeHexInt64 {
-                            i.map(|x| format!("0x{:x}", x))
-                        } else {
-                            i.map(|x| x.to_string())
-                        }
-                    }
-                    TdhInType::InTypeHexInt64 => {
-                        let i = TryParse::<i64>::try_parse(parser, &property.name);
-                        i.map(|x| format!("0x{:x}", x))
-                    }
-                    TdhInType::InTypePointer | TdhInType::InTypeSizeT => {
-                        TryParse::<u64>::try_parse(parser, &property.name)
-                            .map(|x| format!("0x{:x}", x))
-                    }
-                    TdhInType::InTypeGuid => TryParse::<GUID>::try_parse(parser, &property.name)
-                        .map(|x| format!("{:?}", x)),
-                    TdhInType::InTypeInt32 => {
-                        TryParse::<i32>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    TdhInType::InTypeFloat => {
-                        TryParse::<f32>::try_parse(parser, &property.name).map(|x| x.to_string())
-                    }
-                    _ => Ok(format!("Unknown {:?} -> {:?}", desc.in_type, desc.out_type)),
-                }
-            }
-            PropertyDesc::Struct(desc) => Ok(format!(
-                "unhandled struct {} {}",
-                desc.start_index, desc.num_members
-            )),
-        };
+        let value = TryParse::<Vec<u8>>::try_parse(parser, &property.name).and_then(|buffer| {
+            let mut remaining = &buffer[..];
+            format_value(
+                &|index| parser.property_at_index(index),
+                property,
+                &mut remaining,
+                parser.pointer_size(),
+                true,
+                0,
+            )
+        });
         let value = match value {
             Ok(value) => value,
             Err(ParserError::InvalidType) => format!("invalid type {:?}", property.desc),
@@ -491,6 +652,286 @@ pub fn write_property(
     }
 }
 
+/// Formats the value of `property` at the start of `buffer`, and advances
+/// `buffer` past it. Arrays are formatted as a comma-separated list, e.g. `[1, 2, 3]`.
+///
+/// `is_top_level` is true if `buffer` contains exactly this property's data, as
+/// determined by the parser. In that case, the buffer size is used for values whose
+/// size or element count is stored in another property.
+///
+/// `depth` is the number of structs that this property is nested in.
+fn format_value(
+    property_at_index: &dyn Fn(u32) -> Property,
+    property: &Property,
+    buffer: &mut &[u8],
+    pointer_size: usize,
+    is_top_level: bool,
+    depth: u32,
+) -> Result<String, ParserError> {
+    let is_array = property.flags.intersects(
+        PropertyFlags::PROPERTY_PARAM_COUNT | PropertyFlags::PROPERTY_PARAM_FIXED_COUNT,
+    ) || property.count > 1;
+
+    if !is_array {
+        if let (PropertyDesc::Primitive(desc), true) = (&property.desc, is_top_level) {
+            let value = format_primitive(desc, buffer)?;
+            *buffer = &[];
+            return Ok(value);
+        }
+        return format_element(property_at_index, property, buffer, pointer_size, depth);
+    }
+
+    let mut elements = Vec::new();
+    if property.flags.contains(PropertyFlags::PROPERTY_PARAM_COUNT) {
+        // The element count is stored in another property, and `property.count`
+        // is the index of that property.
+        if !is_top_level {
+            return Err(ParserError::InvalidType);
+        }
+        while !buffer.is_empty() {
+            elements.push(format_element(
+                property_at_index,
+                property,
+                buffer,
+                pointer_size,
+                depth,
+            )?);
+        }
+    } else {
+        for _ in 0..property.count {
+            elements.push(format_element(
+                property_at_index,
+                property,
+                buffer,
+                pointer_size,
+                depth,
+            )?);
+        }
+    }
+    Ok(format!("[{}]", elements.join(", ")))
+}
+
+/// Formats a single element of `property` at the start of `buffer`, and advances
+/// `buffer` past it.
+fn format_element(
+    property_at_index: &dyn Fn(u32) -> Property,
+    property: &Property,
+    buffer: &mut &[u8],
+    pointer_size: usize,
+    depth: u32,
+) -> Result<String, ParserError> {
+    match &property.desc {
+        PropertyDesc::Primitive(desc) => {
+            let size = primitive_size(desc, property.length, buffer, pointer_size)
+                .ok_or(ParserError::InvalidType)?;
+            if size == 0 || buffer.len() < size {
+                return Err(ParserError::LengthMismatch);
+            }
+            let (value_buffer, rest) = buffer.split_at(size);
+            *buffer = rest;
+            format_primitive(desc, value_buffer)
+        }
+        PropertyDesc::Struct(desc) => {
+            format_struct(property_at_index, desc, buffer, pointer_size, depth)
+        }
+    }
+}
+
+/// The maximum nesting depth of struct-typed properties. Schemas come from the
+/// trace, so a struct member may refer back to the struct itself.
+const MAX_STRUCT_DEPTH: u32 = 32;
+
+/// Formats the members of a struct-typed property, e.g. `{ X: 1, Y: 2 }`.
+///
+/// The member properties are found at `desc.start_index..desc.start_index + desc.num_members`
+/// in the event's property array, and `property_at_index` is used to look them up.
+/// Members are read from the start of `buffer`, and `buffer` is advanced past them.
+///
+/// Returns [`ParserError::InvalidType`] if structs are nested more than
+/// [`MAX_STRUCT_DEPTH`] levels deep, which happens if a struct contains itself.
+fn format_struct(
+    property_at_index: &dyn Fn(u32) -> Property,
+    desc: &StructDesc,
+    buffer: &mut &[u8],
+    pointer_size: usize,
+    depth: u32,
+) -> Result<String, ParserError> {
+    if depth >= MAX_STRUCT_DEPTH {
+        return Err(ParserError::InvalidType);
+    }
+    let mut members = Vec::with_capacity(desc.num_members as usize);
+    for i in 0..desc.num_members {
+        let member = property_at_index(u32::from(desc.start_index) + u32::from(i));
+        let value = format_value(
+            property_at_index,
+            &member,
+            buffer,
+            pointer_size,
+            false,
+            depth + 1,
+        )?;
+        members.push(format!("{}: {}", member.name, value));
+    }
+    Ok(format!("{{ {} }}", members.join(", ")))
+}
+
+/// Returns the number of bytes that a primitive value of this type occupies
+/// at the start of `buffer`, or `None` if the size can't be determined.
+fn primitive_size(
+    desc: &PrimitiveDesc,
+    length: PropertyLength,
+    buffer: &[u8],
+    pointer_size: usize,
+) -> Option<usize> {
+    use TdhInType::*;
+    match desc.in_type {
+        InTypeInt8 | InTypeUInt8 | InTypeAnsiChar => Some(1),
+        InTypeInt16 | InTypeUInt16 | InTypeUnicodeChar => Some(2),
+        InTypeInt32 | InTypeUInt32 | InTypeHexInt32 | InTypeFloat | InTypeBoolean => Some(4),
+        InTypeInt64 | InTypeUInt64 | InTypeHexInt64 | InTypeDouble | InTypeFileTime => Some(8),
+        InTypeGuid | InTypeSystemTime => Some(16),
+        InTypePointer | InTypeSizeT => Some(pointer_size),
+        // The last string in an event may be missing its null terminator.
+        InTypeUnicodeString => {
+            Some(utils::parse_unk_size_null_unicode_size(buffer).min(buffer.len()))
+        }
+        InTypeAnsiString => Some(utils::parse_unk_size_null_ansi_size(buffer).min(buffer.len())),
+        // Revision, sub-authority count, 6-byte authority, then the sub-authorities.
+        InTypeSid => buffer.get(1).map(|&count| 8 + 4 * count as usize),
+        _ => match length {
+            PropertyLength::Length(length) if length > 0 => Some(length as usize),
+            _ => None,
+        },
+    }
+}
+
+fn bytes<const N: usize>(buffer: &[u8]) -> Result<[u8; N], ParserError> {
+    buffer.try_into().map_err(|_| ParserError::LengthMismatch)
+}
+
+/// Formats a primitive value, taking both the in_type (how the value is stored)
+/// and the out_type (how the value should be displayed) into account.
+fn format_primitive(desc: &PrimitiveDesc, buffer: &[u8]) -> Result<String, ParserError> {
+    if let Some((bits, signed)) = read_integer(desc.in_type, buffer)? {
+        return Ok(format_integer(desc, bits, signed, buffer));
+    }
+
+    let value = match desc.in_type {
+        TdhInType::InTypeUnicodeString => utils::parse_null_utf16_string(buffer),
+        TdhInType::InTypeAnsiString => String::from_utf8(buffer.to_vec())?
+            .trim_matches(char::default())
+            .to_string(),
+        TdhInType::InTypeGuid => {
+            let guid: [u8; 16] = bytes(buffer)?;
+            format!(
+                "{:?}",
+                GUID::from_values(
+                    u32::from_ne_bytes(bytes(&guid[0..4])?),
+                    u16::from_ne_bytes(bytes(&guid[4..6])?),
+                    u16::from_ne_bytes(bytes(&guid[6..8])?),
+                    bytes(&guid[8..16])?,
+                )
+            )
+        }
+        TdhInType::InTypeFloat => f32::from_ne_bytes(bytes(buffer)?).to_string(),
+        TdhInType::InTypeDouble => f64::from_ne_bytes(bytes(buffer)?).to_string(),
+        TdhInType::InTypeBinary if desc.out_type == TdhOutType::OutTypeIpv6 => {
+            Ipv6Addr::from(bytes::<16>(buffer)?).to_string()
+        }
+        TdhInType::InTypeBinary => buffer.iter().map(|b| format!("{:02x}", b)).collect(),
+        TdhInType::InTypeSid => format_sid(buffer)?,
+        _ => format!("Unknown {:?} -> {:?}", desc.in_type, desc.out_type),
+    };
+    Ok(value)
+}
+
+/// Formats a binary SID in its string form, e.g. `S-1-5-32-544`.
+fn format_sid(buffer: &[u8]) -> Result<String, ParserError> {
+    if buffer.len() < 8 {
+        return Err(ParserError::LengthMismatch);
+    }
+    let revision = buffer[0];
+    let sub_authority_count = buffer[1] as usize;
+    let authority = buffer[2..8]
+        .iter()
+        .fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+    let sub_authorities = buffer[8..].chunks_exact(4);
+    if sub_authorities.len() != sub_authority_count || !sub_authorities.remainder().is_empty() {
+        return Err(ParserError::LengthMismatch);
+    }
+    let mut sid = format!("S-{}-{}", revision, authority);
+    for sub_authority in sub_authorities {
+        sid.push_str(&format!("-{}", u32::from_le_bytes(bytes(sub_authority)?)));
+    }
+    Ok(sid)
+}
+
+/// Reads an integer-typed value. Returns the value's bits zero-extended to
+/// 64 bits, and, for signed types, the sign-extended value.
+fn read_integer(
+    in_type: TdhInType,
+    buffer: &[u8],
+) -> Result<Option<(u64, Option<i64>)>, ParserError> {
+    use TdhInType::*;
+    let integer = match in_type {
+        InTypeInt8 => {
+            let v = i8::from_ne_bytes(bytes(buffer)?);
+            (v as u8 as u64, Some(v as i64))
+        }
+        InTypeUInt8 => (u8::from_ne_bytes(bytes(buffer)?) as u64, None),
+        InTypeInt16 => {
+            let v = i16::from_ne_bytes(bytes(buffer)?);
+            (v as u16 as u64, Some(v as i64))
+        }
+        InTypeUInt16 => (u16::from_ne_bytes(bytes(buffer)?) as u64, None),
+        InTypeInt32 => {
+            let v = i32::from_ne_bytes(bytes(buffer)?);
+            (v as u32 as u64, Some(v as i64))
+        }
+        InTypeUInt32 | InTypeHexInt32 | InTypeBoolean => {
+            (u32::from_ne_bytes(bytes(buffer)?) as u64, None)
+        }
+        InTypeInt64 => {
+            let v = i64::from_ne_bytes(bytes(buffer)?);
+            (v as u64, Some(v))
+        }
+        InTypeUInt64 | InTypeHexInt64 => (u64::from_ne_bytes(bytes(buffer)?), None),
+        InTypePointer | InTypeSizeT => match buffer.len() {
+            4 => (u32::from_ne_bytes(bytes(buffer)?) as u64, None),
+            _ => (u64::from_ne_bytes(bytes(buffer)?), None),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(integer))
+}
+
+fn format_integer(desc: &PrimitiveDesc, bits: u64, signed: Option<i64>, buffer: &[u8]) -> String {
+    use TdhOutType::*;
+    match desc.out_type {
+        OutTypeHexInt8 | OutTypeHexInt16 | OutTypeHexInt32 | OutTypeHexInt64
+        | OutTypeCodePointer | OutTypeWin32Error | OutTypeNtStatus | OutTypeHResult => {
+            format!("0x{:x}", bits)
+        }
+        OutTypeBoolean => (bits != 0).to_string(),
+        // IPv4 addresses and ports are stored in network byte order.
+        OutTypeIpv4 if buffer.len() == 4 => {
+            Ipv4Addr::new(buffer[0], buffer[1], buffer[2], buffer[3]).to_string()
+        }
+        OutTypePort if buffer.len() == 2 => u16::from_be_bytes([buffer[0], buffer[1]]).to_string(),
+        _ => match desc.in_type {
+            TdhInType::InTypeHexInt32
+            | TdhInType::InTypeHexInt64
+            | TdhInType::InTypePointer
+            | TdhInType::InTypeSizeT => format!("0x{:x}", bits),
+            TdhInType::InTypeBoolean => (bits != 0).to_string(),
+            _ => match signed {
+                Some(signed) => signed.to_string(),
+                None => bits.to_string(),
+            },
+        },
+    }
+}
+
 pub fn print_property(parser: &mut Parser, property: &Property, write_types: bool) {
     let mut result = String::new();
     write_property(&mut result, parser, property, write_types);
@@ -624,3 +1065,285 @@ pub fn get_provider_info(guid: &GUID) -> Vec<u8> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::EventSchema;
+
+    fn make_property(name: &str, desc: PropertyDesc) -> Property {
+        Property {
+            name: name.to_owned(),
+            flags: PropertyFlags::empty(),
+            length: PropertyLength::Length(0),
+            desc,
+            map_info: None,
+            count: 1,
+        }
+    }
+
+    fn primitive(in_type: TdhInType, out_type: TdhOutType) -> PropertyDesc {
+        PropertyDesc::Primitive(PrimitiveDesc { in_type, out_type })
+    }
+
+    #[test]
+    fn nested_struct() {
+        // Property 0 is the top-level struct { Id, Position { X, Y }, Address }.
+        let properties = [
+            make_property(
+                "Outer",
+                PropertyDesc::Struct(StructDesc {
+                    start_index: 1,
+                    num_members: 3,
+                }),
+            ),
+            make_property(
+                "Id",
+                primitive(TdhInType::InTypeUInt16, TdhOutType::OutTypeUInt16),
+            ),
+            make_property(
+                "Position",
+                PropertyDesc::Struct(StructDesc {
+                    start_index: 4,
+                    num_members: 2,
+                }),
+            ),
+            make_property(
+                "Address",
+                primitive(TdhInType::InTypePointer, TdhOutType::OutTypeHexInt64),
+            ),
+            make_property(
+                "X",
+                primitive(TdhInType::InTypeInt32, TdhOutType::OutTypeInt32),
+            ),
+            make_property(
+                "Y",
+                primitive(TdhInType::InTypeInt32, TdhOutType::OutTypeInt32),
+            ),
+        ];
+        let mut data = Vec::new();
+        data.extend_from_slice(&7u16.to_ne_bytes());
+        data.extend_from_slice(&(-3i32).to_ne_bytes());
+        data.extend_from_slice(&12i32.to_ne_bytes());
+        data.extend_from_slice(&0x1234u64.to_ne_bytes());
+        data.extend_from_slice(b"trailing");
+
+        let PropertyDesc::Struct(desc) = &properties[0].desc else {
+            unreachable!()
+        };
+        let mut remaining = &data[..];
+        let formatted = format_struct(
+            &|index| properties[index as usize].clone(),
+            desc,
+            &mut remaining,
+            8,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            formatted,
+            "{ Id: 7, Position: { X: -3, Y: 12 }, Address: 0x1234 }"
+        );
+        assert_eq!(remaining, b"trailing");
+
+        // Not enough data for the last member.
+        let mut remaining = &data[..12];
+        assert!(matches!(
+            format_struct(
+                &|index| properties[index as usize].clone(),
+                desc,
+                &mut remaining,
+                8,
+                0
+            ),
+            Err(ParserError::LengthMismatch)
+        ));
+    }
+
+    #[test]
+    fn self_referencing_struct() {
+        // The only member of the struct is the struct itself.
+        let properties = [make_property(
+            "Outer",
+            PropertyDesc::Struct(StructDesc {
+                start_index: 0,
+                num_members: 1,
+            }),
+        )];
+        let PropertyDesc::Struct(desc) = &properties[0].desc else {
+            unreachable!()
+        };
+        let mut remaining = &[0u8; 8][..];
+        assert!(matches!(
+            format_struct(
+                &|index| properties[index as usize].clone(),
+                desc,
+                &mut remaining,
+                8,
+                0
+            ),
+            Err(ParserError::InvalidType)
+        ));
+    }
+
+    #[test]
+    fn custom_schema_struct() {
+        // Format the properties of the ImageID event as the members of a struct.
+        let schema = custom_schemas::ImageID {};
+        let desc = StructDesc {
+            start_index: 0,
+            num_members: schema.property_count() as u16,
+        };
+        let mut data = Vec::new();
+        data.extend_from_slice(&0x7ff6_1234_0000u64.to_ne_bytes()); // ImageBase
+        data.extend_from_slice(&0x5000u32.to_ne_bytes()); // ImageSize
+        data.extend_from_slice(&0u64.to_ne_bytes()); // Unknown
+        data.extend_from_slice(&0x6543_2100u32.to_ne_bytes()); // TimeDateStamp
+        for c in "firefox.exe\0".encode_utf16() {
+            data.extend_from_slice(&c.to_ne_bytes()); // OriginalFileName
+        }
+        data.extend_from_slice(b"trailing");
+
+        let mut remaining = &data[..];
+        let formatted =
+            format_struct(&|index| schema.property(index), &desc, &mut remaining, 8, 0).unwrap();
+        assert_eq!(
+            formatted,
+            "{ ImageBase: 0x7ff612340000, ImageSize: 20480, Unknown: 0x0, TimeDateStamp: 1698898176, OriginalFileName: firefox.exe }"
+        );
+        assert_eq!(remaining, b"trailing");
+    }
+
+    #[test]
+    fn out_types() {
+        let format = |in_type, out_type, buffer: &[u8]| {
+            format_primitive(&PrimitiveDesc { in_type, out_type }, buffer).unwrap()
+        };
+        use TdhInType::*;
+        use TdhOutType::*;
+        assert_eq!(
+            format(InTypeUInt32, OutTypeNull, &255u32.to_ne_bytes()),
+            "255"
+        );
+        assert_eq!(
+            format(InTypeUInt32, OutTypeHexInt32, &255u32.to_ne_bytes()),
+            "0xff"
+        );
+        assert_eq!(
+            format(InTypeHexInt32, OutTypeNull, &255u32.to_ne_bytes()),
+            "0xff"
+        );
+        assert_eq!(
+            format(InTypeInt32, OutTypeNull, &(-1i32).to_ne_bytes()),
+            "-1"
+        );
+        assert_eq!(
+            format(InTypeInt32, OutTypeHexInt32, &(-1i32).to_ne_bytes()),
+            "0xffffffff"
+        );
+        assert_eq!(
+            format(InTypeUInt32, OutTypeIpv4, &[192, 168, 0, 1]),
+            "192.168.0.1"
+        );
+        assert_eq!(format(InTypeUInt16, OutTypePort, &[0x1f, 0x90]), "8080");
+        assert_eq!(format(InTypeUInt8, OutTypeBoolean, &[1]), "true");
+        assert_eq!(
+            format(InTypeUInt32, OutTypeBoolean, &0u32.to_ne_bytes()),
+            "false"
+        );
+        assert_eq!(
+            format(InTypeBoolean, OutTypeNull, &1u32.to_ne_bytes()),
+            "true"
+        );
+        assert_eq!(
+            format(InTypeUInt64, OutTypeHexInt64, &0x1234u64.to_ne_bytes()),
+            "0x1234"
+        );
+        assert_eq!(
+            format(InTypePointer, OutTypeNull, &0x10u32.to_ne_bytes()),
+            "0x10"
+        );
+        let mut ipv6 = [0u8; 16];
+        ipv6[15] = 1;
+        assert_eq!(format(InTypeBinary, OutTypeIpv6, &ipv6), "::1");
+    }
+
+    #[test]
+    fn binary_and_arrays() {
+        let format = |in_type, out_type, buffer: &[u8]| {
+            format_primitive(&PrimitiveDesc { in_type, out_type }, buffer).unwrap()
+        };
+        assert_eq!(
+            format(
+                TdhInType::InTypeBinary,
+                TdhOutType::OutTypeHexBinary,
+                &[0xde, 0xad, 0x01]
+            ),
+            "dead01"
+        );
+        let sid = [1, 2, 0, 0, 0, 0, 0, 5, 0x20, 0, 0, 0, 0x20, 2, 0, 0];
+        assert_eq!(
+            format(TdhInType::InTypeSid, TdhOutType::OutTypeNull, &sid),
+            "S-1-5-32-544"
+        );
+
+        let no_properties = |_| unreachable!();
+
+        // A fixed-count array inside a struct only consumes its own elements.
+        let mut fixed = make_property(
+            "Values",
+            primitive(TdhInType::InTypeUInt16, TdhOutType::OutTypeUInt16),
+        );
+        fixed.flags = PropertyFlags::PROPERTY_PARAM_FIXED_COUNT;
+        fixed.count = 2;
+        let data = [1, 0, 2, 0, 3, 0];
+        let mut remaining = &data[..];
+        let value = format_value(&no_properties, &fixed, &mut remaining, 8, false, 0).unwrap();
+        assert_eq!(value, "[1, 2]");
+        assert_eq!(remaining, &[3, 0]);
+
+        // A top-level array whose count is stored in another property uses the whole buffer.
+        let mut counted = make_property(
+            "Names",
+            primitive(TdhInType::InTypeAnsiString, TdhOutType::OutTypeString),
+        );
+        counted.flags = PropertyFlags::PROPERTY_PARAM_COUNT;
+        counted.count = 0;
+        let data = b"ab\0cde\0";
+        let mut remaining = &data[..];
+        let value = format_value(&no_properties, &counted, &mut remaining, 8, true, 0).unwrap();
+        assert_eq!(value, "[ab, cde]");
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn timestamp_conversion() {
+        // 2024-01-01T00:00:00Z as a FILETIME.
+        let boot_time = TraceContext::UNIX_EPOCH_AS_FILETIME + 1_704_067_200 * 10_000_000;
+        let qpc = TraceContext {
+            clock_type: 1,
+            perf_freq: 10_000_000,
+            cpu_speed_mhz: 0,
+            boot_time,
+        };
+        assert_eq!(qpc.timestamp_to_nanos(25_000_000), 2_500_000_000);
+        assert_eq!(qpc.timestamp_to_nanos(-5), 0);
+        assert_eq!(
+            qpc.timestamp_to_system_time(25_000_000),
+            UNIX_EPOCH + Duration::from_nanos(1_704_067_202_500_000_000)
+        );
+
+        let system_time = TraceContext {
+            clock_type: 2,
+            ..qpc
+        };
+        assert_eq!(system_time.timestamp_to_nanos(boot_time + 30), 3000);
+
+        let cycles = TraceContext {
+            clock_type: 3,
+            cpu_speed_mhz: 2000,
+            ..qpc
+        };
+        assert_eq!(cycles.timestamp_to_nanos(4000), 2000);
+    }
+}
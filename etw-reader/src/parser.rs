@@ -171,6 +171,24 @@ impl<'a> Parser<'a> {
         )?)
     }*/
 
+    /// Returns the property at `index` in the event's full property array.
+    ///
+    /// Unlike the properties that are looked up by name, this also includes
+    /// the members of struct-typed properties, see
+    /// [`StructDesc`](super::tdh_types::StructDesc).
+    pub fn property_at_index(&self, index: u32) -> Property {
+        self.event.property(index)
+    }
+
+    /// Returns the size of a pointer in this event, in bytes.
+    pub fn pointer_size(&self) -> usize {
+        if (self.event.event_flags() & EVENT_HEADER_FLAG_32_BIT_HEADER) != 0 {
+            4
+        } else {
+            8
+        }
+    }
+
     // TODO: Find a cleaner way to do this, not very happy with it rn
     fn find_property_size(&self, property: &Property) -> ParserResult<usize> {
         match property.length {
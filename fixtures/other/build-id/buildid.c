@@ -0,0 +1,15 @@
+// Built with:
+//   gcc -O1 -g -fdebug-prefix-map=$PWD=. -Wl,--build-id buildid.c -o buildid.full
+//   objcopy --only-keep-debug buildid.full <build-id>.debug
+//   strip --strip-all buildid.full -o buildid
+//   rm buildid.full
+#include <stdio.h>
+
+__attribute__((noinline)) static int triple(int x) {
+  return x * 3;
+}
+
+int main(int argc, char** argv) {
+  printf("%d\n", triple(argc));
+  return 0;
+}
@@ -0,0 +1,20 @@
+; Built with:
+;   llc -O1 -function-sections -mtriple=x86_64-pc-windows-msvc -filetype=obj -o example-coff.obj example-coff.ll
+target triple = "x86_64-pc-windows-msvc"
+
+define internal i32 @internal_helper(i32 %x) noinline {
+  %a = mul i32 %x, 3
+  %b = add i32 %a, 1
+  ret i32 %b
+}
+
+define i32 @"?compute@@YAHH@Z"(i32 %x) noinline {
+  %a = call i32 @internal_helper(i32 %x)
+  %b = xor i32 %a, 85
+  ret i32 %b
+}
+
+define i32 @main() {
+  %r = call i32 @"?compute@@YAHH@Z"(i32 7)
+  ret i32 %r
+}
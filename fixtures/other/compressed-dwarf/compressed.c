@@ -0,0 +1,20 @@
+// Built with:
+//   gcc -g -O1 -fdebug-prefix-map=$PWD=. compressed.c -o compressed
+//   objcopy --compress-debug-sections=zstd compressed compressed-zstd
+//   objcopy --compress-debug-sections=zlib-gnu compressed compressed-zdebug
+//   rm compressed
+#include <stdio.h>
+
+static inline __attribute__((always_inline)) int add_one(int x) {
+  return x + 1;
+}
+
+__attribute__((noinline)) int compute(int x) {
+  int y = add_one(x);
+  printf("%d\n", y);
+  return y;
+}
+
+int main(int argc, char** argv) {
+  return compute(argc);
+}
@@ -0,0 +1,18 @@
+// Built with:
+//   gcc -O1 -g -fno-asynchronous-unwind-tables -fno-unwind-tables debugframe.c -o debugframe.full
+//   objcopy --strip-all --keep-section=.debug_frame debugframe.full debugframe
+//   rm debugframe.full
+#include <stdio.h>
+
+__attribute__((noinline)) static int square(int x) {
+  return x * x;
+}
+
+__attribute__((noinline)) static int sum_of_squares(int a, int b) {
+  return square(a) + square(b);
+}
+
+int main(int argc, char** argv) {
+  printf("%d\n", sum_of_squares(argc, argc + 1));
+  return 0;
+}
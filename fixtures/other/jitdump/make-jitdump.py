@@ -0,0 +1,56 @@
+#!/usr/bin/env python3
+# Writes jit-1234.dump, a little-endian jitdump file with two functions:
+# "JS:add", which has a JIT_CODE_DEBUG_INFO record with line information,
+# and "JS:noDebugInfo", which doesn't.
+# The format is described in tools/perf/Documentation/jitdump-specification.txt
+# in the Linux kernel source.
+import struct
+
+PID = 1234
+TIMESTAMP = 1000
+EM_X86_64 = 62
+
+JIT_CODE_LOAD = 0
+JIT_CODE_DEBUG_INFO = 2
+
+
+def record(record_id, timestamp, body):
+    return struct.pack("<IIQ", record_id, 16 + len(body), timestamp) + body
+
+
+def code_load(timestamp, code_addr, code_index, name, code_size):
+    body = struct.pack("<IIQQQQ", PID, PID, code_addr, code_addr, code_size, code_index)
+    body += name.encode() + b"\0"
+    body += b"\x90" * code_size  # nop
+    return record(JIT_CODE_LOAD, timestamp, body)
+
+
+def debug_info(timestamp, code_addr, entries):
+    body = struct.pack("<QQ", code_addr, len(entries))
+    for addr, line, column, file_path in entries:
+        body += struct.pack("<QII", addr, line, column) + file_path.encode() + b"\0"
+    return record(JIT_CODE_DEBUG_INFO, timestamp, body)
+
+
+header = b"DTiJ" + struct.pack("<IIIIIQQ", 1, 40, EM_X86_64, 0, PID, TIMESTAMP, 0)
+
+add_addr = 0x7F0000001000
+records = [
+    # The debug info record comes right before the code load record it describes.
+    debug_info(
+        TIMESTAMP + 1,
+        add_addr,
+        [
+            (add_addr, 10, 1, "/home/user/app/add.js"),
+            (add_addr + 0x10, 11, 5, "/home/user/app/add.js"),
+            (add_addr + 0x30, 12, 3, "/home/user/app/add.js"),
+        ],
+    ),
+    code_load(TIMESTAMP + 1, add_addr, 0, "JS:add", 0x40),
+    code_load(TIMESTAMP + 2, 0x7F0000002000, 1, "JS:noDebugInfo", 0x20),
+]
+
+with open("jit-1234.dump", "wb") as f:
+    f.write(header)
+    for r in records:
+        f.write(r)
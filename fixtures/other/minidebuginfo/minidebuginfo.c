@@ -0,0 +1,15 @@
+// Built with: gcc -O1 -g -shared -fPIC -o libminidebuginfo.so minidebuginfo.c
+// Then stripped, with a .gnu_debugdata section added as described in
+// https://sourceware.org/gdb/current/onlinedocs/gdb.html/MiniDebugInfo.html
+
+__attribute__((noinline)) static int internal_helper(int x) {
+  return x * 3 + 1;
+}
+
+__attribute__((noinline)) static int another_internal_helper(int x) {
+  return internal_helper(x) ^ 0x55;
+}
+
+int exported_function(int x) {
+  return another_internal_helper(x) + 2;
+}
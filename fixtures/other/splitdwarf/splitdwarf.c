@@ -0,0 +1,15 @@
+#include <stdio.h>
+
+static inline __attribute__((always_inline)) int add_one(int x) {
+  return x + 1;
+}
+
+__attribute__((noinline)) int compute(int x) {
+  int y = add_one(x);
+  printf("%d\n", y);
+  return y;
+}
+
+int main(int argc, char** argv) {
+  return compute(argc);
+}
@@ -0,0 +1,28 @@
+// There is no Swift toolchain in the environment this fixture was created
+// in, so this C file defines functions whose symbol names are the ones swiftc
+// emits for the following main.swift on Linux:
+//
+//   class Foo {
+//     var count: Int = 0
+//     func bar() {}
+//   }
+//   func add(_ a: Int, _ b: Int) -> Int { a + b }
+//
+// Built with:
+//   gcc -O1 swift-symbols.c -o swift-symbols
+__attribute__((used, noinline)) void foo_bar(void) __asm__("$s4main3FooC3baryyF");
+void foo_bar(void) {}
+
+__attribute__((used, noinline)) long foo_count_getter(void) __asm__("$s4main3FooC5countSivg");
+long foo_count_getter(void) {
+  return 0;
+}
+
+__attribute__((used, noinline)) long add(long a, long b) __asm__("$s4main3addyS2i_SitF");
+long add(long a, long b) {
+  return a + b;
+}
+
+int main(void) {
+  return 0;
+}
@@ -21,7 +21,7 @@ impl Serialize for CategoryHandle {
 
 /// A profiling subcategory, can be set on stack frames and markers as part of a [`CategoryPairHandle`].
 ///
-/// Subategories can be created with [`Profile::add_subcategory`](crate::Profile::add_subcategory).
+/// Subcategories can be created with [`Profile::add_subcategory`](crate::Profile::add_subcategory).
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct SubcategoryIndex(pub u8);
 
@@ -42,7 +42,7 @@ impl From<CategoryHandle> for CategoryPairHandle {
 }
 
 /// The information about a category.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Category {
     pub name: String,
     pub color: CategoryColor,
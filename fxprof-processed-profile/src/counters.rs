@@ -6,7 +6,7 @@ use crate::{ProcessHandle, Timestamp};
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct CounterHandle(pub(crate) usize);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Counter {
     name: String,
     category: String,
@@ -34,6 +34,11 @@ impl Counter {
         }
     }
 
+    pub fn set_process(&mut self, process: ProcessHandle, pid: &str) {
+        self.process = process;
+        self.pid = pid.to_owned();
+    }
+
     pub fn process(&self) -> ProcessHandle {
         self.process
     }
@@ -75,7 +80,7 @@ impl Serialize for SerializableCounter<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct CounterSamples {
     time: Vec<Timestamp>,
     number: Vec<u32>,
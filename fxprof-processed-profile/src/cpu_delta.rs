@@ -45,6 +45,48 @@ impl CpuDelta {
         }
     }
 
+    /// Create a CPU delta from a CPU utilization fraction over a time interval.
+    ///
+    /// This is useful if the data source reports how busy the thread was, for
+    /// example 0.25 for 25% CPU usage, rather than an absolute amount of CPU
+    /// time. `interval` is usually the time since the thread's previous sample.
+    ///
+    /// The result is rounded to the nearest microsecond, because CPU deltas are
+    /// serialized in microseconds (`threadCPUDelta` uses the unit "µs").
+    /// The fraction is clamped to the range `0.0..=1.0`, since a single thread
+    /// can't use more than one CPU at a time; NaN is treated as zero.
+    ///
+    /// ```
+    /// use fxprof_processed_profile::CpuDelta;
+    /// use std::time::Duration;
+    ///
+    /// let delta = CpuDelta::from_fraction_of_interval(0.25, Duration::from_millis(1));
+    /// assert_eq!(delta, CpuDelta::from_micros(250));
+    /// ```
+    pub fn from_fraction_of_interval(fraction: f64, interval: Duration) -> Self {
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        Self {
+            micros: (interval.as_secs_f64() * 1_000_000.0 * fraction).round() as u64,
+        }
+    }
+
+    /// Returns this CPU delta, or `max` if this CPU delta is larger.
+    ///
+    /// ```
+    /// use fxprof_processed_profile::CpuDelta;
+    ///
+    /// let max = CpuDelta::from_micros(1000);
+    /// assert_eq!(CpuDelta::from_micros(1500).clamp_to(max), max);
+    /// assert_eq!(CpuDelta::from_micros(500).clamp_to(max), CpuDelta::from_micros(500));
+    /// ```
+    pub fn clamp_to(self, max: CpuDelta) -> Self {
+        self.min(max)
+    }
+
     /// Whether the CPU delta is zero.
     pub fn is_zero(&self) -> bool {
         self.micros == 0
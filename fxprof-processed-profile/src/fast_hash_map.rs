@@ -3,4 +3,11 @@ use std::hash::BuildHasherDefault;
 
 use fxhash::FxHasher;
 
+/// A hash map with a fast, non-cryptographic hasher.
+///
+/// The hasher is unseeded, so the iteration order is deterministic for the same
+/// sequence of insertions. Code which affects the serialized profile should
+/// still not depend on the iteration order, so that the output is reproducible
+/// across hasher changes. For example, symbolication sorts the frames by
+/// frame index before updating the frame table.
 pub type FastHashMap<K, V> = HashMap<K, V, BuildHasherDefault<FxHasher>>;
@@ -59,6 +59,28 @@ pub struct FrameInfo {
     pub category_pair: CategoryPairHandle,
     /// The flags of this frame. Use `FrameFlags::empty()` if unsure.
     pub flags: FrameFlags,
+    /// The implementation of this frame, for runtimes which execute code in
+    /// multiple tiers, for example `"interpreter"`, `"baseline"` or `"ion"`
+    /// for JavaScript. The Firefox Profiler can break down the call tree by
+    /// implementation. Use `None` if unsure.
+    pub implementation: Option<StringHandle>,
+    /// The source file and line of this frame, if known. Frames with a file
+    /// get a `fileName` in the func table, so the Firefox Profiler can show
+    /// the source view for them. Use `SourceLocation::default()` if unsure.
+    pub source_location: SourceLocation,
+}
+
+/// The location of a stack frame in the source code.
+///
+/// Symbolication replaces this information for frames with an address in a
+/// library, using the outer function of the address's symbol info.
+#[derive(Debug, Clone, Copy, Default, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    /// The path of the source file, as a string returned by
+    /// [`Profile::handle_for_file_path`](crate::Profile::handle_for_file_path).
+    pub file: Option<StringHandle>,
+    /// The 1-based line number within the file.
+    pub line: Option<u32>,
 }
 
 bitflags! {
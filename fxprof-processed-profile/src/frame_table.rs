@@ -10,6 +10,7 @@ use crate::global_lib_table::{GlobalLibIndex, GlobalLibTable};
 use crate::native_symbols::{NativeSymbolIndex, NativeSymbols};
 use crate::resource_table::ResourceTable;
 use crate::serialization_helpers::SerializableSingleValueColumn;
+use crate::symbol_info::LibSymbolInfo;
 use crate::thread_string_table::{ThreadInternalStringIndex, ThreadStringTable};
 
 #[derive(Debug, Clone, Default)]
@@ -18,6 +19,8 @@ pub struct FrameTable {
     categories: Vec<CategoryHandle>,
     subcategories: Vec<Subcategory>,
     funcs: Vec<FuncIndex>,
+    lines: Vec<Option<u32>>,
+    implementations: Vec<Option<ThreadInternalStringIndex>>,
     native_symbols: Vec<Option<NativeSymbolIndex>>,
     internal_frame_to_frame_index: FastHashMap<InternalFrame, usize>,
 }
@@ -27,6 +30,14 @@ impl FrameTable {
         Default::default()
     }
 
+    pub fn len(&self) -> usize {
+        self.funcs.len()
+    }
+
+    pub fn uses_frame_implementation(&self) -> bool {
+        self.implementations.iter().any(Option::is_some)
+    }
+
     pub fn index_for_frame(
         &mut self,
         string_table: &mut ThreadStringTable,
@@ -38,6 +49,8 @@ impl FrameTable {
     ) -> usize {
         let addresses = &mut self.addresses;
         let funcs = &mut self.funcs;
+        let lines = &mut self.lines;
+        let implementations = &mut self.implementations;
         let native_symbols = &mut self.native_symbols;
         let categories = &mut self.categories;
         let subcategories = &mut self.subcategories;
@@ -84,8 +97,12 @@ impl FrameTable {
                     }
                     InternalFrameLocation::Label(string_index) => (None, string_index, None, None),
                 };
-                let func_index =
-                    func_table.index_for_func(location_string_index, resource, frame.flags);
+                let func_index = func_table.index_for_func(
+                    location_string_index,
+                    frame.file,
+                    resource,
+                    frame.flags,
+                );
                 let CategoryPairHandle(category, subcategory_index) = frame.category_pair;
                 let subcategory = match subcategory_index {
                     Some(index) => Subcategory::Normal(index),
@@ -95,6 +112,8 @@ impl FrameTable {
                 categories.push(category);
                 subcategories.push(subcategory);
                 funcs.push(func_index);
+                lines.push(frame.line);
+                implementations.push(frame.implementation);
                 native_symbols.push(native_symbol);
                 frame_index
             })
@@ -109,6 +128,48 @@ impl FrameTable {
         CategoryPairHandle(category, subcategory)
     }
 
+    /// Replaces the func and line of every frame whose (lib, relative address)
+    /// has an entry in `lib_symbols`. The outer function of the address info
+    /// is used; inlined calls are not expanded into separate frames.
+    pub fn symbolicate(
+        &mut self,
+        string_table: &mut ThreadStringTable,
+        func_table: &mut FuncTable,
+        lib_symbols: &FastHashMap<GlobalLibIndex, &LibSymbolInfo>,
+    ) {
+        // Visit the frames in frame index order, so that the new strings and
+        // funcs are added in a deterministic order.
+        let mut frames: Vec<_> = self.internal_frame_to_frame_index.iter().collect();
+        frames.sort_unstable_by_key(|&(_, &frame_index)| frame_index);
+        for (frame, &frame_index) in frames {
+            let InternalFrameLocation::AddressInLib(address, lib_index) = frame.location else {
+                continue;
+            };
+            let Some(address_info) = lib_symbols
+                .get(&lib_index)
+                .and_then(|lib_symbol_info| lib_symbol_info.address_infos.get(&address))
+            else {
+                continue;
+            };
+            let outer_frame = address_info.frames.last();
+            let name = outer_frame
+                .and_then(|frame| frame.function_name.as_deref())
+                .unwrap_or(&address_info.symbol_name);
+            let name = string_table.index_for_string(name);
+            let file = outer_frame
+                .and_then(|frame| frame.file_path.as_deref())
+                .map(|file| string_table.index_for_string(file));
+            let old_func = self.funcs[frame_index];
+            self.funcs[frame_index] = func_table.index_for_func(
+                name,
+                file,
+                func_table.get_resource(old_func),
+                func_table.get_flags(old_func),
+            );
+            self.lines[frame_index] = outer_frame.and_then(|frame| frame.line_number);
+        }
+    }
+
     pub fn as_serializable<'a>(&'a self, categories: &'a [Category]) -> impl Serialize + 'a {
         SerializableFrameTable {
             table: self,
@@ -140,8 +201,8 @@ impl Serialize for SerializableFrameTable<'_> {
         map.serialize_entry("func", &self.table.funcs)?;
         map.serialize_entry("nativeSymbol", &self.table.native_symbols)?;
         map.serialize_entry("innerWindowID", &SerializableSingleValueColumn((), len))?;
-        map.serialize_entry("implementation", &SerializableSingleValueColumn((), len))?;
-        map.serialize_entry("line", &SerializableSingleValueColumn((), len))?;
+        map.serialize_entry("implementation", &self.table.implementations)?;
+        map.serialize_entry("line", &self.table.lines)?;
         map.serialize_entry("column", &SerializableSingleValueColumn((), len))?;
         map.end()
     }
@@ -167,6 +228,9 @@ pub struct InternalFrame {
     pub location: InternalFrameLocation,
     pub category_pair: CategoryPairHandle,
     pub flags: FrameFlags,
+    pub implementation: Option<ThreadInternalStringIndex>,
+    pub file: Option<ThreadInternalStringIndex>,
+    pub line: Option<u32>,
 }
 
 #[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
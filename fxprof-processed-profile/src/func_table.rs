@@ -9,10 +9,10 @@ use crate::thread_string_table::ThreadInternalStringIndex;
 #[derive(Debug, Clone, Default)]
 pub struct FuncTable {
     names: Vec<ThreadInternalStringIndex>,
+    files: Vec<Option<ThreadInternalStringIndex>>,
     resources: Vec<Option<ResourceIndex>>,
     flags: Vec<FrameFlags>,
-    func_name_and_resource_and_flags_to_func_index:
-        FastHashMap<(ThreadInternalStringIndex, Option<ResourceIndex>, FrameFlags), usize>,
+    func_key_to_func_index: FastHashMap<FuncKey, usize>,
     contains_js_function: bool,
 }
 
@@ -24,15 +24,22 @@ impl FuncTable {
     pub fn index_for_func(
         &mut self,
         name: ThreadInternalStringIndex,
+        file: Option<ThreadInternalStringIndex>,
         resource: Option<ResourceIndex>,
         flags: FrameFlags,
     ) -> FuncIndex {
         let func_index = *self
-            .func_name_and_resource_and_flags_to_func_index
-            .entry((name, resource, flags))
+            .func_key_to_func_index
+            .entry(FuncKey {
+                name,
+                file,
+                resource,
+                flags,
+            })
             .or_insert_with(|| {
                 let func_index = self.names.len();
                 self.names.push(name);
+                self.files.push(file);
                 self.resources.push(resource);
                 self.flags.push(flags);
                 func_index
@@ -43,11 +50,27 @@ impl FuncTable {
         FuncIndex(func_index as u32)
     }
 
+    pub fn get_resource(&self, func_index: FuncIndex) -> Option<ResourceIndex> {
+        self.resources[func_index.0 as usize]
+    }
+
+    pub fn get_flags(&self, func_index: FuncIndex) -> FrameFlags {
+        self.flags[func_index.0 as usize]
+    }
+
     pub fn contains_js_function(&self) -> bool {
         self.contains_js_function
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FuncKey {
+    name: ThreadInternalStringIndex,
+    file: Option<ThreadInternalStringIndex>,
+    resource: Option<ResourceIndex>,
+    flags: FrameFlags,
+}
+
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct FuncIndex(u32);
 
@@ -75,7 +98,7 @@ impl Serialize for FuncTable {
             "resource",
             &SerializableFuncTableResourceColumn(&self.resources),
         )?;
-        map.serialize_entry("fileName", &SerializableSingleValueColumn((), len))?;
+        map.serialize_entry("fileName", &self.files)?;
         map.serialize_entry("lineNumber", &SerializableSingleValueColumn((), len))?;
         map.serialize_entry("columnNumber", &SerializableSingleValueColumn((), len))?;
         map.end()
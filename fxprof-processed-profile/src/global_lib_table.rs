@@ -1,12 +1,31 @@
 use std::collections::BTreeSet;
 use std::sync::Arc;
 
+use debugid::DebugId;
 use serde::ser::{Serialize, Serializer};
 
 use crate::fast_hash_map::FastHashMap;
 use crate::{LibraryInfo, SymbolTable};
 
-#[derive(Debug)]
+/// How [`Profile::add_lib`](crate::Profile::add_lib) decides whether two
+/// libraries are the same library.
+///
+/// Used with [`Profile::set_lib_deduplication_policy`](crate::Profile::set_lib_deduplication_policy).
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub enum LibDeduplicationPolicy {
+    /// Libraries are only merged if all their [`LibraryInfo`] fields match.
+    /// This is the default.
+    ExactMatch,
+    /// Libraries with the same `debug_id` and `code_id` are merged, even if
+    /// other fields, such as their paths, differ. The first added library's
+    /// information is used for the merged library. This is useful if the same
+    /// library is loaded via different paths, for example through symlinks.
+    ///
+    /// Libraries with a nil `debug_id` are only merged on an exact match.
+    DebugIdAndCodeId,
+}
+
+#[derive(Debug, Clone)]
 pub struct GlobalLibTable {
     /// All libraries added via `Profile::add_lib`. May or may not be used.
     /// Indexed by `LibraryHandle.0`.
@@ -14,6 +33,9 @@ pub struct GlobalLibTable {
     /// Indexed by `GlobalLibIndex.0`.
     used_libs: Vec<LibraryHandle>, // append-only for stable GlobalLibIndexes
     lib_map: FastHashMap<LibraryInfo, LibraryHandle>,
+    /// The first library added for each (debug ID, code ID) pair.
+    lib_id_map: FastHashMap<(DebugId, Option<String>), LibraryHandle>,
+    dedup_policy: LibDeduplicationPolicy,
     used_lib_map: FastHashMap<LibraryHandle, GlobalLibIndex>,
     /// We keep track of RVA addresses that exist in frames that are assigned to this
     /// library, so that we can potentially provide symbolication info ahead of time.
@@ -29,18 +51,40 @@ impl GlobalLibTable {
             all_libs: Vec::new(),
             used_libs: Vec::new(),
             lib_map: FastHashMap::default(),
+            lib_id_map: FastHashMap::default(),
+            dedup_policy: LibDeduplicationPolicy::ExactMatch,
             used_lib_map: FastHashMap::default(),
             used_libs_seen_rvas: Vec::new(),
         }
     }
 
+    pub fn set_dedup_policy(&mut self, policy: LibDeduplicationPolicy) {
+        self.dedup_policy = policy;
+    }
+
     pub fn handle_for_lib(&mut self, lib: LibraryInfo) -> LibraryHandle {
-        let all_libs = &mut self.all_libs;
-        *self.lib_map.entry(lib.clone()).or_insert_with(|| {
-            let handle = LibraryHandle(all_libs.len());
-            all_libs.push(lib);
-            handle
-        })
+        if let Some(handle) = self.lib_map.get(&lib) {
+            return *handle;
+        }
+        // A nil debug ID means that the library's identity is unknown, so
+        // such libraries are only ever merged on an exact match.
+        let id_key = (!lib.debug_id.is_nil()).then(|| (lib.debug_id, lib.code_id.clone()));
+        if self.dedup_policy == LibDeduplicationPolicy::DebugIdAndCodeId {
+            if let Some(handle) = id_key.as_ref().and_then(|key| self.lib_id_map.get(key)) {
+                let handle = *handle;
+                self.lib_map.insert(lib, handle);
+                return handle;
+            }
+        }
+        let handle = LibraryHandle(self.all_libs.len());
+        self.all_libs.push(lib.clone());
+        self.lib_map.insert(lib, handle);
+        if let Some(id_key) = id_key {
+            // Keep the first library for each ID, so that a policy change
+            // also merges with libraries which were added earlier.
+            self.lib_id_map.entry(id_key).or_insert(handle);
+        }
+        handle
     }
 
     pub fn set_lib_symbol_table(&mut self, library: LibraryHandle, symbol_table: Arc<SymbolTable>) {
@@ -62,6 +106,13 @@ impl GlobalLibTable {
         self.all_libs.get(handle.0)
     }
 
+    pub fn used_libs(&self) -> impl Iterator<Item = (GlobalLibIndex, &LibraryInfo)> + '_ {
+        self.used_libs
+            .iter()
+            .enumerate()
+            .map(|(index, handle)| (GlobalLibIndex(index), &self.all_libs[handle.0]))
+    }
+
     pub fn add_lib_used_rva(&mut self, index: GlobalLibIndex, address: u32) {
         self.used_libs_seen_rvas[index.0].insert(address);
     }
@@ -2,6 +2,9 @@
 //! the [Firefox Profiler](https://profiler.firefox.com/).
 //!
 //! Specifically, this uses the ["Processed profile format"](https://github.com/firefox-devtools/profiler/blob/main/docs-developer/processed-profile-format.md).
+//! The generated JSON declares `meta.preprocessedProfileVersion` 49. The Firefox
+//! Profiler upgrades profiles with older format versions when it loads them, so
+//! newer versions of the profiler can load these profiles as well.
 //!
 //! Use [`Profile::new`] to create a new [`Profile`] object. Then add all the
 //! information into it. To convert it to JSON, use [`serde_json`], for
@@ -10,7 +13,7 @@
 //! ## Example
 //!
 //! ```
-//! use fxprof_processed_profile::{Profile, CategoryHandle, CpuDelta, Frame, FrameInfo, FrameFlags, SamplingInterval, Timestamp};
+//! use fxprof_processed_profile::{Profile, CategoryHandle, CpuDelta, Frame, FrameInfo, FrameFlags, SamplingInterval, SourceLocation, Timestamp};
 //! use std::time::SystemTime;
 //!
 //! # fn write_profile(output_file: std::fs::File) -> Result<(), Box<dyn std::error::Error>> {
@@ -19,8 +22,8 @@
 //! let thread = profile.add_thread(process, 54132000, Timestamp::from_millis_since_reference(0.0), true);
 //! profile.set_thread_name(thread, "Main thread");
 //! let stack_frames = vec![
-//!     FrameInfo { frame: Frame::Label(profile.intern_string("Root node")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty() },
-//!     FrameInfo { frame: Frame::Label(profile.intern_string("First callee")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty() }
+//!     FrameInfo { frame: Frame::Label(profile.intern_string("Root node")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty(), implementation: None, source_location: SourceLocation::default() },
+//!     FrameInfo { frame: Frame::Label(profile.intern_string("First callee")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty(), implementation: None, source_location: SourceLocation::default() }
 //! ];
 //! let stack = profile.intern_stack_frames(thread, stack_frames.into_iter());
 //! profile.add_sample(thread, Timestamp::from_millis_since_reference(0.0), stack, CpuDelta::ZERO, 1);
@@ -55,6 +58,7 @@ mod sample_table;
 mod serialization_helpers;
 mod stack_table;
 mod string_table;
+mod symbol_info;
 mod thread;
 mod thread_string_table;
 mod timestamp;
@@ -63,8 +67,8 @@ pub use category::{CategoryHandle, CategoryPairHandle};
 pub use category_color::CategoryColor;
 pub use counters::CounterHandle;
 pub use cpu_delta::CpuDelta;
-pub use frame::{Frame, FrameFlags, FrameInfo};
-pub use global_lib_table::{LibraryHandle, UsedLibraryAddressesIterator};
+pub use frame::{Frame, FrameFlags, FrameInfo, SourceLocation};
+pub use global_lib_table::{LibDeduplicationPolicy, LibraryHandle, UsedLibraryAddressesIterator};
 pub use lib_mappings::LibMappings;
 pub use library_info::{LibraryInfo, Symbol, SymbolTable};
 pub use markers::{
@@ -73,7 +77,11 @@ pub use markers::{
     StaticSchemaMarker,
 };
 pub use process::ThreadHandle;
-pub use profile::{FrameHandle, Profile, SamplingInterval, StackHandle, StringHandle};
+pub use profile::{
+    FrameHandle, InvalidHandleError, OutOfOrderSampleError, OutOfOrderSamplePolicy, Profile,
+    SamplingInterval, StackHandle, StringHandle, Track,
+};
 pub use reference_timestamp::ReferenceTimestamp;
+pub use symbol_info::{AddressInfo, FrameSymbolInfo, LibSymbolInfo, ProfileSymbolInfo};
 pub use thread::ProcessHandle;
 pub use timestamp::*;
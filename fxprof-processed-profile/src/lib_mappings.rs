@@ -13,7 +13,8 @@ use std::collections::BTreeMap;
 pub struct LibMappings<T> {
     /// A BTreeMap of non-overlapping Mappings. The key is the start_avma of the mapping.
     ///
-    /// When a new mapping is added, overlapping mappings are removed.
+    /// When a new mapping is added, overlapping mappings are trimmed or split so that
+    /// they no longer overlap with the new mapping.
     map: BTreeMap<u64, Mapping<T>>,
 }
 
@@ -32,7 +33,9 @@ impl<T> LibMappings<T> {
     }
 
     /// Add a mapping to this address space. Any existing mappings which overlap with the
-    /// new mapping are removed.
+    /// new mapping are trimmed to the parts outside of the new mapping's address range.
+    /// If an existing mapping fully covers the new mapping, it is split into two parts.
+    /// Existing mappings which are fully covered by the new mapping are removed.
     ///
     /// `start_avma` and `end_avma` describe the address range that this mapping
     /// occupies.
@@ -58,32 +61,72 @@ impl<T> LibMappings<T> {
         end_avma: u64,
         relative_address_at_start: u32,
         value: T,
-    ) {
+    ) where
+        T: Clone,
+    {
+        self.remove_range(start_avma, end_avma);
+        self.map.insert(
+            start_avma,
+            Mapping {
+                start_avma,
+                end_avma,
+                relative_address_at_start,
+                value,
+            },
+        );
+    }
+
+    /// Remove the address range `start_avma..end_avma` from this address space.
+    ///
+    /// Mappings which are fully inside the range are removed. Mappings which
+    /// partially overlap with the range are trimmed to the parts outside of the
+    /// range, and a mapping which covers the entire range is split into two parts.
+    /// Empty and inverted ranges don't remove anything.
+    pub fn remove_range(&mut self, start_avma: u64, end_avma: u64)
+    where
+        T: Clone,
+    {
+        if start_avma >= end_avma {
+            return;
+        }
         let removal_avma_range_start =
             if let Some(mapping_overlapping_with_start_avma) = self.lookup_impl(start_avma) {
                 mapping_overlapping_with_start_avma.start_avma
             } else {
                 start_avma
             };
-        // self.map.drain(removal_avma_range_start..end_avma);
         let overlapping_keys: Vec<u64> = self
             .map
             .range(removal_avma_range_start..end_avma)
             .map(|(start_avma, _)| *start_avma)
             .collect();
         for key in overlapping_keys {
-            self.map.remove(&key);
+            let mapping = self.map.remove(&key).unwrap();
+            if mapping.end_avma > end_avma {
+                // Keep the part of the old mapping which is after the removed range.
+                let offset_from_mapping_start = (end_avma - mapping.start_avma) as u32;
+                self.map.insert(
+                    end_avma,
+                    Mapping {
+                        start_avma: end_avma,
+                        end_avma: mapping.end_avma,
+                        relative_address_at_start: mapping.relative_address_at_start
+                            + offset_from_mapping_start,
+                        value: mapping.value.clone(),
+                    },
+                );
+            }
+            if mapping.start_avma < start_avma {
+                // Keep the part of the old mapping which is before the removed range.
+                self.map.insert(
+                    mapping.start_avma,
+                    Mapping {
+                        end_avma: start_avma,
+                        ..mapping
+                    },
+                );
+            }
         }
-
-        self.map.insert(
-            start_avma,
-            Mapping {
-                start_avma,
-                end_avma,
-                relative_address_at_start,
-                value,
-            },
-        );
     }
 
     /// Remove a mapping which starts at the given address. If found, this returns
@@ -94,6 +137,23 @@ impl<T> LibMappings<T> {
             .map(|m| (m.relative_address_at_start, m.value))
     }
 
+    /// Add all mappings from `other` to this address space, as if they were
+    /// added with [`LibMappings::add_mapping`]. Where they overlap with existing
+    /// mappings, the mappings from `other` take precedence.
+    pub fn add_mappings_from(&mut self, other: LibMappings<T>)
+    where
+        T: Clone,
+    {
+        for mapping in other.map.into_values() {
+            self.add_mapping(
+                mapping.start_avma,
+                mapping.end_avma,
+                mapping.relative_address_at_start,
+                mapping.value,
+            );
+        }
+    }
+
     /// Clear all mappings.
     pub fn clear(&mut self) {
         self.map.clear();
@@ -147,15 +207,60 @@ mod test {
         assert_eq!(m.lookup(200), Some(&"200..250"));
         m.add_mapping(180, 220, 180, "180..220");
         assert_eq!(m.lookup(200), Some(&"180..220"));
-        assert_eq!(m.lookup(170), None);
-        assert_eq!(m.lookup(220), None);
+        assert_eq!(m.lookup(170), Some(&"100..200"));
+        assert_eq!(m.lookup(220), Some(&"200..250"));
         m.add_mapping(225, 250, 225, "225..250");
         m.add_mapping(255, 270, 255, "255..270");
         m.add_mapping(100, 150, 100, "100..150");
         assert_eq!(m.lookup(90), None);
-        assert_eq!(m.lookup(150), None);
         assert_eq!(m.lookup(149), Some(&"100..150"));
+        assert_eq!(m.lookup(150), Some(&"100..200"));
         assert_eq!(m.lookup(200), Some(&"180..220"));
+        assert_eq!(m.lookup(224), Some(&"200..250"));
+        assert_eq!(m.lookup(252), None);
         assert_eq!(m.lookup(260), Some(&"255..270"));
     }
+
+    #[test]
+    fn test_overlapping_jit_mappings() {
+        let mut m = LibMappings::new();
+        // A JIT region with one big function.
+        m.add_mapping(0x1000, 0x2000, 0x0, "jit_a");
+        // A new function is mapped at the start of the old region, partially overlapping.
+        m.add_mapping(0x800, 0x1400, 0x5000, "jit_b");
+        assert_eq!(m.convert_address(0x13ff), Some((0x5bff, &"jit_b")));
+        assert_eq!(m.convert_address(0x1400), Some((0x400, &"jit_a")));
+        assert_eq!(m.convert_address(0x1fff), Some((0xfff, &"jit_a")));
+        // A new function is mapped in the middle of the remaining old region.
+        m.add_mapping(0x1800, 0x1900, 0x6000, "jit_c");
+        assert_eq!(m.convert_address(0x17ff), Some((0x7ff, &"jit_a")));
+        assert_eq!(m.convert_address(0x1800), Some((0x6000, &"jit_c")));
+        assert_eq!(m.convert_address(0x1900), Some((0x900, &"jit_a")));
+        // A new function is mapped at the end, overlapping with both jit_a and beyond.
+        m.add_mapping(0x1c00, 0x2400, 0x7000, "jit_d");
+        assert_eq!(m.convert_address(0x1bff), Some((0xbff, &"jit_a")));
+        assert_eq!(m.convert_address(0x1c00), Some((0x7000, &"jit_d")));
+        assert_eq!(m.convert_address(0x2000), Some((0x7400, &"jit_d")));
+        assert_eq!(m.convert_address(0x2400), None);
+        assert_eq!(m.convert_address(0x7ff), None);
+        assert_eq!(m.convert_address(0x800), Some((0x5000, &"jit_b")));
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let mut m = LibMappings::new();
+        m.add_mapping(0x1000, 0x2000, 0x0, "a");
+        m.add_mapping(0x2000, 0x3000, 0x0, "b");
+        m.add_mapping(0x3000, 0x4000, 0x0, "c");
+        m.remove_range(0x1800, 0x3400);
+        assert_eq!(m.convert_address(0x17ff), Some((0x7ff, &"a")));
+        assert_eq!(m.convert_address(0x1800), None);
+        assert_eq!(m.convert_address(0x2800), None);
+        assert_eq!(m.convert_address(0x33ff), None);
+        assert_eq!(m.convert_address(0x3400), Some((0x400, &"c")));
+        m.remove_range(0x3800, 0x3900);
+        assert_eq!(m.convert_address(0x37ff), Some((0x7ff, &"c")));
+        assert_eq!(m.convert_address(0x3800), None);
+        assert_eq!(m.convert_address(0x3900), Some((0x900, &"c")));
+    }
 }
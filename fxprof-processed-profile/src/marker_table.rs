@@ -4,10 +4,7 @@ use crate::markers::{InternalMarkerSchema, MarkerFieldFormatKind};
 use crate::serialization_helpers::SerializableOptionalTimestampColumn;
 use crate::string_table::{GlobalStringIndex, GlobalStringTable, StringIndex};
 use crate::thread_string_table::{ThreadInternalStringIndex, ThreadStringTable};
-use crate::{
-    CategoryHandle, Marker, MarkerFieldFormat, MarkerHandle, MarkerTiming, MarkerTypeHandle,
-    Timestamp,
-};
+use crate::{CategoryHandle, Marker, MarkerHandle, MarkerTiming, MarkerTypeHandle, Timestamp};
 
 #[derive(Debug, Clone, Default)]
 pub struct MarkerTable {
@@ -18,6 +15,10 @@ pub struct MarkerTable {
     marker_phases: Vec<Phase>,
     marker_type_handles: Vec<MarkerTypeHandle>,
     marker_stacks: Vec<Option<usize>>,
+    /// The tid of the thread each marker belongs to, if it differs from the
+    /// thread whose marker table contains it. Only created once the first
+    /// such marker is added.
+    marker_thread_ids: Option<Vec<Option<String>>>,
     /// The field values for any marker fields of [kind](`MarkerFieldFormat::kind`) [`MarkerFieldFormatKind::Number`].
     ///
     /// This Vec can contain zero or more values per marker, depending on the marker's
@@ -50,6 +51,10 @@ impl MarkerTable {
         Default::default()
     }
 
+    pub fn len(&self) -> usize {
+        self.marker_name_string_indexes.len()
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn add_marker<T: Marker>(
         &mut self,
@@ -75,12 +80,15 @@ impl MarkerTable {
         self.marker_phases.push(phase);
         self.marker_type_handles.push(marker_type_handle);
         self.marker_stacks.push(None);
+        if let Some(thread_ids) = &mut self.marker_thread_ids {
+            thread_ids.push(None);
+        }
         for (field_index, field) in schema.fields().iter().enumerate() {
             match field.format.kind() {
                 MarkerFieldFormatKind::String => {
                     let global_string_index = marker.string_field_value(field_index as u32).0;
-                    let string_index = if field.format == MarkerFieldFormat::String {
-                        // This one ends up as `format: "unique-string"` with an index into the thread string table
+                    let string_index = if field.format.uses_string_index() {
+                        // These end up as e.g. `format: "unique-string"` with an index into the thread string table
                         let thread_string_index = thread_string_table
                             .index_for_global_string(global_string_index, global_string_table);
                         thread_string_index.0
@@ -104,6 +112,14 @@ impl MarkerTable {
         self.marker_stacks[marker.0] = stack_index;
     }
 
+    pub fn set_marker_thread_id(&mut self, marker: MarkerHandle, tid: String) {
+        let len = self.marker_stacks.len();
+        let thread_ids = self
+            .marker_thread_ids
+            .get_or_insert_with(|| vec![None; len]);
+        thread_ids[marker.0] = Some(tid);
+    }
+
     pub fn as_serializable<'a>(
         &'a self,
         schemas: &'a [InternalMarkerSchema],
@@ -141,6 +157,9 @@ impl Serialize for SerializableMarkerTable<'_> {
             "startTime",
             &SerializableOptionalTimestampColumn(&marker_table.marker_starts),
         )?;
+        if let Some(thread_ids) = &marker_table.marker_thread_ids {
+            map.serialize_entry("threadId", thread_ids)?;
+        }
         map.end()
     }
 }
@@ -205,7 +224,7 @@ impl Serialize for SerializableMarkerDataElement<'_> {
                 MarkerFieldFormatKind::String => {
                     let value;
                     (value, string_fields) = string_fields.split_first().unwrap();
-                    if field.format == MarkerFieldFormat::String {
+                    if field.format.uses_string_index() {
                         map.serialize_entry(&field.key, value)?;
                     } else {
                         let str_val = global_string_table
@@ -73,6 +73,9 @@ pub trait Marker {
     ///
     /// `field_index` is an index into the schema's [`fields`](MarkerSchema::fields).
     ///
+    /// For fields with the [`MarkerFieldFormat::String`] format, the returned string
+    /// is added to the thread's string table, so that repeated values are deduplicated.
+    ///
     /// You can panic for any unexpected field indexes, for example
     /// using `unreachable!()`. You can even panic unconditionally if this
     /// marker type doesn't have any string fields.
@@ -177,6 +180,9 @@ pub trait StaticSchemaMarker {
     ///
     /// `field_index` is an index into the schema's [`fields`](MarkerSchema::fields).
     ///
+    /// For fields with the [`MarkerFieldFormat::String`] format, the returned string
+    /// is added to the thread's string table, so that repeated values are deduplicated.
+    ///
     /// You can panic for any unexpected field indexes, for example
     /// using `unreachable!()`. You can even panic unconditionally if this
     /// marker type doesn't have any string fields.
@@ -310,6 +316,93 @@ pub struct MarkerSchema {
     pub static_fields: Vec<MarkerStaticField>,
 }
 
+impl MarkerSchema {
+    /// Creates a schema for the marker type `type_name`, without any locations,
+    /// labels or fields. Use the `with_*` methods to fill in the rest:
+    ///
+    /// ```
+    /// use fxprof_processed_profile::{MarkerFieldFormat, MarkerLocation, MarkerSchema};
+    ///
+    /// let schema = MarkerSchema::new("custom")
+    ///     .with_locations(&[MarkerLocation::MarkerChart, MarkerLocation::MarkerTable])
+    ///     .with_chart_label("{marker.data.eventName}")
+    ///     .with_searchable_field("eventName", "Event name", MarkerFieldFormat::String)
+    ///     .with_field("allocationSize", "Allocation size", MarkerFieldFormat::Bytes)
+    ///     .with_static_field("Description", "This is a test marker with a custom schema.");
+    /// assert_eq!(schema.fields.len(), 2);
+    /// ```
+    pub fn new(type_name: &str) -> Self {
+        Self {
+            type_name: type_name.to_string(),
+            locations: Vec::new(),
+            chart_label: None,
+            tooltip_label: None,
+            table_label: None,
+            fields: Vec::new(),
+            static_fields: Vec::new(),
+        }
+    }
+
+    /// Adds the given display locations.
+    pub fn with_locations(mut self, locations: &[MarkerLocation]) -> Self {
+        self.locations.extend_from_slice(locations);
+        self
+    }
+
+    /// Sets [`chart_label`](MarkerSchema::chart_label).
+    pub fn with_chart_label(mut self, label: &str) -> Self {
+        self.chart_label = Some(label.to_string());
+        self
+    }
+
+    /// Sets [`tooltip_label`](MarkerSchema::tooltip_label).
+    pub fn with_tooltip_label(mut self, label: &str) -> Self {
+        self.tooltip_label = Some(label.to_string());
+        self
+    }
+
+    /// Sets [`table_label`](MarkerSchema::table_label).
+    pub fn with_table_label(mut self, label: &str) -> Self {
+        self.table_label = Some(label.to_string());
+        self
+    }
+
+    /// Adds a field which is not searchable.
+    pub fn with_field(self, key: &str, label: &str, format: MarkerFieldFormat) -> Self {
+        self.with_field_schema(key, label, format, false)
+    }
+
+    /// Adds a field whose value is matched against search terms.
+    pub fn with_searchable_field(self, key: &str, label: &str, format: MarkerFieldFormat) -> Self {
+        self.with_field_schema(key, label, format, true)
+    }
+
+    /// Adds a static field, with a value that applies to all markers of this type.
+    pub fn with_static_field(mut self, label: &str, value: &str) -> Self {
+        self.static_fields.push(MarkerStaticField {
+            label: label.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    fn with_field_schema(
+        mut self,
+        key: &str,
+        label: &str,
+        format: MarkerFieldFormat,
+        searchable: bool,
+    ) -> Self {
+        self.fields.push(MarkerFieldSchema {
+            key: key.to_string(),
+            label: label.to_string(),
+            format,
+            searchable,
+        });
+        self
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InternalMarkerSchema {
     /// The name of this marker type.
@@ -493,6 +586,10 @@ pub enum MarkerFieldFormat {
     /// A regular string, supports PII sanitization.
     /// Concretely this means that these strings are stripped when uploading
     /// profiles if you uncheck "Include resource URLs and paths".
+    ///
+    /// Use this format for marker fields which may contain user data. It is
+    /// serialized as `"sanitized-string"` in the schema, and the values are
+    /// written out as JSON strings.
     SanitizedString,
 
     /// A plain String, never sanitized for PII.
@@ -500,9 +597,29 @@ pub enum MarkerFieldFormat {
     /// Important: Do not put URL or file path information here, as it will not
     /// be sanitized during profile upload. Please be careful with including
     /// other types of PII here as well.
+    ///
+    /// Values of this format are stored as indexes into the thread's string table,
+    /// so a string which is used by many markers only appears once in the JSON.
+    /// This makes this format a good fit for large values which repeat often, such
+    /// as SQL queries. The values for the other string formats are currently written
+    /// out in full for every marker, because the Firefox Profiler does not support
+    /// string indexes for them yet.
     #[serde(rename = "unique-string")]
     String,
 
+    /// An identifier which connects this marker to other markers with the same
+    /// flow ID, even across threads. The Firefox Profiler uses this to draw
+    /// arrows between related markers, for example between sending a request
+    /// on one thread and receiving the response on another.
+    ///
+    /// Like [`MarkerFieldFormat::String`], values of this format are stored as
+    /// indexes into the thread's string table.
+    FlowId,
+
+    /// Like [`MarkerFieldFormat::FlowId`], but also marks the end of the flow.
+    /// Markers with the same flow ID which come after this marker start a new flow.
+    TerminatingFlowId,
+
     // ----------------------------------------------------
     // Numeric types
     /// For time data that represents a duration of time.
@@ -573,9 +690,12 @@ impl MarkerFieldFormat {
     /// `string_field_value` to get the field values.
     pub fn kind(&self) -> MarkerFieldFormatKind {
         match self {
-            Self::Url | Self::FilePath | Self::SanitizedString | Self::String => {
-                MarkerFieldFormatKind::String
-            }
+            Self::Url
+            | Self::FilePath
+            | Self::SanitizedString
+            | Self::String
+            | Self::FlowId
+            | Self::TerminatingFlowId => MarkerFieldFormatKind::String,
             Self::Duration
             | Self::Time
             | Self::Seconds
@@ -588,4 +708,10 @@ impl MarkerFieldFormat {
             | Self::Decimal => MarkerFieldFormatKind::Number,
         }
     }
+
+    /// Whether values of this string format are serialized as indexes into the
+    /// thread's string table, rather than as JSON strings.
+    pub(crate) fn uses_string_index(&self) -> bool {
+        matches!(self, Self::String | Self::FlowId | Self::TerminatingFlowId)
+    }
 }
@@ -10,24 +10,28 @@ use crate::Timestamp;
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct ThreadHandle(pub(crate) usize);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Process {
     pid: String,
     name: String,
     threads: Vec<ThreadHandle>,
-    start_time: Timestamp,
+    start_time: Option<Timestamp>,
     end_time: Option<Timestamp>,
+    cmdline: Option<String>,
+    env: Option<Vec<(String, String)>>,
     libs: LibMappings<LibraryHandle>,
 }
 
 impl Process {
-    pub fn new(name: &str, pid: String, start_time: Timestamp) -> Self {
+    pub fn new(name: &str, pid: String, start_time: Option<Timestamp>) -> Self {
         Self {
             pid,
             threads: Vec::new(),
             libs: LibMappings::new(),
             start_time,
             end_time: None,
+            cmdline: None,
+            env: None,
             name: name.to_owned(),
         }
     }
@@ -37,10 +41,10 @@ impl Process {
     }
 
     pub fn set_start_time(&mut self, start_time: Timestamp) {
-        self.start_time = start_time;
+        self.start_time = Some(start_time);
     }
 
-    pub fn start_time(&self) -> Timestamp {
+    pub fn start_time(&self) -> Option<Timestamp> {
         self.start_time
     }
 
@@ -60,10 +64,38 @@ impl Process {
         &self.name
     }
 
+    pub fn set_cmdline(&mut self, cmdline: &str) {
+        self.cmdline = Some(cmdline.to_string());
+    }
+
+    pub fn cmdline(&self) -> Option<&str> {
+        self.cmdline.as_deref()
+    }
+
+    pub fn set_env(&mut self, env: Vec<(String, String)>) {
+        self.env = Some(env);
+    }
+
+    pub fn env(&self) -> Option<&[(String, String)]> {
+        self.env.as_deref()
+    }
+
     pub fn add_thread(&mut self, thread: ThreadHandle) {
         self.threads.push(thread);
     }
 
+    pub fn take_threads(&mut self) -> Vec<ThreadHandle> {
+        std::mem::take(&mut self.threads)
+    }
+
+    pub fn take_lib_mappings(&mut self) -> LibMappings<LibraryHandle> {
+        std::mem::take(&mut self.libs)
+    }
+
+    pub fn add_lib_mappings(&mut self, libs: LibMappings<LibraryHandle>) {
+        self.libs.add_mappings_from(libs);
+    }
+
     pub fn pid(&self) -> &str {
         &self.pid
     }
@@ -115,6 +147,10 @@ impl Process {
         self.libs.remove_mapping(start_avma);
     }
 
+    pub fn remove_lib_mapping_range(&mut self, start_avma: u64, end_avma: u64) {
+        self.libs.remove_range(start_avma, end_avma);
+    }
+
     pub fn remove_all_lib_mappings(&mut self) {
         self.libs.clear();
     }
@@ -10,9 +10,12 @@ use crate::category_color::CategoryColor;
 use crate::counters::{Counter, CounterHandle};
 use crate::cpu_delta::CpuDelta;
 use crate::fast_hash_map::FastHashMap;
-use crate::frame::{Frame, FrameInfo};
+use crate::frame::{Frame, FrameFlags, FrameInfo, SourceLocation};
 use crate::frame_table::{InternalFrame, InternalFrameLocation};
-use crate::global_lib_table::{GlobalLibTable, LibraryHandle, UsedLibraryAddressesIterator};
+use crate::global_lib_table::{
+    GlobalLibIndex, GlobalLibTable, LibDeduplicationPolicy, LibraryHandle,
+    UsedLibraryAddressesIterator,
+};
 use crate::lib_mappings::LibMappings;
 use crate::library_info::{LibraryInfo, SymbolTable};
 use crate::markers::{
@@ -21,7 +24,9 @@ use crate::markers::{
 };
 use crate::process::{Process, ThreadHandle};
 use crate::reference_timestamp::ReferenceTimestamp;
+use crate::sample_table::WeightType;
 use crate::string_table::{GlobalStringIndex, GlobalStringTable};
+use crate::symbol_info::{LibSymbolInfo, ProfileSymbolInfo};
 use crate::thread::{ProcessHandle, Thread};
 use crate::timestamp::Timestamp;
 
@@ -74,6 +79,72 @@ impl From<Duration> for SamplingInterval {
     }
 }
 
+/// What to do when a sample is added to a thread with a timestamp that is
+/// earlier than the timestamp of the thread's previous sample.
+///
+/// Used with [`Profile::set_out_of_order_sample_policy`].
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub enum OutOfOrderSamplePolicy {
+    /// Accept the sample. The thread's samples are sorted by timestamp when the
+    /// profile is serialized. This is the default.
+    SortOnSerialize,
+    /// Panic in debug builds. This is useful to catch importer bugs during
+    /// development. In release builds, this behaves like `SortOnSerialize`.
+    /// Use [`Profile::try_add_sample_in_order`] to detect out-of-order samples
+    /// in release builds.
+    DebugAssert,
+}
+
+/// The error returned by [`Profile::try_add_sample_in_order`] if the sample's
+/// timestamp is earlier than the timestamp of the thread's previous sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutOfOrderSampleError {
+    /// The thread to which the sample was added.
+    pub thread: ThreadHandle,
+    /// The timestamp of the rejected sample.
+    pub timestamp: Timestamp,
+    /// The timestamp of the thread's previous sample.
+    pub previous_timestamp: Timestamp,
+}
+
+impl std::fmt::Display for OutOfOrderSampleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Sample at {:?} was added out of order to thread {:?}, after a sample at {:?}",
+            self.timestamp, self.thread, self.previous_timestamp
+        )
+    }
+}
+
+impl std::error::Error for OutOfOrderSampleError {}
+
+/// The error returned by the `try_` methods of [`Profile`] if they are called
+/// with a handle which is not valid for this profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvalidHandleError {
+    /// The thread handle doesn't refer to a thread in this profile.
+    UnknownThread(ThreadHandle),
+    /// The stack handle was created for a different thread than the one it was
+    /// used with.
+    StackFromDifferentThread(StackHandle, ThreadHandle),
+}
+
+impl std::fmt::Display for InvalidHandleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidHandleError::UnknownThread(thread) => {
+                write!(f, "{thread:?} is not a thread in this profile")
+            }
+            InvalidHandleError::StackFromDifferentThread(stack, thread) => {
+                write!(f, "{stack:?} was passed for a different thread {thread:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidHandleError {}
+
 /// A handle for an interned string, returned from [`Profile::intern_string`].
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct StringHandle(pub(crate) GlobalStringIndex);
@@ -86,13 +157,22 @@ pub struct FrameHandle(ThreadHandle, usize);
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct StackHandle(ThreadHandle, usize);
 
+/// A track in the profiler's timeline. Used with [`Profile::set_track_order`].
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub enum Track {
+    /// The track for a thread.
+    Thread(ThreadHandle),
+    /// The track for a counter.
+    Counter(CounterHandle),
+}
+
 /// Stores the profile data and can be serialized as JSON, via [`serde::Serialize`].
 ///
 /// The profile data is organized into a list of processes with threads.
 /// Each thread has its own samples and markers.
 ///
 /// ```
-/// use fxprof_processed_profile::{Profile, CategoryHandle, CpuDelta, Frame, FrameInfo, FrameFlags, SamplingInterval, Timestamp};
+/// use fxprof_processed_profile::{Profile, CategoryHandle, CpuDelta, Frame, FrameInfo, FrameFlags, SamplingInterval, SourceLocation, Timestamp};
 /// use std::time::SystemTime;
 ///
 /// # fn write_profile(output_file: std::fs::File) -> Result<(), Box<dyn std::error::Error>> {
@@ -101,8 +181,8 @@ pub struct StackHandle(ThreadHandle, usize);
 /// let thread = profile.add_thread(process, 54132000, Timestamp::from_millis_since_reference(0.0), true);
 /// profile.set_thread_name(thread, "Main thread");
 /// let stack_frames = vec![
-///     FrameInfo { frame: Frame::Label(profile.intern_string("Root node")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty() },
-///     FrameInfo { frame: Frame::Label(profile.intern_string("First callee")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty() }
+///     FrameInfo { frame: Frame::Label(profile.intern_string("Root node")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty(), implementation: None, source_location: SourceLocation::default() },
+///     FrameInfo { frame: Frame::Label(profile.intern_string("First callee")), category_pair: CategoryHandle::OTHER.into(), flags: FrameFlags::empty(), implementation: None, source_location: SourceLocation::default() }
 /// ];
 /// let stack = profile.intern_stack_frames(thread, stack_frames.into_iter());
 /// profile.add_sample(thread, Timestamp::from_millis_since_reference(0.0), stack, CpuDelta::ZERO, 1);
@@ -112,7 +192,7 @@ pub struct StackHandle(ThreadHandle, usize);
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Profile {
     pub(crate) product: String,
     pub(crate) os_name: Option<String>,
@@ -126,9 +206,15 @@ pub struct Profile {
     pub(crate) reference_timestamp: ReferenceTimestamp,
     pub(crate) string_table: GlobalStringTable,
     pub(crate) marker_schemas: Vec<InternalMarkerSchema>,
+    track_order: Option<Vec<Track>>,
+    symbolicated: bool,
+    uses_only_one_stack_type: Option<bool>,
+    sample_weight_type: WeightType,
+    out_of_order_sample_policy: OutOfOrderSamplePolicy,
+    clamp_cpu_delta_to_interval: bool,
     static_schema_marker_types: FastHashMap<&'static str, MarkerTypeHandle>,
-    used_pids: FastHashMap<u32, u32>,
-    used_tids: FastHashMap<u32, u32>,
+    used_pids: FastHashMap<u64, u32>,
+    used_tids: FastHashMap<u64, u32>,
 }
 
 impl Profile {
@@ -154,6 +240,12 @@ impl Profile {
             processes: Vec::new(),
             string_table: GlobalStringTable::new(),
             marker_schemas: Vec::new(),
+            track_order: None,
+            symbolicated: false,
+            uses_only_one_stack_type: None,
+            sample_weight_type: WeightType::Samples,
+            out_of_order_sample_policy: OutOfOrderSamplePolicy::SortOnSerialize,
+            clamp_cpu_delta_to_interval: false,
             categories: vec![Category {
                 name: "Other".to_string(),
                 color: CategoryColor::Gray,
@@ -166,12 +258,82 @@ impl Profile {
         }
     }
 
+    /// Create a new profile whose sample weights are byte counts, for example
+    /// for a memory allocation profile.
+    ///
+    /// The arguments are the same as for [`Profile::new`]. In a profile created
+    /// with this constructor, the `weight` passed to [`Profile::add_sample`] and
+    /// [`Profile::add_sample_same_stack_zero_cpu`] is interpreted as a number of
+    /// bytes, and the Firefox Profiler displays the call tree with byte sizes
+    /// instead of sample counts. The CPU deltas of such samples are not
+    /// meaningful and should be [`CpuDelta::ZERO`].
+    ///
+    /// If you want to record allocations alongside regular time-based samples,
+    /// use [`Profile::add_allocation_sample`] on a regular profile instead.
+    pub fn new_allocation_profile(
+        product: &str,
+        reference_timestamp: ReferenceTimestamp,
+        interval: SamplingInterval,
+    ) -> Self {
+        let mut profile = Self::new(product, reference_timestamp, interval);
+        profile.sample_weight_type = WeightType::Bytes;
+        profile
+    }
+
     /// Change the declared sampling interval.
     pub fn set_interval(&mut self, interval: SamplingInterval) {
         self.interval = interval;
     }
 
+    /// Change what happens when samples are added to a thread out of timestamp
+    /// order. The default is [`OutOfOrderSamplePolicy::SortOnSerialize`].
+    pub fn set_out_of_order_sample_policy(&mut self, policy: OutOfOrderSamplePolicy) {
+        self.out_of_order_sample_policy = policy;
+    }
+
+    /// Clamp the CPU delta of each sample passed to [`Profile::add_sample`] to
+    /// the time since the previous sample on the same thread. Off by default.
+    ///
+    /// A thread can't use more CPU time than the wall-clock time that passed, but
+    /// noisy or coarse-grained CPU time counters sometimes report larger deltas,
+    /// which the Firefox Profiler displays as more than 100% CPU usage. This is a
+    /// correctness aid for such imperfect counters; accurate counters don't need it.
+    /// The first sample of each thread is not clamped.
+    pub fn set_clamp_cpu_delta_to_interval(&mut self, clamp: bool) {
+        self.clamp_cpu_delta_to_interval = clamp;
+    }
+
+    /// Set whether the profile is already symbolicated. This is serialized as
+    /// `meta.symbolicated`, and defaults to `false`.
+    ///
+    /// Set it to `true` if all frames already have their final function names,
+    /// for example because they were added as labels, so that the Firefox Profiler
+    /// doesn't try to symbolicate the profile again.
+    pub fn set_symbolicated(&mut self, symbolicated: bool) {
+        self.symbolicated = symbolicated;
+    }
+
+    /// Override `meta.usesOnlyOneStackType`, which lets the Firefox Profiler skip
+    /// some work for profiles that don't mix native and JS stacks.
+    ///
+    /// By default, this is `true` unless any frame has [`FrameFlags::IS_JS`](crate::FrameFlags::IS_JS).
+    pub fn set_uses_only_one_stack_type(&mut self, uses_only_one_stack_type: bool) {
+        self.uses_only_one_stack_type = Some(uses_only_one_stack_type);
+    }
+
     /// Change the reference timestamp.
+    ///
+    /// All [`Timestamp`]s in the profile are relative to the reference timestamp,
+    /// and this relationship is kept when the reference timestamp changes: the
+    /// timestamps of samples and markers which have already been added are not
+    /// adjusted. Instead, they are now interpreted relative to the new reference
+    /// timestamp, and the serialized `meta.startTime` is the new reference
+    /// timestamp.
+    ///
+    /// This can be used if the absolute anchor is only known after the data has
+    /// been added, for example when combining data from sources whose clocks are
+    /// aligned later. Any `Timestamp`s added after this call must also be
+    /// relative to the new reference timestamp.
     pub fn set_reference_timestamp(&mut self, reference_timestamp: ReferenceTimestamp) {
         self.reference_timestamp = reference_timestamp;
     }
@@ -210,18 +372,27 @@ impl Profile {
 
     /// Add an empty process. The name, pid and start time can be changed afterwards,
     /// but they are required here because they have to be present in the profile JSON.
-    pub fn add_process(&mut self, name: &str, pid: u32, start_time: Timestamp) -> ProcessHandle {
+    ///
+    /// If the start time of the process is not known, pass `None`. The process's
+    /// `processStartupTime` is then serialized as null, rather than as zero.
+    pub fn add_process(
+        &mut self,
+        name: &str,
+        pid: u64,
+        start_time: impl Into<Option<Timestamp>>,
+    ) -> ProcessHandle {
         let pid = self.make_unique_pid(pid);
         let handle = ProcessHandle(self.processes.len());
-        self.processes.push(Process::new(name, pid, start_time));
+        self.processes
+            .push(Process::new(name, pid, start_time.into()));
         handle
     }
 
-    fn make_unique_pid(&mut self, pid: u32) -> String {
+    fn make_unique_pid(&mut self, pid: u64) -> String {
         Self::make_unique_pid_or_tid(&mut self.used_pids, pid)
     }
 
-    fn make_unique_tid(&mut self, tid: u32) -> String {
+    fn make_unique_tid(&mut self, tid: u64) -> String {
         Self::make_unique_pid_or_tid(&mut self.used_tids, tid)
     }
 
@@ -229,7 +400,7 @@ impl Profile {
     ///
     /// The map contains the next suffix for each pid/tid, or no entry if the pid/tid
     /// hasn't been used before and needs no suffix.
-    fn make_unique_pid_or_tid(map: &mut FastHashMap<u32, u32>, id: u32) -> String {
+    fn make_unique_pid_or_tid(map: &mut FastHashMap<u64, u32>, id: u64) -> String {
         match map.entry(id) {
             std::collections::hash_map::Entry::Occupied(mut entry) => {
                 let suffix = *entry.get();
@@ -292,6 +463,46 @@ impl Profile {
         self.processes[process.0].set_name(name);
     }
 
+    /// Set the command line with which a process was launched.
+    ///
+    /// This is serialized as `processCommandLine` on each of the process's threads.
+    pub fn set_process_cmdline(&mut self, process: ProcessHandle, cmdline: &str) {
+        self.processes[process.0].set_cmdline(cmdline);
+    }
+
+    /// Set the environment variables of a process, as a list of name and value pairs.
+    ///
+    /// This is serialized as `processEnvironment` on each of the process's threads,
+    /// as an array of `[name, value]` arrays in the given order.
+    pub fn set_process_env(&mut self, process: ProcessHandle, env: Vec<(String, String)>) {
+        self.processes[process.0].set_env(env);
+    }
+
+    /// Set the order in which tracks should be displayed in the profiler's timeline.
+    ///
+    /// By default, threads are grouped by process, counters are displayed with
+    /// their process, and the Firefox Profiler applies its own heuristics to order
+    /// them. Once a track order is set, it is serialized as the global `trackOrder`
+    /// in the profile's meta, so threads and counters can be interleaved freely,
+    /// e.g. to put a counter between two threads. Tracks which aren't part of
+    /// `tracks` are placed after the listed tracks: first the remaining threads,
+    /// then the remaining counters, each in the default order.
+    ///
+    /// The threads and counters are also serialized in this order, and the
+    /// profiler is asked to keep the thread order.
+    pub fn set_track_order(&mut self, tracks: &[Track]) {
+        self.track_order = Some(tracks.to_vec());
+    }
+
+    /// Change how [`Profile::add_lib`] detects that a library has already been
+    /// added. The default is [`LibDeduplicationPolicy::ExactMatch`].
+    ///
+    /// This only affects calls to `add_lib` after this call, but those calls
+    /// can return the handle of a matching library which was added earlier.
+    pub fn set_lib_deduplication_policy(&mut self, policy: LibDeduplicationPolicy) {
+        self.global_libs.set_dedup_policy(policy);
+    }
+
     /// Get the [`LibraryHandle`] for a library. This handle is used in [`Profile::add_lib_mapping`]
     /// and in the pre-resolved [`Frame`] variants.
     ///
@@ -317,7 +528,8 @@ impl Profile {
     /// For a given process, define where in the virtual memory of this process the given library
     /// is mapped.
     ///
-    /// Existing mappings which overlap with the range `start_avma..end_avma` will be removed.
+    /// Existing mappings which overlap with the range `start_avma..end_avma` will be trimmed
+    /// or split so that they only cover the addresses outside of this range.
     ///
     /// A single library can have multiple mappings in the same process.
     ///
@@ -345,6 +557,18 @@ impl Profile {
         self.processes[process.0].remove_lib_mapping(start_avma);
     }
 
+    /// Mark the address range `start_avma..end_avma` in the specified process as unmapped,
+    /// so that future calls to [`Profile::add_sample`] resolve addresses in this range to
+    /// unknown addresses.
+    ///
+    /// Unlike [`Profile::remove_lib_mapping`], this doesn't require the range to match an
+    /// existing mapping. Mappings which intersect with the range are trimmed or split, and
+    /// mappings which are fully contained in the range are removed. This is useful for JIT
+    /// code, where an unmap event can cover just a part of a mapping, or multiple mappings.
+    pub fn unload_lib_range(&mut self, process: ProcessHandle, start_avma: u64, end_avma: u64) {
+        self.processes[process.0].remove_lib_mapping_range(start_avma, end_avma);
+    }
+
     /// Clear all library mappings in the specified process.
     pub fn clear_process_lib_mappings(&mut self, process: ProcessHandle) {
         self.processes[process.0].remove_all_lib_mappings();
@@ -374,22 +598,104 @@ impl Profile {
         self.kernel_libs.remove_mapping(start_avma);
     }
 
+    /// Merge process `b` into process `a`, for example if two data sources
+    /// independently created a process for the same pid. Afterwards, `a` and `b`
+    /// are shown as a single process.
+    ///
+    /// All threads and counters of `b` are moved to `a`. If `a` already has a main
+    /// thread, the threads from `b` are no longer marked as main threads. The lib
+    /// mappings of `b` are added to `a`; where they overlap with lib mappings of
+    /// `a`, the mappings from `b` take precedence, as if they had been added to
+    /// `a` with [`Profile::add_lib_mapping`]. `a` gets the earlier of the two start
+    /// times and the later of the two end times.
+    ///
+    /// `b` stays a valid handle, but it no longer has any threads or lib mappings,
+    /// so it doesn't show up in the profile.
+    pub fn merge_processes(&mut self, a: ProcessHandle, b: ProcessHandle) {
+        if a == b {
+            return;
+        }
+        let a_has_main_thread = self.processes[a.0]
+            .threads()
+            .iter()
+            .any(|thread| self.threads[thread.0].is_main());
+        for thread in self.processes[b.0].take_threads() {
+            let thread_data = &mut self.threads[thread.0];
+            thread_data.set_process(a);
+            if a_has_main_thread {
+                thread_data.set_is_main(false);
+            }
+            self.processes[a.0].add_thread(thread);
+        }
+
+        let b_libs = self.processes[b.0].take_lib_mappings();
+        self.processes[a.0].add_lib_mappings(b_libs);
+
+        let (b_start_time, b_end_time) = {
+            let b = &self.processes[b.0];
+            (b.start_time(), b.end_time())
+        };
+        let a_process = &mut self.processes[a.0];
+        if let Some(b_start_time) = b_start_time {
+            if a_process.start_time().map_or(true, |t| b_start_time < t) {
+                a_process.set_start_time(b_start_time);
+            }
+        }
+        if let Some(b_end_time) = b_end_time {
+            if a_process.end_time().map_or(true, |t| b_end_time > t) {
+                a_process.set_end_time(b_end_time);
+            }
+        }
+
+        let a_process = &self.processes[a.0];
+        for counter in &mut self.counters {
+            if counter.process() == b {
+                counter.set_process(a, a_process.pid());
+            }
+        }
+    }
+
     /// Add an empty thread to the specified process.
+    ///
+    /// If the start time of the thread is not known, pass `None`. The thread's
+    /// `registerTime` is then serialized as null; it does not default to the
+    /// start time of the process.
     pub fn add_thread(
         &mut self,
         process: ProcessHandle,
-        tid: u32,
-        start_time: Timestamp,
+        tid: u64,
+        start_time: impl Into<Option<Timestamp>>,
         is_main: bool,
     ) -> ThreadHandle {
         let tid = self.make_unique_tid(tid);
         let handle = ThreadHandle(self.threads.len());
-        self.threads
-            .push(Thread::new(process, tid, start_time, is_main));
+        self.threads.push(Thread::new(
+            process,
+            tid,
+            start_time.into(),
+            is_main,
+            self.sample_weight_type,
+        ));
         self.processes[process.0].add_thread(handle);
         handle
     }
 
+    /// Returns whether `thread` refers to a thread in this profile.
+    ///
+    /// Handles don't remember which profile they were created for, so this only
+    /// detects handles which are out of range for this profile.
+    pub fn contains_thread(&self, thread: ThreadHandle) -> bool {
+        thread.0 < self.threads.len()
+    }
+
+    /// Returns whether `process` refers to a process in this profile.
+    ///
+    /// Handles don't remember which profile they were created for, so this only
+    /// detects handles which are out of range for this profile.
+    pub fn contains_process(&self, process: ProcessHandle) -> bool {
+        process.0 < self.processes.len()
+    }
+
     /// Change the name of a thread.
     pub fn set_thread_name(&mut self, thread: ThreadHandle, name: &str) {
         self.threads[thread.0].set_name(name);
@@ -406,16 +712,60 @@ impl Profile {
     }
 
     /// Set the tid (thread ID) of a thread.
-    pub fn set_thread_tid(&mut self, thread: ThreadHandle, tid: u32) {
+    pub fn set_thread_tid(&mut self, thread: ThreadHandle, tid: u64) {
         let tid = self.make_unique_tid(tid);
         self.threads[thread.0].set_tid(tid);
     }
 
+    /// Change whether a thread is the main thread of its process, after it was
+    /// added with [`Profile::add_thread`].
+    ///
+    /// The main thread is displayed with the name of the process, and is sorted
+    /// before the other threads of the process. Marking a thread as the main
+    /// thread unmarks all other threads of the same process, so that each process
+    /// has at most one main thread.
+    pub fn set_thread_is_main(&mut self, thread: ThreadHandle, is_main: bool) {
+        if is_main {
+            let process = self.threads[thread.0].process();
+            for other_thread in self.processes[process.0].threads() {
+                self.threads[other_thread.0].set_is_main(false);
+            }
+        }
+        self.threads[thread.0].set_is_main(is_main);
+    }
+
+    /// Mark a thread as the thread which runs the main event loop of its process.
+    ///
+    /// This is separate from the `is_main` flag passed to [`Profile::add_thread`]:
+    /// In some runtimes, the event loop doesn't run on the process's main thread.
+    /// The Firefox Profiler shows the event delay track and the jank markers
+    /// for the event loop thread, based on the event delays set with
+    /// [`Profile::set_last_sample_event_delay`]. Threads which aren't marked as
+    /// the main event loop thread don't have their event delays serialized.
+    pub fn set_thread_is_main_event_loop(
+        &mut self,
+        thread: ThreadHandle,
+        is_main_event_loop: bool,
+    ) {
+        self.threads[thread.0].set_is_main_event_loop(is_main_event_loop);
+    }
+
     /// Turn the string into in a [`StringHandle`], for use in [`Frame::Label`].
     pub fn intern_string(&mut self, s: &str) -> StringHandle {
         StringHandle(self.string_table.index_for_string(s))
     }
 
+    /// Turn a file path into a [`StringHandle`]. Backslashes are normalized to
+    /// forward slashes before interning, so that `a\b` and `a/b` share the same
+    /// string and are grouped as the same file in the UI.
+    pub fn handle_for_file_path(&mut self, path: &str) -> StringHandle {
+        if path.contains('\\') {
+            self.intern_string(&path.replace('\\', "/"))
+        } else {
+            self.intern_string(path)
+        }
+    }
+
     /// Get the string for a string handle. This is sometimes useful when writing tests.
     ///
     /// Panics if the handle wasn't found, which can happen if you pass a handle
@@ -442,6 +792,34 @@ impl Profile {
         FrameHandle(thread_handle, frame_index)
     }
 
+    /// Get the frame handle for a code address in a library, if the library-relative
+    /// address is already known.
+    ///
+    /// This is a shorthand for [`Profile::intern_frame`] with a
+    /// [`Frame::RelativeAddressFromInstructionPointer`] frame. Because the address is
+    /// already relative to the library, it doesn't need to be resolved using the process's
+    /// library mappings, so this works even if no mapping was added for `lib` with
+    /// [`Profile::add_lib_mapping`].
+    ///
+    /// The returned handle can only be used with this thread.
+    pub fn handle_for_frame_with_address_in_lib(
+        &mut self,
+        thread: ThreadHandle,
+        lib: LibraryHandle,
+        relative_address: u32,
+        category_pair: CategoryPairHandle,
+        flags: FrameFlags,
+    ) -> FrameHandle {
+        let frame_info = FrameInfo {
+            frame: Frame::RelativeAddressFromInstructionPointer(lib, relative_address),
+            category_pair,
+            flags,
+            implementation: None,
+            source_location: SourceLocation::default(),
+        };
+        self.intern_frame(thread, frame_info)
+    }
+
     /// Get the stack handle for a stack with the given `frame` and `parent`,
     /// for the given thread.
     ///
@@ -499,13 +877,18 @@ impl Profile {
     /// The sample has a timestamp, a stack, a CPU delta, and a weight.
     ///
     /// To get the stack handle, you can use [`Profile::intern_stack`] or
-    /// [`Profile::intern_stack_frames`].
+    /// [`Profile::intern_stack_frames`]. Stack handles stay valid, so if the same
+    /// stack is sampled repeatedly, you can keep its handle and pass it again for
+    /// later samples without interning the frames again.
     ///
     /// The CPU delta is the amount of CPU time that the CPU was busy with work for this
     /// thread since the previous sample. It should always be less than or equal the
     /// time delta between the sample timestamps.
     ///
-    /// The weight affects the sample's stack's score in the call tree. You usually set
+    /// In a profile created with [`Profile::new_allocation_profile`], the weight is
+    /// the number of bytes attributed to this sample's stack.
+    ///
+    /// Otherwise, the weight affects the sample's stack's score in the call tree. You usually set
     /// this to 1. You can use weights greater than one if you want to combine multiple
     /// adjacent samples with the same stack into one sample, to save space. However,
     /// this discards any CPU deltas between the adjacent samples, so it's only really
@@ -517,6 +900,12 @@ impl Profile {
     /// and "after" groups, you can use -1 for all "before" samples and 1 for all "after"
     /// samples, and the call tree will show you which stacks occur more frequently in
     /// the "after" part of the profile, by sorting those stacks to the top.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `thread` is not a thread of this profile, or if `stack` was
+    /// created for a different thread. Use [`Profile::try_add_sample`] if the
+    /// handles come from untrusted input.
     pub fn add_sample(
         &mut self,
         thread: ThreadHandle,
@@ -535,12 +924,84 @@ impl Profile {
             }
             None => None,
         };
-        self.threads[thread.0].add_sample(timestamp, stack_index, cpu_delta, weight);
+        let thread_data = &mut self.threads[thread.0];
+        let cpu_delta = match thread_data.last_sample_timestamp() {
+            Some(previous) if self.clamp_cpu_delta_to_interval => {
+                cpu_delta.clamp_to(CpuDelta::from(timestamp - previous))
+            }
+            _ => cpu_delta,
+        };
+        thread_data.add_sample(timestamp, stack_index, cpu_delta, weight);
+        self.check_sample_order(thread, timestamp);
+    }
+
+    /// Reserve capacity for at least `additional` more samples on this thread.
+    ///
+    /// This is an optimization for when the number of samples is known up front,
+    /// for example when converting a recording. It avoids repeatedly growing the
+    /// thread's sample columns while samples are added.
+    pub fn reserve_samples(&mut self, thread: ThreadHandle, additional: usize) {
+        self.threads[thread.0].reserve_samples(additional);
+    }
+
+    /// Like [`Profile::add_sample`], but returns an error instead of panicking if
+    /// `thread` is not a thread of this profile or if `stack` was created for a
+    /// different thread.
+    pub fn try_add_sample(
+        &mut self,
+        thread: ThreadHandle,
+        timestamp: Timestamp,
+        stack: Option<StackHandle>,
+        cpu_delta: CpuDelta,
+        weight: i32,
+    ) -> Result<(), InvalidHandleError> {
+        if !self.contains_thread(thread) {
+            return Err(InvalidHandleError::UnknownThread(thread));
+        }
+        if let Some(stack @ StackHandle(stack_thread_handle, _)) = stack {
+            if stack_thread_handle != thread {
+                return Err(InvalidHandleError::StackFromDifferentThread(stack, thread));
+            }
+        }
+        self.add_sample(thread, timestamp, stack, cpu_delta, weight);
+        Ok(())
+    }
+
+    /// Like [`Profile::add_sample`], but returns an error and doesn't add the
+    /// sample if its timestamp is earlier than the timestamp of the thread's
+    /// previous sample.
+    ///
+    /// Unlike [`OutOfOrderSamplePolicy::DebugAssert`], this check is also done
+    /// in release builds, so importers can report or drop bad samples.
+    pub fn try_add_sample_in_order(
+        &mut self,
+        thread: ThreadHandle,
+        timestamp: Timestamp,
+        stack: Option<StackHandle>,
+        cpu_delta: CpuDelta,
+        weight: i32,
+    ) -> Result<(), OutOfOrderSampleError> {
+        match self.threads[thread.0].last_sample_timestamp() {
+            Some(previous_timestamp) if timestamp < previous_timestamp => {
+                Err(OutOfOrderSampleError {
+                    thread,
+                    timestamp,
+                    previous_timestamp,
+                })
+            }
+            _ => {
+                self.add_sample(thread, timestamp, stack, cpu_delta, weight);
+                Ok(())
+            }
+        }
     }
 
     /// Add a sample with a CPU delta of zero. Internally, multiple consecutive
     /// samples with a delta of zero will be combined into one sample with an accumulated
     /// weight.
+    ///
+    /// If the previous sample was added with [`Profile::add_off_cpu_sample`], the
+    /// weight is added to that sample, so it stays marked as off-CPU.
     pub fn add_sample_same_stack_zero_cpu(
         &mut self,
         thread: ThreadHandle,
@@ -548,6 +1009,91 @@ impl Profile {
         weight: i32,
     ) {
         self.threads[thread.0].add_sample_same_stack_zero_cpu(timestamp, weight);
+        self.check_sample_order(thread, timestamp);
+    }
+
+    /// Add a sample for a time period during which the thread was not running
+    /// on any CPU, for example because it was blocked or sleeping. This is
+    /// useful for wall-clock profiles, which also record samples for off-CPU time.
+    ///
+    /// The sample has a CPU delta of zero and is marked as off-CPU in the
+    /// samples' `isOffCpu` column, so that idle time can be told apart from
+    /// on-CPU samples which happen to have a zero CPU delta. The stack is usually
+    /// the stack at which the thread went to sleep.
+    ///
+    /// A subsequent call to [`Profile::add_sample_same_stack_zero_cpu`] is folded
+    /// into this sample, so it also counts as off-CPU. Use this method again to
+    /// start a new off-CPU period after an on-CPU sample.
+    pub fn add_off_cpu_sample(
+        &mut self,
+        thread: ThreadHandle,
+        timestamp: Timestamp,
+        stack: Option<StackHandle>,
+        weight: i32,
+    ) {
+        let stack_index = match stack {
+            Some(StackHandle(stack_thread_handle, stack_index)) => {
+                assert_eq!(
+                    stack_thread_handle, thread,
+                    "StackHandle from different thread passed to Profile::add_off_cpu_sample"
+                );
+                Some(stack_index)
+            }
+            None => None,
+        };
+        self.threads[thread.0].add_off_cpu_sample(timestamp, stack_index, weight);
+        self.check_sample_order(thread, timestamp);
+    }
+
+    /// Returns the number of samples in this thread.
+    ///
+    /// Samples which were folded into the previous sample by
+    /// [`Profile::add_sample_same_stack_zero_cpu`] are not counted separately.
+    pub fn thread_sample_count(&self, thread: ThreadHandle) -> usize {
+        self.threads[thread.0].sample_count()
+    }
+
+    /// Returns the number of entries in this thread's stack table.
+    pub fn thread_stack_count(&self, thread: ThreadHandle) -> usize {
+        self.threads[thread.0].stack_count()
+    }
+
+    /// Returns the number of entries in this thread's frame table.
+    pub fn thread_frame_count(&self, thread: ThreadHandle) -> usize {
+        self.threads[thread.0].frame_count()
+    }
+
+    /// Returns the number of markers in this thread.
+    pub fn thread_marker_count(&self, thread: ThreadHandle) -> usize {
+        self.threads[thread.0].marker_count()
+    }
+
+    /// Returns whether samples were added to this thread out of timestamp order.
+    ///
+    /// Such samples are sorted by timestamp during serialization, but they
+    /// usually indicate a bug in the code which produced them.
+    pub fn thread_has_out_of_order_samples(&self, thread: ThreadHandle) -> bool {
+        !self.threads[thread.0].samples_are_sorted_by_time()
+    }
+
+    fn check_sample_order(&self, thread: ThreadHandle, timestamp: Timestamp) {
+        if self.out_of_order_sample_policy == OutOfOrderSamplePolicy::DebugAssert {
+            debug_assert!(
+                !self.thread_has_out_of_order_samples(thread),
+                "Sample at {timestamp:?} was added out of order to thread {thread:?}"
+            );
+        }
+    }
+
+    /// Set the event delay of the most recently added sample on this thread.
+    ///
+    /// The event delay is the time, in milliseconds, that an event which was
+    /// put into the event queue at the time of the sample would have to wait
+    /// before it can be processed. Samples without an event delay have an event
+    /// delay of zero. Event delays are only serialized for threads marked with
+    /// [`Profile::set_thread_is_main_event_loop`].
+    pub fn set_last_sample_event_delay(&mut self, thread: ThreadHandle, event_delay_ms: f64) {
+        self.threads[thread.0].set_last_sample_event_delay(event_delay_ms);
     }
 
     /// Add an allocation or deallocation sample to the given thread. This is used
@@ -738,6 +1284,53 @@ impl Profile {
         )
     }
 
+    /// Add a marker to the thread with the given `tid` in `process`. This is
+    /// useful for importers whose markers come in a single stream, with the
+    /// tid of each marker's thread.
+    ///
+    /// If `process` has several threads with this tid, because the tid was reused,
+    /// the marker is added to the most recently added one. If `process` has no
+    /// thread with this tid, the marker is added to the process's main thread, or
+    /// to its first thread if no thread is marked as the main thread, and the
+    /// marker's `threadId` is set to `tid` so that the Firefox Profiler still knows
+    /// which thread it belongs to.
+    ///
+    /// Returns the thread which the marker was added to, and the marker's handle.
+    /// Returns `None` if `process` doesn't have any threads.
+    pub fn add_global_marker<T: Marker>(
+        &mut self,
+        process: ProcessHandle,
+        tid: u64,
+        timing: MarkerTiming,
+        marker: T,
+    ) -> Option<(ThreadHandle, MarkerHandle)> {
+        let tid_str = tid.to_string();
+        let threads = self.processes[process.0].threads();
+        let matching_thread = threads.iter().rev().copied().find(|thread| {
+            let thread_tid = self.threads[thread.0].tid();
+            thread_tid == tid_str
+                || thread_tid
+                    .strip_prefix(tid_str.as_str())
+                    .map_or(false, |suffix| suffix.starts_with('.'))
+        });
+        let (thread, needs_thread_id) = match matching_thread {
+            Some(thread) => (thread, false),
+            None => {
+                let fallback = threads
+                    .iter()
+                    .copied()
+                    .find(|thread| self.threads[thread.0].is_main())
+                    .or_else(|| threads.first().copied())?;
+                (fallback, true)
+            }
+        };
+        let marker = self.add_marker(thread, timing, marker);
+        if needs_thread_id {
+            self.threads[thread.0].set_marker_thread_id(marker, tid_str);
+        }
+        Some((thread, marker))
+    }
+
     /// Sets a marker's stack. Every marker can have an optional stack, regardless
     /// of its marker type.
     ///
@@ -825,10 +1418,20 @@ impl Profile {
                 InternalFrameLocation::Label(thread_string_index)
             }
         };
+        let implementation = frame_info
+            .implementation
+            .map(|s| thread.convert_string_index(string_table, s.0));
+        let file = frame_info
+            .source_location
+            .file
+            .map(|s| thread.convert_string_index(string_table, s.0));
         let internal_frame = InternalFrame {
             location,
             flags: frame_info.flags,
             category_pair: frame_info.category_pair,
+            implementation,
+            file,
+            line: frame_info.source_location.line,
         };
         thread.frame_index_for_frame(internal_frame, global_libs)
     }
@@ -864,7 +1467,6 @@ impl Profile {
     // UI to group threads from the same process.
     fn sorted_threads(&self) -> (Vec<ThreadHandle>, Vec<usize>) {
         let mut sorted_threads = Vec::with_capacity(self.threads.len());
-        let mut first_thread_index_per_process = vec![0; self.processes.len()];
 
         let mut sorted_processes: Vec<_> = (0..self.processes.len()).map(ProcessHandle).collect();
         sorted_processes.sort_by(|a_handle, b_handle| {
@@ -875,7 +1477,6 @@ impl Profile {
 
         for process in sorted_processes {
             let prev_len = sorted_threads.len();
-            first_thread_index_per_process[process.0] = prev_len;
             sorted_threads.extend_from_slice(self.processes[process.0].threads());
 
             let sorted_threads_for_this_process = &mut sorted_threads[prev_len..];
@@ -886,7 +1487,58 @@ impl Profile {
             });
         }
 
-        (sorted_threads, first_thread_index_per_process)
+        if let Some(track_order) = &self.track_order {
+            let thread_order: Vec<ThreadHandle> = track_order
+                .iter()
+                .filter_map(|track| match track {
+                    Track::Thread(thread) => Some(*thread),
+                    Track::Counter(_) => None,
+                })
+                .collect();
+            // The sort is stable, so unlisted threads keep their default order.
+            sorted_threads.sort_by_key(|thread| {
+                thread_order
+                    .iter()
+                    .position(|t| t == thread)
+                    .unwrap_or(thread_order.len())
+            });
+        }
+
+        // Counters refer to the serialized index of their process's main thread,
+        // or of the process's first thread if none of its threads is the main thread.
+        let mut main_thread_index_per_process = vec![0; self.processes.len()];
+        let mut has_main_thread = vec![false; self.processes.len()];
+        for (thread_index, thread) in sorted_threads.iter().enumerate().rev() {
+            let thread = &self.threads[thread.0];
+            let process = thread.process().0;
+            if thread.is_main() || !has_main_thread[process] {
+                main_thread_index_per_process[process] = thread_index;
+                has_main_thread[process] |= thread.is_main();
+            }
+        }
+
+        (sorted_threads, main_thread_index_per_process)
+    }
+
+    /// Returns the counters in the order in which they should be serialized.
+    fn sorted_counters(&self) -> Vec<CounterHandle> {
+        let mut sorted_counters: Vec<_> = (0..self.counters.len()).map(CounterHandle).collect();
+        if let Some(track_order) = &self.track_order {
+            let counter_order: Vec<CounterHandle> = track_order
+                .iter()
+                .filter_map(|track| match track {
+                    Track::Counter(counter) => Some(*counter),
+                    Track::Thread(_) => None,
+                })
+                .collect();
+            sorted_counters.sort_by_key(|counter| {
+                counter_order
+                    .iter()
+                    .position(|c| c == counter)
+                    .unwrap_or(counter_order.len())
+            });
+        }
+        sorted_counters
     }
 
     fn serializable_threads<'a>(
@@ -905,11 +1557,13 @@ impl Profile {
 
     fn serializable_counters<'a>(
         &'a self,
-        first_thread_index_per_process: &'a [usize],
+        sorted_counters: &'a [CounterHandle],
+        main_thread_index_per_process: &'a [usize],
     ) -> SerializableProfileCountersProperty<'a> {
         SerializableProfileCountersProperty {
             counters: &self.counters,
-            first_thread_index_per_process,
+            sorted_counters,
+            main_thread_index_per_process,
         }
     }
 
@@ -917,29 +1571,71 @@ impl Profile {
         self.threads.iter().any(|t| t.contains_js_function())
     }
 
+    fn uses_frame_implementation(&self) -> bool {
+        self.threads.iter().any(|t| t.uses_frame_implementation())
+    }
+
     pub fn lib_used_rva_iter(&self) -> UsedLibraryAddressesIterator {
         self.global_libs.lib_used_rva_iter()
     }
+
+    /// Create a copy of this profile with the symbol information from `info`
+    /// applied to it. The returned profile is marked as symbolicated.
+    ///
+    /// Each [`LibSymbolInfo`] is matched to a library in this profile by its
+    /// `debug_name` and `debug_id`. Then every frame in that library whose
+    /// relative address has an entry in `address_infos` gets its function
+    /// name, file and line replaced. The name, file and line are taken from
+    /// the last (outermost) [`FrameSymbolInfo`](crate::FrameSymbolInfo) of
+    /// the [`AddressInfo`](crate::AddressInfo), with the function name falling
+    /// back to the `symbol_name`. Inlined calls are not expanded into separate
+    /// frames.
+    ///
+    /// Frames without a matching entry are left unchanged.
+    pub fn make_symbolicated_profile(&self, info: &ProfileSymbolInfo) -> Profile {
+        let lib_symbols: FastHashMap<GlobalLibIndex, &LibSymbolInfo> = self
+            .global_libs
+            .used_libs()
+            .filter_map(|(lib_index, lib)| {
+                let lib_symbol_info = info.lib_symbols.iter().find(|lib_symbol_info| {
+                    lib_symbol_info.debug_name == lib.debug_name
+                        && lib_symbol_info.debug_id == lib.debug_id
+                })?;
+                Some((lib_index, lib_symbol_info))
+            })
+            .collect();
+
+        let mut profile = self.clone();
+        for thread in &mut profile.threads {
+            thread.symbolicate(&lib_symbols);
+        }
+        profile.symbolicated = true;
+        profile
+    }
 }
 
 impl Serialize for Profile {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let (sorted_threads, first_thread_index_per_process) = self.sorted_threads();
+        let (sorted_threads, main_thread_index_per_process) = self.sorted_threads();
+        let sorted_counters = self.sorted_counters();
         let mut map = serializer.serialize_map(None)?;
-        map.serialize_entry("meta", &SerializableProfileMeta(self))?;
+        map.serialize_entry(
+            "meta",
+            &SerializableProfileMeta(self, &sorted_threads, &sorted_counters),
+        )?;
         map.serialize_entry("libs", &self.global_libs)?;
         map.serialize_entry("threads", &self.serializable_threads(&sorted_threads))?;
         map.serialize_entry("pages", &[] as &[()])?;
         map.serialize_entry("profilerOverhead", &[] as &[()])?;
         map.serialize_entry(
             "counters",
-            &self.serializable_counters(&first_thread_index_per_process),
+            &self.serializable_counters(&sorted_counters, &main_thread_index_per_process),
         )?;
         map.end()
     }
 }
 
-struct SerializableProfileMeta<'a>(&'a Profile);
+struct SerializableProfileMeta<'a>(&'a Profile, &'a [ThreadHandle], &'a [CounterHandle]);
 
 impl Serialize for SerializableProfileMeta<'_> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
@@ -962,21 +1658,41 @@ impl Serialize for SerializableProfileMeta<'_> {
         if let Some(os_name) = &self.0.os_name {
             map.serialize_entry("oscpu", os_name)?;
         }
-        map.serialize_entry(
-            "sampleUnits",
-            &json!({
+        // Allocation profiles have no meaningful CPU deltas, so we don't declare
+        // a unit for them. This keeps the Firefox Profiler from drawing a CPU graph.
+        let sample_units = match self.0.sample_weight_type {
+            WeightType::Bytes => json!({
+                "time": "ms",
+                "eventDelay": "ms",
+            }),
+            _ => json!({
                 "time": "ms",
                 "eventDelay": "ms",
                 "threadCPUDelta": "µs",
             }),
-        )?;
+        };
+        map.serialize_entry("sampleUnits", &sample_units)?;
         map.serialize_entry("startTime", &self.0.reference_timestamp)?;
-        map.serialize_entry("symbolicated", &false)?;
+        map.serialize_entry("symbolicated", &self.0.symbolicated)?;
         map.serialize_entry("pausedRanges", &[] as &[()])?;
         map.serialize_entry("version", &24)?;
-        map.serialize_entry("usesOnlyOneStackType", &(!self.0.contains_js_function()))?;
-        map.serialize_entry("doesNotUseFrameImplementation", &true)?;
+        let uses_only_one_stack_type = self
+            .0
+            .uses_only_one_stack_type
+            .unwrap_or_else(|| !self.0.contains_js_function());
+        map.serialize_entry("usesOnlyOneStackType", &uses_only_one_stack_type)?;
+        map.serialize_entry(
+            "doesNotUseFrameImplementation",
+            &!self.0.uses_frame_implementation(),
+        )?;
         map.serialize_entry("sourceCodeIsNotOnSearchfox", &true)?;
+        if let Some(track_order) = &self.0.track_order {
+            map.serialize_entry("keepProfileThreadOrder", &true)?;
+            map.serialize_entry(
+                "trackOrder",
+                &SerializableTrackOrder(track_order, self.1, self.2),
+            )?;
+        }
 
         let mut marker_schemas: Vec<InternalMarkerSchema> = self.0.marker_schemas.clone();
         marker_schemas.sort_by(|a, b| a.type_name().cmp(b.type_name()));
@@ -986,6 +1702,46 @@ impl Serialize for SerializableProfileMeta<'_> {
     }
 }
 
+/// The global track order, as a list of `{ "type", "index" }` objects. The index
+/// refers to the serialized `threads` or `counters` array, depending on the type.
+struct SerializableTrackOrder<'a>(&'a [Track], &'a [ThreadHandle], &'a [CounterHandle]);
+
+impl Serialize for SerializableTrackOrder<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let SerializableTrackOrder(track_order, sorted_threads, sorted_counters) = *self;
+        let unlisted_threads = sorted_threads
+            .iter()
+            .filter(|thread| !track_order.contains(&Track::Thread(**thread)))
+            .map(|thread| Track::Thread(*thread));
+        let unlisted_counters = sorted_counters
+            .iter()
+            .filter(|counter| !track_order.contains(&Track::Counter(**counter)))
+            .map(|counter| Track::Counter(*counter));
+        let mut seq = serializer.serialize_seq(None)?;
+        for track in track_order
+            .iter()
+            .copied()
+            .chain(unlisted_threads)
+            .chain(unlisted_counters)
+        {
+            let entry = match track {
+                Track::Thread(thread) => sorted_threads
+                    .iter()
+                    .position(|t| *t == thread)
+                    .map(|index| json!({ "type": "thread", "index": index })),
+                Track::Counter(counter) => sorted_counters
+                    .iter()
+                    .position(|c| *c == counter)
+                    .map(|index| json!({ "type": "counter", "index": index })),
+            };
+            if let Some(entry) = entry {
+                seq.serialize_element(&entry)?;
+            }
+        }
+        seq.end()
+    }
+}
+
 struct SerializableProfileThreadsProperty<'a> {
     threads: &'a [Thread],
     processes: &'a [Process],
@@ -1020,15 +1776,17 @@ impl Serialize for SerializableProfileThreadsProperty<'_> {
 
 struct SerializableProfileCountersProperty<'a> {
     counters: &'a [Counter],
-    first_thread_index_per_process: &'a [usize],
+    sorted_counters: &'a [CounterHandle],
+    main_thread_index_per_process: &'a [usize],
 }
 
 impl Serialize for SerializableProfileCountersProperty<'_> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let mut seq = serializer.serialize_seq(Some(self.counters.len()))?;
 
-        for counter in self.counters {
-            let main_thread_index = self.first_thread_index_per_process[counter.process().0];
+        for counter in self.sorted_counters {
+            let counter = &self.counters[counter.0];
+            let main_thread_index = self.main_thread_index_per_process[counter.process().0];
             seq.serialize_element(&counter.as_serializable(main_thread_index))?;
         }
 
@@ -1064,6 +1822,8 @@ impl Serialize for SerializableProfileThread<'_> {
             process_end_time,
             process_name,
             pid,
+            process.cmdline(),
+            process.env(),
             marker_schemas,
             global_string_table,
         )
@@ -7,6 +7,8 @@ use serde::ser::{Serialize, Serializer};
 /// In the profile JSON, this uses a UNIX timestamp.
 ///
 /// All timestamps in the profile are relative to this reference timestamp.
+/// It is usually the time at which profiling started, but it can be any point
+/// in time, see [`Profile::set_reference_timestamp`](crate::Profile::set_reference_timestamp).
 #[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
 pub struct ReferenceTimestamp {
     ms_since_unix_epoch: f64,
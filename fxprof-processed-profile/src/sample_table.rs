@@ -20,6 +20,12 @@ pub struct SampleTable {
     sample_stack_indexes: Vec<Option<usize>>,
     /// CPU usage delta since the previous sample for this thread, for each sample.
     sample_cpu_deltas: Vec<CpuDelta>,
+    /// The event delay in milliseconds for each sample. Only present once an
+    /// event delay has been set for any sample; missing values are zero.
+    sample_event_delays: Option<Vec<f64>>,
+    /// Whether the thread was off-CPU for each sample. Only present once any
+    /// sample has been marked as off-CPU; missing values are false.
+    sample_is_off_cpu: Option<Vec<bool>>,
     sorted_by_time: bool,
     last_sample_timestamp: Timestamp,
 }
@@ -67,7 +73,7 @@ pub struct SampleTable {
 ///
 /// Documentation and code from:
 /// <https://github.com/firefox-devtools/profiler/blob/7bf02b3f747a33a8c166c533dc29304fde725517/src/types/profile.js#L127>
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WeightType {
     /// The weight is an integer multiplier.
     ///
@@ -105,13 +111,15 @@ impl Serialize for WeightType {
 }
 
 impl SampleTable {
-    pub fn new() -> Self {
+    pub fn new(sample_type: WeightType) -> Self {
         Self {
-            sample_type: WeightType::Samples,
+            sample_type,
             sample_weights: Vec::new(),
             sample_timestamps: Vec::new(),
             sample_stack_indexes: Vec::new(),
             sample_cpu_deltas: Vec::new(),
+            sample_event_delays: None,
+            sample_is_off_cpu: None,
             sorted_by_time: true,
             last_sample_timestamp: Timestamp::from_nanos_since_reference(0),
         }
@@ -128,49 +136,149 @@ impl SampleTable {
         self.sample_timestamps.push(timestamp);
         self.sample_stack_indexes.push(stack_index);
         self.sample_cpu_deltas.push(cpu_delta);
+        if let Some(event_delays) = &mut self.sample_event_delays {
+            event_delays.push(0.0);
+        }
+        if let Some(is_off_cpu) = &mut self.sample_is_off_cpu {
+            is_off_cpu.push(false);
+        }
         if timestamp < self.last_sample_timestamp {
             self.sorted_by_time = false;
         }
         self.last_sample_timestamp = timestamp;
     }
 
+    pub fn len(&self) -> usize {
+        self.sample_timestamps.len()
+    }
+
+    /// Reserve capacity for at least `additional` more samples.
+    pub fn reserve(&mut self, additional: usize) {
+        self.sample_weights.reserve(additional);
+        self.sample_timestamps.reserve(additional);
+        self.sample_stack_indexes.reserve(additional);
+        self.sample_cpu_deltas.reserve(additional);
+        if let Some(event_delays) = &mut self.sample_event_delays {
+            event_delays.reserve(additional);
+        }
+        if let Some(is_off_cpu) = &mut self.sample_is_off_cpu {
+            is_off_cpu.reserve(additional);
+        }
+    }
+
+    /// The timestamp of the most recently added sample, if any.
+    pub fn last_sample_timestamp(&self) -> Option<Timestamp> {
+        self.sample_timestamps.last().copied()
+    }
+
     pub fn modify_last_sample(&mut self, timestamp: Timestamp, weight: i32) {
         *self.sample_weights.last_mut().unwrap() += weight;
         *self.sample_timestamps.last_mut().unwrap() = timestamp;
+        let len = self.sample_timestamps.len();
+        if len >= 2 && timestamp < self.sample_timestamps[len - 2] {
+            self.sorted_by_time = false;
+        }
+        self.last_sample_timestamp = timestamp;
+    }
+
+    /// Whether all samples were added in timestamp order.
+    pub fn is_sorted_by_time(&self) -> bool {
+        self.sorted_by_time
+    }
+
+    /// Set the event delay of the most recently added sample, if there is one.
+    pub fn set_last_sample_event_delay(&mut self, event_delay_ms: f64) {
+        let len = self.sample_timestamps.len();
+        if len == 0 {
+            return;
+        }
+        let event_delays = self
+            .sample_event_delays
+            .get_or_insert_with(|| vec![0.0; len]);
+        event_delays[len - 1] = event_delay_ms;
+    }
+
+    /// Mark the most recently added sample as off-CPU, if there is one.
+    pub fn set_last_sample_off_cpu(&mut self) {
+        let len = self.sample_timestamps.len();
+        if len == 0 {
+            return;
+        }
+        let is_off_cpu = self
+            .sample_is_off_cpu
+            .get_or_insert_with(|| vec![false; len]);
+        is_off_cpu[len - 1] = true;
+    }
+
+    pub fn as_serializable(&self, include_event_delay: bool) -> impl Serialize + '_ {
+        SerializableSampleTable {
+            table: self,
+            include_event_delay,
+        }
     }
 }
 
-impl Serialize for SampleTable {
+struct SerializableSampleTable<'a> {
+    table: &'a SampleTable,
+    include_event_delay: bool,
+}
+
+impl Serialize for SerializableSampleTable<'_> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let len = self.sample_timestamps.len();
+        let len = self.table.sample_timestamps.len();
+        let zero_event_delays;
+        let event_delays: Option<&[f64]> =
+            match (self.include_event_delay, &self.table.sample_event_delays) {
+                (false, _) => None,
+                (true, Some(event_delays)) => Some(event_delays),
+                (true, None) => {
+                    zero_event_delays = vec![0.0; len];
+                    Some(&zero_event_delays)
+                }
+            };
+        let table = self.table;
         let mut map = serializer.serialize_map(None)?;
         map.serialize_entry("length", &len)?;
-        map.serialize_entry("weightType", &self.sample_type.to_string())?;
+        map.serialize_entry("weightType", &table.sample_type)?;
 
-        if self.sorted_by_time {
-            map.serialize_entry("stack", &self.sample_stack_indexes)?;
-            map.serialize_entry("time", &self.sample_timestamps)?;
-            map.serialize_entry("weight", &self.sample_weights)?;
-            map.serialize_entry("threadCPUDelta", &self.sample_cpu_deltas)?;
+        if table.sorted_by_time {
+            map.serialize_entry("stack", &table.sample_stack_indexes)?;
+            map.serialize_entry("time", &table.sample_timestamps)?;
+            map.serialize_entry("weight", &table.sample_weights)?;
+            map.serialize_entry("threadCPUDelta", &table.sample_cpu_deltas)?;
+            if let Some(event_delays) = event_delays {
+                map.serialize_entry("eventDelay", event_delays)?;
+            }
+            if let Some(is_off_cpu) = &table.sample_is_off_cpu {
+                map.serialize_entry("isOffCpu", is_off_cpu)?;
+            }
         } else {
-            let mut indexes: Vec<usize> = (0..self.sample_timestamps.len()).collect();
-            indexes.sort_unstable_by_key(|index| self.sample_timestamps[*index]);
+            let mut indexes: Vec<usize> = (0..table.sample_timestamps.len()).collect();
+            // Use a stable sort, so that samples with the same timestamp keep
+            // the order in which they were added, and the output is reproducible.
+            indexes.sort_by_key(|index| table.sample_timestamps[*index]);
             map.serialize_entry(
                 "stack",
-                &SliceWithPermutation(&self.sample_stack_indexes, &indexes),
+                &SliceWithPermutation(&table.sample_stack_indexes, &indexes),
             )?;
             map.serialize_entry(
                 "time",
-                &SliceWithPermutation(&self.sample_timestamps, &indexes),
+                &SliceWithPermutation(&table.sample_timestamps, &indexes),
             )?;
             map.serialize_entry(
                 "weight",
-                &SliceWithPermutation(&self.sample_weights, &indexes),
+                &SliceWithPermutation(&table.sample_weights, &indexes),
             )?;
             map.serialize_entry(
                 "threadCPUDelta",
-                &SliceWithPermutation(&self.sample_cpu_deltas, &indexes),
+                &SliceWithPermutation(&table.sample_cpu_deltas, &indexes),
             )?;
+            if let Some(event_delays) = event_delays {
+                map.serialize_entry("eventDelay", &SliceWithPermutation(event_delays, &indexes))?;
+            }
+            if let Some(is_off_cpu) = &table.sample_is_off_cpu {
+                map.serialize_entry("isOffCpu", &SliceWithPermutation(is_off_cpu, &indexes))?;
+            }
         }
         map.end()
     }
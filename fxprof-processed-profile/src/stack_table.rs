@@ -60,6 +60,10 @@ impl StackTable {
         Default::default()
     }
 
+    pub fn len(&self) -> usize {
+        self.stack_frames.len()
+    }
+
     pub fn index_for_stack(
         &mut self,
         prefix: Option<usize>,
@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+use debugid::DebugId;
+
+/// Symbolication results for a profile, to be applied with
+/// [`Profile::make_symbolicated_profile`](crate::Profile::make_symbolicated_profile).
+///
+/// The addresses which need to be symbolicated can be obtained with
+/// [`Profile::lib_used_rva_iter`](crate::Profile::lib_used_rva_iter).
+#[derive(Debug, Clone, Default)]
+pub struct ProfileSymbolInfo {
+    /// The symbol information for each library. Libraries without an entry
+    /// are left unsymbolicated.
+    pub lib_symbols: Vec<LibSymbolInfo>,
+}
+
+/// Symbol information for the addresses of a single library.
+///
+/// The library is matched against the profile's libraries by
+/// `(debug_name, debug_id)`, i.e. by the same fields that are used as the
+/// library identifier in [`LibraryInfo`](crate::LibraryInfo).
+#[derive(Debug, Clone)]
+pub struct LibSymbolInfo {
+    /// The library's debug name, matching [`LibraryInfo::debug_name`](crate::LibraryInfo::debug_name).
+    pub debug_name: String,
+    /// The library's debug ID, matching [`LibraryInfo::debug_id`](crate::LibraryInfo::debug_id).
+    pub debug_id: DebugId,
+    /// The symbol information for each address, keyed by the address relative
+    /// to the library's base address. This is the same relative address that
+    /// is used by [`Frame::RelativeAddressFromInstructionPointer`](crate::Frame::RelativeAddressFromInstructionPointer)
+    /// and friends, i.e. after any return address adjustment.
+    pub address_infos: BTreeMap<u32, AddressInfo>,
+}
+
+/// The symbol information for a single address.
+#[derive(Debug, Clone)]
+pub struct AddressInfo {
+    /// The name of the function containing this address, usually from the
+    /// library's symbol table.
+    pub symbol_name: String,
+    /// The debug info for this address, if available, ordered from the
+    /// innermost inlined call to the outer function. May be empty.
+    pub frames: Vec<FrameSymbolInfo>,
+}
+
+/// The debug info for one (possibly inlined) function call at an address.
+#[derive(Debug, Clone, Default)]
+pub struct FrameSymbolInfo {
+    /// The function name, if known.
+    pub function_name: Option<String>,
+    /// The source file path, if known.
+    pub file_path: Option<String>,
+    /// The line number, if known.
+    pub line_number: Option<u32>,
+}
@@ -5,16 +5,18 @@ use serde::ser::{SerializeMap, Serializer};
 
 use crate::category::{Category, CategoryPairHandle};
 use crate::cpu_delta::CpuDelta;
+use crate::fast_hash_map::FastHashMap;
 use crate::frame_table::{FrameTable, InternalFrame};
 use crate::func_table::FuncTable;
-use crate::global_lib_table::GlobalLibTable;
+use crate::global_lib_table::{GlobalLibIndex, GlobalLibTable};
 use crate::marker_table::MarkerTable;
 use crate::markers::InternalMarkerSchema;
 use crate::native_symbols::NativeSymbols;
 use crate::resource_table::ResourceTable;
-use crate::sample_table::{NativeAllocationsTable, SampleTable};
+use crate::sample_table::{NativeAllocationsTable, SampleTable, WeightType};
 use crate::stack_table::StackTable;
 use crate::string_table::{GlobalStringIndex, GlobalStringTable};
+use crate::symbol_info::LibSymbolInfo;
 use crate::thread_string_table::{ThreadInternalStringIndex, ThreadStringTable};
 use crate::{CategoryHandle, Marker, MarkerHandle, MarkerTiming, MarkerTypeHandle, Timestamp};
 
@@ -22,14 +24,15 @@ use crate::{CategoryHandle, Marker, MarkerHandle, MarkerTiming, MarkerTypeHandle
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct ProcessHandle(pub(crate) usize);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Thread {
     process: ProcessHandle,
     tid: String,
     name: Option<String>,
-    start_time: Timestamp,
+    start_time: Option<Timestamp>,
     end_time: Option<Timestamp>,
     is_main: bool,
+    is_main_event_loop: bool,
     stack_table: StackTable,
     frame_table: FrameTable,
     func_table: FuncTable,
@@ -44,7 +47,13 @@ pub struct Thread {
 }
 
 impl Thread {
-    pub fn new(process: ProcessHandle, tid: String, start_time: Timestamp, is_main: bool) -> Self {
+    pub fn new(
+        process: ProcessHandle,
+        tid: String,
+        start_time: Option<Timestamp>,
+        is_main: bool,
+        sample_weight_type: WeightType,
+    ) -> Self {
         Self {
             process,
             tid,
@@ -52,10 +61,11 @@ impl Thread {
             start_time,
             end_time: None,
             is_main,
+            is_main_event_loop: false,
             stack_table: StackTable::new(),
             frame_table: FrameTable::new(),
             func_table: FuncTable::new(),
-            samples: SampleTable::new(),
+            samples: SampleTable::new(sample_weight_type),
             native_allocations: None,
             markers: MarkerTable::new(),
             resources: ResourceTable::new(),
@@ -71,17 +81,37 @@ impl Thread {
     }
 
     pub fn set_start_time(&mut self, start_time: Timestamp) {
-        self.start_time = start_time;
+        self.start_time = Some(start_time);
     }
 
     pub fn set_end_time(&mut self, end_time: Timestamp) {
         self.end_time = Some(end_time);
     }
 
+    pub fn tid(&self) -> &str {
+        &self.tid
+    }
+
+    pub fn is_main(&self) -> bool {
+        self.is_main
+    }
+
     pub fn set_tid(&mut self, tid: String) {
         self.tid = tid;
     }
 
+    pub fn set_is_main(&mut self, is_main: bool) {
+        self.is_main = is_main;
+    }
+
+    pub fn set_is_main_event_loop(&mut self, is_main_event_loop: bool) {
+        self.is_main_event_loop = is_main_event_loop;
+    }
+
+    pub fn set_process(&mut self, process: ProcessHandle) {
+        self.process = process;
+    }
+
     pub fn process(&self) -> ProcessHandle {
         self.process
     }
@@ -110,6 +140,11 @@ impl Thread {
         )
     }
 
+    pub fn symbolicate(&mut self, lib_symbols: &FastHashMap<GlobalLibIndex, &LibSymbolInfo>) {
+        self.frame_table
+            .symbolicate(&mut self.string_table, &mut self.func_table, lib_symbols);
+    }
+
     pub fn get_frame_category(&self, frame: usize) -> CategoryPairHandle {
         self.frame_table.get_category(frame)
     }
@@ -137,6 +172,48 @@ impl Thread {
         self.last_sample_was_zero_cpu = cpu_delta == CpuDelta::ZERO;
     }
 
+    pub fn add_off_cpu_sample(
+        &mut self,
+        timestamp: Timestamp,
+        stack_index: Option<usize>,
+        weight: i32,
+    ) {
+        self.add_sample(timestamp, stack_index, CpuDelta::ZERO, weight);
+        self.samples.set_last_sample_off_cpu();
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn stack_count(&self) -> usize {
+        self.stack_table.len()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_table.len()
+    }
+
+    pub fn marker_count(&self) -> usize {
+        self.markers.len()
+    }
+
+    pub fn reserve_samples(&mut self, additional: usize) {
+        self.samples.reserve(additional);
+    }
+
+    pub fn last_sample_timestamp(&self) -> Option<Timestamp> {
+        self.samples.last_sample_timestamp()
+    }
+
+    pub fn samples_are_sorted_by_time(&self) -> bool {
+        self.samples.is_sorted_by_time()
+    }
+
+    pub fn set_last_sample_event_delay(&mut self, event_delay_ms: f64) {
+        self.samples.set_last_sample_event_delay(event_delay_ms);
+    }
+
     pub fn add_allocation_sample(
         &mut self,
         timestamp: Timestamp,
@@ -185,6 +262,10 @@ impl Thread {
         )
     }
 
+    pub fn set_marker_thread_id(&mut self, marker: MarkerHandle, tid: String) {
+        self.markers.set_marker_thread_id(marker, tid);
+    }
+
     pub fn set_marker_stack(&mut self, marker: MarkerHandle, stack_index: Option<usize>) {
         self.markers.set_marker_stack(marker, stack_index);
     }
@@ -193,6 +274,10 @@ impl Thread {
         self.func_table.contains_js_function()
     }
 
+    pub fn uses_frame_implementation(&self) -> bool {
+        self.frame_table.uses_frame_implementation()
+    }
+
     pub fn cmp_for_json_order(&self, other: &Thread) -> Ordering {
         let ordering = (!self.is_main).cmp(&(!other.is_main));
         if ordering != Ordering::Equal {
@@ -215,10 +300,12 @@ impl Thread {
         &self,
         serializer: S,
         categories: &[Category],
-        process_start_time: Timestamp,
+        process_start_time: Option<Timestamp>,
         process_end_time: Option<Timestamp>,
         process_name: &str,
         pid: &str,
+        process_cmdline: Option<&str>,
+        process_env: Option<&[(String, String)]>,
         marker_schemas: &[InternalMarkerSchema],
         global_string_table: &GlobalStringTable,
     ) -> Result<S::Ok, S::Error> {
@@ -246,12 +333,21 @@ impl Thread {
         map.serialize_entry("pausedRanges", &[] as &[()])?;
         map.serialize_entry("pid", &pid)?;
         map.serialize_entry("processName", process_name)?;
+        if let Some(cmdline) = process_cmdline {
+            map.serialize_entry("processCommandLine", cmdline)?;
+        }
+        if let Some(env) = process_env {
+            map.serialize_entry("processEnvironment", env)?;
+        }
         map.serialize_entry("processShutdownTime", &process_end_time)?;
         map.serialize_entry("processStartupTime", &process_start_time)?;
         map.serialize_entry("processType", &"default")?;
         map.serialize_entry("registerTime", &thread_register_time)?;
         map.serialize_entry("resourceTable", &self.resources)?;
-        map.serialize_entry("samples", &self.samples)?;
+        map.serialize_entry(
+            "samples",
+            &self.samples.as_serializable(self.is_main_event_loop),
+        )?;
         if let Some(allocations) = &self.native_allocations {
             map.serialize_entry("nativeAllocations", &allocations)?;
         }
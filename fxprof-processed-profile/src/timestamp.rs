@@ -1,8 +1,25 @@
+use std::ops::Sub;
+use std::time::Duration;
+
 use serde::ser::{Serialize, Serializer};
 
 /// The type used for sample and marker timestamps.
 ///
 /// Timestamps in the profile are stored in reference to the profile's [`ReferenceTimestamp`](crate::ReferenceTimestamp).
+///
+/// Timestamps have nanosecond precision. Subtracting two timestamps gives the
+/// [`Duration`] between them, which avoids a lossy round-trip through float
+/// milliseconds:
+///
+/// ```
+/// use fxprof_processed_profile::Timestamp;
+/// use std::time::Duration;
+///
+/// let start = Timestamp::from_nanos_since_reference(1_000);
+/// let end = start.saturating_add(Duration::from_nanos(2_500));
+/// assert_eq!(end - start, Duration::from_nanos(2_500));
+/// assert!(start < end);
+/// ```
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct Timestamp {
     nanos: u64,
@@ -18,6 +35,25 @@ impl Timestamp {
             nanos: (millis * 1_000_000.0) as u64,
         }
     }
+
+    /// Returns the timestamp which is `duration` after this one, or the
+    /// maximum representable timestamp if that would overflow.
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        Self {
+            nanos: self.nanos.saturating_add(nanos),
+        }
+    }
+}
+
+/// Returns the duration between two timestamps. If `rhs` is later than `self`,
+/// the result is zero, like for [`std::time::Instant`].
+impl Sub<Timestamp> for Timestamp {
+    type Output = Duration;
+
+    fn sub(self, rhs: Timestamp) -> Duration {
+        Duration::from_nanos(self.nanos.saturating_sub(rhs.nanos))
+    }
 }
 
 impl Serialize for Timestamp {
@@ -4,10 +4,12 @@ use std::time::Duration;
 use assert_json_diff::assert_json_eq;
 use debugid::DebugId;
 use fxprof_processed_profile::{
-    CategoryColor, CategoryHandle, CpuDelta, Frame, FrameFlags, FrameInfo, LibraryInfo,
-    MarkerFieldFormat, MarkerFieldSchema, MarkerLocation, MarkerSchema, MarkerStaticField,
-    MarkerTiming, Profile, ReferenceTimestamp, SamplingInterval, StaticSchemaMarker, StringHandle,
-    Symbol, SymbolTable, Timestamp,
+    AddressInfo, CategoryColor, CategoryHandle, CategoryPairHandle, CpuDelta, Frame, FrameFlags,
+    FrameInfo, FrameSymbolInfo, InvalidHandleError, LibDeduplicationPolicy, LibSymbolInfo,
+    LibraryInfo, MarkerFieldFormat, MarkerFieldSchema, MarkerLocation, MarkerSchema,
+    MarkerStaticField, MarkerTiming, OutOfOrderSampleError, OutOfOrderSamplePolicy, Profile,
+    ProfileSymbolInfo, ReferenceTimestamp, SamplingInterval, SourceLocation, StaticSchemaMarker,
+    StringHandle, Symbol, SymbolTable, Timestamp, Track,
 };
 use serde_json::json;
 
@@ -24,20 +26,11 @@ impl StaticSchemaMarker for TextMarker {
     const UNIQUE_MARKER_TYPE_NAME: &'static str = "Text";
 
     fn schema() -> MarkerSchema {
-        MarkerSchema {
-            type_name: Self::UNIQUE_MARKER_TYPE_NAME.into(),
-            locations: vec![MarkerLocation::MarkerChart, MarkerLocation::MarkerTable],
-            chart_label: Some("{marker.data.name}".into()),
-            tooltip_label: None,
-            table_label: Some("{marker.name} - {marker.data.name}".into()),
-            fields: vec![MarkerFieldSchema {
-                key: "name".into(),
-                label: "Details".into(),
-                format: MarkerFieldFormat::String,
-                searchable: true,
-            }],
-            static_fields: vec![],
-        }
+        MarkerSchema::new(Self::UNIQUE_MARKER_TYPE_NAME)
+            .with_locations(&[MarkerLocation::MarkerChart, MarkerLocation::MarkerTable])
+            .with_chart_label("{marker.data.name}")
+            .with_table_label("{marker.name} - {marker.data.name}")
+            .with_searchable_field("name", "Details", MarkerFieldFormat::String)
     }
 
     fn name(&self, _profile: &mut Profile) -> StringHandle {
@@ -230,6 +223,8 @@ fn profile_without_js() {
             frame,
             category_pair: category.into(),
             flags: FrameFlags::empty(),
+            implementation: None,
+            source_location: SourceLocation::default(),
         }),
     );
     profile.add_sample(
@@ -264,6 +259,8 @@ fn profile_without_js() {
             frame,
             category_pair: category.into(),
             flags: FrameFlags::empty(),
+            implementation: None,
+            source_location: SourceLocation::default(),
         }),
     );
     profile.add_sample(
@@ -298,6 +295,8 @@ fn profile_without_js() {
             frame,
             category_pair: category.into(),
             flags: FrameFlags::empty(),
+            implementation: None,
+            source_location: SourceLocation::default(),
         }),
     );
     profile.add_sample(
@@ -1067,11 +1066,15 @@ fn profile_with_js() {
                 frame: Frame::Label(some_label_string),
                 category_pair: category.into(),
                 flags: FrameFlags::IS_JS,
+                implementation: None,
+                source_location: SourceLocation::default(),
             },
             FrameInfo {
                 frame: Frame::ReturnAddress(0x7f76b7ffc0e7),
                 category_pair: category.into(),
                 flags: FrameFlags::empty(),
+                implementation: None,
+                source_location: SourceLocation::default(),
             },
         ]
         .into_iter(),
@@ -1602,3 +1605,1384 @@ fn profile_counters_with_sorted_processes() {
         )
     )
 }
+
+#[test]
+fn profile_with_track_order() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process0 = profile.add_process("test 1", 123, Timestamp::from_millis_since_reference(0.0));
+    let process1 = profile.add_process("test 2", 124, Timestamp::from_millis_since_reference(1.0));
+    profile.add_thread(
+        process0,
+        12345,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let thread1 = profile.add_thread(
+        process0,
+        12346,
+        Timestamp::from_millis_since_reference(0.0),
+        false,
+    );
+    let thread2 = profile.add_thread(
+        process1,
+        54321,
+        Timestamp::from_millis_since_reference(1.0),
+        true,
+    );
+    profile.set_thread_name(thread1, "Worker");
+    profile.add_counter(process0, "malloc", "Memory 1", "Allocated memory");
+    let counter1 = profile.add_counter(process1, "malloc", "Memory 2", "Allocated memory");
+
+    profile.set_track_order(&[
+        Track::Thread(thread2),
+        Track::Counter(counter1),
+        Track::Thread(thread1),
+    ]);
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(json["meta"]["keepProfileThreadOrder"], json!(true));
+    let thread_tids: Vec<_> = json["threads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|thread| thread["tid"].clone())
+        .collect();
+    assert_eq!(
+        thread_tids,
+        vec![json!("54321"), json!("12346"), json!("12345")]
+    );
+    let counters = json["counters"].as_array().unwrap();
+    assert_eq!(counters[0]["category"], json!("Memory 2"));
+    assert_eq!(counters[0]["mainThreadIndex"], json!(0));
+    assert_eq!(counters[1]["category"], json!("Memory 1"));
+    // The main thread of "test 1" was not listed, so it comes last.
+    assert_eq!(counters[1]["mainThreadIndex"], json!(2));
+
+    // The counter is placed between the two listed threads, and the unlisted
+    // tracks come last: threads first, then counters.
+    assert_eq!(
+        json["meta"]["trackOrder"],
+        json!([
+            { "type": "thread", "index": 0 },
+            { "type": "counter", "index": 0 },
+            { "type": "thread", "index": 1 },
+            { "type": "thread", "index": 2 },
+            { "type": "counter", "index": 1 },
+        ])
+    );
+}
+
+#[test]
+fn profile_make_symbolicated() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        12345,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let debug_id = DebugId::from_breakpad("1629FCF0BE5C8860C0E1ADF03B0048FB0").unwrap();
+    let lib = profile.add_lib(LibraryInfo {
+        name: "libfoo.so".to_string(),
+        debug_name: "libfoo.so".to_string(),
+        path: "/usr/lib/libfoo.so".to_string(),
+        debug_path: "/usr/lib/libfoo.so".to_string(),
+        debug_id,
+        code_id: None,
+        arch: None,
+        symbol_table: None,
+    });
+    let category = CategoryHandle::OTHER.into();
+    let frame0 = profile.handle_for_frame_with_address_in_lib(
+        thread,
+        lib,
+        0x10,
+        category,
+        FrameFlags::empty(),
+    );
+    let frame1 = profile.handle_for_frame_with_address_in_lib(
+        thread,
+        lib,
+        0x20,
+        category,
+        FrameFlags::empty(),
+    );
+    let frame2 = profile.handle_for_frame_with_address_in_lib(
+        thread,
+        lib,
+        0x30,
+        category,
+        FrameFlags::empty(),
+    );
+    let stack0 = profile.intern_stack(thread, None, frame0);
+    let stack1 = profile.intern_stack(thread, Some(stack0), frame1);
+    let stack2 = profile.intern_stack(thread, Some(stack1), frame2);
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        Some(stack2),
+        CpuDelta::ZERO,
+        1,
+    );
+
+    let info = ProfileSymbolInfo {
+        lib_symbols: vec![LibSymbolInfo {
+            debug_name: "libfoo.so".to_string(),
+            debug_id,
+            address_infos: [
+                (
+                    0x10,
+                    AddressInfo {
+                        symbol_name: "main".to_string(),
+                        frames: vec![],
+                    },
+                ),
+                (
+                    0x20,
+                    AddressInfo {
+                        symbol_name: "_ZN3foo3barEv".to_string(),
+                        frames: vec![
+                            FrameSymbolInfo {
+                                function_name: Some("foo::inlined".to_string()),
+                                file_path: Some("src/inlined.rs".to_string()),
+                                line_number: Some(5),
+                            },
+                            FrameSymbolInfo {
+                                function_name: Some("foo::bar".to_string()),
+                                file_path: Some("src/foo.rs".to_string()),
+                                line_number: Some(42),
+                            },
+                        ],
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }],
+    };
+
+    let original_json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(original_json["meta"]["symbolicated"], json!(false));
+
+    let symbolicated = profile.make_symbolicated_profile(&info);
+    let json = serde_json::to_value(&symbolicated).unwrap();
+    assert_eq!(json["meta"]["symbolicated"], json!(true));
+
+    let thread = &json["threads"][0];
+    let strings = thread["stringArray"].as_array().unwrap();
+    let frame_table = &thread["frameTable"];
+    let func_table = &thread["funcTable"];
+    let func_name = |frame_index: usize| {
+        let func = frame_table["func"][frame_index].as_u64().unwrap() as usize;
+        strings[func_table["name"][func].as_u64().unwrap() as usize].clone()
+    };
+    let func_file = |frame_index: usize| {
+        let func = frame_table["func"][frame_index].as_u64().unwrap() as usize;
+        match func_table["fileName"][func].as_u64() {
+            Some(file) => strings[file as usize].clone(),
+            None => json!(null),
+        }
+    };
+    assert_eq!(func_name(0), json!("main"));
+    assert_eq!(func_file(0), json!(null));
+    assert_eq!(frame_table["line"][0], json!(null));
+    assert_eq!(func_name(1), json!("foo::bar"));
+    assert_eq!(func_file(1), json!("src/foo.rs"));
+    assert_eq!(frame_table["line"][1], json!(42));
+    assert_eq!(func_name(2), json!("0x30"));
+    assert_eq!(frame_table["line"][2], json!(null));
+
+    // The original profile is unchanged.
+    assert_eq!(serde_json::to_value(&profile).unwrap(), original_json);
+}
+
+#[test]
+fn profile_with_main_event_loop_thread() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let main_thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let event_loop_thread = profile.add_thread(
+        process,
+        124,
+        Timestamp::from_millis_since_reference(0.0),
+        false,
+    );
+    profile.set_thread_name(event_loop_thread, "tokio-runtime-worker");
+    profile.set_thread_is_main_event_loop(event_loop_thread, true);
+
+    for thread in [main_thread, event_loop_thread] {
+        profile.add_sample(
+            thread,
+            Timestamp::from_millis_since_reference(1.0),
+            None,
+            CpuDelta::ZERO,
+            1,
+        );
+        profile.add_sample(
+            thread,
+            Timestamp::from_millis_since_reference(2.0),
+            None,
+            CpuDelta::ZERO,
+            1,
+        );
+        profile.set_last_sample_event_delay(thread, 75.0);
+        profile.add_sample(
+            thread,
+            Timestamp::from_millis_since_reference(3.0),
+            None,
+            CpuDelta::ZERO,
+            1,
+        );
+    }
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let threads = json["threads"].as_array().unwrap();
+    assert_eq!(threads[0]["tid"], json!("123"));
+    assert_eq!(threads[0]["isMainThread"], json!(true));
+    assert_eq!(threads[0]["samples"].get("eventDelay"), None);
+    assert_eq!(threads[1]["tid"], json!("124"));
+    assert_eq!(threads[1]["isMainThread"], json!(false));
+    assert_eq!(threads[1]["samples"]["eventDelay"], json!([0.0, 75.0, 0.0]));
+}
+
+fn make_profile_with_out_of_order_samples(policy: OutOfOrderSamplePolicy) -> Profile {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    profile.set_out_of_order_sample_policy(policy);
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    for (time, weight) in [(1.0, 1), (3.0, 3)] {
+        profile.add_sample(
+            thread,
+            Timestamp::from_millis_since_reference(time),
+            None,
+            CpuDelta::ZERO,
+            weight,
+        );
+    }
+    assert!(!profile.thread_has_out_of_order_samples(thread));
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(2.0),
+        None,
+        CpuDelta::from_micros(10),
+        2,
+    );
+    assert!(profile.thread_has_out_of_order_samples(thread));
+    profile
+}
+
+#[test]
+fn profile_with_out_of_order_samples() {
+    let profile = make_profile_with_out_of_order_samples(OutOfOrderSamplePolicy::SortOnSerialize);
+    let json = serde_json::to_value(&profile).unwrap();
+    let samples = &json["threads"][0]["samples"];
+    assert_eq!(samples["time"], json!([1.0, 2.0, 3.0]));
+    assert_eq!(samples["weight"], json!([1, 2, 3]));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "out of order")]
+fn profile_with_out_of_order_samples_debug_assert() {
+    make_profile_with_out_of_order_samples(OutOfOrderSamplePolicy::DebugAssert);
+}
+
+#[test]
+fn try_add_sample_in_order_rejects_out_of_order_samples() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    for time in [1.0, 3.0, 3.0] {
+        profile
+            .try_add_sample_in_order(
+                thread,
+                Timestamp::from_millis_since_reference(time),
+                None,
+                CpuDelta::ZERO,
+                1,
+            )
+            .unwrap();
+    }
+    assert_eq!(
+        profile.try_add_sample_in_order(
+            thread,
+            Timestamp::from_millis_since_reference(2.0),
+            None,
+            CpuDelta::ZERO,
+            1,
+        ),
+        Err(OutOfOrderSampleError {
+            thread,
+            timestamp: Timestamp::from_millis_since_reference(2.0),
+            previous_timestamp: Timestamp::from_millis_since_reference(3.0),
+        })
+    );
+    assert_eq!(profile.thread_sample_count(thread), 3);
+    assert!(!profile.thread_has_out_of_order_samples(thread));
+}
+
+#[test]
+fn allocation_profile() {
+    let mut profile = Profile::new_allocation_profile(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        None,
+        CpuDelta::ZERO,
+        4096,
+    );
+    profile.add_sample_same_stack_zero_cpu(thread, Timestamp::from_millis_since_reference(2.0), 96);
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(
+        json["meta"]["sampleUnits"],
+        json!({ "time": "ms", "eventDelay": "ms" })
+    );
+    let samples = &json["threads"][0]["samples"];
+    assert_eq!(samples["weightType"], json!("bytes"));
+    assert_eq!(samples["weight"], json!([4192]));
+}
+
+#[test]
+fn profile_with_changed_reference_timestamp() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(5.0),
+        None,
+        CpuDelta::ZERO,
+        1,
+    );
+    profile.set_reference_timestamp(ReferenceTimestamp::from_millis_since_unix_epoch(
+        1636162232000.0,
+    ));
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(json["meta"]["startTime"], json!(1636162232000.0));
+    assert_eq!(json["threads"][0]["samples"]["time"], json!([5.0]));
+}
+
+#[test]
+fn profile_with_process_cmdline_and_env() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let other_process =
+        profile.add_process("other", 456, Timestamp::from_millis_since_reference(1.0));
+    profile.add_thread(
+        other_process,
+        456,
+        Timestamp::from_millis_since_reference(1.0),
+        true,
+    );
+    profile.set_process_cmdline(process, "test --flag value");
+    profile.set_process_env(process, vec![("RUST_LOG".into(), "debug".into())]);
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let threads = json["threads"].as_array().unwrap();
+    assert_eq!(threads[0]["processCommandLine"], json!("test --flag value"));
+    assert_eq!(
+        threads[0]["processEnvironment"],
+        json!([["RUST_LOG", "debug"]])
+    );
+    assert_eq!(threads[1].get("processCommandLine"), None);
+    assert_eq!(threads[1].get("processEnvironment"), None);
+}
+
+#[test]
+fn profile_with_off_cpu_samples() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        None,
+        CpuDelta::from_micros(1000),
+        1,
+    );
+    profile.add_off_cpu_sample(thread, Timestamp::from_millis_since_reference(2.0), None, 1);
+    profile.add_sample_same_stack_zero_cpu(thread, Timestamp::from_millis_since_reference(3.0), 1);
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(4.0),
+        None,
+        CpuDelta::ZERO,
+        1,
+    );
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let samples = &json["threads"][0]["samples"];
+    assert_eq!(samples["weight"], json!([1, 2, 1]));
+    assert_eq!(samples["isOffCpu"], json!([false, true, false]));
+}
+
+#[test]
+fn profile_with_reserved_samples() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    profile.reserve_samples(thread, 1000);
+    for i in 0..3 {
+        profile.add_sample(
+            thread,
+            Timestamp::from_millis_since_reference(f64::from(i)),
+            None,
+            CpuDelta::ZERO,
+            1,
+        );
+    }
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let samples = &json["threads"][0]["samples"];
+    assert_eq!(samples["length"], json!(3));
+    assert_eq!(samples["time"], json!([0.0, 1.0, 2.0]));
+}
+
+#[test]
+fn profile_unload_lib_range_ignores_empty_and_inverted_ranges() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(process, 123, None, true);
+    let lib = profile.add_lib(LibraryInfo {
+        name: "a.so".to_string(),
+        debug_name: "a.so".to_string(),
+        path: "/usr/lib/a.so".to_string(),
+        code_id: None,
+        debug_path: "/usr/lib/a.so".to_string(),
+        debug_id: DebugId::nil(),
+        arch: None,
+        symbol_table: None,
+    });
+    profile.add_lib_mapping(process, lib, 0x1000, 0x2000, 0);
+
+    // Unmap events can have arbitrary ranges. These must neither panic nor
+    // remove the mapping.
+    profile.unload_lib_range(process, 0x1800, 0x800);
+    profile.unload_lib_range(process, 0x1100, 0x1100);
+
+    let frame = FrameInfo {
+        frame: Frame::InstructionPointer(0x1100),
+        category_pair: CategoryHandle::OTHER.into(),
+        flags: FrameFlags::empty(),
+        implementation: None,
+        source_location: SourceLocation::default(),
+    };
+    let stack = profile.intern_stack_frames(thread, std::iter::once(frame));
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        stack,
+        CpuDelta::ZERO,
+        1,
+    );
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(json["threads"][0]["frameTable"]["address"], json!([0x100]));
+}
+
+#[test]
+fn profile_merge_processes() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let a = profile.add_process("a", 100, Timestamp::from_millis_since_reference(5.0));
+    let b = profile.add_process("b", 100, Timestamp::from_millis_since_reference(2.0));
+    let a_thread = profile.add_thread(a, 100, None, true);
+    let b_thread = profile.add_thread(b, 100, None, true);
+    let counter = profile.add_counter(b, "malloc", "Memory", "Amount of allocated memory");
+    profile.add_counter_sample(counter, Timestamp::from_millis_since_reference(3.0), 1.0, 1);
+    let add_lib = |profile: &mut Profile, process, name: &str, start, end| {
+        let lib = profile.add_lib(LibraryInfo {
+            name: name.to_string(),
+            debug_name: name.to_string(),
+            path: format!("/usr/lib/{name}"),
+            code_id: None,
+            debug_path: format!("/usr/lib/{name}"),
+            debug_id: DebugId::nil(),
+            arch: None,
+            symbol_table: None,
+        });
+        profile.add_lib_mapping(process, lib, start, end, 0);
+    };
+    add_lib(&mut profile, a, "a.so", 0x1000, 0x2000);
+    add_lib(&mut profile, b, "b.so", 0x1800, 0x3000);
+
+    profile.merge_processes(a, b);
+
+    // The mapping from b takes precedence where it overlaps with the one from a.
+    for (thread, address) in [(a_thread, 0x1100), (b_thread, 0x1900)] {
+        let frame = FrameInfo {
+            frame: Frame::InstructionPointer(address),
+            category_pair: CategoryHandle::OTHER.into(),
+            flags: FrameFlags::empty(),
+            implementation: None,
+            source_location: SourceLocation::default(),
+        };
+        let stack = profile.intern_stack_frames(thread, std::iter::once(frame));
+        profile.add_sample(
+            thread,
+            Timestamp::from_millis_since_reference(6.0),
+            stack,
+            CpuDelta::ZERO,
+            1,
+        );
+    }
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let threads = json["threads"].as_array().unwrap();
+    assert_eq!(threads.len(), 2);
+    for thread in threads {
+        assert_eq!(thread["pid"], json!("100"));
+        assert_eq!(thread["processName"], json!("a"));
+        assert_eq!(thread["processStartupTime"], json!(2.0));
+    }
+    // Only the thread which came from a stays the main thread.
+    assert_eq!(threads[0]["isMainThread"], json!(true));
+    assert_eq!(threads[1]["isMainThread"], json!(false));
+    assert_eq!(threads[0]["frameTable"]["address"], json!([0x100]));
+    assert_eq!(threads[1]["frameTable"]["address"], json!([0x100]));
+    let lib_names: Vec<_> = json["libs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|lib| lib["name"].clone())
+        .collect();
+    assert_eq!(lib_names, vec![json!("a.so"), json!("b.so")]);
+    assert_eq!(json["counters"][0]["pid"], json!("100"));
+    assert_eq!(json["counters"][0]["mainThreadIndex"], json!(0));
+}
+
+#[test]
+fn profile_with_subcategories() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let js = profile.add_category("JavaScript", CategoryColor::Yellow);
+    let gc = profile.add_subcategory(js, "GC");
+    let jit = profile.add_subcategory(js, "JIT");
+    let frames = [
+        ("run", CategoryPairHandle::from(js)),
+        ("compile", jit),
+        ("collect", gc),
+    ]
+    .map(|(name, category_pair)| FrameInfo {
+        frame: Frame::Label(profile.intern_string(name)),
+        category_pair,
+        flags: FrameFlags::empty(),
+        implementation: None,
+        source_location: SourceLocation::default(),
+    });
+    let stack = profile.intern_stack_frames(thread, frames.into_iter());
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        stack,
+        CpuDelta::ZERO,
+        1,
+    );
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(
+        json["meta"]["categories"][1]["subcategories"],
+        json!(["GC", "JIT", "Other"])
+    );
+    let thread_json = &json["threads"][0];
+    // Frames without a subcategory use the category's implicit "Other" subcategory.
+    assert_eq!(thread_json["frameTable"]["category"], json!([1, 1, 1]));
+    assert_eq!(thread_json["frameTable"]["subcategory"], json!([2, 1, 0]));
+    assert_eq!(thread_json["stackTable"]["subcategory"], json!([2, 1, 0]));
+}
+
+#[test]
+fn profile_thread_counts() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    assert_eq!(profile.thread_sample_count(thread), 0);
+    assert_eq!(profile.thread_stack_count(thread), 0);
+    assert_eq!(profile.thread_frame_count(thread), 0);
+    assert_eq!(profile.thread_marker_count(thread), 0);
+
+    let frames = ["main", "foo", "bar"].map(|name| FrameInfo {
+        frame: Frame::Label(profile.intern_string(name)),
+        category_pair: CategoryHandle::OTHER.into(),
+        flags: FrameFlags::empty(),
+        implementation: None,
+        source_location: SourceLocation::default(),
+    });
+    let stack = profile.intern_stack_frames(thread, frames.iter().cloned());
+    let prefix_stack = profile.intern_stack_frames(thread, frames[..2].iter().cloned());
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        stack,
+        CpuDelta::ZERO,
+        1,
+    );
+    profile.add_sample_same_stack_zero_cpu(thread, Timestamp::from_millis_since_reference(2.0), 1);
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(3.0),
+        prefix_stack,
+        CpuDelta::ZERO,
+        1,
+    );
+    let marker = TextMarker {
+        name: profile.intern_string("Event"),
+        text: profile.intern_string("text"),
+    };
+    profile.add_marker(
+        thread,
+        MarkerTiming::Instant(Timestamp::from_millis_since_reference(1.0)),
+        marker,
+    );
+
+    assert_eq!(profile.thread_sample_count(thread), 2);
+    assert_eq!(profile.thread_stack_count(thread), 3);
+    assert_eq!(profile.thread_frame_count(thread), 3);
+    assert_eq!(profile.thread_marker_count(thread), 1);
+}
+
+#[test]
+fn profile_with_clamped_cpu_deltas() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    profile.set_clamp_cpu_delta_to_interval(true);
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    for (time_ms, cpu_delta_us) in [(1.0, 5000), (2.0, 1500), (4.0, 1500)] {
+        profile.add_sample(
+            thread,
+            Timestamp::from_millis_since_reference(time_ms),
+            None,
+            CpuDelta::from_micros(cpu_delta_us),
+            1,
+        );
+    }
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let samples = &json["threads"][0]["samples"];
+    // The first sample has no previous sample and is not clamped.
+    assert_eq!(samples["threadCPUDelta"], json!([5000, 1000, 1500]));
+}
+
+#[test]
+fn profile_with_unknown_start_times() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, None);
+    profile.add_thread(process, 123, None, true);
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let thread = &json["threads"][0];
+    assert_eq!(thread["processStartupTime"], json!(null));
+    assert_eq!(thread["processShutdownTime"], json!(null));
+    assert_eq!(thread["registerTime"], json!(null));
+    assert_eq!(thread["unregisterTime"], json!(null));
+}
+
+#[test]
+fn profile_try_add_sample_with_invalid_handles() {
+    let mut other_profile = Profile::new(
+        "other",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let other_process =
+        other_profile.add_process("other", 1, Timestamp::from_millis_since_reference(0.0));
+    let other_thread = other_profile.add_thread(
+        other_process,
+        1,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    assert!(!profile.contains_process(other_process));
+    assert!(!profile.contains_thread(other_thread));
+    assert_eq!(
+        profile.try_add_sample(
+            other_thread,
+            Timestamp::from_millis_since_reference(1.0),
+            None,
+            CpuDelta::ZERO,
+            1,
+        ),
+        Err(InvalidHandleError::UnknownThread(other_thread))
+    );
+
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread1 = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let thread2 = profile.add_thread(
+        process,
+        124,
+        Timestamp::from_millis_since_reference(0.0),
+        false,
+    );
+    assert!(profile.contains_thread(thread2));
+    let label = profile.intern_string("label");
+    let stack = profile.intern_stack_frames(
+        thread1,
+        std::iter::once(FrameInfo {
+            frame: Frame::Label(label),
+            category_pair: CategoryHandle::OTHER.into(),
+            flags: FrameFlags::empty(),
+            implementation: None,
+            source_location: SourceLocation::default(),
+        }),
+    );
+    let result = profile.try_add_sample(
+        thread2,
+        Timestamp::from_millis_since_reference(1.0),
+        stack,
+        CpuDelta::ZERO,
+        1,
+    );
+    assert_eq!(
+        result,
+        Err(InvalidHandleError::StackFromDifferentThread(
+            stack.unwrap(),
+            thread2
+        ))
+    );
+    assert!(profile
+        .try_add_sample(
+            thread1,
+            Timestamp::from_millis_since_reference(1.0),
+            stack,
+            CpuDelta::ZERO,
+            1,
+        )
+        .is_ok());
+}
+
+#[test]
+fn profile_lib_deduplication_by_id() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let make_lib = |path: &str| LibraryInfo {
+        name: "libfoo.so".to_string(),
+        debug_name: "libfoo.so".to_string(),
+        path: path.to_string(),
+        debug_path: path.to_string(),
+        debug_id: DebugId::from_breakpad("5C0A0D51EA1980DF43F203B4525BE9BE0").unwrap(),
+        code_id: Some("510d0a5c19eadf8043f203b4525be9be3dcb9554".to_string()),
+        arch: None,
+        symbol_table: None,
+    };
+    let lib1 = profile.add_lib(make_lib("/usr/lib/libfoo.so"));
+    let lib2 = profile.add_lib(make_lib("/usr/lib64/libfoo.so"));
+    assert_ne!(lib1, lib2);
+
+    profile.set_lib_deduplication_policy(LibDeduplicationPolicy::DebugIdAndCodeId);
+    let lib3 = profile.add_lib(make_lib("/usr/lib/libfoo.so"));
+    let lib4 = profile.add_lib(make_lib("/opt/lib/libfoo.so"));
+    assert_eq!(lib3, lib1);
+    assert_eq!(lib4, lib1);
+
+    let lib5 = profile.add_lib(LibraryInfo {
+        code_id: None,
+        ..make_lib("/opt/lib/libfoo.so")
+    });
+    assert_ne!(lib5, lib1);
+}
+
+/// A marker type which connects related markers via a flow ID.
+struct FlowMarker {
+    name: StringHandle,
+    flow_id: StringHandle,
+}
+
+impl StaticSchemaMarker for FlowMarker {
+    const UNIQUE_MARKER_TYPE_NAME: &'static str = "Flow";
+
+    fn schema() -> MarkerSchema {
+        MarkerSchema::new(Self::UNIQUE_MARKER_TYPE_NAME)
+            .with_locations(&[MarkerLocation::MarkerChart, MarkerLocation::MarkerTable])
+            .with_field("flow", "Flow", MarkerFieldFormat::FlowId)
+            .with_field(
+                "endFlow",
+                "End of flow",
+                MarkerFieldFormat::TerminatingFlowId,
+            )
+    }
+
+    fn name(&self, _profile: &mut Profile) -> StringHandle {
+        self.name
+    }
+
+    fn category(&self, _profile: &mut Profile) -> CategoryHandle {
+        CategoryHandle::OTHER
+    }
+
+    fn string_field_value(&self, _field_index: u32) -> StringHandle {
+        self.flow_id
+    }
+
+    fn number_field_value(&self, _field_index: u32) -> f64 {
+        unreachable!()
+    }
+}
+
+#[test]
+fn profile_with_flow_markers() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let marker = FlowMarker {
+        name: profile.intern_string("Request"),
+        flow_id: profile.intern_string("0x1234"),
+    };
+    profile.add_marker(
+        thread,
+        MarkerTiming::Instant(Timestamp::from_millis_since_reference(1.0)),
+        marker,
+    );
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(
+        json["meta"]["markerSchema"][0]["data"],
+        json!([
+            { "key": "flow", "label": "Flow", "format": "flow-id", "searchable": false },
+            { "key": "endFlow", "label": "End of flow", "format": "terminating-flow-id", "searchable": false },
+        ])
+    );
+    let thread = &json["threads"][0];
+    let data = &thread["markers"]["data"][0];
+    let flow_index = data["flow"].as_u64().unwrap() as usize;
+    assert_eq!(thread["stringArray"][flow_index], json!("0x1234"));
+    assert_eq!(data["endFlow"], data["flow"]);
+}
+
+/// A marker type with a field which is redacted when sharing the profile.
+struct SensitiveMarker {
+    query: StringHandle,
+}
+
+impl StaticSchemaMarker for SensitiveMarker {
+    const UNIQUE_MARKER_TYPE_NAME: &'static str = "Sensitive";
+
+    fn schema() -> MarkerSchema {
+        MarkerSchema::new(Self::UNIQUE_MARKER_TYPE_NAME)
+            .with_locations(&[MarkerLocation::MarkerTable])
+            .with_field("query", "Query", MarkerFieldFormat::SanitizedString)
+    }
+
+    fn name(&self, profile: &mut Profile) -> StringHandle {
+        profile.intern_string("Query")
+    }
+
+    fn category(&self, _profile: &mut Profile) -> CategoryHandle {
+        CategoryHandle::OTHER
+    }
+
+    fn string_field_value(&self, _field_index: u32) -> StringHandle {
+        self.query
+    }
+
+    fn number_field_value(&self, _field_index: u32) -> f64 {
+        unreachable!()
+    }
+}
+
+#[test]
+fn profile_with_sanitized_string_marker_field() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let marker = SensitiveMarker {
+        query: profile.intern_string("/home/user/secret.txt"),
+    };
+    profile.add_marker(
+        thread,
+        MarkerTiming::Instant(Timestamp::from_millis_since_reference(1.0)),
+        marker,
+    );
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(
+        json["meta"]["markerSchema"][0]["data"][0]["format"],
+        json!("sanitized-string")
+    );
+    assert_eq!(
+        json["threads"][0]["markers"]["data"][0]["query"],
+        json!("/home/user/secret.txt")
+    );
+}
+
+#[test]
+fn profile_serialization_is_reproducible() {
+    fn make_profile() -> Profile {
+        let mut profile = Profile::new(
+            "test",
+            ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+            SamplingInterval::from_millis(1),
+        );
+        let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+        let category = profile.add_category("Regular", CategoryColor::Blue);
+        for tid in [125, 124, 123, 124] {
+            let thread = profile.add_thread(process, tid, None, tid == 123);
+            for lib_index in 0..20u64 {
+                let lib = profile.add_lib(LibraryInfo {
+                    name: format!("lib{lib_index}.so"),
+                    debug_name: format!("lib{lib_index}.so"),
+                    path: format!("/usr/lib/lib{lib_index}.so"),
+                    code_id: None,
+                    debug_path: format!("/usr/lib/lib{lib_index}.so"),
+                    debug_id: DebugId::nil(),
+                    arch: None,
+                    symbol_table: None,
+                });
+                let start = 0x10000 * (lib_index + 1);
+                profile.add_lib_mapping(process, lib, start, start + 0x8000, 0);
+            }
+            for (i, time_ms) in [3.0, 1.0, 2.0, 1.0, 3.0].into_iter().enumerate() {
+                let i = i as u64;
+                let frames = (0..5).map(|depth| FrameInfo {
+                    frame: Frame::InstructionPointer(0x10000 * (1 + (i * 7 + depth) % 20) + i),
+                    category_pair: category.into(),
+                    flags: FrameFlags::empty(),
+                    implementation: None,
+                    source_location: SourceLocation::default(),
+                });
+                let stack = profile.intern_stack_frames(thread, frames);
+                profile.add_sample(
+                    thread,
+                    Timestamp::from_millis_since_reference(time_ms),
+                    stack,
+                    CpuDelta::from_micros(i),
+                    i as i32 + 1,
+                );
+            }
+            for text in ["b", "a", "c"] {
+                let marker = TextMarker {
+                    name: profile.intern_string("Event"),
+                    text: profile.intern_string(text),
+                };
+                profile.add_marker(
+                    thread,
+                    MarkerTiming::Instant(Timestamp::from_millis_since_reference(1.0)),
+                    marker,
+                );
+            }
+        }
+        profile
+    }
+
+    // Two profiles built from the same inputs have their own hash maps, and
+    // must still serialize to the same bytes.
+    let bytes = serde_json::to_vec(&make_profile()).unwrap();
+    let other_bytes = serde_json::to_vec(&make_profile()).unwrap();
+    assert_eq!(other_bytes, bytes);
+
+    // Samples with the same timestamp keep the order in which they were added.
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let samples = &json["threads"][0]["samples"];
+    assert_eq!(samples["time"], json!([1.0, 1.0, 2.0, 3.0, 3.0]));
+    assert_eq!(samples["weight"], json!([2, 4, 3, 1, 5]));
+}
+
+#[test]
+fn profile_meta_overrides() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(json["meta"]["symbolicated"], json!(false));
+    assert_eq!(json["meta"]["usesOnlyOneStackType"], json!(true));
+
+    profile.set_symbolicated(true);
+    profile.set_uses_only_one_stack_type(false);
+    let json = serde_json::to_value(&profile).unwrap();
+    assert_eq!(json["meta"]["symbolicated"], json!(true));
+    assert_eq!(json["meta"]["usesOnlyOneStackType"], json!(false));
+}
+
+#[test]
+fn profile_with_global_markers() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let empty_process = profile.add_process("empty", 200, None);
+    let main_thread = profile.add_thread(process, 123, None, true);
+    let other_thread = profile.add_thread(process, 124, None, false);
+
+    let add_global_marker = |profile: &mut Profile, process, tid, text| {
+        let marker = TextMarker {
+            name: profile.intern_string("Event"),
+            text: profile.intern_string(text),
+        };
+        let timing = MarkerTiming::Instant(Timestamp::from_millis_since_reference(1.0));
+        profile.add_global_marker(process, tid, timing, marker)
+    };
+    let (thread, _) = add_global_marker(&mut profile, process, 124, "on 124").unwrap();
+    assert_eq!(thread, other_thread);
+    let (thread, _) = add_global_marker(&mut profile, process, 125, "on 125").unwrap();
+    assert_eq!(thread, main_thread);
+    let (thread, _) = add_global_marker(&mut profile, process, 123, "on 123").unwrap();
+    assert_eq!(thread, main_thread);
+    assert!(add_global_marker(&mut profile, empty_process, 200, "nowhere").is_none());
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let threads = json["threads"].as_array().unwrap();
+    let thread_json = |tid: &str| threads.iter().find(|t| t["tid"] == json!(tid)).unwrap();
+    let main_markers = &thread_json("123")["markers"];
+    assert_eq!(main_markers["length"], json!(2));
+    assert_eq!(main_markers["threadId"], json!(["125", null]));
+    let other_markers = &thread_json("124")["markers"];
+    assert_eq!(other_markers["length"], json!(1));
+    assert_eq!(other_markers.get("threadId"), None);
+}
+
+#[test]
+fn large_pids_and_tids() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let large_id = u64::from(u32::MAX) + 5;
+    let process = profile.add_process("test", large_id, None);
+    profile.add_thread(process, large_id, None, true);
+    // 4 is what large_id would be truncated to with 32 bit tids, so it must not
+    // be treated as a duplicate tid.
+    profile.add_thread(process, 4, None, false);
+    let thread = profile.add_thread(process, 5, None, false);
+    profile.set_thread_tid(thread, large_id + 1);
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let threads = json["threads"].as_array().unwrap();
+    assert_eq!(threads[0]["pid"], json!("4294967300"));
+    assert_eq!(threads[0]["tid"], json!("4294967300"));
+    assert_eq!(threads[1]["tid"], json!("4"));
+    assert_eq!(threads[2]["tid"], json!("4294967301"));
+}
+
+#[test]
+fn set_thread_is_main() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, None);
+    let thread0 = profile.add_thread(process, 123, None, true);
+    let thread1 = profile.add_thread(process, 124, None, false);
+    profile.set_thread_name(thread0, "Launcher");
+    profile.set_thread_name(thread1, "Worker");
+    let other_process = profile.add_process("other", 200, None);
+    profile.add_thread(other_process, 200, None, true);
+
+    // Move the main thread flag to thread1. This unmarks thread0, but doesn't
+    // affect the other process.
+    profile.set_thread_is_main(thread1, true);
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let threads: Vec<_> = json["threads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|thread| {
+            (
+                thread["tid"].as_str().unwrap().to_owned(),
+                thread["name"].as_str().unwrap().to_owned(),
+                thread["isMainThread"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        threads,
+        vec![
+            ("124".to_owned(), "test".to_owned(), true),
+            ("123".to_owned(), "Launcher".to_owned(), false),
+            ("200".to_owned(), "other".to_owned(), true),
+        ]
+    );
+}
+
+#[test]
+fn frame_implementation() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let root = profile.intern_string("root");
+    let callee = profile.intern_string("callee");
+    let baseline = profile.intern_string("baseline");
+    let stack = profile.intern_stack_frames(
+        thread,
+        [
+            FrameInfo {
+                frame: Frame::Label(root),
+                category_pair: CategoryHandle::OTHER.into(),
+                flags: FrameFlags::IS_JS | FrameFlags::IS_RELEVANT_FOR_JS,
+                implementation: None,
+                source_location: SourceLocation::default(),
+            },
+            FrameInfo {
+                frame: Frame::Label(callee),
+                category_pair: CategoryHandle::OTHER.into(),
+                flags: FrameFlags::IS_JS,
+                implementation: Some(baseline),
+                source_location: SourceLocation::default(),
+            },
+        ]
+        .into_iter(),
+    );
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        stack,
+        CpuDelta::ZERO,
+        1,
+    );
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let thread = &json["threads"][0];
+    let strings = thread["stringArray"].as_array().unwrap();
+    let implementations = thread["frameTable"]["implementation"].as_array().unwrap();
+    assert_eq!(implementations.len(), 2);
+    assert_eq!(implementations[0], json!(null));
+    let implementation = implementations[1].as_u64().unwrap() as usize;
+    assert_eq!(strings[implementation], json!("baseline"));
+    assert_eq!(thread["funcTable"]["relevantForJS"], json!([true, false]));
+    assert_eq!(json["meta"]["doesNotUseFrameImplementation"], json!(false));
+}
+
+#[test]
+fn handle_for_file_path() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let windows_path = profile.handle_for_file_path(r"src\foo\bar.rs");
+    let unix_path = profile.handle_for_file_path("src/foo/bar.rs");
+    assert_eq!(windows_path, unix_path);
+    assert_eq!(profile.get_string(windows_path), "src/foo/bar.rs");
+    let other_path = profile.handle_for_file_path("src/foo/baz.rs");
+    assert_ne!(other_path, unix_path);
+}
+
+#[test]
+fn frame_source_location() {
+    let mut profile = Profile::new(
+        "test",
+        ReferenceTimestamp::from_millis_since_unix_epoch(1636162232627.0),
+        SamplingInterval::from_millis(1),
+    );
+    let process = profile.add_process("test", 123, Timestamp::from_millis_since_reference(0.0));
+    let thread = profile.add_thread(
+        process,
+        123,
+        Timestamp::from_millis_since_reference(0.0),
+        true,
+    );
+    let root = profile.intern_string("root");
+    let callee = profile.intern_string("callee");
+    let file = profile.handle_for_file_path(r"src\callee.js");
+    let stack = profile.intern_stack_frames(
+        thread,
+        [
+            FrameInfo {
+                frame: Frame::Label(root),
+                category_pair: CategoryHandle::OTHER.into(),
+                flags: FrameFlags::empty(),
+                implementation: None,
+                source_location: SourceLocation::default(),
+            },
+            FrameInfo {
+                frame: Frame::Label(callee),
+                category_pair: CategoryHandle::OTHER.into(),
+                flags: FrameFlags::IS_JS,
+                implementation: None,
+                source_location: SourceLocation {
+                    file: Some(file),
+                    line: Some(42),
+                },
+            },
+        ]
+        .into_iter(),
+    );
+    profile.add_sample(
+        thread,
+        Timestamp::from_millis_since_reference(1.0),
+        stack,
+        CpuDelta::ZERO,
+        1,
+    );
+
+    let json = serde_json::to_value(&profile).unwrap();
+    let thread = &json["threads"][0];
+    let strings = thread["stringArray"].as_array().unwrap();
+    let file_names = thread["funcTable"]["fileName"].as_array().unwrap();
+    assert_eq!(file_names.len(), 2);
+    assert_eq!(file_names[0], json!(null));
+    let file_name = file_names[1].as_u64().unwrap() as usize;
+    assert_eq!(strings[file_name], json!("src/callee.js"));
+    assert_eq!(thread["frameTable"]["line"], json!([null, 42]));
+}
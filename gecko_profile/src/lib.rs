@@ -299,6 +299,8 @@ impl ThreadBuilder {
         stack_index
     }
 
+    /// Adds a sample whose stack was returned by an earlier call to
+    /// [`ThreadBuilder::add_sample`] or [`ThreadBuilder::handle_for_stack`].
     pub fn add_sample_same_stack(
         &mut self,
         timestamp: Instant,
@@ -312,14 +314,42 @@ impl ThreadBuilder {
         });
     }
 
+    /// Returns the index of the stack consisting of `frame` on top of the stack
+    /// `prefix`, creating it if needed.
+    ///
+    /// This allows building up stacks one frame at a time, root first, and
+    /// reusing the stacks of common prefixes across samples. The returned index
+    /// can be passed to [`ThreadBuilder::add_sample_same_stack`].
+    pub fn handle_for_stack(&mut self, prefix: Option<usize>, frame: Frame) -> usize {
+        let frame_index = self.frame_index_for_frame(frame);
+        self.stack_table.index_for_stack(prefix, frame_index)
+    }
+
     /// Main marker API to add a new marker to profiler buffer.
+    ///
+    /// The marker is put into the "Regular" category, see
+    /// [`ThreadBuilder::add_marker_with_category`].
     pub fn add_marker<T: ProfilerMarker>(&mut self, name: &str, marker: T, timing: MarkerTiming) {
+        self.add_marker_with_category(name, 0, marker, timing)
+    }
+
+    /// Like [`ThreadBuilder::add_marker`], but puts the marker into the given
+    /// category. `category` is an index into the profile's categories: 0 is
+    /// "Regular", 1 is "Other".
+    pub fn add_marker_with_category<T: ProfilerMarker>(
+        &mut self,
+        name: &str,
+        category: u32,
+        marker: T,
+        timing: MarkerTiming,
+    ) {
         self.marker_schemas
             .entry(T::MARKER_TYPE_NAME)
             .or_insert_with(T::schema);
         let name_string_index = self.string_table.index_for_string(name);
         self.markers.0.push(Marker {
             name_string_index,
+            category,
             timing,
             data: marker.json_marker_data(),
         })
@@ -330,10 +360,9 @@ impl ThreadBuilder {
     }
 
     fn stack_index_for_frames(&mut self, frames: impl Iterator<Item = Frame>) -> Option<usize> {
-        let frame_indexes: Vec<_> = frames
-            .map(|frame| self.frame_index_for_frame(frame))
-            .collect();
-        self.stack_table.index_for_frames(&frame_indexes)
+        frames.fold(None, |prefix, frame| {
+            Some(self.handle_for_stack(prefix, frame))
+        })
     }
 
     fn frame_index_for_frame(&mut self, frame: Frame) -> usize {
@@ -451,22 +480,16 @@ impl StackTable {
         }
     }
 
-    pub fn index_for_frames(&mut self, frame_indexes: &[usize]) -> Option<usize> {
-        let mut prefix = None;
-        for &frame_index in frame_indexes {
-            match self.index.get(&(prefix, frame_index)) {
-                Some(stack_index) => {
-                    prefix = Some(*stack_index);
-                }
-                None => {
-                    let stack_index = self.stacks.len();
-                    self.stacks.push((prefix, frame_index));
-                    self.index.insert((prefix, frame_index), stack_index);
-                    prefix = Some(stack_index);
-                }
+    pub fn index_for_stack(&mut self, prefix: Option<usize>, frame_index: usize) -> usize {
+        match self.index.get(&(prefix, frame_index)) {
+            Some(stack_index) => *stack_index,
+            None => {
+                let stack_index = self.stacks.len();
+                self.stacks.push((prefix, frame_index));
+                self.index.insert((prefix, frame_index), stack_index);
+                stack_index
             }
         }
-        prefix
     }
 }
 
@@ -735,6 +758,7 @@ struct SerializableMarkerTableDataValue<'a> {
     start: f64,
     end: f64,
     phase: u8,
+    category: u32,
     data: &'a Value,
 }
 
@@ -745,7 +769,7 @@ impl Serialize for SerializableMarkerTableDataValue<'_> {
         seq.serialize_element(&self.start)?; // startTime
         seq.serialize_element(&self.end)?; // endTime
         seq.serialize_element(&self.phase)?; // phase
-        seq.serialize_element(&0)?; // category
+        seq.serialize_element(&self.category)?; // category
         seq.serialize_element(self.data)?; // data
         seq.end()
     }
@@ -762,6 +786,7 @@ enum Phase {
 #[derive(Debug, Clone)]
 struct Marker {
     name_string_index: StringIndex,
+    category: u32,
     timing: MarkerTiming,
     data: Value,
 }
@@ -793,6 +818,7 @@ impl Marker {
             start: s,
             end: e,
             phase: phase as u8,
+            category: self.category,
             data: &self.data,
         }
     }
@@ -833,12 +859,13 @@ impl StringTable {
 mod test {
     use std::time::{Duration, Instant, SystemTime};
 
-    use assert_json_diff::assert_json_eq;
+    use assert_json_diff::{assert_json_eq, assert_json_include};
     use serde_json::json;
 
     use crate::{
-        MarkerDynamicField, MarkerFieldFormat, MarkerLocation, MarkerSchema, MarkerSchemaField,
-        MarkerStaticField, MarkerTiming, ProfileBuilder, ProfilerMarker, TextMarker, ThreadBuilder,
+        Frame, MarkerDynamicField, MarkerFieldFormat, MarkerLocation, MarkerSchema,
+        MarkerSchemaField, MarkerStaticField, MarkerTiming, ProfileBuilder, ProfilerMarker,
+        TextMarker, ThreadBuilder,
     };
 
     #[test]
@@ -1067,4 +1094,73 @@ mod test {
             )
         )
     }
+
+    #[test]
+    fn marker_category() {
+        let start_time = Instant::now();
+        let start_time_system = SystemTime::UNIX_EPOCH + Duration::from_millis(1636162232627);
+        let mut thread = ThreadBuilder::new(123, 0, start_time, true, false);
+        thread.add_marker_with_category(
+            "Experimental",
+            1,
+            TextMarker("Hello world!".to_string()),
+            MarkerTiming::Instant(start_time),
+        );
+        let mut profile = ProfileBuilder::new(
+            start_time,
+            start_time_system,
+            "test",
+            123,
+            Duration::from_millis(1),
+        );
+        profile.add_thread(thread);
+
+        let profile = serde_json::to_value(profile.to_serializable()).unwrap();
+        assert_json_include!(
+            actual: &profile["threads"][0]["markers"],
+            expected: json!({
+                "data": [[0, 0.0, 0.0, 0, 1, { "name": "Hello world!", "type": "Text" }]],
+                "schema": { "category": 4 }
+            })
+        );
+    }
+
+    #[test]
+    fn incremental_stacks() {
+        let start_time = Instant::now();
+        let mut thread = ThreadBuilder::new(123, 0, start_time, true, false);
+        let root = thread.handle_for_stack(None, Frame::Address(0x1000));
+        let child = thread.handle_for_stack(Some(root), Frame::Address(0x2000));
+        assert_eq!(thread.handle_for_stack(None, Frame::Address(0x1000)), root);
+        assert_eq!(
+            thread.add_sample(
+                start_time,
+                vec![Frame::Address(0x1000), Frame::Address(0x2000)].into_iter(),
+                Duration::ZERO,
+            ),
+            Some(child)
+        );
+        thread.add_sample_same_stack(
+            start_time + Duration::from_millis(1),
+            Some(child),
+            Duration::ZERO,
+        );
+
+        let mut profile = ProfileBuilder::new(
+            start_time,
+            SystemTime::UNIX_EPOCH,
+            "test",
+            123,
+            Duration::from_millis(1),
+        );
+        profile.add_thread(thread);
+        let profile = serde_json::to_value(profile.to_serializable()).unwrap();
+        assert_json_include!(
+            actual: &profile["threads"][0],
+            expected: json!({
+                "stackTable": { "data": [[null, 0], [0, 1]] },
+                "samples": { "data": [[1, 0.0, 0.0, 0], [1, 1.0, 0.0, 0]] }
+            })
+        );
+    }
 }
@@ -13,10 +13,10 @@ use self::response_json::Response;
 use crate::asm::response_json::DecodedInstruction;
 
 mod request_json;
-mod response_json;
+pub mod response_json;
 
 #[derive(thiserror::Error, Debug)]
-enum AsmError {
+pub enum AsmError {
     #[error("Couldn't parse request: {0}")]
     ParseRequestErrorSerde(#[from] serde_json::error::Error),
 
@@ -57,11 +57,20 @@ impl<'a, H: FileAndPathHelper> AsmApi<'a, H> {
     }
 
     async fn query_api_fallible_json(&self, request_json: &str) -> Result<String, AsmError> {
-        let request: request_json::Request = serde_json::from_str(request_json)?;
-        let response = self.query_api(&request).await?;
+        let response = self.query_api_typed(request_json).await?;
         Ok(serde_json::to_string(&response)?)
     }
 
+    /// Like [`query_api_json`](Self::query_api_json), but returns the response
+    /// and the error as Rust types.
+    pub async fn query_api_typed(
+        &self,
+        request_json: &str,
+    ) -> Result<response_json::Response, AsmError> {
+        let request: request_json::Request = serde_json::from_str(request_json)?;
+        self.query_api(&request).await
+    }
+
     async fn query_api(
         &self,
         request: &request_json::Request,
@@ -74,6 +83,7 @@ impl<'a, H: FileAndPathHelper> AsmApi<'a, H> {
             start_address,
             size,
             continue_until_function_end,
+            include_bytes,
             ..
         } = request;
 
@@ -148,7 +158,13 @@ impl<'a, H: FileAndPathHelper> AsmApi<'a, H> {
                 CodeByteReadingError::FileIO(e) => AsmError::FileIO(e),
             })?;
 
-        decode_arch(bytes, architecture, rel_address, disassembly_len)
+        decode_arch(
+            bytes,
+            architecture,
+            rel_address,
+            disassembly_len,
+            *include_bytes,
+        )
     }
 
     async fn get_function_end_address(
@@ -170,16 +186,24 @@ fn decode_arch(
     arch: Option<&str>,
     rel_address: u32,
     decode_len: u32,
+    include_bytes: bool,
 ) -> Result<Response, AsmError> {
     Ok(match arch {
-        Some("x86") => decode::<yaxpeax_x86::protected_mode::Arch>(bytes, rel_address, decode_len),
+        Some("x86") => decode::<yaxpeax_x86::protected_mode::Arch>(
+            bytes,
+            rel_address,
+            decode_len,
+            include_bytes,
+        ),
         Some("x86_64" | "x86_64h") => {
-            decode::<yaxpeax_x86::amd64::Arch>(bytes, rel_address, decode_len)
+            decode::<yaxpeax_x86::amd64::Arch>(bytes, rel_address, decode_len, include_bytes)
         }
         Some("arm64" | "arm64e") => {
-            decode::<yaxpeax_arm::armv8::a64::ARMv8>(bytes, rel_address, decode_len)
+            decode::<yaxpeax_arm::armv8::a64::ARMv8>(bytes, rel_address, decode_len, include_bytes)
+        }
+        Some("arm") => {
+            decode::<yaxpeax_arm::armv7::ARMv7>(bytes, rel_address, decode_len, include_bytes)
         }
-        Some("arm") => decode::<yaxpeax_arm::armv7::ARMv7>(bytes, rel_address, decode_len),
         _ => {
             return Err(AsmError::UnrecognizedArch(
                 arch.map_or_else(|| "unknown".to_string(), |a| a.to_string()),
@@ -273,6 +297,7 @@ impl InstructionDecoding for yaxpeax_x86::amd64::Arch {
         DecodedInstruction {
             offset,
             decoded_string_per_syntax: vec![intel_insn, c_insn],
+            bytes: None,
         }
     }
 }
@@ -294,6 +319,7 @@ impl InstructionDecoding for yaxpeax_x86::protected_mode::Arch {
         DecodedInstruction {
             offset,
             decoded_string_per_syntax: vec![inst.to_string()],
+            bytes: None,
         }
     }
 }
@@ -315,6 +341,7 @@ impl InstructionDecoding for yaxpeax_arm::armv8::a64::ARMv8 {
         DecodedInstruction {
             offset,
             decoded_string_per_syntax: vec![inst.to_string()],
+            bytes: None,
         }
     }
 }
@@ -347,6 +374,7 @@ impl InstructionDecoding for yaxpeax_arm::armv7::ARMv7 {
         DecodedInstruction {
             offset,
             decoded_string_per_syntax: vec![inst.to_string()],
+            bytes: None,
         }
     }
 }
@@ -355,6 +383,7 @@ fn decode<'a, A: InstructionDecoding>(
     bytes: &'a [u8],
     rel_address: u32,
     decode_len: u32,
+    include_bytes: bool,
 ) -> Response
 where
     u64: From<A::Address>,
@@ -372,9 +401,16 @@ where
         let before = u64::from(reader.total_offset()) as u32;
         match decoder.decode(&mut reader) {
             Ok(inst) => {
-                instructions.push(A::stringify_inst(rel_address, offset, inst));
+                let mut instruction = A::stringify_inst(rel_address, offset, inst);
                 let after = u64::from(reader.total_offset()) as u32;
-                offset += after - before;
+                let len = after - before;
+                if include_bytes {
+                    instruction.bytes = bytes
+                        .get(offset as usize..(offset + len) as usize)
+                        .map(<[u8]>::to_vec);
+                }
+                instructions.push(instruction);
+                offset += len;
             }
             Err(e) => {
                 if e.data_exhausted() {
@@ -406,6 +442,13 @@ where
                             )
                         })
                         .collect(),
+                    bytes: include_bytes.then(|| {
+                        remaining_bytes
+                            .iter()
+                            .take(A::ADJUST_BY_AFTER_ERROR)
+                            .copied()
+                            .collect()
+                    }),
                 });
 
                 offset += A::ADJUST_BY_AFTER_ERROR as u32;
@@ -28,6 +28,11 @@ pub struct Request {
     /// not provide that information.
     #[serde(default)]
     pub continue_until_function_end: bool,
+
+    /// Whether to include the raw bytes of each instruction in the response.
+    /// This field is optional and defaults to false.
+    #[serde(default)]
+    pub include_bytes: bool,
 }
 
 #[cfg(test)]
@@ -50,6 +55,7 @@ mod test {
         assert_eq!(r.start_address, 30426946);
         assert_eq!(r.debug_id, Some("A14CAFD390A3E1884C4C44205044422E1".into()));
         assert!(!r.continue_until_function_end);
+        assert!(!r.include_bytes);
         Ok(())
     }
 }
@@ -35,6 +35,15 @@ pub struct DecodedInstruction {
 
     /// The decoded instruction as a string, one for each syntax (e.g. Intel and then C-Style).
     pub decoded_string_per_syntax: Vec<String>,
+
+    /// The bytes of this instruction, if the request asked for them.
+    pub bytes: Option<Vec<u8>>,
+}
+
+#[derive(Serialize)]
+struct InstructionBytes {
+    /// The instruction bytes as a hex string without spaces, e.g. "4889e5".
+    bytes: String,
 }
 
 impl serde::Serialize for DecodedInstruction {
@@ -54,7 +63,13 @@ impl serde::Serialize for DecodedInstruction {
             seq.serialize_element(decoded_string)?;
         }
 
-        // In the future we may append more elements here, for extra per-instruction information.
+        // Extra per-instruction information is appended as an object, e.g. `{ "bytes": "4889e5" }`.
+        if let Some(bytes) = &self.bytes {
+            let bytes = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            seq.serialize_element(&InstructionBytes { bytes })?;
+        }
+
+        // In the future we may append more elements here.
         // For example `{ "jumpTarget": "0x1390" }`.
         // Or even `{ "jumpTarget": "0x2468", "destSymbol": { "name": "MyFunction()", "address": "0x2468", "size": "0x38" } }`
 
@@ -79,10 +94,12 @@ mod test {
                 DecodedInstruction {
                     offset: 0,
                     decoded_string_per_syntax: vec!["push rbp".to_string()],
+                    bytes: None,
                 },
                 DecodedInstruction {
                     offset: 1,
                     decoded_string_per_syntax: vec!["mov rbp, rsp".to_string()],
+                    bytes: Some(vec![0x48, 0x89, 0xe5]),
                 },
             ],
         };
@@ -101,7 +118,10 @@ mod test {
     ],
     [
       1,
-      "mov rbp, rsp"
+      "mov rbp, rsp",
+      {
+        "bytes": "4889e5"
+      }
     ]
   ]
 }"#;
@@ -142,21 +142,21 @@
 //! }
 //! ```
 
-use asm::AsmApi;
+use asm::{AsmApi, AsmError};
 use debugid::DebugId;
 pub use samply_symbols;
 pub use samply_symbols::debugid;
 use samply_symbols::{FileAndPathHelper, SymbolManager};
 use serde_json::json;
-use source::SourceApi;
+use source::{SourceApi, SourceError};
 use symbolicate::SymbolicateApi;
 
 mod api_file_path;
-mod asm;
+pub mod asm;
 mod error;
 mod hex;
-mod source;
-mod symbolicate;
+pub mod source;
+pub mod symbolicate;
 
 pub(crate) fn to_debug_id(breakpad_id: &str) -> Result<DebugId, samply_symbols::Error> {
     // Only accept breakpad IDs with the right syntax, and which aren't all-zeros.
@@ -171,12 +171,67 @@ pub(crate) fn to_debug_id(breakpad_id: &str) -> Result<DebugId, samply_symbols::
 #[derive(Clone, Copy)]
 pub struct Api<'a, H: FileAndPathHelper> {
     symbol_manager: &'a SymbolManager<H>,
+    symbolication_concurrency_limit: usize,
 }
 
 impl<'a, H: FileAndPathHelper> Api<'a, H> {
     /// Create a [`Api`] instance which uses the provided [`SymbolManager`].
     pub fn new(symbol_manager: &'a SymbolManager<H>) -> Self {
-        Self { symbol_manager }
+        Self {
+            symbol_manager,
+            symbolication_concurrency_limit: symbolicate::DEFAULT_CONCURRENCY_LIMIT,
+        }
+    }
+
+    /// Set the maximum number of libraries which `/symbolicate/v5` looks up at
+    /// the same time. The default is 8.
+    ///
+    /// Higher limits reduce the latency for requests which span many libraries,
+    /// at the cost of having more symbol files loaded at the same time. The
+    /// lookups are interleaved on the current task and don't need a multi-threaded
+    /// executor, so this also works in WebAssembly.
+    pub fn with_symbolication_concurrency_limit(mut self, limit: usize) -> Self {
+        self.symbolication_concurrency_limit = limit;
+        self
+    }
+
+    /// Like [`query_api`](Self::query_api), but returns the response as a Rust
+    /// type instead of a JSON string, and returns errors as `Err`.
+    ///
+    /// This is useful when using this crate as a library, because it saves
+    /// parsing the returned JSON. [`QueryApiResponse::to_json`] creates the
+    /// same JSON that `query_api` would have returned.
+    pub async fn query_api_typed(
+        self,
+        request_url: &str,
+        request_json_data: &str,
+    ) -> Result<QueryApiResponse, QueryApiError> {
+        let response = match request_url {
+            "/symbolicate/v5" => QueryApiResponse::SymbolicateV5(
+                SymbolicateApi::new(self.symbol_manager)
+                    .with_concurrency_limit(self.symbolication_concurrency_limit)
+                    .query_api_typed(request_json_data)
+                    .await?,
+            ),
+            "/symbolicate/v6" => QueryApiResponse::SymbolicateV6(
+                SymbolicateApi::new(self.symbol_manager)
+                    .with_concurrency_limit(self.symbolication_concurrency_limit)
+                    .query_api_v6_typed(request_json_data)
+                    .await?,
+            ),
+            "/source/v1" => QueryApiResponse::Source(
+                SourceApi::new(self.symbol_manager)
+                    .query_api_typed(request_json_data)
+                    .await?,
+            ),
+            "/asm/v1" => QueryApiResponse::Asm(
+                AsmApi::new(self.symbol_manager)
+                    .query_api_typed(request_json_data)
+                    .await?,
+            ),
+            _ => return Err(QueryApiError::UnrecognizedUrl(request_url.to_string())),
+        };
+        Ok(response)
     }
 
     /// This is the main API of this crate.
@@ -190,22 +245,64 @@ impl<'a, H: FileAndPathHelper> Api<'a, H> {
     /// The following "URLs" are supported:
     ///  - `/symbolicate/v5`: This API is documented at <https://tecken.readthedocs.io/en/latest/symbolication.html>.
     ///    The returned data has two extra fields: inlines (per address) and module_errors (per job).
+    ///  - `/symbolicate/v6`: Takes the same requests as `/symbolicate/v5`, and returns the same
+    ///    response shape, except that the debug info is structured differently: each stack frame
+    ///    has a `frames` array with the functions at that address, innermost first. Each entry has
+    ///    `function`, `file`, `line`, `inline` (false only for the last entry) and `module_offset`.
     ///  - `/source/v1`: Experimental API. Symbolicates an address and lets you read one of the files in the
     ///    symbol information for that address.
-    ///  - `/asm/v1`: Experimental API. Symbolicates an address and lets you read one of the files in the
-    ///    symbol information for that address.
+    ///  - `/asm/v1`: Experimental API. Disassembles the machine code in an address range of a
+    ///    library. Set `includeBytes` in the request to also get the raw bytes of each instruction.
     pub async fn query_api(self, request_url: &str, request_json_data: &str) -> String {
-        if request_url == "/symbolicate/v5" {
-            let symbolicate_api = SymbolicateApi::new(self.symbol_manager);
-            symbolicate_api.query_api_json(request_json_data).await
-        } else if request_url == "/source/v1" {
-            let source_api = SourceApi::new(self.symbol_manager);
-            source_api.query_api_json(request_json_data).await
-        } else if request_url == "/asm/v1" {
-            let asm_api = AsmApi::new(self.symbol_manager);
-            asm_api.query_api_json(request_json_data).await
-        } else {
-            json!({ "error": format!("Unrecognized URL {request_url}") }).to_string()
+        match self.query_api_typed(request_url, request_json_data).await {
+            Ok(response) => response.to_json(),
+            Err(err) => json!({ "error": err.to_string() }).to_string(),
         }
     }
 }
+
+/// The successful result of [`Api::query_api_typed`], with one variant per
+/// supported URL.
+#[derive(Debug)]
+pub enum QueryApiResponse {
+    /// The response for `/symbolicate/v5`.
+    SymbolicateV5(symbolicate::response_json::Response),
+    /// The response for `/symbolicate/v6`.
+    SymbolicateV6(symbolicate::v6::Response),
+    /// The response for `/source/v1`.
+    Source(source::response_json::Response),
+    /// The response for `/asm/v1`.
+    Asm(asm::response_json::Response),
+}
+
+impl QueryApiResponse {
+    /// Serializes the response into the JSON format of the respective API.
+    pub fn to_json(&self) -> String {
+        let json = match self {
+            QueryApiResponse::SymbolicateV5(response) => serde_json::to_string(response),
+            QueryApiResponse::SymbolicateV6(response) => serde_json::to_string(response),
+            QueryApiResponse::Source(response) => serde_json::to_string(response),
+            QueryApiResponse::Asm(response) => serde_json::to_string(response),
+        };
+        json.unwrap_or_else(|err| json!({ "error": err.to_string() }).to_string())
+    }
+}
+
+/// The error type of [`Api::query_api_typed`].
+///
+/// The `Display` implementation gives the message that [`Api::query_api`]
+/// puts into the `error` field of its JSON response.
+#[derive(thiserror::Error, Debug)]
+pub enum QueryApiError {
+    #[error("Unrecognized URL {0}")]
+    UnrecognizedUrl(String),
+
+    #[error(transparent)]
+    Symbolicate(#[from] symbolicate::Error),
+
+    #[error(transparent)]
+    Source(#[from] SourceError),
+
+    #[error(transparent)]
+    Asm(#[from] AsmError),
+}
@@ -7,10 +7,10 @@ use crate::api_file_path::to_api_file_path;
 use crate::to_debug_id;
 
 mod request_json;
-mod response_json;
+pub mod response_json;
 
 #[derive(thiserror::Error, Debug)]
-enum SourceError {
+pub enum SourceError {
     #[error("Couldn't parse request: {0}")]
     ParseRequestErrorSerde(#[from] serde_json::error::Error),
 
@@ -45,11 +45,20 @@ impl<'a, H: FileAndPathHelper> SourceApi<'a, H> {
     }
 
     async fn query_api_fallible_json(&self, request_json: &str) -> Result<String, SourceError> {
-        let request: request_json::Request = serde_json::from_str(request_json)?;
-        let response = self.query_api(&request).await?;
+        let response = self.query_api_typed(request_json).await?;
         Ok(serde_json::to_string(&response)?)
     }
 
+    /// Like [`query_api_json`](Self::query_api_json), but returns the response
+    /// and the error as Rust types.
+    pub async fn query_api_typed(
+        &self,
+        request_json: &str,
+    ) -> Result<response_json::Response, SourceError> {
+        let request: request_json::Request = serde_json::from_str(request_json)?;
+        self.query_api(&request).await
+    }
+
     async fn query_api(
         &self,
         request: &request_json::Request,
@@ -85,6 +94,10 @@ impl<'a, H: FileAndPathHelper> SourceApi<'a, H> {
             .find(|file_path| to_api_file_path(file_path) == *requested_file)
             .ok_or(SourceError::InvalidPath)?;
 
+        let url = source_file_path
+            .mapped_path()
+            .and_then(|mapped_path| mapped_path.to_web_url());
+
         // If we got here, it means that the file access is allowed. Read the file.
         let source = self
             .symbol_manager
@@ -96,6 +109,7 @@ impl<'a, H: FileAndPathHelper> SourceApi<'a, H> {
             source_last_modified: None,
             file: requested_file.to_string(),
             source,
+            url,
         })
     }
 }
@@ -16,6 +16,11 @@ pub struct Response {
 
     /// The full source code of the requested file.
     pub source: String,
+
+    /// If the file is hosted online, a URL at which it can be viewed in a web
+    /// browser. See [`MappedPath::to_web_url`](samply_symbols::MappedPath::to_web_url).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
 #[cfg(test)]
@@ -27,6 +32,7 @@ mod test {
         let response = super::Response {
             symbols_last_modified: None,
             source_last_modified: None,
+            url: None,
             file: "/Users/mstange/code/mozilla/modules/zlib/src/deflate.c".to_string(),
             source: r#"/* deflate.c -- compress data using the deflation algorithm
 * Copyright (C) 1995-2017 Jean-loup Gailly and Mark Adler
@@ -1,30 +1,52 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::num::NonZeroU32;
+use std::panic::AssertUnwindSafe;
 
-use samply_symbols::{
-    FileAndPathHelper, FramesLookupResult, LibraryInfo, LookupAddress, SymbolManager,
-};
+use futures_util::stream::{self, StreamExt};
+use futures_util::FutureExt;
+use samply_symbols::{FileAndPathHelper, LibraryInfo, SymbolManager};
 
 use crate::api_file_path::to_api_file_path;
-use crate::error::Error;
+pub use crate::error::Error;
 use crate::to_debug_id;
 
 pub mod looked_up_addresses;
 pub mod request_json;
 pub mod response_json;
+pub mod v6;
 
 use looked_up_addresses::{AddressResults, LookedUpAddresses};
 use request_json::Lib;
 use serde_json::json;
 
+/// The default number of libraries which are symbolicated concurrently.
+pub const DEFAULT_CONCURRENCY_LIMIT: usize = 8;
+
 pub struct SymbolicateApi<'a, H: FileAndPathHelper> {
     symbol_manager: &'a SymbolManager<H>,
+    concurrency_limit: usize,
 }
 
 impl<'a, H: FileAndPathHelper> SymbolicateApi<'a, H> {
     /// Create a [`SymbolicateApi`] instance which uses the provided [`SymbolManager`].
     pub fn new(symbol_manager: &'a SymbolManager<H>) -> Self {
-        Self { symbol_manager }
+        Self {
+            symbol_manager,
+            concurrency_limit: DEFAULT_CONCURRENCY_LIMIT,
+        }
+    }
+
+    /// Set the maximum number of libraries which are symbolicated at the same
+    /// time. The default is [`DEFAULT_CONCURRENCY_LIMIT`]. A limit of 1 handles
+    /// the libraries one after the other, and 0 is treated as 1.
+    ///
+    /// The libraries are symbolicated concurrently, not in parallel: this
+    /// only interleaves the futures, for example while waiting for files to
+    /// load, and doesn't spawn any threads.
+    pub fn with_concurrency_limit(mut self, concurrency_limit: usize) -> Self {
+        self.concurrency_limit = concurrency_limit.max(1);
+        self
     }
 
     pub async fn query_api_json(&self, request_json: &str) -> String {
@@ -35,11 +57,19 @@ impl<'a, H: FileAndPathHelper> SymbolicateApi<'a, H> {
     }
 
     pub async fn query_api_fallible_json(&self, request_json: &str) -> Result<String, Error> {
-        let request: request_json::Request = serde_json::from_str(request_json)?;
-        let response = self.query_api(&request).await?;
+        let response = self.query_api_typed(request_json).await?;
         Ok(serde_json::to_string(&response)?)
     }
 
+    /// Parses the request JSON and returns the response as a Rust type.
+    pub async fn query_api_typed(
+        &self,
+        request_json: &str,
+    ) -> Result<response_json::Response, Error> {
+        let request: request_json::Request = serde_json::from_str(request_json)?;
+        self.query_api(&request).await
+    }
+
     pub async fn query_api(
         &self,
         request: &request_json::Request,
@@ -51,18 +81,61 @@ impl<'a, H: FileAndPathHelper> SymbolicateApi<'a, H> {
         Ok(create_response(request, symbolicated_addresses))
     }
 
+    pub async fn query_api_v6_json(&self, request_json: &str) -> String {
+        match self.query_api_v6_fallible_json(request_json).await {
+            Ok(response_json) => response_json,
+            Err(err) => json!({ "error": err.to_string() }).to_string(),
+        }
+    }
+
+    pub async fn query_api_v6_fallible_json(&self, request_json: &str) -> Result<String, Error> {
+        let response = self.query_api_v6_typed(request_json).await?;
+        Ok(serde_json::to_string(&response)?)
+    }
+
+    /// Like [`SymbolicateApi::query_api_typed`], but for the `/symbolicate/v6`
+    /// response format.
+    pub async fn query_api_v6_typed(&self, request_json: &str) -> Result<v6::Response, Error> {
+        let request: request_json::Request = serde_json::from_str(request_json)?;
+        self.query_api_v6(&request).await
+    }
+
+    /// Like [`SymbolicateApi::query_api`], but returns the `/symbolicate/v6`
+    /// response format. The request format is the same as for v5.
+    pub async fn query_api_v6(
+        &self,
+        request: &request_json::Request,
+    ) -> Result<v6::Response, Error> {
+        let requested_addresses = gather_requested_addresses(request)?;
+        let symbolicated_addresses = self
+            .symbolicate_requested_addresses(requested_addresses)
+            .await;
+        Ok(v6::create_response(request, symbolicated_addresses))
+    }
+
     async fn symbolicate_requested_addresses(
         &self,
         requested_addresses: HashMap<Lib, Vec<u32>>,
-    ) -> HashMap<Lib, Result<LookedUpAddresses, samply_symbols::Error>> {
-        let mut symbolicated_addresses = HashMap::new();
-        for (lib, addresses) in requested_addresses.into_iter() {
-            let address_results = self
-                .symbolicate_requested_addresses_for_lib(&lib, addresses)
-                .await;
-            symbolicated_addresses.insert(lib, address_results);
-        }
-        symbolicated_addresses
+    ) -> HashMap<Lib, Result<LookedUpAddresses, response_json::Error>> {
+        // Each library is independent, so we can look them up concurrently.
+        // A failure in one library, even a panic, only affects the results for
+        // that library; it ends up in the module_errors of the response.
+        stream::iter(requested_addresses)
+            .map(|(lib, addresses)| async move {
+                let address_results =
+                    AssertUnwindSafe(self.symbolicate_requested_addresses_for_lib(&lib, addresses))
+                        .catch_unwind()
+                        .await;
+                let address_results = match address_results {
+                    Ok(Ok(address_results)) => Ok(address_results),
+                    Ok(Err(err)) => Err((&err).into()),
+                    Err(panic_payload) => Err(panic_error(panic_payload)),
+                };
+                (lib, address_results)
+            })
+            .buffer_unordered(self.concurrency_limit)
+            .collect()
+            .await
     }
 
     async fn symbolicate_requested_addresses_for_lib(
@@ -78,11 +151,6 @@ impl<'a, H: FileAndPathHelper> SymbolicateApi<'a, H> {
         let debug_id = to_debug_id(&lib.breakpad_id)?;
 
         let mut symbolication_result = LookedUpAddresses::for_addresses(&addresses);
-        let mut external_addresses = Vec::new();
-
-        // Do the synchronous work first, and accumulate external_addresses which need
-        // to be handled asynchronously. This allows us to group async file loads by
-        // the external file.
 
         let info = LibraryInfo {
             debug_name: Some(lib.debug_name.to_string()),
@@ -93,34 +161,18 @@ impl<'a, H: FileAndPathHelper> SymbolicateApi<'a, H> {
 
         symbolication_result.set_total_symbol_count(symbol_map.symbol_count() as u32);
 
-        for &address in &addresses {
-            if let Some(address_info) = symbol_map.lookup_sync(LookupAddress::Relative(address)) {
-                symbolication_result.add_address_symbol(
-                    address,
-                    address_info.symbol.address,
-                    address_info.symbol.name,
-                    address_info.symbol.size,
-                );
-                match address_info.frames {
-                    Some(FramesLookupResult::Available(frames)) => {
-                        symbolication_result.add_address_debug_info(address, frames)
-                    }
-                    Some(FramesLookupResult::External(ext_address)) => {
-                        external_addresses.push((address, ext_address));
-                    }
-                    None => {}
-                }
-            }
-        }
-
-        // Look up any addresses whose debug info is in an external file.
-        // The symbol_manager caches the most recent external file, so we sort our
-        // external addresses by ExternalFileAddressRef before we do the lookup,
-        // in order to get the best hit rate in lookup_external.
-        external_addresses.sort_unstable_by(|(_, a), (_, b)| a.cmp(b));
-
-        for (address, ext_address) in external_addresses {
-            if let Some(frames) = symbol_map.lookup_external(&ext_address).await {
+        let address_infos = symbol_map.lookup_addresses(&addresses).await;
+        for (&address, address_info) in addresses.iter().zip(address_infos) {
+            let Some(address_info) = address_info else {
+                continue;
+            };
+            symbolication_result.add_address_symbol(
+                address,
+                address_info.symbol.address,
+                address_info.symbol.name,
+                address_info.symbol.size,
+            );
+            if let Some(frames) = address_info.frames {
                 symbolication_result.add_address_debug_info(address, frames);
             }
         }
@@ -129,6 +181,24 @@ impl<'a, H: FileAndPathHelper> SymbolicateApi<'a, H> {
     }
 }
 
+/// Creates a module error for a panic which happened during the symbolication
+/// of a library.
+fn panic_error(panic_payload: Box<dyn Any + Send>) -> response_json::Error {
+    let message = if let Some(s) = panic_payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = panic_payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Unknown panic payload".to_string()
+    };
+    response_json::Error {
+        name: "Panic".to_string(),
+        message,
+        filename: None,
+        line: None,
+    }
+}
+
 fn gather_requested_addresses(
     request: &request_json::Request,
 ) -> Result<HashMap<Lib, Vec<u32>>, Error> {
@@ -156,34 +226,56 @@ fn gather_requested_addresses(
     Ok(requested_addresses)
 }
 
+/// The per-library outcome of the lookups for one job.
+struct JobModuleResults<'a> {
+    found_modules: HashMap<String, bool>,
+    module_errors: HashMap<String, Vec<response_json::Error>>,
+    symbols_by_module_index: HashMap<u32, &'a AddressResults>,
+}
+
+fn module_results_for_job<'a>(
+    job: &request_json::Job,
+    symbolicated_addresses: &'a HashMap<Lib, Result<LookedUpAddresses, response_json::Error>>,
+) -> JobModuleResults<'a> {
+    let mut found_modules = HashMap::new();
+    let mut module_errors = HashMap::new();
+    let mut symbols_by_module_index = HashMap::new();
+    for (module_index, lib) in job.memory_map.iter().enumerate() {
+        if let Some(symbol_result) = symbolicated_addresses.get(lib) {
+            let module_key = format!("{}/{}", lib.debug_name, lib.breakpad_id);
+            match symbol_result {
+                Ok(symbols) => {
+                    symbols_by_module_index.insert(module_index as u32, &symbols.address_results);
+                }
+                Err(err) => {
+                    module_errors.insert(module_key.clone(), vec![err.clone()]);
+                }
+            }
+            found_modules.insert(module_key, symbol_result.is_ok());
+        }
+    }
+    JobModuleResults {
+        found_modules,
+        module_errors,
+        symbols_by_module_index,
+    }
+}
+
 fn create_response(
     request: &request_json::Request,
-    symbolicated_addresses: HashMap<Lib, Result<LookedUpAddresses, samply_symbols::Error>>,
+    symbolicated_addresses: HashMap<Lib, Result<LookedUpAddresses, response_json::Error>>,
 ) -> response_json::Response {
     use response_json::{DebugInfo, FrameDebugInfo, Response, Stack, StackFrame, Symbol};
 
     fn result_for_job(
         job: &request_json::Job,
-        symbolicated_addresses: &HashMap<Lib, Result<LookedUpAddresses, samply_symbols::Error>>,
+        symbolicated_addresses: &HashMap<Lib, Result<LookedUpAddresses, response_json::Error>>,
     ) -> response_json::Result {
-        let mut found_modules = HashMap::new();
-        let mut module_errors = HashMap::new();
-        let mut symbols_by_module_index = HashMap::new();
-        for (module_index, lib) in job.memory_map.iter().enumerate() {
-            if let Some(symbol_result) = symbolicated_addresses.get(lib) {
-                let module_key = format!("{}/{}", lib.debug_name, lib.breakpad_id);
-                match symbol_result {
-                    Ok(symbols) => {
-                        symbols_by_module_index
-                            .insert(module_index as u32, &symbols.address_results);
-                    }
-                    Err(err) => {
-                        module_errors.insert(module_key.clone(), vec![err.into()]);
-                    }
-                }
-                found_modules.insert(module_key, symbol_result.is_ok());
-            }
-        }
+        let JobModuleResults {
+            found_modules,
+            module_errors,
+            symbols_by_module_index,
+        } = module_results_for_job(job, symbolicated_addresses);
 
         let stacks = job.stacks.iter().map(|stack| {
             response_stack_for_request_stack(stack, &job.memory_map, &symbols_by_module_index)
@@ -221,12 +313,11 @@ fn create_response(
         let symbol = symbols_by_module_index
             .get(&frame.module_index)
             .and_then(|symbol_map| {
-                // If we have a symbol table for this library, then we know that
-                // this address is present in it.
+                // If we have a symbol table for this library, then this address
+                // should be present in it.
                 symbol_map
                     .get(&frame.address)
-                    .unwrap()
-                    .as_ref()
+                    .and_then(Option::as_ref)
                     .map(|address_result| Symbol {
                         function: address_result.symbol_name.clone(),
                         function_offset: frame.address - address_result.symbol_address,
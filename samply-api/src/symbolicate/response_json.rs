@@ -75,7 +75,7 @@ pub struct FrameDebugInfo {
     pub line: Option<NonZeroU32>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct Error {
     pub name: String,
     pub message: String,
@@ -0,0 +1,248 @@
+//! The response format of `/symbolicate/v6`.
+//!
+//! The request format is the same as for `/symbolicate/v5`. The response has
+//! the same overall shape as the v5 response, but each stack frame lists the
+//! functions at its address in a `frames` array, rather than splitting them
+//! into the outer function and an `inlines` field:
+//!
+//! ```json
+//! {
+//!   "results": [
+//!     {
+//!       "stacks": [
+//!         [
+//!           {
+//!             "frame": 0,
+//!             "module": "firefox.pdb",
+//!             "module_offset": "0x31fe8",
+//!             "function": "mozilla::Outer()",
+//!             "function_offset": "0x18",
+//!             "function_size": "0x40",
+//!             "frames": [
+//!               { "function": "mozilla::Inner()", "file": "inner.h", "line": 12, "inline": true, "module_offset": "0x31fe8" },
+//!               { "function": "mozilla::Outer()", "file": "outer.cpp", "line": 34, "inline": false, "module_offset": "0x31fe8" }
+//!             ]
+//!           }
+//!         ]
+//!       ],
+//!       "found_modules": { "firefox.pdb/AA152DEB2D9B76084C4C44205044422E1": true }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The `frames` array is ordered from the innermost inlined function to the
+//! outer function, so its last element has `"inline": false`. If there is a
+//! symbol but no debug info for the address, `frames` only contains the outer
+//! function, without `file` and `line`. If the address couldn't be symbolicated,
+//! `function`, `function_offset` and `function_size` are absent and `frames`
+//! is empty. `module_errors` is omitted if there were no errors.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+
+use serde_derive::Serialize;
+
+use super::looked_up_addresses::{AddressResult, LookedUpAddresses};
+use super::request_json::{self, Lib};
+use super::response_json::Error;
+use super::{module_results_for_job, JobModuleResults};
+use crate::api_file_path::to_api_file_path;
+
+#[derive(Serialize, Debug)]
+pub struct Response {
+    pub results: Vec<Result>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Result {
+    pub stacks: Vec<Stack>,
+    pub found_modules: HashMap<String, bool>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub module_errors: HashMap<String, Vec<Error>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Stack(pub Vec<StackFrame>);
+
+#[derive(Serialize, Debug)]
+pub struct StackFrame {
+    /// index of this StackFrame in its parent Stack
+    pub frame: u32,
+
+    #[serde(serialize_with = "crate::hex::as_hex_string")]
+    pub module_offset: u32,
+
+    pub module: String,
+
+    #[serde(flatten)]
+    pub symbol: Option<Symbol>,
+
+    /// The functions at this address, innermost first.
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Symbol {
+    pub function: String,
+
+    #[serde(serialize_with = "crate::hex::as_hex_string")]
+    pub function_offset: u32,
+
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::hex::as_optional_hex_string"
+    )]
+    pub function_size: Option<u32>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Frame {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<NonZeroU32>,
+
+    /// Whether this function was inlined into the next frame.
+    pub inline: bool,
+
+    #[serde(serialize_with = "crate::hex::as_hex_string")]
+    pub module_offset: u32,
+}
+
+pub(super) fn create_response(
+    request: &request_json::Request,
+    symbolicated_addresses: HashMap<Lib, std::result::Result<LookedUpAddresses, Error>>,
+) -> Response {
+    Response {
+        results: request
+            .jobs()
+            .map(|job| result_for_job(job, &symbolicated_addresses))
+            .collect(),
+    }
+}
+
+fn result_for_job(
+    job: &request_json::Job,
+    symbolicated_addresses: &HashMap<Lib, std::result::Result<LookedUpAddresses, Error>>,
+) -> Result {
+    let JobModuleResults {
+        found_modules,
+        module_errors,
+        symbols_by_module_index,
+    } = module_results_for_job(job, symbolicated_addresses);
+
+    let stacks = job.stacks.iter().map(|stack| {
+        let frames = stack.0.iter().enumerate().map(|(frame_index, frame)| {
+            let address_result = symbols_by_module_index
+                .get(&frame.module_index)
+                .and_then(|address_results| address_results.get(&frame.address))
+                .and_then(Option::as_ref);
+            response_frame(
+                frame_index as u32,
+                frame.address,
+                &job.memory_map[frame.module_index as usize],
+                address_result,
+            )
+        });
+        Stack(frames.collect())
+    });
+
+    Result {
+        stacks: stacks.collect(),
+        found_modules,
+        module_errors,
+    }
+}
+
+fn response_frame(
+    frame_index: u32,
+    address: u32,
+    lib: &Lib,
+    address_result: Option<&AddressResult>,
+) -> StackFrame {
+    let Some(address_result) = address_result else {
+        return StackFrame {
+            frame: frame_index,
+            module_offset: address,
+            module: lib.debug_name.clone(),
+            symbol: None,
+            frames: Vec::new(),
+        };
+    };
+
+    // An empty list of frames is treated like having no frame information.
+    let frames = match address_result
+        .inline_frames
+        .as_ref()
+        .filter(|inline_frames| !inline_frames.is_empty())
+    {
+        Some(inline_frames) => {
+            let outer_index = inline_frames.len() - 1;
+            inline_frames
+                .iter()
+                .enumerate()
+                .map(|(index, inline_frame)| Frame {
+                    function: inline_frame.function.clone(),
+                    file: inline_frame.file_path.as_ref().map(to_api_file_path),
+                    line: inline_frame.line_number.and_then(NonZeroU32::new),
+                    inline: index != outer_index,
+                    module_offset: address,
+                })
+                .collect()
+        }
+        None => vec![Frame {
+            function: Some(address_result.symbol_name.clone()),
+            file: None,
+            line: None,
+            inline: false,
+            module_offset: address,
+        }],
+    };
+
+    StackFrame {
+        frame: frame_index,
+        module_offset: address,
+        module: lib.debug_name.clone(),
+        symbol: Some(Symbol {
+            function: address_result.symbol_name.clone(),
+            function_offset: address - address_result.symbol_address,
+            function_size: address_result.function_size,
+        }),
+        frames,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::looked_up_addresses::AddressResult;
+    use super::super::request_json::Lib;
+    use super::response_frame;
+
+    #[test]
+    fn empty_inline_frames() {
+        // Some backends return an empty list of frames instead of no frames.
+        let lib = Lib {
+            debug_name: String::from("xul.pdb"),
+            breakpad_id: String::from("44E4EC8C2F41492B9369D6B9A059577C2"),
+        };
+        let address_result = AddressResult {
+            symbol_address: 0x1000,
+            symbol_name: String::from("some_function"),
+            function_size: None,
+            inline_frames: Some(Vec::new()),
+        };
+        let stack_frame = response_frame(0, 0x1010, &lib, Some(&address_result));
+        assert_eq!(stack_frame.frames.len(), 1);
+        assert_eq!(
+            stack_frame.frames[0].function.as_deref(),
+            Some("some_function")
+        );
+        assert!(!stack_frame.frames[0].inline);
+    }
+}
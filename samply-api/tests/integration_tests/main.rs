@@ -4,7 +4,7 @@ use std::path::PathBuf;
 
 use assert_json_diff::assert_json_eq;
 pub use samply_api::debugid::DebugId;
-use samply_api::{samply_symbols, Api};
+use samply_api::{samply_symbols, Api, QueryApiError, QueryApiResponse};
 use samply_symbols::{
     CandidatePathInfo, FileAndPathHelper, FileAndPathHelperResult, FileLocation, LibraryInfo,
     OptionallySendFuture, SymbolManager,
@@ -442,3 +442,198 @@ fn asm_x86_64() {
         "output-asm_x86_64.txt",
     )
 }
+
+#[test]
+fn asm_x86_64_with_bytes() {
+    compare_snapshot(
+        "/asm/v1",
+        r#"{
+            "name": "firefox.exe",
+            "debugName": "firefox.pdb",
+            "debugId": "8A913DE821D9DE764C4C44205044422E1",
+            "startAddress": "0x17a20",
+            "size": "0x10",
+            "includeBytes": true
+        }"#,
+        fixtures_dir().join("win64-local"),
+        "asm_x86_64_with_bytes.txt",
+        "output-asm_x86_64_with_bytes.txt",
+    )
+}
+
+#[test]
+fn v5_garbage_module_does_not_affect_other_modules() {
+    let dir =
+        std::env::temp_dir().join(format!("samply-api-garbage-module-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(
+        fixtures_dir().join("win64-ci").join("firefox.pdb"),
+        dir.join("firefox.pdb"),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("garbage.pdb"),
+        b"this is not a PDB file\0\x01\x02\x03",
+    )
+    .unwrap();
+
+    let output = futures::executor::block_on(crate::query_api(
+        "/symbolicate/v5",
+        r#"{
+                "memoryMap": [
+                  [
+                    "garbage.pdb",
+                    "63C609072D3499F64C4C44205044422E1"
+                  ],
+                  [
+                    "firefox.pdb",
+                    "AA152DEB2D9B76084C4C44205044422E1"
+                  ]
+                ],
+                "stacks": [
+                  [
+                    [0, 244290],
+                    [1, 204776]
+                  ]
+                ]
+              }"#,
+        dir.clone(),
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let result = &output["results"][0];
+    assert_eq!(
+        result["found_modules"]["garbage.pdb/63C609072D3499F64C4C44205044422E1"],
+        false
+    );
+    assert_eq!(
+        result["found_modules"]["firefox.pdb/AA152DEB2D9B76084C4C44205044422E1"],
+        true
+    );
+    let garbage_errors = result["module_errors"]["garbage.pdb/63C609072D3499F64C4C44205044422E1"]
+        .as_array()
+        .unwrap();
+    assert!(!garbage_errors.is_empty());
+    assert!(result["module_errors"]
+        .get("firefox.pdb/AA152DEB2D9B76084C4C44205044422E1")
+        .is_none());
+
+    let frames = result["stacks"][0].as_array().unwrap();
+    assert_eq!(frames[0]["module"], "garbage.pdb");
+    assert!(frames[0].get("function").is_none());
+    assert_eq!(frames[1]["module"], "firefox.pdb");
+    assert!(frames[1]["function"].is_string());
+}
+
+#[test]
+fn win64_ci_v6_snapshot() {
+    compare_snapshot(
+        "/symbolicate/v6",
+        r#"{
+                "memoryMap": [
+                  [
+                    "firefox.pdb",
+                    "AA152DEB2D9B76084C4C44205044422E1"
+                  ],
+                  [
+                    "mozglue.pdb",
+                    "63C609072D3499F64C4C44205044422E1"
+                  ]
+                ],
+                "stacks": [
+                  [
+                    [0, 204776],
+                    [0, 129423],
+                    [0, 244290],
+                    [0, 244219],
+                    [1, 244290],
+                    [1, 244219],
+                    [1, 237799]
+                  ]
+                ]
+              }"#,
+        fixtures_dir().join("win64-ci"),
+        "api-v6-win64-ci.txt",
+        "output-api-v6-win64-ci.txt",
+    );
+}
+
+#[test]
+fn android32_v6_local() {
+    compare_snapshot(
+        "/symbolicate/v6",
+        r#"{
+                "memoryMap": [
+                  [
+                    "libmozglue.so",
+                    "0CE47B7C29F27CED55C41233B93EBA450"
+                  ]
+                ],
+                "stacks": [
+                  [
+                    [0, 247618],
+                    [0, 685896],
+                    [0, 686768]
+                  ]
+                ]
+              }"#,
+        fixtures_dir().join("android32-local"),
+        "api-v6-android32-local.txt",
+        "output-api-v6-android32-local.txt",
+    );
+}
+
+#[test]
+fn typed_query_api() {
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("win64-ci"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let request_json = r#"{
+            "memoryMap": [
+              [
+                "firefox.pdb",
+                "AA152DEB2D9B76084C4C44205044422E1"
+              ]
+            ],
+            "stacks": [
+              [
+                [0, 204776]
+              ]
+            ]
+          }"#;
+
+    let response = futures::executor::block_on(
+        Api::new(&symbol_manager).query_api_typed("/symbolicate/v5", request_json),
+    )
+    .unwrap();
+    let QueryApiResponse::SymbolicateV5(typed_response) = &response else {
+        panic!("Unexpected response type: {response:?}");
+    };
+    let frame = &typed_response.results[0].stacks[0].0[0];
+    assert_eq!(frame.module, "firefox.pdb");
+    assert_eq!(
+        frame.symbol.as_ref().unwrap().function,
+        "sandbox::ProcessMitigationsWin32KDispatcher::EnumDisplayMonitors(sandbox::IPCInfo*, sandbox::CountedBuffer*)"
+    );
+
+    // to_json() gives the same output as the string-returning API.
+    let string_response = futures::executor::block_on(
+        Api::new(&symbol_manager).query_api("/symbolicate/v5", request_json),
+    );
+    assert_eq!(response.to_json(), string_response);
+
+    let err = futures::executor::block_on(
+        Api::new(&symbol_manager).query_api_typed("/symbolicate/v4", request_json),
+    )
+    .unwrap_err();
+    assert!(matches!(err, QueryApiError::UnrecognizedUrl(_)));
+    assert_eq!(err.to_string(), "Unrecognized URL /symbolicate/v4");
+
+    let err = futures::executor::block_on(
+        Api::new(&symbol_manager).query_api_typed("/symbolicate/v5", "{"),
+    )
+    .unwrap_err();
+    assert!(matches!(err, QueryApiError::Symbolicate(_)));
+}
@@ -188,6 +188,7 @@ impl<F: FileContents> BinaryImageInner<F> {
             code_id,
             path,
             arch,
+            base_address: None,
         };
         Ok(info)
     }
@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::io::Write;
 use std::str::FromStr;
 use std::{mem, str};
 
@@ -45,6 +46,11 @@ impl BreakpadIndex {
         if &header.magic != b"SYMINDEX" {
             return Err(BreakpadSymindexParseError::WrongMagicBytes);
         }
+        if header.version.get() != SYMINDEX_VERSION {
+            return Err(BreakpadSymindexParseError::VersionMismatch(
+                header.version.get(),
+            ));
+        }
         let module_info_end_offset = header
             .module_info_offset
             .get()
@@ -211,6 +217,13 @@ impl BreakpadIndex {
         })
     }
 
+    /// Writes this index in the .symindex format, which [`BreakpadIndex::parse_symindex_file`]
+    /// can read back.
+    pub fn write_symindex<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&self.serialize_to_bytes())?;
+        writer.flush()
+    }
+
     pub fn serialize_to_bytes(&self) -> Vec<u8> {
         let header_len = HEADER_SIZE;
         let module_info_offset = header_len;
@@ -230,7 +243,7 @@ impl BreakpadIndex {
         let total_file_len = symbol_entries_offset + symbol_entries_len;
         let header = BreakpadSymindexFileHeader {
             magic: *b"SYMINDEX",
-            version: 1.into(),
+            version: SYMINDEX_VERSION.into(),
             module_info_offset: module_info_offset.into(),
             module_info_len: module_info_len.into(),
             file_count: file_count.into(),
@@ -318,6 +331,9 @@ pub enum BreakpadSymindexParseError {
     #[error("Wrong magic bytes in the symindex header")]
     WrongMagicBytes,
 
+    #[error("Unsupported symindex version {0}, expected {expected}", expected = SYMINDEX_VERSION)]
+    VersionMismatch(u32),
+
     #[error("Module offset + len overflowed u32")]
     ModuleInfoOffsetLenOverflow,
 
@@ -367,12 +383,19 @@ pub enum BreakpadSymindexParseError {
     CouldntReadSymbolEntryListBytes,
 }
 
+/// The version of the .symindex format which is written and accepted by this
+/// crate. Must be bumped whenever the layout described at
+/// [`BreakpadSymindexFileHeader`] changes; files with a different version are
+/// rejected with [`BreakpadSymindexParseError::VersionMismatch`], and the
+/// index is then rebuilt from the .sym file.
+const SYMINDEX_VERSION: u32 = 1;
+
 #[derive(FromBytes, KnownLayout, Immutable, IntoBytes, Unaligned)]
 #[repr(C)]
 struct BreakpadSymindexFileHeader {
     /// Always b"SYMINDEX", at 0
     magic: [u8; 8],
-    /// Always 1, at 8
+    /// Always `SYMINDEX_VERSION`, at 8
     version: U32<LittleEndian>,
     /// Points right after header, to where the module info starts, 4-byte aligned, at 12
     module_info_offset: U32<LittleEndian>,
@@ -421,7 +444,7 @@ struct SymbolEntry {
 /// Format: (all numbers encoded as little-endian)
 ///
 /// magic: [u8; 8], // always b"SYMINDEX", at 0
-/// version: u32, // always 1, at 8
+/// version: u32, // SYMINDEX_VERSION, currently 1, at 8
 /// module_info_offset: u32, // points right after header, to where the module info starts, 4-byte aligned, at 12
 /// module_info_len: u32, // the length, in bytes, of the module info, at 16
 /// file_count: u32, // the number of entries in the file list, at 20
@@ -650,6 +673,20 @@ impl BreakpadIndexParser {
             .finish(|offset, line| inner.process_line(offset, line));
         self.inner.finish(final_offset)
     }
+
+    /// Finishes parsing and writes the resulting index to `writer`, as a
+    /// .symindex file which can be placed next to the .sym file, at
+    /// [`FileLocation::location_for_breakpad_symindex`](crate::FileLocation::location_for_breakpad_symindex).
+    /// Loading the .sym file is much faster if this index is present.
+    ///
+    /// Parse errors are returned as [`std::io::ErrorKind::InvalidData`].
+    pub fn write_symindex<W: Write>(self, writer: W) -> std::io::Result<BreakpadIndex> {
+        let index = self
+            .finish()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        index.write_symindex(writer)?;
+        Ok(index)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1061,9 +1098,17 @@ fn file_line(input: &[u8]) -> IResult<&[u8], (u32, &[u8])> {
 }
 
 // Matches an INLINE_ORIGIN record.
+//
+// An INLINE_ORIGIN record has the form `INLINE_ORIGIN <origin_id> <name>`.
+// Older versions of Breakpad's dump_syms also emitted an unused file ID,
+// `INLINE_ORIGIN <origin_id> <file_id> <name>`; the file ID is skipped.
 fn inline_origin_line(input: &[u8]) -> IResult<&[u8], (u32, &[u8])> {
     let (input, _) = terminated(tag("INLINE_ORIGIN"), space1)(input)?;
-    let (input, (id, function)) = cut(tuple((terminated(decimal_u32, space1), rest)))(input)?;
+    let (input, (id, _file_id, function)) = cut(tuple((
+        terminated(decimal_u32, space1),
+        opt(terminated(decimal_u32, space1)),
+        rest,
+    )))(input)?;
     Ok((input, (id, function)))
 }
 
@@ -1259,6 +1304,32 @@ mod test {
         assert_eq!(index2, index);
     }
 
+    #[test]
+    fn write_symindex() {
+        let sym = b"MODULE Linux x86_64 BE4E976C325246EE9D6B7847A670B2A90 example-linux\n\
+            FILE 0 main.c\nPUBLIC 1000 0 _start\nFUNC 1100 40 0 main\n1100 40 3 0\n";
+        let mut parser = BreakpadIndexParser::new();
+        parser.consume(sym);
+        let mut symindex = Vec::new();
+        let index = parser.write_symindex(&mut symindex).unwrap();
+        assert_eq!(&symindex[..8], b"SYMINDEX");
+        assert_eq!(
+            BreakpadIndex::parse_symindex_file(&symindex).unwrap(),
+            index
+        );
+
+        symindex[8..12].copy_from_slice(&(SYMINDEX_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            BreakpadIndex::parse_symindex_file(&symindex),
+            Err(BreakpadSymindexParseError::VersionMismatch(version)) if version == SYMINDEX_VERSION + 1
+        ));
+
+        let mut parser = BreakpadIndexParser::new();
+        parser.consume(b"not a sym file");
+        let err = parser.write_symindex(Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn func_parsing() {
         let block =
@@ -1,10 +1,12 @@
 mod index;
 mod symbol_map;
+mod writer;
 
 pub use index::{
     BreakpadIndex, BreakpadIndexParser, BreakpadParseError, BreakpadSymindexParseError,
 };
 pub use symbol_map::get_symbol_map_for_breakpad_sym;
+pub use writer::{write_breakpad_sym, BreakpadModuleInfo};
 
 use crate::{FileContents, FileContentsWrapper};
 
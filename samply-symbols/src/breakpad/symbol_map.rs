@@ -13,8 +13,8 @@ use super::index::{
 };
 use crate::symbol_map::{GetInnerSymbolMap, SymbolMapTrait};
 use crate::{
-    Error, FileContents, FileContentsWrapper, FrameDebugInfo, FramesLookupResult, LookupAddress,
-    SourceFilePath, SymbolInfo, SyncAddressInfo,
+    Error, FileContents, FileContentsWrapper, FrameDebugInfo, FramesLookupResult, LineRange,
+    LookupAddress, SourceFilePath, SymbolInfo, SyncAddressInfo,
 };
 
 pub fn get_symbol_map_for_breakpad_sym<FC: FileContents + 'static>(
@@ -230,6 +230,30 @@ impl<T: FileContents> SymbolMapTrait for BreakpadSymbolMapInner<'_, T> {
         Box::new(iter)
     }
 
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        let iter = (0..self.symbol_count()).filter_map(move |i| {
+            let address = self.index.symbol_addresses[i];
+            let mut cache = self.cache.lock().unwrap();
+            let (size, name) = match &self.index.symbol_offsets[i] {
+                super::index::BreakpadSymbolType::Public(public) => {
+                    // PUBLIC records don't have a size. They extend to the next symbol.
+                    let public_info = cache.symbols.get_public_info(public, self.data).ok()?;
+                    let next_address = self.index.symbol_addresses.get(i + 1);
+                    let size = next_address.and_then(|next| next.checked_sub(address));
+                    (size, public_info.name)
+                }
+                super::index::BreakpadSymbolType::Func(func) => {
+                    let func_info = cache.symbols.get_func_info(func, self.data).ok()?;
+                    (Some(func_info.size), func_info.name)
+                }
+            };
+            Some((address, size, Cow::Borrowed(name)))
+        });
+        Box::new(iter)
+    }
+
     fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
         let address = match address {
             LookupAddress::Relative(relative_address) => relative_address,
@@ -321,6 +345,49 @@ impl<T: FileContents> SymbolMapTrait for BreakpadSymbolMapInner<'_, T> {
             }
         }
     }
+
+    fn line_ranges(&self, address: u32, size: u32) -> Vec<LineRange> {
+        let end = address.saturating_add(size);
+        let first_index = match self.index.symbol_addresses.binary_search(&address) {
+            Ok(i) => i,
+            Err(0) => 0,
+            Err(i) => i - 1,
+        };
+        let mut cache = self.cache.lock().unwrap();
+        let BreakpadSymbolMapCache { files, symbols, .. } = &mut *cache;
+        let mut ranges = Vec::new();
+        for (symbol_address, symbol) in self.index.symbol_addresses[first_index..]
+            .iter()
+            .zip(&self.index.symbol_offsets[first_index..])
+        {
+            if *symbol_address >= end {
+                break;
+            }
+            let BreakpadSymbolType::Func(func) = symbol else {
+                continue;
+            };
+            let Ok(info) = symbols.get_func_info(func, self.data) else {
+                continue;
+            };
+            for line in &info.lines {
+                let start = line.address.max(address);
+                let line_end = line.address.saturating_add(line.size).min(end);
+                if start >= line_end {
+                    continue;
+                }
+                let Ok(file) = files.get_str(line.file) else {
+                    continue;
+                };
+                ranges.push(LineRange {
+                    address: start,
+                    size: line_end - start,
+                    file_path: SourceFilePath::from_breakpad_path(file.to_string()),
+                    line: line.line,
+                });
+            }
+        }
+        ranges
+    }
 }
 
 #[cfg(test)]
@@ -345,6 +412,59 @@ mod test {
         );
     }
 
+    #[test]
+    fn inline_frames() {
+        // Origin 1 uses the older INLINE_ORIGIN form with an (unused) file ID.
+        let sym = b"MODULE Linux x86_64 BE4E976C325246EE9D6B7847A670B2A90 example-linux\n\
+            FILE 0 main.c\nFILE 1 helpers.h\n\
+            INLINE_ORIGIN 0 outer_helper\nINLINE_ORIGIN 1 1 inner_helper\n\
+            FUNC 1000 30 0 main\n\
+            INLINE 0 12 0 0 1008 10\nINLINE 1 5 1 1 100c 8\n\
+            1000 8 10 0\n1008 4 4 1\n100c 8 20 1\n1014 4 6 1\n1018 18 14 0\n";
+        let fc = FileContentsWrapper::new(&sym[..]);
+        let symbol_map = get_symbol_map_for_breakpad_sym(fc, None).unwrap();
+        let frames = |address| {
+            let lookup_result = symbol_map
+                .get_inner_symbol_map()
+                .lookup_sync(LookupAddress::Relative(address))
+                .unwrap();
+            assert_eq!(lookup_result.symbol.name, "main");
+            match lookup_result.frames {
+                Some(FramesLookupResult::Available(frames)) => frames
+                    .into_iter()
+                    .map(|frame| {
+                        (
+                            frame.function.unwrap(),
+                            frame.file_path.unwrap().raw_path().to_string(),
+                            frame.line_number.unwrap(),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+                _ => panic!("Frames should be available"),
+            }
+        };
+        let frame =
+            |function: &str, file: &str, line| (function.to_string(), file.to_string(), line);
+
+        assert_eq!(frames(0x1004), vec![frame("main", "main.c", 10)]);
+        assert_eq!(
+            frames(0x1008),
+            vec![
+                frame("outer_helper", "helpers.h", 4),
+                frame("main", "main.c", 12),
+            ]
+        );
+        assert_eq!(
+            frames(0x1010),
+            vec![
+                frame("inner_helper", "helpers.h", 20),
+                frame("outer_helper", "helpers.h", 5),
+                frame("main", "main.c", 12),
+            ]
+        );
+        assert_eq!(frames(0x1020), vec![frame("main", "main.c", 14)]);
+    }
+
     #[test]
     fn lookup_with_index() {
         // This test simulates the case where an index is created independently, for
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::symbol_map::SymbolMapTrait;
+use crate::{FileAndPathHelper, SymbolMap};
+
+/// The values for the `MODULE` record at the start of a Breakpad .sym file,
+/// which can't be derived from the [`SymbolMap`] itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakpadModuleInfo<'a> {
+    /// The operating system, for example `Linux`, `mac` or `windows`.
+    pub os: &'a str,
+    /// The architecture, for example `x86_64` or `arm64`.
+    pub arch: &'a str,
+    /// The name of the debug file, for example `libxul.so` or `xul.pdb`.
+    pub debug_name: &'a str,
+}
+
+/// Writes the symbols of `symbol_map` as a Breakpad .sym file.
+///
+/// Symbols which have a size and line info are written as `FUNC` records, with
+/// one line record per line table entry from [`SymbolMap::line_ranges`]. All
+/// other symbols are written as `PUBLIC` records. Inlined frames are not written,
+/// i.e. there are no `INLINE` records.
+pub fn write_breakpad_sym<H: FileAndPathHelper, W: Write>(
+    symbol_map: &SymbolMap<H>,
+    module_info: &BreakpadModuleInfo<'_>,
+    writer: W,
+) -> std::io::Result<()> {
+    write_breakpad_sym_for_symbol_map_trait(symbol_map.inner(), module_info, writer)
+}
+
+struct FuncLine {
+    address: u32,
+    size: u32,
+    line: u32,
+    file_index: u32,
+}
+
+pub(crate) fn write_breakpad_sym_for_symbol_map_trait<W: Write>(
+    symbol_map: &dyn SymbolMapTrait,
+    module_info: &BreakpadModuleInfo<'_>,
+    mut writer: W,
+) -> std::io::Result<()> {
+    // FILE records need to come before the FUNC records which refer to them,
+    // so collect all symbols first.
+    let mut files: HashMap<String, u32> = HashMap::new();
+    let mut file_names = Vec::new();
+    let mut symbols = Vec::with_capacity(symbol_map.symbol_count());
+    for (address, size, name) in symbol_map.iter_symbols_with_sizes() {
+        let line_ranges = match size {
+            Some(size) if size != 0 => symbol_map.line_ranges(address, size),
+            _ => Vec::new(),
+        };
+        let lines: Vec<FuncLine> = line_ranges
+            .into_iter()
+            .map(|range| {
+                let path = range.file_path.raw_path().to_owned();
+                let file_index = *files.entry(path).or_insert_with_key(|path| {
+                    file_names.push(path.clone());
+                    (file_names.len() - 1) as u32
+                });
+                FuncLine {
+                    address: range.address,
+                    size: range.size,
+                    line: range.line,
+                    file_index,
+                }
+            })
+            .collect();
+        symbols.push((address, size, sanitize_name(&name), lines));
+    }
+
+    writeln!(
+        writer,
+        "MODULE {} {} {} {}",
+        module_info.os,
+        module_info.arch,
+        symbol_map.debug_id().breakpad(),
+        module_info.debug_name
+    )?;
+    for (index, file_name) in file_names.iter().enumerate() {
+        writeln!(writer, "FILE {index} {}", sanitize_name(file_name))?;
+    }
+    for (address, size, name, lines) in symbols {
+        match size {
+            Some(size) if !lines.is_empty() => {
+                writeln!(writer, "FUNC {address:x} {size:x} 0 {name}")?;
+                for line in lines {
+                    let FuncLine {
+                        address,
+                        size,
+                        line,
+                        file_index,
+                    } = line;
+                    writeln!(writer, "{address:x} {size:x} {line} {file_index}")?;
+                }
+            }
+            _ => {
+                writeln!(writer, "PUBLIC {address:x} 0 {name}")?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Records are line-based, so names must not contain line breaks.
+fn sanitize_name(name: &str) -> String {
+    name.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::breakpad::get_symbol_map_for_breakpad_sym;
+    use crate::symbol_map::GetInnerSymbolMap;
+    use crate::{FileContentsWrapper, FramesLookupResult, LookupAddress};
+
+    #[test]
+    fn round_trip() {
+        let sym = b"MODULE Linux x86_64 BE4E976C325246EE9D6B7847A670B2A90 example-linux\n\
+            FILE 0 src/main.c\nFILE 1 src/util.c\n\
+            PUBLIC 1000 0 _start\n\
+            FUNC 1100 40 0 main\n1100 10 3 0\n1110 30 4 0\n\
+            FUNC 1200 20 0 helper\n1200 20 12 1\n\
+            PUBLIC 1300 0 tail\n";
+        let fc = FileContentsWrapper::new(&sym[..]);
+        let symbol_map = get_symbol_map_for_breakpad_sym(fc, None).unwrap();
+        let module_info = BreakpadModuleInfo {
+            os: "Linux",
+            arch: "x86_64",
+            debug_name: "example-linux",
+        };
+        let mut output = Vec::new();
+        write_breakpad_sym_for_symbol_map_trait(
+            symbol_map.get_inner_symbol_map(),
+            &module_info,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            std::str::from_utf8(&output).unwrap(),
+            "MODULE Linux x86_64 BE4E976C325246EE9D6B7847A670B2A90 example-linux\n\
+            FILE 0 src/main.c\nFILE 1 src/util.c\n\
+            PUBLIC 1000 0 _start\n\
+            FUNC 1100 40 0 main\n1100 10 3 0\n1110 30 4 0\n\
+            FUNC 1200 20 0 helper\n1200 20 12 1\n\
+            PUBLIC 1300 0 tail\n"
+        );
+
+        let fc = FileContentsWrapper::new(output);
+        let written_map = get_symbol_map_for_breakpad_sym(fc, None).unwrap();
+        let written_map = written_map.get_inner_symbol_map();
+        let original_map = symbol_map.get_inner_symbol_map();
+        assert_eq!(written_map.debug_id(), original_map.debug_id());
+        assert!(written_map.iter_symbols().eq(original_map.iter_symbols()));
+        let lookup = |address| {
+            let info = written_map
+                .lookup_sync(LookupAddress::Relative(address))
+                .unwrap();
+            let frames = match info.frames {
+                Some(FramesLookupResult::Available(frames)) => frames
+                    .into_iter()
+                    .map(|frame| {
+                        (
+                            frame.file_path.unwrap().raw_path().to_string(),
+                            frame.line_number.unwrap(),
+                        )
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            (info.symbol.name, frames)
+        };
+        assert_eq!(lookup(0x1004), ("_start".to_string(), vec![]));
+        assert_eq!(
+            lookup(0x1114),
+            ("main".to_string(), vec![("src/main.c".to_string(), 4)])
+        );
+        assert_eq!(
+            lookup(0x1204),
+            ("helper".to_string(), vec![("src/util.c".to_string(), 12)])
+        );
+    }
+}
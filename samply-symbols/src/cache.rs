@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::ops::Range;
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Mutex;
 
 use elsa::sync::FrozenVec;
@@ -8,7 +8,8 @@ use elsa::sync::FrozenVec;
 use crate::chunked_read_buffer_manager::{ChunkedReadBufferManager, RangeLocation, RangeSourcing};
 use crate::{FileAndPathHelperResult, FileContents};
 
-const CHUNK_SIZE: u64 = 32 * 1024;
+/// The chunk size used by [`FileContentsWithChunkedCaching::new`].
+pub const DEFAULT_CHUNK_SIZE: u64 = 32 * 1024;
 
 pub trait FileByteSource: Send + Sync {
     /// Read `size` bytes at offset `offset` and append them to `buffer`.
@@ -22,24 +23,83 @@ pub trait FileByteSource: Send + Sync {
     ) -> FileAndPathHelperResult<()>;
 }
 
+/// Statistics about the reads of a [`FileContentsWithChunkedCaching`], returned
+/// by [`FileContentsWithChunkedCaching::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChunkedCachingStats {
+    /// The total number of bytes which were read from the [`FileByteSource`].
+    pub bytes_read_from_source: u64,
+    /// The number of reads which were served from already cached chunks.
+    pub cache_hits: u64,
+    /// The number of reads which needed to read new chunks from the source.
+    /// Reads which are passed through to the source without caching, via
+    /// [`FileContents::read_bytes_into`], are not counted here.
+    pub cache_misses: u64,
+    /// The number of cached buffers. Each buffer spans one or more chunks.
+    pub resident_buffer_count: usize,
+    /// The total size of all cached buffers, in bytes.
+    pub resident_bytes: u64,
+}
+
 pub struct FileContentsWithChunkedCaching<S: FileByteSource> {
     source: S,
     file_len: u64,
-    buffer_manager: Mutex<ChunkedReadBufferManager<CHUNK_SIZE>>,
+    buffer_manager: Mutex<ChunkedReadBufferManager>,
     string_cache: Mutex<HashMap<(u64, u8), RangeLocation>>,
     buffers: FrozenVec<Box<[u8]>>,
     buffer_count: AtomicUsize,
+    bytes_read_from_source: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    resident_bytes: AtomicU64,
 }
 
 impl<S: FileByteSource> FileContentsWithChunkedCaching<S> {
+    /// Creates a `FileContentsWithChunkedCaching` which reads from `source` in
+    /// chunks of [`DEFAULT_CHUNK_SIZE`] (32KiB) bytes.
     pub fn new(file_len: u64, source: S) -> Self {
+        Self::with_chunk_size(file_len, source, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a `FileContentsWithChunkedCaching` which reads from `source` in
+    /// chunks of `chunk_size` bytes. Every read from the source is rounded out
+    /// to chunk boundaries, and the bytes that were read stay cached for the
+    /// lifetime of this object.
+    ///
+    /// Larger chunks mean fewer reads from the source, which helps if each
+    /// read is expensive, for example if it is a network request. Smaller
+    /// chunks mean that less memory is used for bytes which are never
+    /// accessed, which is preferable if reads are cheap, for example from a
+    /// local file or memory map. Use [`FileContentsWithChunkedCaching::stats`]
+    /// to see how a chunk size performs.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(file_len: u64, source: S, chunk_size: u64) -> Self {
         FileContentsWithChunkedCaching {
             source,
             buffers: FrozenVec::new(),
             file_len,
-            buffer_manager: Mutex::new(ChunkedReadBufferManager::new_with_size(file_len)),
+            buffer_manager: Mutex::new(ChunkedReadBufferManager::new_with_size(
+                file_len, chunk_size,
+            )),
             string_cache: Mutex::new(HashMap::new()),
             buffer_count: AtomicUsize::new(0),
+            bytes_read_from_source: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            resident_bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns statistics about the reads so far, for example to find out how
+    /// much of the file had to be read.
+    pub fn stats(&self) -> ChunkedCachingStats {
+        ChunkedCachingStats {
+            bytes_read_from_source: self.bytes_read_from_source.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            resident_buffer_count: self.buffer_count.load(Ordering::Relaxed),
+            resident_bytes: self.resident_bytes.load(Ordering::Relaxed),
         }
     }
 
@@ -54,9 +114,13 @@ impl<S: FileByteSource> FileContentsWithChunkedCaching<S> {
     fn get_range_location(&self, range: Range<u64>) -> FileAndPathHelperResult<RangeLocation> {
         let mut buffer_manager = self.buffer_manager.lock().unwrap();
         let read_range = match buffer_manager.determine_range_sourcing(range.clone()) {
-            RangeSourcing::InExistingBuffer(l) => return Ok(l),
+            RangeSourcing::InExistingBuffer(l) => {
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(l);
+            }
             RangeSourcing::NeedToReadNewBuffer(read_range) => read_range,
         };
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
         assert!(read_range.start <= read_range.end);
 
         // Read the bytes from the source.
@@ -65,10 +129,12 @@ impl<S: FileByteSource> FileContentsWithChunkedCaching<S> {
         self.source
             .read_bytes_into(&mut buffer, read_range.start, read_len)?;
         assert!(buffer.len() == read_len);
+        self.bytes_read_from_source
+            .fetch_add(read_len as u64, Ordering::Relaxed);
+        self.resident_bytes
+            .fetch_add(read_len as u64, Ordering::Relaxed);
 
-        let buffer_handle = self
-            .buffer_count
-            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let buffer_handle = self.buffer_count.fetch_add(1, Ordering::SeqCst);
         self.buffers.push(buffer.into_boxed_slice());
         buffer_manager.insert_buffer_range(read_range.clone(), buffer_handle);
 
@@ -132,6 +198,7 @@ impl<S: FileByteSource> FileContents for FileContentsWithChunkedCaching<S> {
 
         let mut string_cache = self.string_cache.lock().unwrap();
         if let Some(location) = string_cache.get(&(range.start, delimiter)) {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
             return Ok(self.slice_from_location(location));
         }
 
@@ -160,6 +227,63 @@ impl<S: FileByteSource> FileContents for FileContentsWithChunkedCaching<S> {
         offset: u64,
         size: usize,
     ) -> FileAndPathHelperResult<()> {
-        self.source.read_bytes_into(buffer, offset, size)
+        self.source.read_bytes_into(buffer, offset, size)?;
+        self.bytes_read_from_source
+            .fetch_add(size as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct VecSource(Vec<u8>);
+
+    impl FileByteSource for VecSource {
+        fn read_bytes_into(
+            &self,
+            buffer: &mut Vec<u8>,
+            offset: u64,
+            size: usize,
+        ) -> FileAndPathHelperResult<()> {
+            buffer.extend_from_slice(&self.0[offset as usize..][..size]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stats_count_hits_and_misses() {
+        let len = DEFAULT_CHUNK_SIZE * 3;
+        let contents = FileContentsWithChunkedCaching::new(len, VecSource(vec![0; len as usize]));
+        assert_eq!(contents.stats(), ChunkedCachingStats::default());
+
+        contents.read_bytes_at(10, 20).unwrap();
+        contents.read_bytes_at(100, 20).unwrap();
+        contents.read_bytes_at(DEFAULT_CHUNK_SIZE * 2, 1).unwrap();
+        let mut buffer = Vec::new();
+        contents.read_bytes_into(&mut buffer, 0, 5).unwrap();
+
+        assert_eq!(
+            contents.stats(),
+            ChunkedCachingStats {
+                bytes_read_from_source: DEFAULT_CHUNK_SIZE * 2 + 5,
+                cache_hits: 1,
+                cache_misses: 2,
+                resident_buffer_count: 2,
+                resident_bytes: DEFAULT_CHUNK_SIZE * 2,
+            }
+        );
+    }
+
+    #[test]
+    fn custom_chunk_size() {
+        let contents =
+            FileContentsWithChunkedCaching::with_chunk_size(1000, VecSource(vec![0; 1000]), 100);
+        contents.read_bytes_at(150, 10).unwrap();
+        contents.read_bytes_at(990, 10).unwrap();
+        let stats = contents.stats();
+        assert_eq!(stats.bytes_read_from_source, 200);
+        assert_eq!(stats.resident_buffer_count, 2);
     }
 }
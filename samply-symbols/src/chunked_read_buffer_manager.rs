@@ -2,8 +2,9 @@ use std::ops::Range;
 
 use rangemap::RangeMap;
 
-pub struct ChunkedReadBufferManager<const CHUNK_SIZE: u64> {
+pub struct ChunkedReadBufferManager {
     file_len: u64,
+    chunk_size: u64,
     buffer_ranges: Vec<BufferRange>,
     range_map: RangeMap<u64, usize>,
 }
@@ -37,10 +38,13 @@ fn round_up_to_multiple(value: u64, factor: u64) -> u64 {
     (value + factor - 1) / factor * factor
 }
 
-impl<const CHUNK_SIZE: u64> ChunkedReadBufferManager<CHUNK_SIZE> {
-    pub fn new_with_size(file_len: u64) -> Self {
+impl ChunkedReadBufferManager {
+    /// Panics if `chunk_size` is zero.
+    pub fn new_with_size(file_len: u64, chunk_size: u64) -> Self {
+        assert!(chunk_size != 0, "The chunk size must not be zero");
         ChunkedReadBufferManager {
             file_len,
+            chunk_size,
             buffer_ranges: Vec::new(),
             range_map: RangeMap::new(),
         }
@@ -70,9 +74,9 @@ impl<const CHUNK_SIZE: u64> ChunkedReadBufferManager<CHUNK_SIZE> {
         let start = if start_is_cached {
             range.start
         } else {
-            round_down_to_multiple(range.start, CHUNK_SIZE)
+            round_down_to_multiple(range.start, self.chunk_size)
         };
-        let end = round_up_to_multiple(range.end, CHUNK_SIZE).clamp(0, self.file_len);
+        let end = round_up_to_multiple(range.end, self.chunk_size).clamp(0, self.file_len);
         RangeSourcing::NeedToReadNewBuffer(start..end)
     }
 
@@ -93,7 +97,7 @@ mod tests {
 
     #[test]
     fn rounds_out_to_chunks() {
-        let manager = ChunkedReadBufferManager::<10>::new_with_size(55);
+        let manager = ChunkedReadBufferManager::new_with_size(55, 10);
         assert_eq!(
             manager.determine_range_sourcing(3..5),
             RangeSourcing::NeedToReadNewBuffer(0..10)
@@ -135,7 +139,7 @@ mod tests {
 
     #[test]
     fn finds_existing_ranges() {
-        let mut manager = ChunkedReadBufferManager::<10>::new_with_size(55);
+        let mut manager = ChunkedReadBufferManager::new_with_size(55, 10);
         assert_eq!(
             manager.determine_range_sourcing(3..5),
             RangeSourcing::NeedToReadNewBuffer(0..10)
@@ -166,7 +170,7 @@ mod tests {
 
     #[test]
     fn last_buffer_wins() {
-        let mut manager = ChunkedReadBufferManager::<10>::new_with_size(55);
+        let mut manager = ChunkedReadBufferManager::new_with_size(55, 10);
         assert_eq!(
             manager.determine_range_sourcing(13..15),
             RangeSourcing::NeedToReadNewBuffer(10..20)
@@ -189,7 +193,7 @@ mod tests {
 
     #[test]
     fn not_rounding_down_when_start_straddles_into_old_chunk() {
-        let mut manager = ChunkedReadBufferManager::<10>::new_with_size(55);
+        let mut manager = ChunkedReadBufferManager::new_with_size(55, 10);
         assert_eq!(
             manager.determine_range_sourcing(13..18),
             RangeSourcing::NeedToReadNewBuffer(10..20)
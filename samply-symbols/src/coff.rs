@@ -0,0 +1,188 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use debugid::DebugId;
+use object::{File, FileKind, Object, ObjectSection, ObjectSymbol, SymbolKind, SymbolSection};
+
+use crate::debugid_util::debug_id_for_object;
+use crate::demangle::{self, DemangleOptions};
+use crate::error::Error;
+use crate::shared::{
+    FileAndPathHelper, FileContents, FileContentsWrapper, LookupAddress, SymbolInfo,
+};
+use crate::symbol_map::{SymbolFileKind, SymbolMap, SymbolMapTrait};
+use crate::SyncAddressInfo;
+
+/// Creates a symbol map for a COFF object file, e.g. a `.obj` file produced by
+/// MSVC or by clang targeting Windows.
+///
+/// Object files are not linked, so every section starts at address zero. To
+/// give each function a unique address, we use file offsets as relative
+/// addresses, i.e. the offset of the function's first instruction in the file.
+///
+/// Only function symbols from the COFF symbol table are used. CodeView line
+/// information is not read yet.
+pub fn get_symbol_map_for_coff<H: FileAndPathHelper>(
+    file_contents: FileContentsWrapper<H::F>,
+    file_kind: FileKind,
+    file_location: H::FL,
+    demangle_options: DemangleOptions,
+) -> Result<SymbolMap<H>, Error> {
+    let symbol_map = CoffSymbolMap::parse(&file_contents, file_kind, demangle_options)?;
+    let mut symbol_map = SymbolMap::with_symbol_map_trait(file_location, Arc::new(symbol_map));
+    symbol_map.set_symbol_file_kind(SymbolFileKind::Coff);
+    Ok(symbol_map)
+}
+
+struct CoffSymbol {
+    /// The file offset of the function start.
+    address: u32,
+    size: u32,
+    name: String,
+}
+
+struct CoffSymbolMap {
+    debug_id: DebugId,
+    /// Sorted by address.
+    symbols: Vec<CoffSymbol>,
+    demangle_options: DemangleOptions,
+}
+
+impl CoffSymbolMap {
+    fn parse<T: FileContents>(
+        file_contents: &FileContentsWrapper<T>,
+        file_kind: FileKind,
+        demangle_options: DemangleOptions,
+    ) -> Result<Self, Error> {
+        let object =
+            File::parse(file_contents).map_err(|e| Error::ObjectParseError(file_kind, e))?;
+        let debug_id = debug_id_for_object(&object).unwrap_or_else(DebugId::nil);
+
+        // Collect (file offset, section end file offset, name) for each defined
+        // function symbol. Symbols in sections without file data (e.g. .bss) are
+        // skipped.
+        let mut entries = Vec::new();
+        for symbol in object.symbols() {
+            if symbol.kind() != SymbolKind::Text {
+                continue;
+            }
+            let SymbolSection::Section(section_index) = symbol.section() else {
+                continue;
+            };
+            let Ok(name) = symbol.name() else {
+                continue;
+            };
+            if name.is_empty() {
+                continue;
+            }
+            let Ok(section) = object.section_by_index(section_index) else {
+                continue;
+            };
+            let Some((section_offset, section_size)) = section.file_range() else {
+                continue;
+            };
+            let offset_in_section = symbol.address().wrapping_sub(section.address());
+            if offset_in_section >= section_size {
+                continue;
+            }
+            let (Ok(address), Ok(section_end)) = (
+                u32::try_from(section_offset + offset_in_section),
+                u32::try_from(section_offset + section_size),
+            ) else {
+                continue;
+            };
+            entries.push((address, section_end, name.to_owned()));
+        }
+        entries.sort_by_key(|(address, _, _)| *address);
+        entries.dedup_by_key(|(address, _, _)| *address);
+
+        // A function extends up to the next function in the same section, or up
+        // to the end of its section.
+        let symbols = (0..entries.len())
+            .map(|i| {
+                let (address, section_end, ref name) = entries[i];
+                let end = match entries.get(i + 1) {
+                    Some(&(next_address, _, _)) if next_address < section_end => next_address,
+                    _ => section_end,
+                };
+                CoffSymbol {
+                    address,
+                    size: end - address,
+                    name: name.clone(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            debug_id,
+            symbols,
+            demangle_options,
+        })
+    }
+}
+
+impl SymbolMapTrait for CoffSymbolMap {
+    fn debug_id(&self) -> DebugId {
+        self.debug_id
+    }
+
+    fn demangle_options(&self) -> DemangleOptions {
+        self.demangle_options
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.symbols.len()
+    }
+
+    fn iter_symbols(&self) -> Box<dyn Iterator<Item = (u32, Cow<'_, str>)> + '_> {
+        Box::new(
+            self.symbols
+                .iter()
+                .map(|symbol| (symbol.address, Cow::Borrowed(symbol.name.as_str()))),
+        )
+    }
+
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        Box::new(self.symbols.iter().map(|symbol| {
+            (
+                symbol.address,
+                Some(symbol.size),
+                Cow::Borrowed(symbol.name.as_str()),
+            )
+        }))
+    }
+
+    fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
+        let address = match address {
+            // Relative addresses are file offsets in this symbol map.
+            LookupAddress::Relative(address) => address,
+            LookupAddress::FileOffset(offset) => u32::try_from(offset).ok()?,
+            LookupAddress::Svma(_) => {
+                // SVMAs are not meaningful for unlinked object files.
+                return None;
+            }
+        };
+        let index = match self
+            .symbols
+            .binary_search_by_key(&address, |symbol| symbol.address)
+        {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        let symbol = &self.symbols[index];
+        if address - symbol.address >= symbol.size {
+            return None;
+        }
+        Some(SyncAddressInfo {
+            symbol: SymbolInfo {
+                address: symbol.address,
+                size: Some(symbol.size),
+                name: demangle::demangle_with_options(&symbol.name, &self.demangle_options),
+            },
+            frames: None,
+        })
+    }
+}
@@ -39,4 +39,95 @@ impl CompactSymbolTable {
             buffer,
         }
     }
+
+    /// Like [`CompactSymbolTable::from_symbol_map`], but only includes the
+    /// symbols which cover at least one of the given addresses. This can be
+    /// much smaller than the full table if only a few addresses need to be
+    /// symbolicated.
+    ///
+    /// The symbol which covers an address is the symbol with the greatest
+    /// address that is less than or equal to it, which is how consumers look
+    /// up addresses in this table. `addresses` doesn't need to be sorted.
+    pub fn from_symbol_map_for_addresses<H: FileAndPathHelper>(
+        map: &SymbolMap<H>,
+        addresses: &[u32],
+    ) -> Self {
+        let mut addresses = addresses.to_vec();
+        addresses.sort_unstable();
+        addresses.dedup();
+
+        let mut addr = Vec::new();
+        let mut index = Vec::new();
+        let mut buffer = Vec::new();
+        let mut remaining_addresses = &addresses[..];
+        let mut symbols = map.iter_symbols().peekable();
+        while let Some((address, name)) = symbols.next() {
+            // Skip addresses which come before this symbol. They are either
+            // covered by an earlier symbol, or by no symbol at all.
+            let first_covered = remaining_addresses.partition_point(|a| *a < address);
+            remaining_addresses = &remaining_addresses[first_covered..];
+            let Some(&next_address) = remaining_addresses.first() else {
+                break;
+            };
+            if let Some((next_symbol_address, _)) = symbols.peek() {
+                if next_address >= *next_symbol_address {
+                    continue;
+                }
+            }
+            addr.push(address);
+            index.push(buffer.len() as u32);
+            buffer.extend_from_slice(name.as_bytes());
+        }
+        index.push(buffer.len() as u32);
+        Self {
+            addr,
+            index,
+            buffer,
+        }
+    }
+
+    /// Merges `other` into this table, for example to combine the tables of
+    /// the different architectures of a universal binary.
+    ///
+    /// The result contains the union of both tables' symbols, ordered by
+    /// address. If both tables have a symbol at the same address, only the
+    /// symbol from `self` is kept.
+    pub fn merge(self, other: Self) -> Self {
+        let len = self.addr.len() + other.addr.len();
+        let mut addr = Vec::with_capacity(len);
+        let mut index = Vec::with_capacity(len + 1);
+        let mut buffer = Vec::with_capacity(self.buffer.len() + other.buffer.len());
+        let mut push = |table: &Self, i: usize| {
+            addr.push(table.addr[i]);
+            index.push(buffer.len() as u32);
+            let name_range = table.index[i] as usize..table.index[i + 1] as usize;
+            buffer.extend_from_slice(&table.buffer[name_range]);
+        };
+        let (mut i, mut j) = (0, 0);
+        while i < self.addr.len() && j < other.addr.len() {
+            let (self_addr, other_addr) = (self.addr[i], other.addr[j]);
+            if self_addr <= other_addr {
+                push(&self, i);
+                i += 1;
+                if self_addr == other_addr {
+                    j += 1;
+                }
+            } else {
+                push(&other, j);
+                j += 1;
+            }
+        }
+        for i in i..self.addr.len() {
+            push(&self, i);
+        }
+        for j in j..other.addr.len() {
+            push(&other, j);
+        }
+        index.push(buffer.len() as u32);
+        Self {
+            addr,
+            index,
+            buffer,
+        }
+    }
 }
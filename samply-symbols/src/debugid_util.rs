@@ -1,8 +1,9 @@
 use debugid::DebugId;
-use object::{Object, ObjectSection};
+use object::{Object, ObjectSection, ReadRef};
 use uuid::Uuid;
 
 use crate::shared::{CodeId, ElfBuildId};
+use crate::wasm;
 
 pub trait DebugIdExt {
     /// Creates a DebugId from some identifier. The identifier could be
@@ -66,6 +67,13 @@ impl DebugIdExt for DebugId {
 /// Tries to obtain a DebugId for an object. This uses the build ID, if available,
 /// and falls back to hashing the first page of the text section otherwise.
 /// Returns None on failure.
+///
+/// The identifier comes from:
+///
+///  - PE: the GUID and age in the CodeView record of the debug directory.
+///  - ELF: the build ID note.
+///  - mach-O: the `LC_UUID` load command.
+///  - Wasm: the `build_id` section.
 pub fn debug_id_for_object<'data>(obj: &impl Object<'data>) -> Option<DebugId> {
     // Windows
     if let Ok(Some(pdb_info)) = obj.pdb_info() {
@@ -82,6 +90,22 @@ pub fn debug_id_for_object<'data>(obj: &impl Object<'data>) -> Option<DebugId> {
         return Some(DebugId::from_uuid(Uuid::from_bytes(uuid)));
     }
 
+    // Wasm. The object crate calls the code section "<code>".
+    if let Some(code_section) = obj.section_by_name("<code>") {
+        if let Some(build_id) = obj
+            .section_by_name("build_id")
+            .and_then(|section| section.data().ok())
+            .and_then(wasm::build_id_from_section_data)
+        {
+            return Some(DebugId::from_identifier(build_id, true));
+        }
+
+        // Wasm sections don't support data_range, so hash the start of the code
+        // section here.
+        let data = code_section.data().ok()?;
+        return Some(DebugId::from_text_first_page(data, true));
+    }
+
     // We were not able to locate a build ID, so fall back to creating a synthetic
     // identifier from a hash of the first page of the ".text" (program code) section.
     if let Some(section) = obj.section_by_name(".text") {
@@ -113,3 +137,13 @@ pub fn code_id_for_object<'data>(obj: &impl Object<'data>) -> Option<CodeId> {
 
     None
 }
+
+/// Computes the DebugId for the contents of a PE, ELF or mach-O binary, in the
+/// same way as [`debug_id_for_object`].
+///
+/// Returns None if the file can't be parsed. Fat mach-O archives contain
+/// multiple objects with different debug IDs, so they also return None.
+pub fn debug_id_for_binary_data<'data>(data: impl ReadRef<'data>) -> Option<DebugId> {
+    let obj = object::File::parse(data).ok()?;
+    debug_id_for_object(&obj)
+}
@@ -1,10 +1,57 @@
 use msvc_demangler::DemangleFlags;
 
 use super::demangle_ocaml;
+#[cfg(feature = "swift_demangle")]
+use super::demangle_swift;
+
+/// Controls which demangling schemes are applied to symbol names.
+///
+/// The default demangles all supported schemes and strips the hash suffix from
+/// Rust names, which is the behavior of [`demangle_any`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DemangleOptions {
+    /// Demangle Rust names, both in the legacy and in the v0 mangling scheme.
+    /// If false, Rust names are returned in their mangled form.
+    pub rust: bool,
+    /// Demangle C++ names, in the Itanium and in the MSVC mangling scheme.
+    /// If false, C++ names are returned in their mangled form.
+    pub cpp: bool,
+    /// Keep the hash suffix (legacy mangling) or the crate disambiguators (v0
+    /// mangling) in demangled Rust names.
+    pub keep_rust_hash: bool,
+    /// Demangle OCaml names.
+    pub ocaml: bool,
+    /// Demangle Swift names (`$s...`). This only has an effect if the
+    /// `swift_demangle` feature is enabled. If false, Swift names are returned
+    /// in their mangled form. The `SymbolManager` only demangles Swift names in
+    /// symbol maps for mach-O files.
+    pub swift: bool,
+}
+
+impl Default for DemangleOptions {
+    fn default() -> Self {
+        Self {
+            rust: true,
+            cpp: true,
+            keep_rust_hash: false,
+            ocaml: true,
+            swift: true,
+        }
+    }
+}
 
 /// Attempt to demangle the passed-in string. This tries a bunch of different demangling schemes.
 pub fn demangle_any(name: &str) -> String {
+    demangle_with_options(name, &DemangleOptions::default())
+}
+
+/// Like [`demangle_any`], but only uses the demangling schemes which are
+/// enabled in `options`.
+pub fn demangle_with_options(name: &str, options: &DemangleOptions) -> String {
     if name.starts_with('?') {
+        if !options.cpp {
+            return name.to_owned();
+        }
         let flags = DemangleFlags::NO_ACCESS_SPECIFIERS
             | DemangleFlags::NO_FUNCTION_RETURNS
             | DemangleFlags::NO_MEMBER_TYPE
@@ -22,21 +69,42 @@ pub fn demangle_any(name: &str) -> String {
         }
     }
 
+    #[cfg(feature = "swift_demangle")]
+    if name.starts_with("$s") || name.starts_with("_$s") {
+        if !options.swift {
+            return name.to_owned();
+        }
+        if let Some(symbol) = demangle_swift::demangle(name) {
+            return symbol;
+        }
+    }
+
     if let Ok(demangled_symbol) = rustc_demangle::try_demangle(name) {
+        if !options.rust {
+            return name.to_owned();
+        }
+        if options.keep_rust_hash {
+            return format!("{demangled_symbol}");
+        }
         return format!("{demangled_symbol:#}");
     }
 
     if name.starts_with('_') {
-        let options = cpp_demangle::DemangleOptions::default().no_return_type();
+        let cpp_options = cpp_demangle::DemangleOptions::default().no_return_type();
         if let Ok(symbol) = cpp_demangle::Symbol::new(name) {
-            if let Ok(demangled_string) = symbol.demangle(&options) {
+            if !options.cpp {
+                return name.to_owned();
+            }
+            if let Ok(demangled_string) = symbol.demangle(&cpp_options) {
                 return demangled_string;
             }
         }
     }
 
-    if let Some(symbol) = demangle_ocaml::demangle(name) {
-        return symbol;
+    if options.ocaml {
+        if let Some(symbol) = demangle_ocaml::demangle(name) {
+            return symbol;
+        }
     }
 
     if name.starts_with('_') {
@@ -48,7 +116,7 @@ pub fn demangle_any(name: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::demangle::demangle_any;
+    use crate::demangle::{demangle_any, demangle_with_options, DemangleOptions};
     #[test]
     fn cpp_demangling() {
         assert_eq!(
@@ -80,6 +148,20 @@ mod tests {
         assert_eq!(demangle_any("camlA__b__c_1002"), "A.b.c_1002")
     }
 
+    #[cfg(feature = "swift_demangle")]
+    #[test]
+    fn swift_demangling() {
+        assert_eq!(demangle_any("_$s4main3FooC3baryyF"), "main.Foo.bar() -> ()");
+        let no_swift = DemangleOptions {
+            swift: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            demangle_with_options("_$s4main3FooC3baryyF", &no_swift),
+            "_$s4main3FooC3baryyF"
+        );
+    }
+
     #[test]
     fn scala_native_demangling() {
         assert_eq!(
@@ -92,4 +174,39 @@ mod tests {
     fn no_demangling() {
         assert_eq!(demangle_any("_!!!!!!!bla"), "!!!!!!!bla")
     }
+
+    #[test]
+    fn demangling_options() {
+        let raw = DemangleOptions {
+            rust: false,
+            cpp: false,
+            keep_rust_hash: false,
+            ocaml: false,
+            swift: false,
+        };
+        let legacy_rust = "_ZN3std2rt10lang_start17h4ac4ad3a3e5fd5c6E";
+        assert_eq!(demangle_with_options(legacy_rust, &raw), legacy_rust);
+        assert_eq!(
+            demangle_with_options("_ZNK8KxVectorI16KxfArcFileRecordjEixEj", &raw),
+            "_ZNK8KxVectorI16KxfArcFileRecordjEixEj"
+        );
+        assert_eq!(
+            demangle_with_options("??_R3?$KxSet@V?$KxSpe@DI@@I@@8", &raw),
+            "??_R3?$KxSet@V?$KxSpe@DI@@I@@8"
+        );
+        assert_eq!(
+            demangle_with_options("camlStdlib__array__map_154", &raw),
+            "camlStdlib__array__map_154"
+        );
+
+        let keep_hash = DemangleOptions {
+            keep_rust_hash: true,
+            ..Default::default()
+        };
+        assert_eq!(demangle_any(legacy_rust), "std::rt::lang_start");
+        assert_eq!(
+            demangle_with_options(legacy_rust, &keep_hash),
+            "std::rt::lang_start::h4ac4ad3a3e5fd5c6"
+        );
+    }
 }
@@ -0,0 +1,1118 @@
+//! A demangler for the Swift 5 mangling scheme (symbols starting with `$s`).
+//!
+//! This covers the parts of the grammar which show up in the symbol tables of
+//! typical Swift binaries: functions, methods, initializers, accessors,
+//! closures, nominal types, generic signatures, tuples and function types.
+//! The output matches the format of `swift demangle` with default options.
+//!
+//! Symbols which use other parts of the grammar (specializations,
+//! reabstraction thunks, witness tables, ...) are not demangled; `demangle`
+//! returns `None` for them rather than guessing.
+
+/// The maximum number of words that identifiers can refer back to.
+const MAX_NUM_WORDS: usize = 26;
+
+/// The maximum repeat count of a substitution, as in Swift's own demangler.
+/// Higher counts are rejected so that a malformed symbol can't make us push
+/// billions of nodes.
+const MAX_REPEAT_COUNT: u64 = 2048;
+
+/// The maximum nesting depth of demangled nodes. Each level renders the text of
+/// the levels inside it, so without a limit, memory use grows quadratically
+/// with the length of a deeply nested symbol.
+const MAX_DEPTH: usize = 1024;
+
+pub fn demangle(name: &str) -> Option<String> {
+    let name = name.strip_prefix('_').unwrap_or(name);
+    let mangled = name.strip_prefix("$s")?;
+    Demangler::new(mangled).demangle_symbol()
+}
+
+#[derive(Debug, Clone)]
+enum Type {
+    /// A class, struct, enum, protocol or type alias, including its context,
+    /// e.g. `Swift.Int`.
+    Nominal {
+        name: String,
+        is_class: bool,
+    },
+    /// Any other type which is printed as a single string, e.g. a generic
+    /// parameter or a bound generic type.
+    Named(String),
+    /// The elements of a tuple, with their optional labels.
+    Tuple(Vec<(Option<String>, Type)>),
+    Function(Box<FunctionType>),
+    /// A generic function type, with its rendered generic signature.
+    Generic(String, Box<Type>),
+}
+
+#[derive(Debug, Clone)]
+struct FunctionType {
+    params: Type,
+    result: Type,
+    is_async: bool,
+    throws: bool,
+}
+
+/// The context of an entity.
+#[derive(Debug, Clone)]
+enum Context {
+    /// A module, type or extension, which is printed as `Prefix.name`.
+    Prefix(String),
+    /// A function, closure or accessor, which is printed as `name in Entity`.
+    Postfix(String),
+}
+
+impl Context {
+    fn text(&self) -> &str {
+        match self {
+            Context::Prefix(text) | Context::Postfix(text) => text,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Identifier(String),
+    Module(String),
+    /// A private declaration name, e.g. `(Foo in _12345678)`.
+    DeclName(String),
+    Type(Type),
+    Extension(String),
+    /// A fully rendered function, closure, variable or other entity.
+    Entity(String),
+    GenericSignature(String),
+    Requirement(String),
+    EmptyList,
+    FirstElementMarker,
+    ThrowsAnnotation,
+    AsyncAnnotation,
+}
+
+struct Demangler<'a> {
+    text: &'a [u8],
+    pos: usize,
+    /// The nodes which haven't been consumed yet, with their nesting depth.
+    stack: Vec<(Node, usize)>,
+    substitutions: Vec<(Node, usize)>,
+    /// The maximum depth of the nodes which the current operator has consumed.
+    child_depth: usize,
+    words: Vec<Vec<u8>>,
+}
+
+impl<'a> Demangler<'a> {
+    fn new(mangled: &'a str) -> Self {
+        Self {
+            text: mangled.as_bytes(),
+            pos: 0,
+            stack: Vec::new(),
+            substitutions: Vec::new(),
+            child_depth: 0,
+            words: Vec::new(),
+        }
+    }
+
+    fn demangle_symbol(mut self) -> Option<String> {
+        while self.pos < self.text.len() {
+            self.child_depth = 0;
+            let node = self.demangle_operator()?;
+            let depth = self.child_depth + 1;
+            if depth > MAX_DEPTH {
+                return None;
+            }
+            self.stack.push((node, depth));
+        }
+        if self.stack.len() != 1 {
+            return None;
+        }
+        match self.stack.pop()?.0 {
+            Node::Entity(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn next_if(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn demangle_natural(&mut self) -> Option<u64> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    fn demangle_repeat_count(&mut self) -> Option<u64> {
+        self.demangle_natural()
+            .filter(|&count| count <= MAX_REPEAT_COUNT)
+    }
+
+    /// `_` is 0, `<n>_` is n + 1.
+    fn demangle_index(&mut self) -> Option<u64> {
+        if self.next_if(b'_') {
+            return Some(0);
+        }
+        let n = self.demangle_natural()?;
+        if !self.next_if(b'_') {
+            return None;
+        }
+        n.checked_add(1)
+    }
+
+    fn pop(&mut self) -> Option<Node> {
+        let (node, depth) = self.stack.pop()?;
+        self.child_depth = self.child_depth.max(depth);
+        Some(node)
+    }
+
+    /// Returns a node which was demangled earlier, such as a substitution, so
+    /// that it keeps its nesting depth.
+    fn reuse(&mut self, node: Node, depth: usize) -> Node {
+        self.child_depth = self.child_depth.max(depth - 1);
+        node
+    }
+
+    fn add_substitution(&mut self, node: &Node) {
+        self.substitutions
+            .push((node.clone(), self.child_depth + 1));
+    }
+
+    fn pop_if(&mut self, predicate: impl FnOnce(&Node) -> bool) -> Option<Node> {
+        if predicate(&self.stack.last()?.0) {
+            self.pop()
+        } else {
+            None
+        }
+    }
+
+    fn pop_empty_list(&mut self) -> bool {
+        self.pop_if(|node| matches!(node, Node::EmptyList))
+            .is_some()
+    }
+
+    fn pop_first_element_marker(&mut self) -> bool {
+        self.pop_if(|node| matches!(node, Node::FirstElementMarker))
+            .is_some()
+    }
+
+    fn pop_type(&mut self) -> Option<Type> {
+        match self.pop_if(|node| matches!(node, Node::Type(_)))? {
+            Node::Type(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    fn pop_decl_name(&mut self) -> Option<String> {
+        match self.pop_if(|node| matches!(node, Node::Identifier(_) | Node::DeclName(_)))? {
+            Node::Identifier(name) | Node::DeclName(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn pop_entity(&mut self) -> Option<String> {
+        match self.pop_if(|node| matches!(node, Node::Entity(_)))? {
+            Node::Entity(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn pop_context(&mut self) -> Option<Context> {
+        match self.pop()? {
+            Node::Identifier(name) | Node::Module(name) => Some(Context::Prefix(name)),
+            Node::Type(Type::Nominal { name, .. }) | Node::Extension(name) => {
+                Some(Context::Prefix(name))
+            }
+            Node::Entity(text) => Some(Context::Postfix(text)),
+            _ => None,
+        }
+    }
+
+    fn demangle_operator(&mut self) -> Option<Node> {
+        let c = self.next()?;
+        match c {
+            b'0'..=b'9' => {
+                self.pos -= 1;
+                self.demangle_identifier()
+            }
+            b'A' => self.demangle_multi_substitutions(),
+            b'C' => self.demangle_nominal_type(true),
+            b'O' | b'P' | b'V' | b'a' => self.demangle_nominal_type(false),
+            b'E' => self.demangle_extension_context(),
+            b'F' => self.demangle_plain_function(),
+            b'G' => self.demangle_bound_generic_type(),
+            b'K' => Some(Node::ThrowsAnnotation),
+            b'L' => self.demangle_local_identifier(),
+            b'M' => self.demangle_metadata(),
+            b'N' => {
+                let ty = self.pop_type()?;
+                Some(Node::Entity(format!(
+                    "type metadata for {}",
+                    render_type(&ty)
+                )))
+            }
+            b'R' => self.demangle_generic_requirement(),
+            b'S' => self.demangle_standard_substitution(),
+            b'T' => self.demangle_thunk(),
+            b'X' => {
+                if !self.next_if(b'E') {
+                    return None;
+                }
+                let function_type = self.pop_function_type()?;
+                Some(Node::Type(Type::Function(Box::new(function_type))))
+            }
+            b'Y' => {
+                if !self.next_if(b'a') {
+                    return None;
+                }
+                Some(Node::AsyncAnnotation)
+            }
+            b'Z' => {
+                let entity = self.pop_entity()?;
+                Some(Node::Entity(format!("static {entity}")))
+            }
+            b'_' => Some(Node::FirstElementMarker),
+            b'c' => {
+                let function_type = self.pop_function_type()?;
+                Some(Node::Type(Type::Function(Box::new(function_type))))
+            }
+            b'f' => self.demangle_function_entity(),
+            b'h' => self.demangle_type_with_prefix("__shared "),
+            b'l' => self.demangle_generic_signature(false),
+            b'm' => {
+                let ty = self.pop_type()?;
+                Some(Node::Type(Type::Named(format!(
+                    "{}.Type",
+                    render_type(&ty)
+                ))))
+            }
+            b'n' => self.demangle_type_with_prefix("__owned "),
+            b'p' => self.demangle_protocol_list(),
+            b'q' => {
+                let name = self.demangle_generic_param_index()?;
+                Some(Node::Type(Type::Named(name)))
+            }
+            b'r' => self.demangle_generic_signature(true),
+            b't' => self.demangle_tuple(),
+            b'u' => {
+                let Some(Node::GenericSignature(signature)) =
+                    self.pop_if(|node| matches!(node, Node::GenericSignature(_)))
+                else {
+                    return None;
+                };
+                let ty = self.pop_type()?;
+                Some(Node::Type(Type::Generic(signature, Box::new(ty))))
+            }
+            b'v' => self.demangle_variable(),
+            b'x' => Some(Node::Type(Type::Named(generic_param_name(0, 0)))),
+            b'y' => Some(Node::EmptyList),
+            b'z' => self.demangle_type_with_prefix("inout "),
+            _ => None,
+        }
+    }
+
+    fn demangle_identifier(&mut self) -> Option<Node> {
+        let mut has_word_substitutions = false;
+        if self.next_if(b'0') {
+            if self.peek() == Some(b'0') {
+                // Punycode-encoded identifiers are not supported.
+                return None;
+            }
+            has_word_substitutions = true;
+        }
+        let mut identifier = Vec::new();
+        loop {
+            while has_word_substitutions && self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                let c = self.next()?;
+                let word_index = if c.is_ascii_lowercase() {
+                    c - b'a'
+                } else {
+                    has_word_substitutions = false;
+                    c - b'A'
+                };
+                identifier.extend_from_slice(self.words.get(usize::from(word_index))?);
+            }
+            if self.next_if(b'0') {
+                break;
+            }
+            let len = usize::try_from(self.demangle_natural()?).ok()?;
+            if len == 0 {
+                return None;
+            }
+            let end = self.pos.checked_add(len)?;
+            let slice = self.text.get(self.pos..end)?;
+            identifier.extend_from_slice(slice);
+            self.add_words(slice);
+            self.pos = end;
+            if !has_word_substitutions {
+                break;
+            }
+        }
+        if identifier.is_empty() {
+            return None;
+        }
+        let node = Node::Identifier(String::from_utf8(identifier).ok()?);
+        self.add_substitution(&node);
+        Some(node)
+    }
+
+    /// Splits an identifier into words which later identifiers can refer to.
+    fn add_words(&mut self, slice: &[u8]) {
+        let is_word_start = |c: u8| !c.is_ascii_digit() && c != b'_' && c != 0;
+        let is_word_end = |c: u8, prev: u8| {
+            c == b'_' || c == 0 || (!prev.is_ascii_uppercase() && c.is_ascii_uppercase())
+        };
+        let mut word_start = None;
+        for index in 0..=slice.len() {
+            let c = slice.get(index).copied().unwrap_or(0);
+            if let Some(start) = word_start {
+                if is_word_end(c, slice[index - 1]) {
+                    if index - start >= 2 && self.words.len() < MAX_NUM_WORDS {
+                        self.words.push(slice[start..index].to_vec());
+                    }
+                    word_start = None;
+                }
+            }
+            if word_start.is_none() && is_word_start(c) {
+                word_start = Some(index);
+            }
+        }
+    }
+
+    fn demangle_multi_substitutions(&mut self) -> Option<Node> {
+        let mut repeat_count = None;
+        loop {
+            let c = self.next()?;
+            if c.is_ascii_lowercase() {
+                let node = self.push_multi_substitutions(repeat_count.take(), c - b'a')?;
+                self.stack.push(node);
+            } else if c.is_ascii_uppercase() {
+                let (node, depth) = self.push_multi_substitutions(repeat_count, c - b'A')?;
+                return Some(self.reuse(node, depth));
+            } else if c == b'_' {
+                let index = repeat_count.map_or(Some(26), |count: u64| count.checked_add(27))?;
+                let (node, depth) = self
+                    .substitutions
+                    .get(usize::try_from(index).ok()?)?
+                    .clone();
+                return Some(self.reuse(node, depth));
+            } else {
+                self.pos -= 1;
+                repeat_count = Some(self.demangle_repeat_count()?);
+            }
+        }
+    }
+
+    fn push_multi_substitutions(
+        &mut self,
+        repeat_count: Option<u64>,
+        index: u8,
+    ) -> Option<(Node, usize)> {
+        let (node, depth) = self.substitutions.get(usize::from(index))?.clone();
+        for _ in 1..repeat_count.unwrap_or(0) {
+            self.stack.push((node.clone(), depth));
+        }
+        Some((node, depth))
+    }
+
+    fn demangle_standard_substitution(&mut self) -> Option<Node> {
+        match self.peek()? {
+            b'o' => {
+                self.pos += 1;
+                return Some(Node::Module("__C".to_string()));
+            }
+            b'C' => {
+                self.pos += 1;
+                return Some(Node::Module("__C_Synthesized".to_string()));
+            }
+            b'g' => {
+                self.pos += 1;
+                let ty = self.pop_type()?;
+                let node = Node::Type(Type::Named(format!("Swift.Optional<{}>", render_type(&ty))));
+                self.add_substitution(&node);
+                return Some(node);
+            }
+            _ => {}
+        }
+        let repeat_count = if self.peek()?.is_ascii_digit() {
+            self.demangle_repeat_count()?
+        } else {
+            1
+        };
+        let name = standard_type_name(self.next()?)?;
+        let node = Node::Type(Type::Nominal {
+            name: format!("Swift.{name}"),
+            is_class: false,
+        });
+        for _ in 1..repeat_count {
+            self.stack.push((node.clone(), 1));
+        }
+        Some(node)
+    }
+
+    fn demangle_nominal_type(&mut self, is_class: bool) -> Option<Node> {
+        let name = self.pop_decl_name()?;
+        let Context::Prefix(context) = self.pop_context()? else {
+            // Local types are not supported.
+            return None;
+        };
+        let node = Node::Type(Type::Nominal {
+            name: format!("{context}.{name}"),
+            is_class,
+        });
+        self.add_substitution(&node);
+        Some(node)
+    }
+
+    fn demangle_extension_context(&mut self) -> Option<Node> {
+        let module = match self.pop()? {
+            Node::Identifier(name) | Node::Module(name) => name,
+            _ => return None,
+        };
+        let Type::Nominal {
+            name: extended_type,
+            ..
+        } = self.pop_type()?
+        else {
+            return None;
+        };
+        Some(Node::Extension(format!(
+            "(extension in {module}):{extended_type}"
+        )))
+    }
+
+    fn demangle_local_identifier(&mut self) -> Option<Node> {
+        if !self.next_if(b'L') {
+            // Only private declaration names are supported.
+            return None;
+        }
+        let Node::Identifier(discriminator) =
+            self.pop_if(|node| matches!(node, Node::Identifier(_)))?
+        else {
+            return None;
+        };
+        let name = self.pop_decl_name()?;
+        Some(Node::DeclName(format!("({name} in {discriminator})")))
+    }
+
+    fn demangle_bound_generic_type(&mut self) -> Option<Node> {
+        let mut type_lists = Vec::new();
+        loop {
+            let mut types = Vec::new();
+            while let Some(ty) = self.pop_type() {
+                types.push(render_type(&ty));
+            }
+            types.reverse();
+            type_lists.push(types);
+            if self.pop_empty_list() {
+                break;
+            }
+            if !self.pop_first_element_marker() {
+                return None;
+            }
+        }
+        // Generic arguments for more than one level of nesting are not supported.
+        let [args] = type_lists.as_slice() else {
+            return None;
+        };
+        let Type::Nominal { name: nominal, .. } = self.pop_type()? else {
+            return None;
+        };
+        let node = Node::Type(Type::Named(format!("{nominal}<{}>", args.join(", "))));
+        self.add_substitution(&node);
+        Some(node)
+    }
+
+    fn demangle_tuple(&mut self) -> Option<Node> {
+        let mut elements = Vec::new();
+        if !self.pop_empty_list() {
+            loop {
+                let is_first = self.pop_first_element_marker();
+                let label = match self.pop_if(|node| matches!(node, Node::Identifier(_))) {
+                    Some(Node::Identifier(label)) => Some(label),
+                    _ => None,
+                };
+                let ty = self.pop_type()?;
+                elements.push((label, ty));
+                if is_first {
+                    break;
+                }
+            }
+            elements.reverse();
+        }
+        Some(Node::Type(Type::Tuple(elements)))
+    }
+
+    fn demangle_protocol_list(&mut self) -> Option<Node> {
+        let mut protocols = Vec::new();
+        if !self.pop_empty_list() {
+            loop {
+                let is_first = self.pop_first_element_marker();
+                protocols.push(render_type(&self.pop_type()?));
+                if is_first {
+                    break;
+                }
+            }
+            protocols.reverse();
+        }
+        let text = if protocols.is_empty() {
+            "Any".to_string()
+        } else {
+            protocols.join(" & ")
+        };
+        Some(Node::Type(Type::Named(text)))
+    }
+
+    fn demangle_type_with_prefix(&mut self, prefix: &str) -> Option<Node> {
+        let ty = self.pop_type()?;
+        Some(Node::Type(Type::Named(format!(
+            "{prefix}{}",
+            render_type(&ty)
+        ))))
+    }
+
+    fn pop_function_params(&mut self) -> Option<Type> {
+        if self.pop_empty_list() {
+            Some(Type::Tuple(Vec::new()))
+        } else {
+            self.pop_type()
+        }
+    }
+
+    fn pop_function_type(&mut self) -> Option<FunctionType> {
+        let throws = self
+            .pop_if(|node| matches!(node, Node::ThrowsAnnotation))
+            .is_some();
+        let is_async = self
+            .pop_if(|node| matches!(node, Node::AsyncAnnotation))
+            .is_some();
+        let params = self.pop_function_params()?;
+        let result = self.pop_function_params()?;
+        Some(FunctionType {
+            params,
+            result,
+            is_async,
+            throws,
+        })
+    }
+
+    /// Pops the argument labels of a function entity. An empty list means that
+    /// the parameters have no labels.
+    fn pop_function_param_labels(&mut self, ty: &Type) -> Option<Vec<Option<String>>> {
+        if self.pop_empty_list() {
+            return Some(Vec::new());
+        }
+        let param_count = match function_type(ty)?.params {
+            Type::Tuple(ref elements) => elements.len(),
+            _ => 1,
+        };
+        let mut labels = Vec::with_capacity(param_count);
+        for _ in 0..param_count {
+            match self.pop()? {
+                Node::Identifier(label) => labels.push(Some(label)),
+                Node::FirstElementMarker => labels.push(None),
+                _ => return None,
+            }
+        }
+        if labels.iter().all(Option::is_none) {
+            return Some(Vec::new());
+        }
+        labels.reverse();
+        Some(labels)
+    }
+
+    fn demangle_plain_function(&mut self) -> Option<Node> {
+        let signature = match self.pop_if(|node| matches!(node, Node::GenericSignature(_))) {
+            Some(Node::GenericSignature(signature)) => Some(signature),
+            _ => None,
+        };
+        let mut ty = Type::Function(Box::new(self.pop_function_type()?));
+        let labels = self.pop_function_param_labels(&ty)?;
+        if let Some(signature) = signature {
+            ty = Type::Generic(signature, Box::new(ty));
+        }
+        let name = self.pop_decl_name()?;
+        let context = self.pop_context()?;
+        let signature = render_function_signature(&ty, &labels)?;
+        Some(Node::Entity(render_entity(&context, &name, &signature)))
+    }
+
+    /// Whether the node on top of the stack is a class. Allocating initializers
+    /// and deallocating deinitializers are only printed as such for classes.
+    fn top_is_class(&self) -> bool {
+        matches!(
+            self.stack.last(),
+            Some((Node::Type(Type::Nominal { is_class: true, .. }), _))
+        )
+    }
+
+    fn demangle_function_entity(&mut self) -> Option<Node> {
+        match self.next()? {
+            b'C' | b'c' => {
+                let is_allocator = self.text[self.pos - 1] == b'C';
+                let ty = self.pop_type()?;
+                let labels = self.pop_function_param_labels(&ty)?;
+                let name = if is_allocator && self.top_is_class() {
+                    "__allocating_init"
+                } else {
+                    "init"
+                };
+                let context = self.pop_context()?;
+                let signature = render_function_signature(&ty, &labels)?;
+                Some(Node::Entity(render_entity(&context, name, &signature)))
+            }
+            b'D' | b'd' => {
+                let name = if self.text[self.pos - 1] == b'D' && self.top_is_class() {
+                    "__deallocating_deinit"
+                } else {
+                    "deinit"
+                };
+                let context = self.pop_context()?;
+                Some(Node::Entity(render_entity(&context, name, "")))
+            }
+            b'U' | b'u' => {
+                let kind = if self.text[self.pos - 1] == b'U' {
+                    "closure"
+                } else {
+                    "implicit closure"
+                };
+                let index = self.demangle_index()?;
+                let ty = self.pop_type()?;
+                let context = self.pop_context()?;
+                Some(Node::Entity(format!(
+                    "{kind} #{} {} in {}",
+                    index + 1,
+                    render_type(&ty),
+                    context.text()
+                )))
+            }
+            b'A' => {
+                let index = self.demangle_index()?;
+                let context = self.pop_context()?;
+                Some(Node::Entity(format!(
+                    "default argument {index} of {}",
+                    context.text()
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    fn demangle_variable(&mut self) -> Option<Node> {
+        let ty = self.pop_type()?;
+        let name = self.pop_decl_name()?;
+        let context = self.pop_context()?;
+        let accessor = match self.next()? {
+            b'p' => None,
+            b'g' | b'G' => Some("getter"),
+            b's' => Some("setter"),
+            b'm' => Some("materializeForSet"),
+            b'w' => Some("willset"),
+            b'W' => Some("didset"),
+            b'r' => Some("read"),
+            b'M' => Some("modify"),
+            _ => return None,
+        };
+        let name = match accessor {
+            Some(accessor) => format!("{name}.{accessor}"),
+            None => name,
+        };
+        let suffix = format!(" : {}", render_type(&ty));
+        Some(Node::Entity(render_entity(&context, &name, &suffix)))
+    }
+
+    fn demangle_generic_param_index(&mut self) -> Option<String> {
+        if self.next_if(b'd') {
+            let depth = self.demangle_index()?.checked_add(1)?;
+            let index = self.demangle_index()?;
+            return Some(generic_param_name(depth, index));
+        }
+        if self.next_if(b'z') {
+            return Some(generic_param_name(0, 0));
+        }
+        let index = self.demangle_index()?.checked_add(1)?;
+        Some(generic_param_name(0, index))
+    }
+
+    fn demangle_generic_signature(&mut self, has_param_counts: bool) -> Option<Node> {
+        let mut param_counts = Vec::new();
+        if has_param_counts {
+            while !self.next_if(b'l') {
+                let count = if self.next_if(b'z') {
+                    0
+                } else {
+                    self.demangle_index()?.checked_add(1)?
+                };
+                param_counts.push(count);
+            }
+        } else {
+            param_counts.push(1);
+        }
+        let mut requirements = Vec::new();
+        while let Some(Node::Requirement(requirement)) =
+            self.pop_if(|node| matches!(node, Node::Requirement(_)))
+        {
+            requirements.push(requirement);
+        }
+        requirements.reverse();
+
+        let params: Vec<String> = param_counts
+            .iter()
+            .enumerate()
+            .map(|(depth, &count)| {
+                (0..count.min(128))
+                    .map(|index| generic_param_name(depth as u64, index))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect();
+        let mut signature = format!("<{}", params.join("><"));
+        if !requirements.is_empty() {
+            signature.push_str(" where ");
+            signature.push_str(&requirements.join(", "));
+        }
+        signature.push('>');
+        Some(Node::GenericSignature(signature))
+    }
+
+    fn demangle_generic_requirement(&mut self) -> Option<Node> {
+        // Only protocol conformance requirements on generic parameters are supported.
+        if !matches!(self.peek()?, b'z' | b'd' | b'_' | b'0'..=b'9') {
+            return None;
+        }
+        let param = self.demangle_generic_param_index()?;
+        let protocol = self.pop_type()?;
+        Some(Node::Requirement(format!(
+            "{param}: {}",
+            render_type(&protocol)
+        )))
+    }
+
+    fn demangle_metadata(&mut self) -> Option<Node> {
+        let description = match self.next()? {
+            b'a' => "type metadata accessor for",
+            b'f' => "full type metadata for",
+            b'm' => "metaclass for",
+            b'n' => "nominal type descriptor for",
+            _ => return None,
+        };
+        let ty = self.pop_type()?;
+        Some(Node::Entity(format!("{description} {}", render_type(&ty))))
+    }
+
+    fn demangle_thunk(&mut self) -> Option<Node> {
+        let c = self.next()?;
+        if c == b'A' {
+            // A partial apply forwarder may or may not refer to an entity.
+            return Some(Node::Entity(match self.pop_entity() {
+                Some(entity) => format!("partial apply forwarder for {entity}"),
+                None => "partial apply forwarder".to_string(),
+            }));
+        }
+        let prefix = match c {
+            b'j' => "dispatch thunk of ",
+            b'm' => "merged ",
+            b'o' => "@objc ",
+            b'q' => "method descriptor for ",
+            _ => return None,
+        };
+        let entity = self.pop_entity()?;
+        Some(Node::Entity(format!("{prefix}{entity}")))
+    }
+}
+
+fn standard_type_name(c: u8) -> Option<&'static str> {
+    let name = match c {
+        b'A' => "AutoreleasingUnsafeMutablePointer",
+        b'a' => "Array",
+        b'b' => "Bool",
+        b'D' => "Dictionary",
+        b'd' => "Double",
+        b'f' => "Float",
+        b'h' => "Set",
+        b'I' => "DefaultIndices",
+        b'i' => "Int",
+        b'J' => "Character",
+        b'N' => "ClosedRange",
+        b'n' => "Range",
+        b'O' => "ObjectIdentifier",
+        b'P' => "UnsafePointer",
+        b'p' => "UnsafeMutablePointer",
+        b'R' => "UnsafeBufferPointer",
+        b'r' => "UnsafeMutableBufferPointer",
+        b'S' => "String",
+        b's' => "Substring",
+        b'u' => "UInt",
+        b'V' => "UnsafeRawPointer",
+        b'v' => "UnsafeMutableRawPointer",
+        b'W' => "UnsafeRawBufferPointer",
+        b'w' => "UnsafeMutableRawBufferPointer",
+        b'q' => "Optional",
+        b'B' => "BinaryFloatingPoint",
+        b'E' => "Encodable",
+        b'e' => "Decodable",
+        b'F' => "FloatingPoint",
+        b'G' => "RandomNumberGenerator",
+        b'H' => "Hashable",
+        b'j' => "Numeric",
+        b'K' => "BidirectionalCollection",
+        b'k' => "RandomAccessCollection",
+        b'L' => "Comparable",
+        b'l' => "Collection",
+        b'M' => "MutableCollection",
+        b'm' => "RangeReplaceableCollection",
+        b'Q' => "Equatable",
+        b'T' => "Sequence",
+        b't' => "IteratorProtocol",
+        b'U' => "UnsignedInteger",
+        b'X' => "RangeExpression",
+        b'x' => "Strideable",
+        b'Y' => "RawRepresentable",
+        b'y' => "StringProtocol",
+        b'Z' => "SignedInteger",
+        b'z' => "BinaryInteger",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Generic parameters are named `A`, `B`, ..., `Z`, `BA`, ... with the depth
+/// appended if it's non-zero.
+fn generic_param_name(depth: u64, mut index: u64) -> String {
+    let mut name = String::new();
+    loop {
+        name.push(char::from(b'A' + (index % 26) as u8));
+        index /= 26;
+        if index == 0 {
+            break;
+        }
+    }
+    if depth != 0 {
+        name.push_str(&depth.to_string());
+    }
+    name
+}
+
+fn function_type(ty: &Type) -> Option<&FunctionType> {
+    match ty {
+        Type::Function(function_type) => Some(function_type),
+        Type::Generic(_, inner) => function_type(inner),
+        _ => None,
+    }
+}
+
+fn render_entity(context: &Context, name: &str, suffix: &str) -> String {
+    match context {
+        Context::Prefix(prefix) => format!("{prefix}.{name}{suffix}"),
+        Context::Postfix(outer) => format!("{name}{suffix} in {outer}"),
+    }
+}
+
+fn render_type(ty: &Type) -> String {
+    match ty {
+        Type::Nominal { name, .. } | Type::Named(name) => name.clone(),
+        Type::Tuple(elements) => {
+            let elements: Vec<String> = elements
+                .iter()
+                .map(|(label, ty)| match label {
+                    Some(label) => format!("{label}: {}", render_type(ty)),
+                    None => render_type(ty),
+                })
+                .collect();
+            format!("({})", elements.join(", "))
+        }
+        Type::Function(function_type) => render_function_type(function_type, &[]),
+        Type::Generic(signature, inner) => format!("{signature}{}", render_type(inner)),
+    }
+}
+
+fn render_function_type(function_type: &FunctionType, labels: &[Option<String>]) -> String {
+    let params: Vec<(Option<&str>, &Type)> = match &function_type.params {
+        Type::Tuple(elements) => elements
+            .iter()
+            .map(|(label, ty)| (label.as_deref(), ty))
+            .collect(),
+        other => vec![(None, other)],
+    };
+    let params: Vec<String> = params
+        .into_iter()
+        .enumerate()
+        .map(|(index, (element_label, ty))| {
+            let label = match labels.get(index) {
+                Some(label) => Some(label.as_deref().unwrap_or("_")),
+                None => element_label,
+            };
+            match label {
+                Some(label) => format!("{label}: {}", render_type(ty)),
+                None => render_type(ty),
+            }
+        })
+        .collect();
+    let mut text = format!("({})", params.join(", "));
+    if function_type.is_async {
+        text.push_str(" async");
+    }
+    if function_type.throws {
+        text.push_str(" throws");
+    }
+    text.push_str(" -> ");
+    text.push_str(&render_type(&function_type.result));
+    text
+}
+
+fn render_function_signature(ty: &Type, labels: &[Option<String>]) -> Option<String> {
+    match ty {
+        Type::Function(function_type) => Some(render_function_type(function_type, labels)),
+        Type::Generic(signature, inner) => Some(format!(
+            "{signature}{}",
+            render_function_signature(inner, labels)?
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::demangle;
+
+    #[track_caller]
+    fn check(mangled: &str, demangled: &str) {
+        assert_eq!(demangle(mangled).as_deref(), Some(demangled));
+    }
+
+    #[test]
+    fn functions() {
+        check("$s4main3fooyyF", "main.foo() -> ()");
+        check("_$s4main3fooyyF", "main.foo() -> ()");
+        check(
+            "$s4main3addyS2i_SitF",
+            "main.add(Swift.Int, Swift.Int) -> Swift.Int",
+        );
+        check(
+            "$s4main3add1a1bS2i_SitF",
+            "main.add(a: Swift.Int, b: Swift.Int) -> Swift.Int",
+        );
+        check(
+            "$s4main5greet4nameySS_tF",
+            "main.greet(name: Swift.String) -> ()",
+        );
+        check("$s4main4workyyYaKF", "main.work() async throws -> ()");
+        check("$s4main8identityyxxlF", "main.identity<A>(A) -> A");
+        check(
+            "$s4main5equalySbx_xtSQRzlF",
+            "main.equal<A where A: Swift.Equatable>(A, A) -> Swift.Bool",
+        );
+        check(
+            "$s4main5firstySiSgSaySiGF",
+            "main.first(Swift.Array<Swift.Int>) -> Swift.Optional<Swift.Int>",
+        );
+    }
+
+    #[test]
+    fn methods_and_accessors() {
+        check("$s4main3FooC3baryyF", "main.Foo.bar() -> ()");
+        check("$s4main3FooV3baryyFZ", "static main.Foo.bar() -> ()");
+        check(
+            "$s4main3FooCACycfC",
+            "main.Foo.__allocating_init() -> main.Foo",
+        );
+        check(
+            "$s4main3FooC1xACSi_tcfc",
+            "main.Foo.init(x: Swift.Int) -> main.Foo",
+        );
+        check("$s4main3FooCfD", "main.Foo.__deallocating_deinit");
+        check(
+            "$s4main3FooC5countSivg",
+            "main.Foo.count.getter : Swift.Int",
+        );
+        check("$s4main5countSivp", "main.count : Swift.Int");
+        check(
+            "$s4main3FooC5countSivs",
+            "main.Foo.count.setter : Swift.Int",
+        );
+        check(
+            "$sSi4mainE6doubleSiyF",
+            "(extension in main):Swift.Int.double() -> Swift.Int",
+        );
+    }
+
+    #[test]
+    fn closures_and_thunks() {
+        check(
+            "$s4main3fooyyFyycfU_",
+            "closure #1 () -> () in main.foo() -> ()",
+        );
+        check(
+            "$s4main3fooyyFyycfU_TA",
+            "partial apply forwarder for closure #1 () -> () in main.foo() -> ()",
+        );
+        check("$s4main3FooCMa", "type metadata accessor for main.Foo");
+        check("$s4main3FooVN", "type metadata for main.Foo");
+        check("$s4main3FooC3baryyFTo", "@objc main.Foo.bar() -> ()");
+    }
+
+    #[test]
+    fn struct_initializer() {
+        check(
+            "$s10Foundation4DataV5countACSi_tcfC",
+            "Foundation.Data.init(count: Swift.Int) -> Foundation.Data",
+        );
+    }
+
+    #[test]
+    fn word_substitutions() {
+        // "doSomething" adds the words "do" and "Something". The label
+        // "doSomethingElse" refers back to both and appends "Else".
+        check(
+            "$s4main11doSomething0bC4ElseySi_tF",
+            "main.doSomething(doSomethingElse: Swift.Int) -> ()",
+        );
+    }
+
+    #[test]
+    fn not_swift() {
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("_ZN3foo3barE"), None);
+        assert_eq!(demangle("$s"), None);
+        // The Swift 4.2 prefix is not supported.
+        assert_eq!(demangle("$S4main3fooyyF"), None);
+        // Generic specializations are not supported.
+        assert_eq!(demangle("$s4main3fooyySiFTf4n_n"), None);
+    }
+
+    #[test]
+    fn repeat_count_limit() {
+        check(
+            "$s4main3addyS2i_SitF",
+            "main.add(Swift.Int, Swift.Int) -> Swift.Int",
+        );
+        assert_eq!(demangle("$sS999999999i"), None);
+        assert_eq!(demangle("$s4mainA999999999a"), None);
+    }
+
+    #[test]
+    fn depth_limit() {
+        let nested = |depth| format!("$s4main1x{}Si{}vp", "Say".repeat(depth), "G".repeat(depth));
+        check(&nested(2), "main.x : Swift.Array<Swift.Array<Swift.Int>>");
+        assert!(demangle(&nested(500)).is_some());
+        assert_eq!(demangle(&nested(25_000)), None);
+    }
+}
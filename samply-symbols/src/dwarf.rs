@@ -7,25 +7,28 @@ use gimli::{DwarfPackage, EndianSlice, Reader, RunTimeEndian, SectionId};
 use object::read::ReadRef;
 use object::CompressionFormat;
 
+use crate::demangle::{self, DemangleOptions};
 use crate::path_mapper::PathMapper;
 use crate::shared::FrameDebugInfo;
-use crate::{demangle, Error, SourceFilePath};
+use crate::{Error, SourceFilePath};
 
 pub fn get_frames<R: Reader>(
     address: u64,
     context: Option<&addr2line::Context<R>>,
     path_mapper: &mut PathMapper<()>,
+    demangle_options: &DemangleOptions,
 ) -> Option<Vec<FrameDebugInfo>> {
     let frame_iter = context?.find_frames(address).skip_all_loads().ok()?;
-    convert_frames(frame_iter, path_mapper)
+    convert_frames(frame_iter, path_mapper, demangle_options)
 }
 
 pub fn convert_frames<'a, R: gimli::Reader>(
     frame_iter: impl FallibleIterator<Item = addr2line::Frame<'a, R>>,
     path_mapper: &mut PathMapper<()>,
+    demangle_options: &DemangleOptions,
 ) -> Option<Vec<FrameDebugInfo>> {
     let frames: Vec<_> = frame_iter
-        .map(|f| Ok(convert_stack_frame(f, &mut *path_mapper)))
+        .map(|f| Ok(convert_stack_frame(f, &mut *path_mapper, demangle_options)))
         .collect()
         .ok()?;
 
@@ -39,11 +42,12 @@ pub fn convert_frames<'a, R: gimli::Reader>(
 pub fn convert_stack_frame<R: gimli::Reader>(
     frame: addr2line::Frame<R>,
     path_mapper: &mut PathMapper<()>,
+    demangle_options: &DemangleOptions,
 ) -> FrameDebugInfo {
     let function = match frame.function {
         Some(function_name) => {
             if let Ok(name) = function_name.raw_name() {
-                Some(demangle::demangle_any(&name))
+                Some(demangle::demangle_with_options(&name, demangle_options))
             } else {
                 None
             }
@@ -3,17 +3,21 @@ use std::sync::Arc;
 
 use debugid::DebugId;
 use elsa::sync::FrozenVec;
-use gimli::{CieOrFde, Dwarf, EhFrame, EndianSlice, RunTimeEndian, UnwindSection};
-use object::{File, FileKind, Object, ObjectSection, ReadRef};
+use gimli::{
+    CieOrFde, DebugFrame, Dwarf, EhFrame, EndianSlice, RunTimeEndian, UnwindOffset, UnwindSection,
+};
+use object::{File, FileKind, Object, ObjectSection, ObjectSymbol, ReadRef, SymbolKind};
 use yoke::Yoke;
 use yoke_derive::Yokeable;
 
+use crate::demangle::DemangleOptions;
 use crate::dwarf::Addr2lineContextData;
 use crate::error::Error;
 use crate::shared::{FileAndPathHelper, FileContents, FileContentsWrapper, FileLocation};
-use crate::symbol_map::SymbolMap;
+use crate::symbol_map::{SymbolFileKind, SymbolMap};
 use crate::symbol_map_object::{
-    DwoDwarfMaker, ObjectSymbolMap, ObjectSymbolMapInnerWrapper, ObjectSymbolMapOuter,
+    AdditionalSymbol, DwoDwarfMaker, ObjectSymbolMap, ObjectSymbolMapInnerWrapper,
+    ObjectSymbolMapOuter,
 };
 use crate::{debug_id_for_object, ElfBuildId};
 
@@ -22,12 +26,34 @@ pub async fn load_symbol_map_for_elf<H: FileAndPathHelper>(
     file_contents: FileContentsWrapper<H::F>,
     file_kind: FileKind,
     helper: Arc<H>,
+    demangle_options: DemangleOptions,
+    best_effort_function_ranges: bool,
 ) -> Result<SymbolMap<H>, Error> {
     let elf_file =
         File::parse(&file_contents).map_err(|e| Error::ObjectParseError(file_kind, e))?;
 
-    if let Some(symbol_map) =
-        try_to_get_symbol_map_from_debug_link(&file_location, &elf_file, file_kind, &*helper).await
+    if let Some(symbol_map) = try_to_get_symbol_map_from_debug_link(
+        &file_location,
+        &elf_file,
+        file_kind,
+        &*helper,
+        demangle_options,
+        best_effort_function_ranges,
+    )
+    .await
+    {
+        return Ok(symbol_map);
+    }
+
+    if let Some(symbol_map) = try_to_get_symbol_map_from_build_id(
+        &file_location,
+        &elf_file,
+        file_kind,
+        &*helper,
+        demangle_options,
+        best_effort_function_ranges,
+    )
+    .await
     {
         return Ok(symbol_map);
     }
@@ -49,25 +75,40 @@ pub async fn load_symbol_map_for_elf<H: FileAndPathHelper>(
             file_contents,
             Some(supplementary_file),
             dwp_file_contents,
+            None,
             file_kind,
             None,
+            best_effort_function_ranges,
         )?;
-        let symbol_map = ObjectSymbolMap::new(owner)?;
-        return Ok(SymbolMap::new_plain(file_location, Box::new(symbol_map)));
+        let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
+        return Ok(SymbolMap::new_plain(
+            file_location,
+            SymbolFileKind::Elf,
+            Box::new(symbol_map),
+        ));
     }
 
-    // If this file has a .gnu_debugdata section, use the uncompressed object from that section instead.
-    if let Some(symbol_map) =
-        try_get_symbol_map_from_mini_debug_info(&elf_file, file_kind, &file_location)
-    {
-        return Ok(symbol_map);
-    }
+    // If this file has a .gnu_debugdata section, also use the symbols from the
+    // compressed object in that section. But prefer full debug info if we have it.
+    let mini_debug_info_data = if elf_file.section_by_name(".debug_info").is_none() {
+        get_mini_debug_info_data(&elf_file)
+    } else {
+        None
+    };
 
-    let owner =
-        ElfSymbolMapDataAndObjects::new(file_contents, None, dwp_file_contents, file_kind, None)?;
-    let symbol_map = ObjectSymbolMap::new(owner)?;
+    let owner = ElfSymbolMapDataAndObjects::new(
+        file_contents,
+        None,
+        dwp_file_contents,
+        mini_debug_info_data,
+        file_kind,
+        None,
+        best_effort_function_ranges,
+    )?;
+    let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
     Ok(SymbolMap::new_with_external_file_support(
         file_location,
+        SymbolFileKind::Elf,
         Box::new(symbol_map),
         helper,
     ))
@@ -78,6 +119,8 @@ async fn try_to_get_symbol_map_from_debug_link<'data, H, R>(
     elf_file: &File<'data, R>,
     file_kind: FileKind,
     helper: &H,
+    demangle_options: DemangleOptions,
+    best_effort_function_ranges: bool,
 ) -> Option<SymbolMap<H>>
 where
     R: ReadRef<'data>,
@@ -98,6 +141,8 @@ where
             crc,
             file_kind,
             helper,
+            demangle_options,
+            best_effort_function_ranges,
         )
         .await;
         if let Ok(symbol_map) = symbol_map {
@@ -108,6 +153,7 @@ where
     None
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn get_symbol_map_for_debug_link_candidate<H>(
     original_file_location: &H::FL,
     path: &H::FL,
@@ -115,6 +161,8 @@ async fn get_symbol_map_for_debug_link_candidate<H>(
     expected_crc: u32,
     file_kind: FileKind,
     helper: &H,
+    demangle_options: DemangleOptions,
+    best_effort_function_ranges: bool,
 ) -> Result<SymbolMap<H>, Error>
 where
     H: FileAndPathHelper,
@@ -122,7 +170,7 @@ where
     let file_contents = helper
         .load_file(path.clone())
         .await
-        .map_err(|e| Error::HelperErrorDuringOpenFile(path.to_string(), e))?;
+        .map_err(|e| Error::helper_error_during_open_file(path.to_string(), e))?;
     let file_contents = FileContentsWrapper::new(file_contents);
     let actual_crc = compute_debug_link_crc_of_file_contents(&file_contents)?;
 
@@ -130,6 +178,82 @@ where
         return Err(Error::DebugLinkCrcMismatch(actual_crc, expected_crc));
     }
 
+    symbol_map_for_separate_debug_file(
+        original_file_location,
+        path,
+        file_contents,
+        debug_id,
+        file_kind,
+        helper,
+        demangle_options,
+        best_effort_function_ranges,
+    )
+    .await
+}
+
+async fn try_to_get_symbol_map_from_build_id<'data, H, R>(
+    original_file_location: &H::FL,
+    elf_file: &File<'data, R>,
+    file_kind: FileKind,
+    helper: &H,
+    demangle_options: DemangleOptions,
+    best_effort_function_ranges: bool,
+) -> Option<SymbolMap<H>>
+where
+    R: ReadRef<'data>,
+    H: FileAndPathHelper,
+{
+    if elf_file.section_by_name(".debug_info").is_some() {
+        return None;
+    }
+    let build_id = ElfBuildId(elf_file.build_id().ok().flatten()?.to_owned());
+    let debug_id = debug_id_for_object(elf_file)?;
+    let candidate_paths = helper
+        .get_candidate_paths_for_debug_file_by_build_id(original_file_location, &build_id)
+        .ok()?;
+
+    for candidate_path in candidate_paths {
+        let file_contents = match helper.load_file(candidate_path.clone()).await {
+            Ok(file_contents) => FileContentsWrapper::new(file_contents),
+            Err(_) => continue,
+        };
+        match File::parse(&file_contents) {
+            Ok(debug_file) if debug_file.build_id().ok().flatten() == Some(&build_id.0) => {}
+            _ => continue,
+        }
+        let symbol_map = symbol_map_for_separate_debug_file(
+            original_file_location,
+            &candidate_path,
+            file_contents,
+            debug_id,
+            file_kind,
+            helper,
+            demangle_options,
+            best_effort_function_ranges,
+        )
+        .await;
+        if let Ok(symbol_map) = symbol_map {
+            return Some(symbol_map);
+        }
+    }
+
+    None
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn symbol_map_for_separate_debug_file<H>(
+    original_file_location: &H::FL,
+    path: &H::FL,
+    file_contents: FileContentsWrapper<H::F>,
+    debug_id: DebugId,
+    file_kind: FileKind,
+    helper: &H,
+    demangle_options: DemangleOptions,
+    best_effort_function_ranges: bool,
+) -> Result<SymbolMap<H>, Error>
+where
+    H: FileAndPathHelper,
+{
     let dwp_file_contents = if let Some(dwp_file_location) = path.location_for_dwp() {
         helper
             .load_file(dwp_file_location)
@@ -139,16 +263,23 @@ where
     } else {
         None
     };
+    let supplementary_file = match File::parse(&file_contents) {
+        Ok(debug_file) => try_to_load_supplementary_file(path, &debug_file, helper).await,
+        Err(_) => None,
+    };
     let owner = ElfSymbolMapDataAndObjects::new(
         file_contents,
-        None,
+        supplementary_file,
         dwp_file_contents,
+        None,
         file_kind,
         Some(debug_id),
+        best_effort_function_ranges,
     )?;
-    let symbol_map = ObjectSymbolMap::new(owner)?;
+    let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
     Ok(SymbolMap::new_plain(
         original_file_location.clone(),
+        SymbolFileKind::Elf,
         Box::new(symbol_map),
     ))
 }
@@ -290,23 +421,18 @@ where
     None
 }
 
-fn try_get_symbol_map_from_mini_debug_info<'data, R: ReadRef<'data>, H: FileAndPathHelper>(
+/// Returns the decompressed contents of the .gnu_debugdata section ("MiniDebugInfo"),
+/// which is an xz-compressed ELF file with a symbol table for the functions that
+/// aren't in .dynsym.
+fn get_mini_debug_info_data<'data, R: ReadRef<'data>>(
     elf_file: &File<'data, R>,
-    file_kind: FileKind,
-    debug_file_location: &H::FL,
-) -> Option<SymbolMap<H>> {
+) -> Option<FileContentsWrapper<Vec<u8>>> {
     let debugdata = elf_file.section_by_name(".gnu_debugdata")?;
     let data = debugdata.data().ok()?;
     let mut cursor = Cursor::new(data);
     let mut objdata = Vec::new();
     lzma_rs::xz_decompress(&mut cursor, &mut objdata).ok()?;
-    let file_contents = FileContentsWrapper::new(objdata);
-    let owner = ElfSymbolMapDataAndObjects::new(file_contents, None, None, file_kind, None).ok()?;
-    let symbol_map = ObjectSymbolMap::new(owner).ok()?;
-    Some(SymbolMap::new_plain(
-        debug_file_location.clone(),
-        Box::new(symbol_map),
-    ))
+    Some(FileContentsWrapper::new(objdata))
 }
 
 struct ElfSymbolMapData<T>
@@ -317,13 +443,17 @@ where
     supplementary_file_data: Option<FileContentsWrapper<T>>,
     dwp_file_data: Option<FileContentsWrapper<T>>,
     dwo_file_data: FrozenVec<Box<FileContentsWrapper<T>>>,
+    mini_debug_info_data: Option<FileContentsWrapper<Vec<u8>>>,
 }
 
 #[derive(Yokeable)]
 struct ElfObjectsWrapper<'data, T: FileContents>(Box<dyn ElfObjectsTrait<T> + Send + Sync + 'data>);
 
 trait ElfObjectsTrait<T: FileContents> {
-    fn make_inner(&self) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error>;
+    fn make_inner(
+        &self,
+        demangle_options: DemangleOptions,
+    ) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error>;
 }
 
 struct ElfObjects<'data, T: FileContents> {
@@ -332,10 +462,14 @@ struct ElfObjects<'data, T: FileContents> {
     dwp_file_data: Option<&'data FileContentsWrapper<T>>,
     dwo_file_data: &'data FrozenVec<Box<FileContentsWrapper<T>>>,
     override_debug_id: Option<DebugId>,
+    /// Whether to synthesize function ranges from .debug_frame in stripped
+    /// files, see `compute_function_addresses_elf`.
+    best_effort_function_ranges: bool,
     addr2line_context_data: Addr2lineContextData,
     object: File<'data, &'data FileContentsWrapper<T>>,
     supplementary_object: Option<File<'data, &'data FileContentsWrapper<T>>>,
     dwp_object: Option<File<'data, &'data FileContentsWrapper<T>>>,
+    mini_debug_info_object: Option<File<'data, &'data FileContentsWrapper<Vec<u8>>>>,
 }
 
 impl<'data, T: FileContents + 'static> ElfObjects<'data, T> {
@@ -383,7 +517,25 @@ impl<'data, T: FileContents + 'static> ElfObjects<'data, T> {
     }
 
     fn function_addresses(&self) -> (Option<Vec<u32>>, Option<Vec<u32>>) {
-        compute_function_addresses_elf(&self.object)
+        compute_function_addresses_elf(&self.object, self.best_effort_function_ranges)
+    }
+
+    fn mini_debug_info_symbols(&self) -> Vec<AdditionalSymbol<'data>> {
+        let Some(mini_debug_info_object) = self.mini_debug_info_object.as_ref() else {
+            return Vec::new();
+        };
+        mini_debug_info_object
+            .symbols()
+            .filter(|symbol| symbol.kind() == SymbolKind::Text && symbol.address() != 0)
+            .filter_map(|symbol| {
+                Some(AdditionalSymbol {
+                    address: symbol.address(),
+                    size: symbol.size(),
+                    name: symbol.name_bytes().ok()?,
+                    forwarded_to: None,
+                })
+            })
+            .collect()
     }
 }
 
@@ -399,7 +551,10 @@ impl<T: FileContents + 'static> DwoDwarfMaker<T> for ElfObjects<'_, T> {
 }
 
 impl<T: FileContents + 'static> ElfObjectsTrait<T> for ElfObjects<'_, T> {
-    fn make_inner(&self) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error> {
+    fn make_inner(
+        &self,
+        demangle_options: DemangleOptions,
+    ) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error> {
         let debug_id = if let Some(debug_id) = self.override_debug_id {
             debug_id
         } else {
@@ -407,15 +562,18 @@ impl<T: FileContents + 'static> ElfObjectsTrait<T> for ElfObjects<'_, T> {
                 .ok_or(Error::InvalidInputError("debug ID cannot be read"))?
         };
         let (function_starts, function_ends) = self.function_addresses();
+        let mini_debug_info_symbols = self.mini_debug_info_symbols();
 
         let inner = ObjectSymbolMapInnerWrapper::new(
             &self.object,
             self.make_addr2line_context().ok(),
             self.make_dwp_package().ok().flatten(),
             debug_id,
+            &mini_debug_info_symbols,
             function_starts.as_deref(),
             function_ends.as_deref(),
             self,
+            demangle_options,
         );
 
         Ok(inner)
@@ -431,14 +589,17 @@ impl<T: FileContents + 'static> ElfSymbolMapDataAndObjects<T> {
         file_data: FileContentsWrapper<T>,
         supplementary_file_data: Option<FileContentsWrapper<T>>,
         dwp_file_data: Option<FileContentsWrapper<T>>,
+        mini_debug_info_data: Option<FileContentsWrapper<Vec<u8>>>,
         file_kind: FileKind,
         override_debug_id: Option<DebugId>,
+        best_effort_function_ranges: bool,
     ) -> Result<Self, Error> {
         let data = ElfSymbolMapData {
             file_data,
             supplementary_file_data,
             dwp_file_data,
             dwo_file_data: FrozenVec::new(),
+            mini_debug_info_data,
         };
         let data_and_objects = Yoke::try_attach_to_cart(
             Box::new(data),
@@ -459,15 +620,23 @@ impl<T: FileContents + 'static> ElfSymbolMapDataAndObjects<T> {
                     ),
                     None => None,
                 };
+                // A broken MiniDebugInfo object shouldn't prevent us from using the
+                // rest of the file, so we ignore parse errors here.
+                let mini_debug_info_object = data
+                    .mini_debug_info_data
+                    .as_ref()
+                    .and_then(|mini_debug_info_data| File::parse(mini_debug_info_data).ok());
                 let elf_objects = ElfObjects {
                     object,
                     supplementary_object,
                     dwp_object,
+                    mini_debug_info_object,
                     dwo_file_data: &data.dwo_file_data,
                     file_data: &data.file_data,
                     supplementary_file_data: data.supplementary_file_data.as_ref(),
                     dwp_file_data: data.dwp_file_data.as_ref(),
                     override_debug_id,
+                    best_effort_function_ranges,
                     addr2line_context_data: Addr2lineContextData::new(),
                 };
                 Ok(ElfObjectsWrapper(Box::new(elf_objects)))
@@ -478,13 +647,17 @@ impl<T: FileContents + 'static> ElfSymbolMapDataAndObjects<T> {
 }
 
 impl<T: FileContents + 'static> ObjectSymbolMapOuter<T> for ElfSymbolMapDataAndObjects<T> {
-    fn make_symbol_map_inner(&self) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error> {
-        self.0.get().0.make_inner()
+    fn make_symbol_map_inner(
+        &self,
+        demangle_options: DemangleOptions,
+    ) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error> {
+        self.0.get().0.make_inner(demangle_options)
     }
 }
 
 fn compute_function_addresses_elf<'data, O: object::Object<'data>>(
     object_file: &O,
+    best_effort_function_ranges: bool,
 ) -> (Option<Vec<u32>>, Option<Vec<u32>>) {
     // Get an approximation of the list of function start addresses by
     // iterating over the exception handling info. Every FDE roughly
@@ -493,8 +666,8 @@ fn compute_function_addresses_elf<'data, O: object::Object<'data>>(
     // not in .eh_frame, it is in __unwind_info (plus some auxiliary data
     // in __eh_frame, but that's only needed for the actual unwinding, not
     // for the function start addresses).
-    // We also don't handle .debug_frame yet, which is sometimes found
-    // instead of .eh_frame.
+    // If best_effort_function_ranges is set, we also look at .debug_frame in
+    // stripped binaries, where it is sometimes found instead of .eh_frame.
     // And we don't have anything for the PE format yet, either.
 
     let eh_frame = object_file.section_by_name(".eh_frame");
@@ -526,33 +699,64 @@ fn compute_function_addresses_elf<'data, O: object::Object<'data>>(
         .address_size()
         .unwrap_or(object::AddressSize::U64) as u8;
 
-    let eh_frame = match eh_frame {
-        Some(eh_frame) => eh_frame,
-        None => return (None, None),
-    };
-
-    let eh_frame_data = match eh_frame.uncompressed_data() {
-        Ok(eh_frame_data) => eh_frame_data,
-        Err(_) => return (None, None),
+    let eh_frame_data = eh_frame.and_then(|section| section.uncompressed_data().ok());
+    // Only fall back to .debug_frame if there is no symbol table. Its FDEs are
+    // usually accompanied by full symbols, and on ARM, Thumb function symbols
+    // have the low bit set while the FDE addresses don't, so mixing the two
+    // would create bogus one-byte functions.
+    let debug_frame_data = if best_effort_function_ranges && object_file.symbols().next().is_none()
+    {
+        object_file
+            .section_by_name(".debug_frame")
+            .and_then(|section| section.uncompressed_data().ok())
+    } else {
+        None
     };
+    if eh_frame_data.is_none() && debug_frame_data.is_none() {
+        return (None, None);
+    }
 
-    let mut eh_frame = EhFrame::new(&eh_frame_data, endian);
-    eh_frame.set_address_size(address_size);
-    let mut cur_cie = None;
-    let mut entries_iter = eh_frame.entries(&bases);
     let mut start_addresses = Vec::new();
     let mut end_addresses = Vec::new();
+    if let Some(eh_frame_data) = &eh_frame_data {
+        let mut eh_frame = EhFrame::new(eh_frame_data, endian);
+        eh_frame.set_address_size(address_size);
+        collect_fde_ranges(&eh_frame, &bases, &mut start_addresses, &mut end_addresses);
+    }
+    if let Some(debug_frame_data) = &debug_frame_data {
+        let mut debug_frame = DebugFrame::new(debug_frame_data, endian);
+        debug_frame.set_address_size(address_size);
+        collect_fde_ranges(
+            &debug_frame,
+            &bases,
+            &mut start_addresses,
+            &mut end_addresses,
+        );
+    }
+    (Some(start_addresses), Some(end_addresses))
+}
+
+/// Adds the address range of every FDE in `section` to `start_addresses` and
+/// `end_addresses`.
+fn collect_fde_ranges<'data, S: UnwindSection<EndianSlice<'data, RunTimeEndian>>>(
+    section: &S,
+    bases: &gimli::BaseAddresses,
+    start_addresses: &mut Vec<u32>,
+    end_addresses: &mut Vec<u32>,
+) {
+    let mut cur_cie = None;
+    let mut entries_iter = section.entries(bases);
     while let Ok(Some(entry)) = entries_iter.next() {
         match entry {
             CieOrFde::Cie(cie) => cur_cie = Some(cie),
             CieOrFde::Fde(partial_fde) => {
-                if let Ok(fde) = partial_fde.parse(|eh_frame, bases, cie_offset| {
+                if let Ok(fde) = partial_fde.parse(|section, bases, cie_offset| {
                     if let Some(cie) = &cur_cie {
-                        if cie.offset() == cie_offset.0 {
+                        if cie.offset() == UnwindOffset::into(cie_offset) {
                             return Ok(cie.clone());
                         }
                     }
-                    let cie = eh_frame.cie_from_offset(bases, cie_offset);
+                    let cie = section.cie_from_offset(bases, cie_offset);
                     if let Ok(cie) = &cie {
                         cur_cie = Some(cie.clone());
                     }
@@ -564,5 +768,4 @@ fn compute_function_addresses_elf<'data, O: object::Object<'data>>(
             }
         }
     }
-    (Some(start_addresses), Some(end_addresses))
 }
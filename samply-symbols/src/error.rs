@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use debugid::DebugId;
 use linux_perf_data::jitdump::JitDumpError;
@@ -9,6 +10,20 @@ use thiserror::Error;
 use crate::breakpad::BreakpadParseError;
 use crate::{CodeId, FatArchiveMember, LibraryInfo};
 
+/// The error which a [`FileAndPathHelper::load_file`](crate::FileAndPathHelper::load_file)
+/// implementation can return if loading the file took too long.
+///
+/// This crate doesn't have a timer of its own, because it doesn't know which
+/// async runtime it runs on, for example in WebAssembly. So the helper needs
+/// to enforce the timeout itself. An error of this type is reported as
+/// [`Error::FileLoadTimeout`] rather than as [`Error::HelperErrorDuringOpenFile`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Loading the file timed out after {timeout:?}")]
+pub struct FileLoadTimeoutError {
+    /// The timeout which was exceeded.
+    pub timeout: Duration,
+}
+
 /// The error type used in this crate.
 #[derive(Error, Debug)]
 #[non_exhaustive]
@@ -28,6 +43,9 @@ pub enum Error {
     #[error("The JITDUMP file was malformed, causing a parsing error: {0}")]
     JitDumpParsing(#[from] JitDumpError),
 
+    #[error("The GSYM file was malformed: {0}")]
+    GsymParsing(&'static str),
+
     #[error("Invalid index {0} for file or inline_origin in breakpad sym file")]
     InvalidFileOrInlineOriginIndexInBreakpadFile(u32),
 
@@ -63,6 +81,9 @@ pub enum Error {
     #[error("The FileLocation for the debug file does not support loading source files.")]
     FileLocationRefusedSourceFileLocation,
 
+    #[error("The FileAndPathHelper does not support loading files from URLs, so {0} was skipped.")]
+    HelperRefusedUrl(String),
+
     #[error(
         "No disambiguator supplied for universal binary, available images: {}", format_multiarch_members(.0)
     )]
@@ -86,6 +107,9 @@ pub enum Error {
     #[error("pdb-addr2line error: {1} ({0})")]
     PdbAddr2lineErrorWithContext(&'static str, #[source] pdb_addr2line::Error),
 
+    #[error("The PDB file was linked with /DEBUG:FASTLINK and has no public symbols; its debug info is stored in the object files it was linked from")]
+    FastlinkPdbUnsupported,
+
     #[error("Invalid input: {0}")]
     InvalidInputError(&'static str),
 
@@ -95,6 +119,9 @@ pub enum Error {
     #[error("Dyld cache parsing error: {0}")]
     DyldCacheParseError(#[source] object::read::Error),
 
+    #[error("The dyld shared cache {0} has a .symbols subcache which could not be loaded: {1}")]
+    DyldCacheSymbolsSubcacheMissing(String, #[source] Box<Error>),
+
     #[error("The dyld shared cache file did not include an entry for the dylib at {0}")]
     NoMatchingDyldCacheImagePath(String),
 
@@ -116,6 +143,9 @@ pub enum Error {
     #[error("open_file helper callback for file {0} returned error: {1}")]
     HelperErrorDuringOpenFile(String, #[source] Box<dyn std::error::Error + Send + Sync>),
 
+    #[error("Loading the file {0} timed out after {1:?}")]
+    FileLoadTimeout(String, Duration),
+
     #[error("FileContents read_bytes_at for file {0} returned error: {1}")]
     HelperErrorDuringFileReading(String, #[source] Box<dyn std::error::Error + Send + Sync>),
 
@@ -227,12 +257,25 @@ impl From<PdbError> for Error {
 }
 
 impl Error {
+    /// Creates the error for a failed [`FileAndPathHelper::load_file`](crate::FileAndPathHelper::load_file)
+    /// call for the file at `path`.
+    pub(crate) fn helper_error_during_open_file(
+        path: String,
+        err: Box<dyn std::error::Error + Send + Sync>,
+    ) -> Self {
+        match err.downcast::<FileLoadTimeoutError>() {
+            Ok(timeout_err) => Error::FileLoadTimeout(path, timeout_err.timeout),
+            Err(err) => Error::HelperErrorDuringOpenFile(path, err),
+        }
+    }
+
     pub fn enum_as_string(&self) -> &'static str {
         match self {
             Error::UnmatchedDebugId(_, _) => "UnmatchedDebugId",
             Error::NoDisambiguatorForFatArchive(_) => "NoDisambiguatorForFatArchive",
             Error::BreakpadParsing(_) => "BreakpadParsing",
             Error::JitDumpParsing(_) => "JitDumpParsing",
+            Error::GsymParsing(_) => "GsymParsing",
             Error::NotEnoughInformationToIdentifyBinary => "NotEnoughInformationToIdentifyBinary",
             Error::NotEnoughInformationToIdentifySymbolMap => {
                 "NotEnoughInformationToIdentifySymbolMap"
@@ -253,13 +296,16 @@ impl Error {
             }
             Error::FileLocationRefusedPdbLocation => "FileLocationRefusedPdbLocation",
             Error::FileLocationRefusedSourceFileLocation => "FileLocationRefusedSourceFileLocation",
+            Error::HelperRefusedUrl(_) => "HelperRefusedUrl",
             Error::UnexpectedExternalFileFileKind(_) => "UnexpectedExternalFileFileKind",
             Error::NoMatchMultiArch(_) => "NoMatchMultiArch",
             Error::NoLuckMacOsSystemLibrary(_) => "NoLuckMacOsSystemLibrary",
             Error::PdbError(_, _) => "PdbError",
             Error::PdbAddr2lineErrorWithContext(_, _) => "PdbAddr2lineErrorWithContext",
+            Error::FastlinkPdbUnsupported => "FastlinkPdbUnsupported",
             Error::InvalidInputError(_) => "InvalidInputError",
             Error::DyldCacheParseError(_) => "DyldCacheParseError",
+            Error::DyldCacheSymbolsSubcacheMissing(_, _) => "DyldCacheSymbolsSubcacheMissing",
             Error::NoMatchingDyldCacheImagePath(_) => "NoMatchingDyldCacheImagePath",
             Error::ObjectParseError(_, _) => "ObjectParseError",
             Error::MachOHeaderParseError(_) => "MachOHeaderParseError",
@@ -273,6 +319,7 @@ impl Error {
                 "HelperErrorDuringGetDyldSharedCachePaths"
             }
             Error::HelperErrorDuringOpenFile(_, _) => "HelperErrorDuringOpenFile",
+            Error::FileLoadTimeout(_, _) => "FileLoadTimeout",
             Error::HelperErrorDuringFileReading(_, _) => "HelperErrorDuringFileReading",
             Error::JitDumpFileReading(_) => "JitDumpFileReading",
             Error::NoCandidatePathForDebugFile(_) => "NoCandidatePathForDebugFile",
@@ -292,3 +339,23 @@ impl Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_errors_from_helper() {
+        let timeout = Duration::from_secs(5);
+        let err = Error::helper_error_during_open_file(
+            "file.pdb".into(),
+            Box::new(FileLoadTimeoutError { timeout }),
+        );
+        assert!(
+            matches!(err, Error::FileLoadTimeout(path, t) if path == "file.pdb" && t == timeout)
+        );
+
+        let err = Error::helper_error_during_open_file("file.pdb".into(), "not found".into());
+        assert_eq!(err.enum_as_string(), "HelperErrorDuringOpenFile");
+    }
+}
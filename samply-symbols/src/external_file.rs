@@ -6,6 +6,7 @@ use object::{File, FileKind, ReadRef};
 use yoke::Yoke;
 use yoke_derive::Yokeable;
 
+use crate::demangle::DemangleOptions;
 use crate::dwarf::{get_frames, Addr2lineContextData};
 use crate::error::Error;
 use crate::path_mapper::PathMapper;
@@ -18,6 +19,7 @@ pub async fn load_external_file<H>(
     helper: &H,
     external_file_location: H::FL,
     external_file_path: &str,
+    demangle_options: DemangleOptions,
 ) -> Result<ExternalFileSymbolMap<H::F>, Error>
 where
     H: FileAndPathHelper,
@@ -25,8 +27,8 @@ where
     let file = helper
         .load_file(external_file_location)
         .await
-        .map_err(|e| Error::HelperErrorDuringOpenFile(external_file_path.to_string(), e))?;
-    let symbol_map = ExternalFileSymbolMap::new(external_file_path, file)?;
+        .map_err(|e| Error::helper_error_during_open_file(external_file_path.to_string(), e))?;
+    let symbol_map = ExternalFileSymbolMap::new(external_file_path, file, demangle_options)?;
     Ok(symbol_map)
 }
 
@@ -34,15 +36,17 @@ struct ExternalFileOuter<F: FileContents> {
     file_path: String,
     file_contents: FileContentsWrapper<F>,
     addr2line_context_data: Addr2lineContextData,
+    demangle_options: DemangleOptions,
 }
 
 impl<F: FileContents> ExternalFileOuter<F> {
-    pub fn new(file_path: &str, file: F) -> Self {
+    pub fn new(file_path: &str, file: F, demangle_options: DemangleOptions) -> Self {
         let file_contents = FileContentsWrapper::new(file);
         Self {
             file_path: file_path.to_owned(),
             file_contents,
             addr2line_context_data: Addr2lineContextData::new(),
+            demangle_options,
         }
     }
 
@@ -154,6 +158,7 @@ impl<F: FileContents> ExternalFileInnerTrait for ExternalFileInner<'_, F> {
         external_file_address: &ExternalFileAddressInFileRef,
     ) -> Option<Vec<FrameDebugInfo>> {
         let mut path_mapper = self.path_mapper.lock().unwrap();
+        let demangle_options = &self.external_file.demangle_options;
         match (&self.member_contexts, external_file_address) {
             (
                 ExternalFileMemberContexts::SingleObject(context),
@@ -161,7 +166,12 @@ impl<F: FileContents> ExternalFileInnerTrait for ExternalFileInner<'_, F> {
                     symbol_name,
                     offset_from_symbol,
                 },
-            ) => context.lookup(symbol_name, *offset_from_symbol, &mut path_mapper),
+            ) => context.lookup(
+                symbol_name,
+                *offset_from_symbol,
+                &mut path_mapper,
+                demangle_options,
+            ),
             (
                 ExternalFileMemberContexts::Archive {
                     member_ranges,
@@ -175,9 +185,12 @@ impl<F: FileContents> ExternalFileInnerTrait for ExternalFileInner<'_, F> {
             ) => {
                 let mut member_contexts = contexts.lock().unwrap();
                 match member_contexts.get(name_in_archive) {
-                    Some(member_context) => {
-                        member_context.lookup(symbol_name, *offset_from_symbol, &mut path_mapper)
-                    }
+                    Some(member_context) => member_context.lookup(
+                        symbol_name,
+                        *offset_from_symbol,
+                        &mut path_mapper,
+                        demangle_options,
+                    ),
                     None => {
                         let range = *member_ranges.get(name_in_archive.as_bytes())?;
                         // .ok_or_else(|| Error::FileNotInArchive(name_in_archive.to_owned()))?;
@@ -186,6 +199,7 @@ impl<F: FileContents> ExternalFileInnerTrait for ExternalFileInner<'_, F> {
                             symbol_name,
                             *offset_from_symbol,
                             &mut path_mapper,
+                            demangle_options,
                         );
                         member_contexts.insert(name_in_archive.to_string(), member_context);
                         res
@@ -216,10 +230,16 @@ impl ExternalFileMemberContext<'_> {
         symbol_name: &[u8],
         offset_from_symbol: u32,
         path_mapper: &mut PathMapper<()>,
+        demangle_options: &DemangleOptions,
     ) -> Option<Vec<FrameDebugInfo>> {
         let symbol_address = self.symbol_addresses.get(symbol_name)?;
         let address = symbol_address + offset_from_symbol as u64;
-        get_frames(address, self.context.as_ref(), path_mapper)
+        get_frames(
+            address,
+            self.context.as_ref(),
+            path_mapper,
+            demangle_options,
+        )
     }
 }
 
@@ -228,8 +248,8 @@ pub struct ExternalFileSymbolMap<F: FileContents + 'static>(
 );
 
 impl<F: FileContents + 'static> ExternalFileSymbolMap<F> {
-    pub fn new(file_path: &str, file: F) -> Result<Self, Error> {
-        let outer = ExternalFileOuter::new(file_path, file);
+    pub fn new(file_path: &str, file: F, demangle_options: DemangleOptions) -> Result<Self, Error> {
+        let outer = ExternalFileOuter::new(file_path, file, demangle_options);
         let inner = Yoke::try_attach_to_cart(
             Box::new(outer),
             |outer| -> Result<ExternalFileInnerWrapper<'_>, Error> {
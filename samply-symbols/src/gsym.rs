@@ -0,0 +1,584 @@
+//! Support for the GSYM symbol format from LLVM, as created by `llvm-gsymutil`.
+//!
+//! A GSYM file contains a sorted address table, and for each address a
+//! "function info" with the function's size and name, and optionally a line
+//! table and inline information.
+//!
+//! GSYM files only know about the addresses in the original file, i.e. SVMAs,
+//! and not about the image base address. We treat SVMAs as relative addresses,
+//! which is correct for ELF shared libraries and position-independent
+//! executables, whose image base address is zero.
+
+use std::borrow::Cow;
+
+use debugid::DebugId;
+use uuid::Uuid;
+use yoke::Yoke;
+use yoke_derive::Yokeable;
+
+use crate::debugid_util::DebugIdExt;
+use crate::demangle::{self, DemangleOptions};
+use crate::error::Error;
+use crate::shared::{
+    FileContents, FileContentsWrapper, FrameDebugInfo, FramesLookupResult, LookupAddress,
+    SourceFilePath, SymbolInfo,
+};
+use crate::symbol_map::{GetInnerSymbolMap, SymbolFileKind, SymbolMap, SymbolMapTrait};
+use crate::{FileAndPathHelper, SyncAddressInfo};
+
+const MAGIC_BYTES_LE: &[u8] = b"MYSG";
+const MAGIC_BYTES_BE: &[u8] = b"GSYM";
+const HEADER_SIZE: usize = 48;
+
+const INFO_TYPE_END_OF_LIST: u32 = 0;
+const INFO_TYPE_LINE_TABLE: u32 = 1;
+const INFO_TYPE_INLINE_INFO: u32 = 2;
+
+const LINE_TABLE_OP_END_SEQUENCE: u8 = 0;
+const LINE_TABLE_OP_SET_FILE: u8 = 1;
+const LINE_TABLE_OP_ADVANCE_PC: u8 = 2;
+const LINE_TABLE_OP_ADVANCE_LINE: u8 = 3;
+const LINE_TABLE_OP_FIRST_SPECIAL: u8 = 4;
+
+pub fn is_gsym_file<T: FileContents>(file_contents: &FileContentsWrapper<T>) -> bool {
+    matches!(
+        file_contents.read_bytes_at(0, 4),
+        Ok(MAGIC_BYTES_LE | MAGIC_BYTES_BE)
+    )
+}
+
+pub fn get_symbol_map_for_gsym<H: FileAndPathHelper>(
+    file_contents: FileContentsWrapper<H::F>,
+    file_location: H::FL,
+    demangle_options: DemangleOptions,
+) -> Result<SymbolMap<H>, Error> {
+    let symbol_map = GsymSymbolMap(Yoke::try_attach_to_cart(
+        Box::new(file_contents),
+        |file_contents| -> Result<GsymSymbolMapInner<'_>, Error> {
+            let data = file_contents
+                .read_bytes_at(0, file_contents.len())
+                .map_err(|_| Error::GsymParsing("Could not read the file contents"))?;
+            Ok(GsymSymbolMapInner {
+                gsym: Gsym::parse(data)?,
+                demangle_options,
+            })
+        },
+    )?);
+    Ok(SymbolMap::new_plain(
+        file_location,
+        SymbolFileKind::Gsym,
+        Box::new(symbol_map),
+    ))
+}
+
+struct GsymSymbolMap<T: FileContents + 'static>(
+    Yoke<GsymSymbolMapInner<'static>, Box<FileContentsWrapper<T>>>,
+);
+
+impl<T: FileContents> GetInnerSymbolMap for GsymSymbolMap<T> {
+    fn get_inner_symbol_map<'a>(&'a self) -> &'a (dyn SymbolMapTrait + 'a) {
+        self.0.get()
+    }
+}
+
+#[derive(Yokeable)]
+struct GsymSymbolMapInner<'data> {
+    gsym: Gsym<'data>,
+    demangle_options: DemangleOptions,
+}
+
+impl SymbolMapTrait for GsymSymbolMapInner<'_> {
+    fn debug_id(&self) -> DebugId {
+        self.gsym.debug_id
+    }
+
+    fn demangle_options(&self) -> DemangleOptions {
+        self.demangle_options
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.gsym.num_addresses
+    }
+
+    fn iter_symbols(&self) -> Box<dyn Iterator<Item = (u32, Cow<'_, str>)> + '_> {
+        Box::new(
+            self.iter_symbols_with_sizes()
+                .map(|(address, _size, name)| (address, name)),
+        )
+    }
+
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        Box::new((0..self.gsym.num_addresses).filter_map(|index| {
+            let address = u32::try_from(self.gsym.address(index)?).ok()?;
+            let function = self.gsym.function_info(index)?;
+            let name = self.gsym.string(function.name)?;
+            Some((address, Some(function.size), String::from_utf8_lossy(name)))
+        }))
+    }
+
+    fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
+        let svma = match address {
+            LookupAddress::Relative(address) => u64::from(address),
+            LookupAddress::Svma(svma) => svma,
+            LookupAddress::FileOffset(_) => {
+                // GSYM files don't know about the file layout of the original file.
+                return None;
+            }
+        };
+        let index = self.gsym.lookup_address_index(svma)?;
+        let start_address = self.gsym.address(index)?;
+        let function = self.gsym.function_info(index)?;
+        if svma - start_address >= u64::from(function.size) {
+            return None;
+        }
+        let name = self.demangled_string(function.name)?;
+        let frames = self
+            .gsym
+            .frames_for_address(&function, start_address, svma)
+            .map(|frames| {
+                let frames = frames
+                    .into_iter()
+                    .map(|(name, file, line)| FrameDebugInfo {
+                        function: self.demangled_string(name),
+                        file_path: self
+                            .gsym
+                            .file_path(file)
+                            .map(|path| SourceFilePath::new(path, None)),
+                        line_number: Some(line).filter(|line| *line != 0),
+                    })
+                    .collect();
+                FramesLookupResult::Available(frames)
+            });
+        Some(SyncAddressInfo {
+            symbol: SymbolInfo {
+                address: u32::try_from(start_address).ok()?,
+                size: Some(function.size),
+                name,
+            },
+            frames,
+        })
+    }
+}
+
+impl GsymSymbolMapInner<'_> {
+    fn demangled_string(&self, offset: u32) -> Option<String> {
+        let name = String::from_utf8_lossy(self.gsym.string(offset)?);
+        Some(demangle::demangle_with_options(
+            &name,
+            &self.demangle_options,
+        ))
+    }
+}
+
+/// The parsed header and table locations of a GSYM file.
+struct Gsym<'data> {
+    data: &'data [u8],
+    big_endian: bool,
+    debug_id: DebugId,
+    base_address: u64,
+    addr_off_size: usize,
+    num_addresses: usize,
+    addr_offsets_start: usize,
+    addr_info_offsets_start: usize,
+    files_start: usize,
+    num_files: u32,
+    strtab: &'data [u8],
+}
+
+struct FunctionInfo<'data> {
+    size: u32,
+    name: u32,
+    line_table: Option<&'data [u8]>,
+    inline_info: Option<&'data [u8]>,
+}
+
+/// A node in the inline tree of a function. The root node describes the
+/// function itself and has a name offset of zero.
+struct InlineInfo {
+    ranges: Vec<(u64, u64)>,
+    name: u32,
+    call_file: u32,
+    call_line: u32,
+    children: Vec<InlineInfo>,
+}
+
+impl<'data> Gsym<'data> {
+    fn parse(data: &'data [u8]) -> Result<Self, Error> {
+        let big_endian = match data.get(..4) {
+            Some(MAGIC_BYTES_LE) => false,
+            Some(MAGIC_BYTES_BE) => true,
+            _ => return Err(Error::GsymParsing("Invalid magic")),
+        };
+        let mut header = Cursor::new(data, 4, big_endian);
+        let (version, addr_off_size, uuid_size, base_address, num_addresses) = (
+            header.u16(),
+            header.u8(),
+            header.u8(),
+            header.u64(),
+            header.u32(),
+        );
+        let (strtab_offset, strtab_size) = (header.u32(), header.u32());
+        let uuid = data.get(28..HEADER_SIZE);
+        let (
+            Some(1),
+            Some(addr_off_size @ (1 | 2 | 4 | 8)),
+            Some(uuid_size @ 0..=20),
+            Some(base_address),
+            Some(num_addresses),
+            Some(strtab_offset),
+            Some(strtab_size),
+            Some(uuid),
+        ) = (
+            version,
+            addr_off_size,
+            uuid_size,
+            base_address,
+            num_addresses,
+            strtab_offset,
+            strtab_size,
+            uuid,
+        )
+        else {
+            return Err(Error::GsymParsing("Invalid header"));
+        };
+        let uuid = &uuid[..usize::from(uuid_size)];
+        let debug_id = match uuid.len() {
+            0 => DebugId::nil(),
+            16 => DebugId::from_uuid(Uuid::from_slice(uuid).unwrap()),
+            _ => DebugId::from_identifier(uuid, !big_endian),
+        };
+
+        let addr_off_size = usize::from(addr_off_size);
+        let num_addresses = num_addresses as usize;
+        let addr_offsets_start = align_to(HEADER_SIZE, addr_off_size);
+        let addr_info_offsets_start =
+            align_to(addr_offsets_start + num_addresses * addr_off_size, 4);
+        let files_count_offset = addr_info_offsets_start + num_addresses * 4;
+        let num_files = Cursor::new(data, files_count_offset, big_endian)
+            .u32()
+            .ok_or(Error::GsymParsing("File table out of bounds"))?;
+        let files_start = files_count_offset + 4;
+        if data.len() < files_start + num_files as usize * 8 {
+            return Err(Error::GsymParsing("File table out of bounds"));
+        }
+        let strtab = data
+            .get(strtab_offset as usize..)
+            .and_then(|strtab| strtab.get(..strtab_size as usize))
+            .ok_or(Error::GsymParsing("String table out of bounds"))?;
+
+        Ok(Self {
+            data,
+            big_endian,
+            debug_id,
+            base_address,
+            addr_off_size,
+            num_addresses,
+            addr_offsets_start,
+            addr_info_offsets_start,
+            files_start,
+            num_files,
+            strtab,
+        })
+    }
+
+    fn cursor(&self, offset: usize) -> Cursor<'data> {
+        Cursor::new(self.data, offset, self.big_endian)
+    }
+
+    fn address(&self, index: usize) -> Option<u64> {
+        let mut cursor = self.cursor(self.addr_offsets_start + index * self.addr_off_size);
+        let offset = match self.addr_off_size {
+            1 => u64::from(cursor.u8()?),
+            2 => u64::from(cursor.u16()?),
+            4 => u64::from(cursor.u32()?),
+            _ => cursor.u64()?,
+        };
+        Some(self.base_address.wrapping_add(offset))
+    }
+
+    /// Returns the index of the last address which is <= `svma`.
+    fn lookup_address_index(&self, svma: u64) -> Option<usize> {
+        let (mut low, mut high) = (0, self.num_addresses);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.address(mid)? <= svma {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low.checked_sub(1)
+    }
+
+    fn function_info(&self, index: usize) -> Option<FunctionInfo<'data>> {
+        let offset = self
+            .cursor(self.addr_info_offsets_start + index * 4)
+            .u32()?;
+        let mut cursor = self.cursor(offset as usize);
+        let size = cursor.u32()?;
+        let name = cursor.u32()?;
+        let mut line_table = None;
+        let mut inline_info = None;
+        loop {
+            let info_type = cursor.u32()?;
+            let length = cursor.u32()?;
+            let info_data = cursor.bytes(length as usize)?;
+            match info_type {
+                INFO_TYPE_END_OF_LIST => break,
+                INFO_TYPE_LINE_TABLE => line_table = Some(info_data),
+                INFO_TYPE_INLINE_INFO => inline_info = Some(info_data),
+                _ => {}
+            }
+        }
+        Some(FunctionInfo {
+            size,
+            name,
+            line_table,
+            inline_info,
+        })
+    }
+
+    fn string(&self, offset: u32) -> Option<&'data [u8]> {
+        let s = self.strtab.get(offset as usize..)?;
+        let len = memchr::memchr(0, s)?;
+        Some(&s[..len])
+    }
+
+    fn file_path(&self, index: u32) -> Option<String> {
+        if index == 0 || index >= self.num_files {
+            return None;
+        }
+        let mut cursor = self.cursor(self.files_start + index as usize * 8);
+        let dir = self.string(cursor.u32()?)?;
+        let base = self.string(cursor.u32()?)?;
+        let path = if dir.is_empty() {
+            String::from_utf8_lossy(base).into_owned()
+        } else {
+            format!(
+                "{}/{}",
+                String::from_utf8_lossy(dir),
+                String::from_utf8_lossy(base)
+            )
+        };
+        Some(path)
+    }
+
+    /// Returns the frames at `svma` as (name, file, line) triples, starting
+    /// with the innermost frame. Returns None if the function has no line table.
+    fn frames_for_address(
+        &self,
+        function: &FunctionInfo,
+        start_address: u64,
+        svma: u64,
+    ) -> Option<Vec<(u32, u32, u32)>> {
+        let (mut file, mut line) =
+            self.line_table_lookup(function.line_table?, start_address, svma)?;
+        let inline_info = function
+            .inline_info
+            .and_then(|data| InlineInfo::parse(&mut self.cursor_for(data), start_address)?);
+        let mut inline_stack = Vec::new();
+        if let Some(inline_info) = &inline_info {
+            // The root entry describes the function itself, which is pushed below.
+            for child in &inline_info.children {
+                if child.get_inline_stack(svma, &mut inline_stack) {
+                    break;
+                }
+            }
+        }
+        let mut frames = Vec::with_capacity(inline_stack.len() + 1);
+        for inline_info in inline_stack {
+            frames.push((inline_info.name, file, line));
+            file = inline_info.call_file;
+            line = inline_info.call_line;
+        }
+        frames.push((function.name, file, line));
+        Some(frames)
+    }
+
+    /// Returns the (file, line) of the last line table row whose address is <= `svma`.
+    ///
+    /// Returns `None` if the line table can't be parsed, including when its values
+    /// would make the line or address computations overflow.
+    fn line_table_lookup(&self, data: &[u8], start_address: u64, svma: u64) -> Option<(u32, u32)> {
+        let mut cursor = self.cursor_for(data);
+        let min_delta = cursor.sleb128()?;
+        let max_delta = cursor.sleb128()?;
+        let line_range = max_delta.checked_sub(min_delta)?.checked_add(1)?;
+        if line_range <= 0 {
+            return None;
+        }
+        let first_line = cursor.uleb128()?;
+        let (mut address, mut file, mut line) = (start_address, 1, first_line as i64);
+        let mut result = None;
+        loop {
+            let op = cursor.u8()?;
+            match op {
+                LINE_TABLE_OP_END_SEQUENCE => break,
+                LINE_TABLE_OP_SET_FILE => {
+                    file = cursor.uleb128()? as u32;
+                    continue;
+                }
+                LINE_TABLE_OP_ADVANCE_LINE => {
+                    line = line.checked_add(cursor.sleb128()?)?;
+                    continue;
+                }
+                LINE_TABLE_OP_ADVANCE_PC => address = address.checked_add(cursor.uleb128()?)?,
+                _ => {
+                    let adjusted_op = i64::from(op - LINE_TABLE_OP_FIRST_SPECIAL);
+                    line = line.checked_add(min_delta.checked_add(adjusted_op % line_range)?)?;
+                    address = address.checked_add((adjusted_op / line_range) as u64)?;
+                }
+            }
+            // A new row has been emitted.
+            if address > svma {
+                break;
+            }
+            result = Some((file, line as u32));
+        }
+        result
+    }
+
+    fn cursor_for(&self, data: &'data [u8]) -> Cursor<'data> {
+        Cursor::new(data, 0, self.big_endian)
+    }
+}
+
+impl InlineInfo {
+    /// Parses an inline info tree. All address ranges are encoded relative to
+    /// the start of the first range of the parent. Returns `Some(None)` for the
+    /// empty terminator entry which ends a list of children.
+    fn parse(cursor: &mut Cursor, base_address: u64) -> Option<Option<Self>> {
+        let num_ranges = cursor.uleb128()?;
+        if num_ranges == 0 {
+            return Some(None);
+        }
+        let mut ranges = Vec::new();
+        for _ in 0..num_ranges {
+            let start = base_address.wrapping_add(cursor.uleb128()?);
+            let size = cursor.uleb128()?;
+            ranges.push((start, start.wrapping_add(size)));
+        }
+        let child_base_address = ranges[0].0;
+        let has_children = cursor.u8()? != 0;
+        let name = cursor.u32()?;
+        let call_file = cursor.uleb128()? as u32;
+        let call_line = cursor.uleb128()? as u32;
+        let mut children = Vec::new();
+        if has_children {
+            while let Some(child) = Self::parse(cursor, child_base_address)? {
+                children.push(child);
+            }
+        }
+        Some(Some(Self {
+            ranges,
+            name,
+            call_file,
+            call_line,
+            children,
+        }))
+    }
+
+    /// If this entry covers `address`, inserts it and any of its descendants
+    /// covering `address` at the front of `stack`, innermost first.
+    fn get_inline_stack<'a>(&'a self, address: u64, stack: &mut Vec<&'a InlineInfo>) -> bool {
+        if !self
+            .ranges
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&address))
+        {
+            return false;
+        }
+        stack.insert(0, self);
+        for child in &self.children {
+            if child.get_inline_stack(address, stack) {
+                break;
+            }
+        }
+        true
+    }
+}
+
+fn align_to(offset: usize, alignment: usize) -> usize {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+struct Cursor<'data> {
+    data: &'data [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl<'data> Cursor<'data> {
+    fn new(data: &'data [u8], offset: usize, big_endian: bool) -> Self {
+        Self {
+            data,
+            offset,
+            big_endian,
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'data [u8]> {
+        let bytes = self.data.get(self.offset..)?.get(..len)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let mut bytes: [u8; N] = self.bytes(N)?.try_into().ok()?;
+        if self.big_endian {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.array()?))
+    }
+
+    fn uleb128(&mut self) -> Option<u64> {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                result |= u64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+    }
+
+    fn sleb128(&mut self) -> Option<i64> {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                result |= i64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1 << shift;
+                }
+                return Some(result);
+            }
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use std::borrow::Cow;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, RwLock};
 
 use debugid::DebugId;
 use linux_perf_data::jitdump::{
-    JitCodeDebugInfoRecord, JitCodeLoadRecord, JitDumpReader, JitDumpRecord, JitDumpRecordHeader,
-    JitDumpRecordType,
+    JitCodeDebugInfoRecord, JitCodeLoadRecord, JitDumpHeader, JitDumpRawRecord, JitDumpReader,
+    JitDumpRecord, JitDumpRecordHeader, JitDumpRecordType,
 };
 use linux_perf_data::linux_perf_event_reader::RawData;
 use linux_perf_data::Endianness;
@@ -18,7 +18,7 @@ use crate::shared::{
     FileContents, FileContentsCursor, FileContentsWrapper, FrameDebugInfo, FramesLookupResult,
     LookupAddress, SourceFilePath, SymbolInfo,
 };
-use crate::symbol_map::{GetInnerSymbolMap, SymbolMap, SymbolMapTrait};
+use crate::symbol_map::{GetInnerSymbolMap, SymbolFileKind, SymbolMap, SymbolMapTrait};
 use crate::{FileAndPathHelper, SyncAddressInfo};
 
 pub fn is_jitdump_file<T: FileContents>(file_contents: &FileContentsWrapper<T>) -> bool {
@@ -62,11 +62,13 @@ impl JitDumpIndex {
         let (debug_id, _code_id_bytes) =
             debug_id_and_code_id_for_jitdump(header.pid, header.timestamp, header.elf_machine_arch);
 
-        let endian = reader.endian();
+        let mut index = Self {
+            endian: reader.endian(),
+            entries: Vec::new(),
+            relative_addresses: Vec::new(),
+            debug_id,
+        };
 
-        let mut entries = Vec::new();
-        let mut relative_addresses = Vec::new();
-        let mut cumulative_address = 0;
         let mut offset_and_len_of_pending_debug_record = None;
         while let Some(record_header) = reader.next_record_header()? {
             match record_header.record_type {
@@ -80,18 +82,11 @@ impl JitDumpIndex {
                     };
                     let code_debug_info_record_offset_and_len =
                         offset_and_len_of_pending_debug_record.take();
-                    let relative_address = cumulative_address;
-                    cumulative_address += record.code_bytes.len() as u32;
-
-                    entries.push(JitDumpIndexEntry {
-                        code_load_record_offset: raw_record.start_offset,
-                        code_bytes_offset: raw_record.start_offset
-                            + record.code_bytes_offset_from_record_header_start() as u64,
-                        name_len: record.function_name.len() as u32,
+                    index.add_entry(JitDumpIndexEntry::for_code_load_record(
+                        &raw_record,
+                        &record,
                         code_debug_info_record_offset_and_len,
-                        code_bytes_len: record.code_bytes.len() as u64,
-                    });
-                    relative_addresses.push(relative_address);
+                    ));
                 }
                 JitDumpRecordType::JIT_CODE_DEBUG_INFO => {
                     let offset = reader.next_record_offset();
@@ -109,12 +104,20 @@ impl JitDumpIndex {
                 }
             }
         }
-        Ok(Self {
-            endian,
-            entries,
-            relative_addresses,
-            debug_id,
-        })
+        Ok(index)
+    }
+
+    /// Appends an entry for a code load record. Each function's code is placed
+    /// right after the previous function's code in the relative address space.
+    fn add_entry(&mut self, entry: JitDumpIndexEntry) {
+        let relative_address = match (self.relative_addresses.last(), self.entries.last()) {
+            (Some(last_address), Some(last_entry)) => {
+                last_address + last_entry.code_bytes_len as u32
+            }
+            _ => 0,
+        };
+        self.entries.push(entry);
+        self.relative_addresses.push(relative_address);
     }
 
     /// Returns (entry index, entry relative address, offset from entry start)
@@ -163,6 +166,23 @@ pub struct JitDumpIndexEntry {
     pub code_bytes_len: u64,
 }
 
+impl JitDumpIndexEntry {
+    fn for_code_load_record(
+        raw_record: &JitDumpRawRecord,
+        record: &JitCodeLoadRecord,
+        code_debug_info_record_offset_and_len: Option<(u64, u32)>,
+    ) -> Self {
+        JitDumpIndexEntry {
+            code_load_record_offset: raw_record.start_offset,
+            code_bytes_offset: raw_record.start_offset
+                + record.code_bytes_offset_from_record_header_start() as u64,
+            name_len: record.function_name.len() as u32,
+            code_debug_info_record_offset_and_len,
+            code_bytes_len: record.code_bytes.len() as u64,
+        }
+    }
+}
+
 pub fn get_symbol_map_for_jitdump<H: FileAndPathHelper>(
     file_contents: FileContentsWrapper<H::F>,
     file_location: H::FL,
@@ -171,7 +191,11 @@ pub fn get_symbol_map_for_jitdump<H: FileAndPathHelper>(
     let symbol_map = JitDumpSymbolMap(Yoke::attach_to_cart(Box::new(outer), |outer| {
         outer.make_symbol_map()
     }));
-    Ok(SymbolMap::new_plain(file_location, Box::new(symbol_map)))
+    Ok(SymbolMap::new_plain(
+        file_location,
+        SymbolFileKind::Jitdump,
+        Box::new(symbol_map),
+    ))
 }
 
 pub struct JitDumpSymbolMap<T: FileContents>(
@@ -319,6 +343,19 @@ impl<T: FileContents> SymbolMapTrait for JitDumpSymbolMapInner<'_, T> {
         Box::new(iter)
     }
 
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        let iter = (0..self.symbol_count()).filter_map(move |i| {
+            let address = self.index.relative_addresses[i];
+            let size = self.index.entries[i].code_bytes_len as u32;
+            let mut cache = self.cache.lock().unwrap();
+            let name = cache.get_function_name(i)?;
+            Some((address, Some(size), String::from_utf8_lossy(name)))
+        });
+        Box::new(iter)
+    }
+
     fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
         let (index, symbol_address, offset_from_symbol) = match address {
             LookupAddress::Relative(address) => self.index.lookup_relative_address(address)?,
@@ -331,3 +368,200 @@ impl<T: FileContents> SymbolMapTrait for JitDumpSymbolMapInner<'_, T> {
         self.lookup_by_entry_index(index, symbol_address, offset_from_symbol)
     }
 }
+
+/// A symbol map for a jitdump file which is still being written, for example
+/// by a process which is being profiled.
+///
+/// Instead of parsing a complete file, the records are fed in one by one with
+/// [`add_record`](Self::add_record), in the order in which they appear in the
+/// file. The symbols get the same relative addresses as they would in a symbol
+/// map for the complete file, so addresses stay valid as more records come in.
+///
+/// Cloning is cheap, and clones share the same data. This makes it possible to
+/// keep adding records to one clone while another clone is used for lookups,
+/// for example via [`to_symbol_map`](Self::to_symbol_map).
+///
+/// Addresses which aren't covered by any of the records added so far return
+/// `None` from lookups. Once a code load record for the address is added,
+/// lookups of the same address succeed.
+#[derive(Debug, Clone)]
+pub struct IncrementalJitDumpSymbolMap {
+    data: Arc<RwLock<IncrementalJitDumpData>>,
+}
+
+#[derive(Debug)]
+struct IncrementalJitDumpData {
+    index: JitDumpIndex,
+    names: Vec<String>,
+    debug_infos: Vec<Option<OwnedJitCodeDebugInfo>>,
+    /// A debug info record describes the code load record which follows it.
+    pending_debug_info: Option<((u64, u32), OwnedJitCodeDebugInfo)>,
+}
+
+#[derive(Debug)]
+struct OwnedJitCodeDebugInfo {
+    code_addr: u64,
+    /// (code address, line, file path), sorted by code address.
+    entries: Vec<(u64, u32, String)>,
+}
+
+impl OwnedJitCodeDebugInfo {
+    fn new(record: &JitCodeDebugInfoRecord) -> Self {
+        let entries = record
+            .entries
+            .iter()
+            .map(|entry| {
+                let file_path = String::from_utf8_lossy(&entry.file_path.as_slice()).into_owned();
+                (entry.code_addr, entry.line, file_path)
+            })
+            .collect();
+        Self {
+            code_addr: record.code_addr,
+            entries,
+        }
+    }
+
+    fn lookup(&self, addr: u64) -> Option<&(u64, u32, String)> {
+        let index = match self.entries.binary_search_by_key(&addr, |entry| entry.0) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        Some(&self.entries[index])
+    }
+}
+
+impl IncrementalJitDumpSymbolMap {
+    /// Creates an empty symbol map for the jitdump file with this header.
+    ///
+    /// The debug ID is the same as the one of a symbol map for the complete file.
+    pub fn new(header: &JitDumpHeader) -> Self {
+        let (debug_id, _code_id_bytes) =
+            debug_id_and_code_id_for_jitdump(header.pid, header.timestamp, header.elf_machine_arch);
+        let endian = match &header.magic {
+            b"JiTD" => Endianness::BigEndian,
+            _ => Endianness::LittleEndian,
+        };
+        let index = JitDumpIndex {
+            endian,
+            entries: Vec::new(),
+            relative_addresses: Vec::new(),
+            debug_id,
+        };
+        let data = IncrementalJitDumpData {
+            index,
+            names: Vec::new(),
+            debug_infos: Vec::new(),
+            pending_debug_info: None,
+        };
+        Self {
+            data: Arc::new(RwLock::new(data)),
+        }
+    }
+
+    /// Adds the information from the next record of the jitdump file.
+    ///
+    /// Code load records add a symbol, and debug info records add line
+    /// information for the code load record which follows them. Other record
+    /// types are ignored.
+    pub fn add_record(&self, raw_record: &JitDumpRawRecord) -> Result<(), std::io::Error> {
+        let mut data = self.data.write().unwrap();
+        match raw_record.parse()? {
+            JitDumpRecord::CodeLoad(record) => {
+                let pending_debug_info = data.pending_debug_info.take();
+                let (debug_record_offset_and_len, debug_info) = match pending_debug_info {
+                    Some((offset_and_len, debug_info)) => (Some(offset_and_len), Some(debug_info)),
+                    None => (None, None),
+                };
+                data.index
+                    .add_entry(JitDumpIndexEntry::for_code_load_record(
+                        raw_record,
+                        &record,
+                        debug_record_offset_and_len,
+                    ));
+                let name = String::from_utf8_lossy(&record.function_name.as_slice()).into_owned();
+                data.names.push(name);
+                data.debug_infos.push(debug_info);
+            }
+            JitDumpRecord::CodeDebugInfo(record) => {
+                let offset_and_len = (raw_record.start_offset, raw_record.record_size);
+                data.pending_debug_info =
+                    Some((offset_and_len, OwnedJitCodeDebugInfo::new(&record)));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns a [`SymbolMap`] which shares its data with this object, so
+    /// it sees the records that are added afterwards.
+    pub fn to_symbol_map<H: FileAndPathHelper>(&self, file_location: H::FL) -> SymbolMap<H> {
+        let mut symbol_map =
+            SymbolMap::with_symbol_map_trait(file_location, Arc::new(self.clone()));
+        symbol_map.set_symbol_file_kind(SymbolFileKind::Jitdump);
+        symbol_map
+    }
+}
+
+impl SymbolMapTrait for IncrementalJitDumpSymbolMap {
+    fn debug_id(&self) -> debugid::DebugId {
+        self.data.read().unwrap().index.debug_id
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.data.read().unwrap().index.relative_addresses.len()
+    }
+
+    fn iter_symbols(&self) -> Box<dyn Iterator<Item = (u32, Cow<'_, str>)> + '_> {
+        Box::new(
+            self.iter_symbols_with_sizes()
+                .map(|(address, _size, name)| (address, name)),
+        )
+    }
+
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        // Take a snapshot, so that we don't hold the lock while the caller iterates.
+        let data = self.data.read().unwrap();
+        let symbols: Vec<_> = (0..data.names.len())
+            .map(|i| {
+                let address = data.index.relative_addresses[i];
+                let size = data.index.entries[i].code_bytes_len as u32;
+                (address, Some(size), Cow::Owned(data.names[i].clone()))
+            })
+            .collect();
+        Box::new(symbols.into_iter())
+    }
+
+    fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
+        let data = self.data.read().unwrap();
+        let (index, symbol_address, offset_from_symbol) = match address {
+            LookupAddress::Relative(address) => data.index.lookup_relative_address(address)?,
+            LookupAddress::Svma(_) => {
+                // SVMAs are not meaningful for JitDump files.
+                return None;
+            }
+            LookupAddress::FileOffset(offset) => data.index.lookup_offset(offset)?,
+        };
+        let name = data.names[index].clone();
+        let frames = data.debug_infos[index].as_ref().and_then(|debug_info| {
+            let lookup_avma = debug_info.code_addr + offset_from_symbol;
+            let (_, line, file_path) = debug_info.lookup(lookup_avma)?;
+            let frame = FrameDebugInfo {
+                function: Some(name.clone()),
+                file_path: Some(SourceFilePath::new(file_path.clone(), None)),
+                line_number: Some(*line),
+            };
+            Some(FramesLookupResult::Available(vec![frame]))
+        });
+        Some(SyncAddressInfo {
+            symbol: SymbolInfo {
+                address: symbol_address,
+                size: Some(data.index.entries[index].code_bytes_len as u32),
+                name,
+            },
+            frames,
+        })
+    }
+}
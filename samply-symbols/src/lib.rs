@@ -48,13 +48,15 @@
 //! # Supported formats and data
 //!
 //! This crate supports obtaining symbol data from PE binaries (Windows), PDB files (Windows),
-//! mach-o binaries (including fat binaries) (macOS & iOS), and ELF binaries (Linux, Android, etc.).
+//! mach-o binaries (including fat binaries) (macOS & iOS), ELF binaries (Linux, Android, etc.),
+//! WebAssembly modules (function names from the "name" section, with code section relative addresses),
+//! and COFF object files (function names from the symbol table, with file offsets as addresses).
 //! For mach-o files it also supports finding debug information in external objects, by following
 //! OSO stabs entries.
 //! It supports gathering both basic symbol information (function name strings) as well as information
 //! based on debug data, i.e. inline callstacks where each frame has a function name, a file name,
 //! and a line number.
-//! For debug data we support both DWARF debug data (inside mach-o and ELF binaries) and PDB debug data.
+//! For debug data we support both DWARF debug data (inside mach-o, ELF and Wasm binaries) and PDB debug data.
 //!
 //! # Example
 //!
@@ -192,8 +194,9 @@
 //!         Some(Self(self.0.with_extension("symindex")))
 //!     }
 //!
-//!     fn location_for_dwo(&self, _comp_dir: &str, path: &str) -> Option<Self> {
-//!         Some(Self(path.into()))
+//!     fn location_for_dwo(&self, comp_dir: &str, path: &str) -> Option<Self> {
+//!         // Relative DWO paths are relative to the compilation directory.
+//!         Some(Self(std::path::Path::new(comp_dir).join(path)))
 //!     }
 //!
 //!     fn location_for_dwp(&self) -> Option<Self> {
@@ -204,62 +207,77 @@
 //! }
 //! ```
 
+use std::ops::Range;
 use std::sync::Arc;
 
 use binary_image::BinaryImageInner;
+use debugid::DebugId;
 use jitdump::JitDumpIndex;
 use linux_perf_data::jitdump::JitDumpReader;
 use object::read::FileKind;
 pub use pdb_addr2line::pdb;
 use shared::FileContentsCursor;
-pub use {debugid, object};
+use symbol_map_cache::SymbolMapCache;
+pub use {debugid, linux_perf_data, object};
 
 mod binary_image;
 mod breakpad;
 mod cache;
 mod chunked_read_buffer_manager;
+mod coff;
 mod compact_symbol_table;
 mod debugid_util;
 mod demangle;
 mod demangle_ocaml;
+#[cfg(feature = "swift_demangle")]
+mod demangle_swift;
 mod dwarf;
 mod elf;
 mod error;
 mod external_file;
+mod gsym;
 mod jitdump;
 mod macho;
 mod mapped_path;
 mod path_mapper;
 mod shared;
 mod symbol_map;
+mod symbol_map_cache;
 mod symbol_map_object;
+mod wasm;
 mod windows;
 
 pub use crate::binary_image::{BinaryImage, CodeByteReadingError};
 pub use crate::breakpad::{
-    BreakpadIndex, BreakpadIndexParser, BreakpadParseError, BreakpadSymindexParseError,
+    write_breakpad_sym, BreakpadIndex, BreakpadIndexParser, BreakpadModuleInfo, BreakpadParseError,
+    BreakpadSymindexParseError,
+};
+pub use crate::cache::{
+    ChunkedCachingStats, FileByteSource, FileContentsWithChunkedCaching, DEFAULT_CHUNK_SIZE,
 };
-pub use crate::cache::{FileByteSource, FileContentsWithChunkedCaching};
 pub use crate::compact_symbol_table::CompactSymbolTable;
-pub use crate::debugid_util::{debug_id_for_object, DebugIdExt};
-pub use crate::demangle::demangle_any;
-pub use crate::error::Error;
+pub use crate::debugid_util::{debug_id_for_binary_data, debug_id_for_object, DebugIdExt};
+pub use crate::demangle::{demangle_any, demangle_with_options, DemangleOptions};
+pub use crate::error::{Error, FileLoadTimeoutError};
 pub use crate::external_file::{load_external_file, ExternalFileSymbolMap};
-pub use crate::jitdump::debug_id_and_code_id_for_jitdump;
+pub use crate::jitdump::{debug_id_and_code_id_for_jitdump, IncrementalJitDumpSymbolMap};
 pub use crate::macho::FatArchiveMember;
 pub use crate::mapped_path::MappedPath;
 pub use crate::shared::{
     relative_address_base, AddressInfo, CandidatePathInfo, CodeId, ElfBuildId,
     ExternalFileAddressInFileRef, ExternalFileAddressRef, ExternalFileRef, FileAndPathHelper,
     FileAndPathHelperError, FileAndPathHelperResult, FileContents, FileContentsWrapper,
-    FileLocation, FrameDebugInfo, FramesLookupResult, LibraryInfo, LookupAddress,
-    MultiArchDisambiguator, OptionallySendFuture, PeCodeId, SourceFilePath, SymbolInfo,
-    SyncAddressInfo,
+    FileLocation, FrameDebugInfo, FramesLookupResult, LibraryInfo, LineRange, LookupAddress,
+    MultiArchDisambiguator, OptionallySendFuture, PeCodeId, SourceFileInfo, SourceFilePath,
+    SymbolInfo, SyncAddressInfo,
 };
-pub use crate::symbol_map::{SymbolMap, SymbolMapTrait};
+pub use crate::symbol_map::{SymbolFileKind, SymbolMap, SymbolMapTrait};
 
 pub struct SymbolManager<H: FileAndPathHelper> {
     helper: Arc<H>,
+    demangle_options: DemangleOptions,
+    best_effort_function_ranges: bool,
+    symbol_map_cache: SymbolMapCache<H>,
 }
 
 impl<H, F, FL> SymbolManager<H>
@@ -272,9 +290,54 @@ where
     pub fn with_helper(helper: H) -> Self {
         Self {
             helper: Arc::new(helper),
+            demangle_options: DemangleOptions::default(),
+            best_effort_function_ranges: false,
+            symbol_map_cache: SymbolMapCache::new(0),
         }
     }
 
+    /// Sets the options for demangling symbol names in the symbol maps which
+    /// are loaded from now on. The default demangles Rust and C++ names.
+    ///
+    /// This does not affect Breakpad symbol files, which contain demangled
+    /// names, and symbol maps from [`FileAndPathHelper::get_symbol_map_for_library`].
+    pub fn set_demangle_options(&mut self, demangle_options: DemangleOptions) {
+        self.demangle_options = demangle_options;
+    }
+
+    /// The demangle options for symbol maps of files which aren't mach-O files.
+    /// Swift names are only demangled in mach-O files.
+    fn non_macho_demangle_options(&self) -> DemangleOptions {
+        DemangleOptions {
+            swift: false,
+            ..self.demangle_options
+        }
+    }
+
+    /// Sets whether function ranges should be synthesized from unwind info in
+    /// stripped ELF files, on a best-effort basis.
+    ///
+    /// If enabled, ELF files without a symbol table also get a `fun_<address>`
+    /// symbol for every function described by the FDEs in `.debug_frame`, in
+    /// addition to the ones from `.eh_frame`. This gives correct function
+    /// grouping for stripped libraries whose unwind info is only in
+    /// `.debug_frame`. Files with a symbol table are not affected.
+    ///
+    /// The default is `false`.
+    pub fn set_best_effort_function_ranges(&mut self, enabled: bool) {
+        self.best_effort_function_ranges = enabled;
+    }
+
+    /// Sets how many symbol maps [`SymbolManager::load_symbol_map`] keeps around,
+    /// so that repeated calls for the same library don't need to load and parse
+    /// the symbol file again. Symbol maps are cached by debug name and debug ID,
+    /// and the least recently used symbol map is evicted first.
+    ///
+    /// The default is 0, i.e. no symbol maps are cached.
+    pub fn set_symbol_map_cache_capacity(&mut self, capacity: usize) {
+        self.symbol_map_cache.set_capacity(capacity);
+    }
+
     /// Exposes the helper.
     pub fn helper(&self) -> Arc<H> {
         self.helper.clone()
@@ -285,14 +348,40 @@ where
         debug_file_location: &H::FL,
         source_file_path: &SourceFilePath,
     ) -> Result<String, Error> {
-        let source_file_location = debug_file_location
+        let mut candidate_locations: Vec<H::FL> = debug_file_location
             .location_for_source_file(source_file_path.raw_path())
-            .ok_or(Error::FileLocationRefusedSourceFileLocation)?;
+            .into_iter()
+            .collect();
+        candidate_locations.extend(
+            self.helper
+                .get_fallback_locations_for_source_file(debug_file_location, source_file_path)
+                .unwrap_or_default(),
+        );
+
+        let mut last_err = Error::FileLocationRefusedSourceFileLocation;
+        for source_file_location in candidate_locations {
+            match self
+                .load_source_file_at_location(source_file_location)
+                .await
+            {
+                Ok(source) => return Ok(source),
+                Err(e) => last_err = e,
+            }
+        }
+        Err(last_err)
+    }
+
+    async fn load_source_file_at_location(
+        &self,
+        source_file_location: H::FL,
+    ) -> Result<String, Error> {
         let file_contents = self
             .helper
             .load_file(source_file_location.clone())
             .await
-            .map_err(|e| Error::HelperErrorDuringOpenFile(source_file_location.to_string(), e))?;
+            .map_err(|e| {
+                Error::helper_error_during_open_file(source_file_location.to_string(), e)
+            })?;
         let file_contents = file_contents
             .read_bytes_at(0, file_contents.len())
             .map_err(|e| {
@@ -317,6 +406,17 @@ where
             None => return Err(Error::NotEnoughInformationToIdentifySymbolMap),
         };
 
+        let cache_key = library_info
+            .debug_name
+            .clone()
+            .map(|debug_name| (debug_name, debug_id));
+        if let Some(symbol_map) = cache_key
+            .as_ref()
+            .and_then(|key| self.symbol_map_cache.get(key))
+        {
+            return Ok(symbol_map);
+        }
+
         let candidate_paths = self
             .helper
             .get_candidate_paths_for_debug_file(library_info)
@@ -337,6 +437,16 @@ where
                     )
                     .await
                 }
+                CandidatePathInfo::Url(url) => match self.helper.location_for_url(&url) {
+                    Some(file_location) => {
+                        self.load_symbol_map_from_location(
+                            file_location,
+                            Some(MultiArchDisambiguator::DebugId(debug_id)),
+                        )
+                        .await
+                    }
+                    None => Err(Error::HelperRefusedUrl(url)),
+                },
                 CandidatePathInfo::InDyldCache {
                     dyld_cache_path,
                     dylib_path,
@@ -345,13 +455,19 @@ where
                         dyld_cache_path,
                         dylib_path,
                         &*self.helper,
+                        self.demangle_options,
                     )
                     .await
                 }
             };
 
             match symbol_map {
-                Ok(symbol_map) if symbol_map.debug_id() == debug_id => return Ok(symbol_map),
+                Ok(symbol_map) if symbol_map.debug_id() == debug_id => {
+                    if let Some(key) = cache_key {
+                        self.symbol_map_cache.insert(key, symbol_map.clone());
+                    }
+                    return Ok(symbol_map);
+                }
                 Ok(symbol_map) => {
                     all_errors.push(Error::UnmatchedDebugId(symbol_map.debug_id(), debug_id));
                 }
@@ -387,8 +503,13 @@ where
         let external_file_location = debug_file_location
             .location_for_external_object_file(external_file_path)
             .ok_or(Error::FileLocationRefusedExternalObjectLocation)?;
-        external_file::load_external_file(&*self.helper, external_file_location, external_file_path)
-            .await
+        external_file::load_external_file(
+            &*self.helper,
+            external_file_location,
+            external_file_path,
+            self.demangle_options,
+        )
+        .await
     }
 
     async fn load_binary_from_dyld_cache(
@@ -399,6 +520,51 @@ where
         macho::load_binary_from_dyld_cache(dyld_cache_path, dylib_path, &*self.helper).await
     }
 
+    /// Symbolicates an address in the address space of a process, given the
+    /// modules of that process and the address ranges they're mapped at.
+    ///
+    /// This finds the module whose range contains the address, loads its symbol
+    /// map with [`load_symbol_map`](SymbolManager::load_symbol_map), and looks up
+    /// the address relative to the module. If the module's
+    /// [`base_address`](LibraryInfo::base_address) is set, the relative address is
+    /// computed from it, otherwise the start of the range is used as the base.
+    ///
+    /// To avoid loading the module's symbol file again for every address, set a
+    /// cache capacity with [`set_symbol_map_cache_capacity`](SymbolManager::set_symbol_map_cache_capacity).
+    ///
+    /// Returns `None` if no module contains the address, if the symbol map could
+    /// not be loaded, or if there is no symbol for the address.
+    pub async fn symbolicate_address(
+        &self,
+        modules: &[(LibraryInfo, Range<u64>)],
+        absolute_address: u64,
+    ) -> Option<AddressInfo> {
+        let (info, range) = modules
+            .iter()
+            .find(|(_, range)| range.contains(&absolute_address))?;
+        let relative_address = match info.base_address {
+            Some(_) => info.relative_address(absolute_address)?,
+            None => u32::try_from(absolute_address - range.start).ok()?,
+        };
+        let symbol_map = self.load_symbol_map(info).await.ok()?;
+        symbol_map
+            .lookup(LookupAddress::Relative(relative_address))
+            .await
+    }
+
+    /// Computes the debug ID of the binary for the given (partial) [`LibraryInfo`],
+    /// for example so that a missing `debug_id` can be filled in before calling
+    /// [`load_symbol_map`](SymbolManager::load_symbol_map).
+    ///
+    /// The binary is found in the same way as in [`load_binary`](SymbolManager::load_binary),
+    /// and the debug ID is read from its headers, see [`debug_id_for_object`].
+    pub async fn debug_id_for_binary(&self, info: &LibraryInfo) -> Result<DebugId, Error> {
+        let binary = self.load_binary(info).await?;
+        binary
+            .debug_id()
+            .ok_or(Error::InvalidInputError("debug ID cannot be read"))
+    }
+
     /// Returns the binary for the given (partial) [`LibraryInfo`].
     ///
     /// This consults the helper to get candidate paths to the binary.
@@ -431,6 +597,18 @@ where
                     )
                     .await
                 }
+                CandidatePathInfo::Url(url) => match self.helper.location_for_url(&url) {
+                    Some(file_location) => {
+                        self.load_binary_at_location(
+                            file_location,
+                            info.name.clone(),
+                            None,
+                            disambiguator.clone(),
+                        )
+                        .await
+                    }
+                    None => Err(Error::HelperRefusedUrl(url)),
+                },
                 CandidatePathInfo::InDyldCache {
                     dyld_cache_path,
                     dylib_path,
@@ -525,6 +703,7 @@ where
                 dyld_cache_path,
                 dylib_path.to_owned(),
                 &*self.helper,
+                self.demangle_options,
             )
             .await;
             match (&multi_arch_disambiguator, symbol_map_res) {
@@ -548,12 +727,84 @@ where
         &self,
         file_location: FL,
         multi_arch_disambiguator: Option<MultiArchDisambiguator>,
+    ) -> Result<SymbolMap<H>, Error> {
+        self.load_symbol_map_from_location_impl(file_location, multi_arch_disambiguator)
+            .await
+    }
+
+    /// For a mach-O binary without DWARF, returns a symbol map for the dSYM
+    /// bundle next to it, if there is one. If there is no dSYM, or if the binary
+    /// has its own DWARF, `binary_symbol_map` is returned.
+    ///
+    /// Release builds often only have their DWARF in the dSYM, and the binary
+    /// only has a (possibly stripped) symbol table. Binaries without an `LC_UUID`
+    /// command can't be matched to a dSYM, because the debug ID of the dSYM
+    /// won't match the one which we derive from the binary's contents.
+    ///
+    /// Returns an error if the dSYM exists but can't be parsed, or if its UUID
+    /// doesn't match the binary.
+    async fn prefer_dsym_for_macho(
+        &self,
+        binary_location: &FL,
+        binary_has_debug_info: bool,
+        binary_symbol_map: SymbolMap<H>,
+    ) -> Result<SymbolMap<H>, Error> {
+        if binary_has_debug_info {
+            return Ok(binary_symbol_map);
+        }
+        let Some(dsym_location) = binary_location.location_for_dsym() else {
+            return Ok(binary_symbol_map);
+        };
+        let Ok(file_contents) = self.helper.load_file(dsym_location.clone()).await else {
+            // There is no dSYM.
+            return Ok(binary_symbol_map);
+        };
+        let debug_id = binary_symbol_map.debug_id();
+        let file_contents = FileContentsWrapper::new(file_contents);
+        let file_kind = FileKind::parse(&file_contents)
+            .map_err(|_| Error::InvalidInputError("Could not determine the dSYM's file kind"))?;
+        let dsym_symbol_map = match file_kind {
+            FileKind::MachOFat32 | FileKind::MachOFat64 => {
+                let member = macho::get_fat_archive_member(
+                    &file_contents,
+                    file_kind,
+                    Some(MultiArchDisambiguator::DebugId(debug_id)),
+                )?;
+                macho::get_symbol_map_for_fat_archive_member(
+                    dsym_location,
+                    file_contents,
+                    member,
+                    self.helper(),
+                    self.demangle_options,
+                )?
+            }
+            FileKind::MachO32 | FileKind::MachO64 => macho::get_symbol_map_for_macho(
+                dsym_location,
+                file_contents,
+                self.helper(),
+                self.demangle_options,
+            )?,
+            _ => return Err(Error::InvalidInputError("The dSYM is not a mach-O file")),
+        };
+        if dsym_symbol_map.debug_id() != debug_id {
+            return Err(Error::UnmatchedDebugId(
+                dsym_symbol_map.debug_id(),
+                debug_id,
+            ));
+        }
+        Ok(dsym_symbol_map)
+    }
+
+    async fn load_symbol_map_from_location_impl(
+        &self,
+        file_location: FL,
+        multi_arch_disambiguator: Option<MultiArchDisambiguator>,
     ) -> Result<SymbolMap<H>, Error> {
         let file_contents = self
             .helper
             .load_file(file_location.clone())
             .await
-            .map_err(|e| Error::HelperErrorDuringOpenFile(file_location.to_string(), e))?;
+            .map_err(|e| Error::helper_error_during_open_file(file_location.to_string(), e))?;
 
         let file_contents = FileContentsWrapper::new(file_contents);
 
@@ -565,6 +816,8 @@ where
                         file_contents,
                         file_kind,
                         self.helper(),
+                        self.non_macho_demangle_options(),
+                        self.best_effort_function_ranges,
                     )
                     .await
                 }
@@ -574,15 +827,29 @@ where
                         file_kind,
                         multi_arch_disambiguator,
                     )?;
-                    macho::get_symbol_map_for_fat_archive_member(
-                        file_location,
+                    let has_debug_info =
+                        macho::macho_has_debug_info(&file_contents, member.offset_and_size);
+                    let symbol_map = macho::get_symbol_map_for_fat_archive_member(
+                        file_location.clone(),
                         file_contents,
                         member,
                         self.helper(),
-                    )
+                        self.demangle_options,
+                    )?;
+                    self.prefer_dsym_for_macho(&file_location, has_debug_info, symbol_map)
+                        .await
                 }
                 FileKind::MachO32 | FileKind::MachO64 => {
-                    macho::get_symbol_map_for_macho(file_location, file_contents, self.helper())
+                    let has_debug_info =
+                        macho::macho_has_debug_info(&file_contents, (0, file_contents.len()));
+                    let symbol_map = macho::get_symbol_map_for_macho(
+                        file_location.clone(),
+                        file_contents,
+                        self.helper(),
+                        self.demangle_options,
+                    )?;
+                    self.prefer_dsym_for_macho(&file_location, has_debug_info, symbol_map)
+                        .await
                 }
                 FileKind::Pe32 | FileKind::Pe64 => {
                     match windows::load_symbol_map_for_pdb_corresponding_to_binary(
@@ -590,6 +857,7 @@ where
                         &file_contents,
                         file_location.clone(),
                         &*self.helper,
+                        self.non_macho_demangle_options(),
                     )
                     .await
                     {
@@ -599,15 +867,31 @@ where
                             file_kind,
                             file_location,
                             self.helper(),
+                            self.non_macho_demangle_options(),
                         ),
                     }
                 }
+                FileKind::Wasm => wasm::get_symbol_map_for_wasm(
+                    file_contents,
+                    file_location,
+                    self.non_macho_demangle_options(),
+                ),
+                FileKind::Coff | FileKind::CoffBig => coff::get_symbol_map_for_coff(
+                    file_contents,
+                    file_kind,
+                    file_location,
+                    self.non_macho_demangle_options(),
+                ),
                 _ => Err(Error::InvalidInputError(
-                    "Input was Archive, Coff or Wasm format, which are unsupported for now",
+                    "Input was Archive format, which is unsupported for now",
                 )),
             }
         } else if windows::is_pdb_file(&file_contents) {
-            windows::get_symbol_map_for_pdb(file_contents, file_location)
+            windows::get_symbol_map_for_pdb(
+                file_contents,
+                file_location,
+                self.non_macho_demangle_options(),
+            )
         } else if breakpad::is_breakpad_file(&file_contents) {
             let index_file_contents =
                 if let Some(index_file_location) = file_location.location_for_breakpad_symindex() {
@@ -621,9 +905,19 @@ where
                 };
             let symbol_map =
                 breakpad::get_symbol_map_for_breakpad_sym(file_contents, index_file_contents)?;
-            Ok(SymbolMap::new_plain(file_location, Box::new(symbol_map)))
+            Ok(SymbolMap::new_plain(
+                file_location,
+                SymbolFileKind::Breakpad,
+                Box::new(symbol_map),
+            ))
         } else if jitdump::is_jitdump_file(&file_contents) {
             jitdump::get_symbol_map_for_jitdump(file_contents, file_location)
+        } else if gsym::is_gsym_file(&file_contents) {
+            gsym::get_symbol_map_for_gsym(
+                file_contents,
+                file_location,
+                self.non_macho_demangle_options(),
+            )
         } else {
             Err(Error::InvalidInputError(
             "The file does not have a known format; PDB::open was not able to parse it and object::FileKind::parse was not able to detect the format.",
@@ -642,7 +936,7 @@ where
             .helper
             .load_file(file_location.clone())
             .await
-            .map_err(|e| Error::HelperErrorDuringOpenFile(file_location.to_string(), e))?;
+            .map_err(|e| Error::helper_error_during_open_file(file_location.to_string(), e))?;
 
         let file_contents = FileContentsWrapper::new(file_contents);
 
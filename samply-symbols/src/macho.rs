@@ -3,27 +3,30 @@ use std::sync::Arc;
 
 use debugid::DebugId;
 use macho_unwind_info::UnwindInfo;
+use object::endian::{U32, U64};
 use object::macho::{self, LinkeditDataCommand, MachHeader32, MachHeader64};
 use object::read::macho::{
-    FatArch, LoadCommandIterator, MachHeader, MachOFatFile32, MachOFatFile64,
+    DyldSubCacheSlice, FatArch, LoadCommandIterator, MachHeader, MachOFatFile32, MachOFatFile64,
+    Nlist,
 };
-use object::read::{File, Object, ObjectSection};
-use object::{Endianness, FileKind, ReadRef};
+use object::read::{File, Object, ObjectSection, StringTable};
+use object::{Endian, Endianness, FileKind, Pod, ReadRef, SectionKind};
 use uuid::Uuid;
 use yoke::Yoke;
 use yoke_derive::Yokeable;
 
 use crate::binary_image::{BinaryImage, BinaryImageInner};
 use crate::debugid_util::debug_id_for_object;
+use crate::demangle::DemangleOptions;
 use crate::dwarf::Addr2lineContextData;
 use crate::error::Error;
 use crate::shared::{
-    FileAndPathHelper, FileContents, FileContentsWrapper, FileLocation, MultiArchDisambiguator,
-    RangeReadRef,
+    relative_address_base, FileAndPathHelper, FileContents, FileContentsWrapper, FileLocation,
+    MultiArchDisambiguator, RangeReadRef,
 };
-use crate::symbol_map::SymbolMap;
+use crate::symbol_map::{SymbolFileKind, SymbolMap};
 use crate::symbol_map_object::{
-    ObjectSymbolMap, ObjectSymbolMapInnerWrapper, ObjectSymbolMapOuter,
+    AdditionalSymbol, ObjectSymbolMap, ObjectSymbolMapInnerWrapper, ObjectSymbolMapOuter,
 };
 
 /// Converts a cpu type/subtype pair into the architecture name.
@@ -120,6 +123,19 @@ pub fn get_fat_archive_members_impl<FC: FileContents, FA: FatArch>(
     Ok(members)
 }
 
+/// Returns whether the mach-O image at `offset_and_size` in `file_contents`
+/// contains DWARF debug info. This is the case for dSYMs and for binaries which
+/// were linked with their debug info.
+pub fn macho_has_debug_info(
+    file_contents: &FileContentsWrapper<impl FileContents>,
+    (start, size): (u64, u64),
+) -> bool {
+    match File::parse(file_contents.range(start, size)) {
+        Ok(file) => file.section_by_name("__debug_info").is_some(),
+        Err(_) => false,
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FatArchiveMember {
     pub offset_and_size: (u64, u64),
@@ -223,7 +239,7 @@ where
         self.helper
             .load_file(self.dyld_cache_path.clone())
             .await
-            .map_err(|e| Error::HelperErrorDuringOpenFile(self.dyld_cache_path.to_string(), e))
+            .map_err(|e| Error::helper_error_during_open_file(self.dyld_cache_path.to_string(), e))
     }
 
     pub async fn load_subcache(&self, suffix: &str) -> Result<F, Error> {
@@ -234,7 +250,7 @@ where
         self.helper
             .load_file(subcache_location)
             .await
-            .map_err(|e| Error::HelperErrorDuringOpenFile(self.dyld_cache_path.to_string(), e))
+            .map_err(|e| Error::helper_error_during_open_file(self.dyld_cache_path.to_string(), e))
     }
 }
 
@@ -250,44 +266,100 @@ where
     let dcl = DyldCacheLoader::new(helper, &dyld_cache_path);
     let root_contents = dcl.load_cache().await?;
     let root_contents = FileContentsWrapper::new(root_contents);
+    let (subcache_suffixes, has_symbols_subcache) = dyld_cache_subcache_suffixes(&root_contents)?;
 
+    // All subcaches listed in the header need to be present, otherwise we can't
+    // map addresses in the cache to file offsets.
     let mut subcache_contents = Vec::new();
-    for subcache_index in 1.. {
-        // Find the subcache at dyld_shared_cache_arm64e.1 or dyld_shared_cache_arm64e.01
-        let suffix = format!(".{subcache_index}");
-        let suffix2 = format!(".{subcache_index:02}");
-        let subcache = match dcl.load_subcache(&suffix).await {
-            Ok(subcache) => subcache,
-            Err(_) => match dcl.load_subcache(&suffix2).await {
-                Ok(subcache) => subcache,
-                Err(_) => break,
-            },
-        };
-        subcache_contents.push(FileContentsWrapper::new(subcache));
+    for suffixes in subcache_suffixes {
+        let mut last_err = None;
+        for suffix in suffixes {
+            match dcl.load_subcache(&suffix).await {
+                Ok(subcache) => {
+                    subcache_contents.push(FileContentsWrapper::new(subcache));
+                    last_err = None;
+                    break;
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        if let Some(e) = last_err {
+            return Err(e);
+        }
     }
-    if let Ok(subcache) = dcl.load_subcache(".symbols").await {
-        subcache_contents.push(FileContentsWrapper::new(subcache));
+
+    // The .symbols subcache contains the local symbols of all images. It is
+    // not needed for anything else, but object's DyldCache::parse insists on
+    // having it if the header refers to it.
+    let symbols_contents = if has_symbols_subcache {
+        let symbols_subcache = dcl.load_subcache(".symbols").await.map_err(|e| {
+            Error::DyldCacheSymbolsSubcacheMissing(dyld_cache_path.to_string(), Box::new(e))
+        })?;
+        Some(FileContentsWrapper::new(symbols_subcache))
+    } else {
+        None
     };
 
     Ok(DyldCacheFileData::new(
         root_contents,
         subcache_contents,
+        symbols_contents,
         dylib_path,
     ))
 }
 
+/// Returns the file name suffixes of the subcaches which are listed in the
+/// header of the root dyld cache file, and whether there is a `.symbols` subcache.
+///
+/// For each subcache, there can be multiple suffix candidates: Caches from
+/// macOS 12 use `.1`, `.2` etc., and caches from macOS 13 use `.01`, `.02` etc.,
+/// but only newer caches store the suffix in the header.
+fn dyld_cache_subcache_suffixes<T: FileContents>(
+    root_contents: &FileContentsWrapper<T>,
+) -> Result<(Vec<Vec<String>>, bool), Error> {
+    let data = root_contents.full_range();
+    let header =
+        macho::DyldCacheHeader::<Endianness>::parse(data).map_err(Error::DyldCacheParseError)?;
+    let (_arch, endian) = header.parse_magic().map_err(Error::DyldCacheParseError)?;
+    let suffixes = match header
+        .subcaches(endian, data)
+        .map_err(Error::DyldCacheParseError)?
+    {
+        None => Vec::new(),
+        Some(DyldSubCacheSlice::V1(subcaches)) => (1..=subcaches.len())
+            .map(|index| vec![format!(".{index}"), format!(".{index:02}")])
+            .collect(),
+        Some(DyldSubCacheSlice::V2(subcaches)) => subcaches
+            .iter()
+            .map(|subcache| {
+                let suffix = &subcache.file_suffix;
+                let len = suffix.iter().position(|b| *b == 0).unwrap_or(suffix.len());
+                vec![String::from_utf8_lossy(&suffix[..len]).into_owned()]
+            })
+            .collect(),
+        Some(_) => return Err(Error::InvalidInputError("Unsupported dyld subcache format")),
+    };
+    let has_symbols_subcache = header.symbols_subcache_uuid(endian).is_some();
+    Ok((suffixes, has_symbols_subcache))
+}
+
 pub async fn load_symbol_map_for_dyld_cache<H>(
     dyld_cache_path: H::FL,
     dylib_path: String,
     helper: &H,
+    demangle_options: DemangleOptions,
 ) -> Result<SymbolMap<H>, Error>
 where
     H: FileAndPathHelper,
 {
     let owner = load_file_data_for_dyld_cache(dyld_cache_path.clone(), dylib_path, helper).await?;
     let owner = FileDataAndObject::new(Box::new(owner))?;
-    let symbol_map = ObjectSymbolMap::new(owner)?;
-    Ok(SymbolMap::new_plain(dyld_cache_path, Box::new(symbol_map)))
+    let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
+    Ok(SymbolMap::new_plain(
+        dyld_cache_path,
+        SymbolFileKind::DyldSharedCache,
+        Box::new(symbol_map),
+    ))
 }
 
 pub struct DyldCacheFileData<T>
@@ -296,6 +368,7 @@ where
 {
     root_file_data: FileContentsWrapper<T>,
     subcache_file_data: Vec<FileContentsWrapper<T>>,
+    symbols_file_data: Option<FileContentsWrapper<T>>,
     dylib_path: String,
 }
 
@@ -306,6 +379,9 @@ pub struct ObjectAndMachOData<'data, T: FileContents + 'static> {
     object: File<'data, FileContentsRange<'data, T>>,
     macho_data: MachOData<'data, FileContentsRange<'data, T>>,
     addr2line_context: Addr2lineContextData,
+    /// Symbols which are not in the object's symbol table, i.e. the local
+    /// symbols of images in the dyld shared cache.
+    additional_symbols: Vec<AdditionalSymbol<'data>>,
 }
 
 impl<'data, T: FileContents + 'static> ObjectAndMachOData<'data, T> {
@@ -317,6 +393,7 @@ impl<'data, T: FileContents + 'static> ObjectAndMachOData<'data, T> {
             object,
             macho_data,
             addr2line_context: Addr2lineContextData::new(),
+            additional_symbols: Vec::new(),
         }
     }
 
@@ -339,11 +416,13 @@ impl<T: FileContents + 'static> DyldCacheFileData<T> {
     pub fn new(
         root_file_data: FileContentsWrapper<T>,
         subcache_file_data: Vec<FileContentsWrapper<T>>,
+        symbols_file_data: Option<FileContentsWrapper<T>>,
         dylib_path: String,
     ) -> Self {
         Self {
             root_file_data,
             subcache_file_data,
+            symbols_file_data,
             dylib_path,
         }
     }
@@ -353,6 +432,7 @@ impl<T: FileContents + 'static> DyldCacheFileData<T> {
         let subcache_ranges: Vec<_> = self
             .subcache_file_data
             .iter()
+            .chain(&self.symbols_file_data)
             .map(FileContentsWrapper::full_range)
             .collect();
         let cache = object::read::macho::DyldCache::<Endianness, _>::parse(
@@ -382,10 +462,208 @@ impl<T: FileContents + 'static> MakeMachObject<T> for DyldCacheFileData<T> {
         self.root_file_data.full_range()
     }
     fn make_dependent_object(&self) -> Result<ObjectAndMachOData<'_, T>, Error> {
-        self.make_object()
+        let mut object_and_data = self.make_object()?;
+        object_and_data.additional_symbols = self.local_symbols(&object_and_data.object);
+        Ok(object_and_data)
+    }
+}
+
+impl<T: FileContents + 'static> DyldCacheFileData<T> {
+    /// Returns the local (non-exported) symbols of the image, which aren't in the
+    /// symbol table of the image. They are stored in a separate area of the cache:
+    /// in the `.symbols` subcache if there is one, otherwise in the root cache file.
+    ///
+    /// Errors are ignored, because the local symbols are only a nice-to-have.
+    fn local_symbols<'data>(
+        &'data self,
+        object: &File<'data, FileContentsRange<'data, T>>,
+    ) -> Vec<AdditionalSymbol<'data>> {
+        let root_data = self.root_file_data.full_range();
+        let symbols_data = match &self.symbols_file_data {
+            Some(symbols_file_data) => symbols_file_data.full_range(),
+            None => root_data,
+        };
+        let Ok(header) = macho::DyldCacheHeader::<Endianness>::parse(root_data) else {
+            return Vec::new();
+        };
+        let Ok((_arch, endian)) = header.parse_magic() else {
+            return Vec::new();
+        };
+        let Some(cache_base_address) = header
+            .mappings(endian, root_data)
+            .ok()
+            .and_then(|mappings| mappings.first())
+            .map(|mapping| mapping.address.get(endian))
+        else {
+            return Vec::new();
+        };
+        // Caches which have a .symbols subcache store 64 bit VM offsets in the
+        // local symbols entries, older caches store 32 bit file offsets. For
+        // the image's mach header, the two are the same in older caches.
+        let format = LocalSymbolsFormat {
+            endian,
+            has_64_bit_entries: header.symbols_subcache_uuid(endian).is_some(),
+            is_64: object.is_64(),
+        };
+        let image_offset = relative_address_base(object).wrapping_sub(cache_base_address);
+        let text_sections: Vec<u8> = object
+            .sections()
+            .filter(|section| section.kind() == SectionKind::Text)
+            .filter_map(|section| u8::try_from(section.index().0).ok())
+            .collect();
+        dyld_cache_local_symbols(symbols_data, format, image_offset, &text_sections)
+            .unwrap_or_default()
+    }
+}
+
+/// Corresponds to struct dyld_cache_local_symbols_info from dyld_cache_format.h.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct DyldCacheLocalSymbolsInfo<E: Endian> {
+    nlist_offset: U32<E>,
+    nlist_count: U32<E>,
+    strings_offset: U32<E>,
+    strings_size: U32<E>,
+    entries_offset: U32<E>,
+    entries_count: U32<E>,
+}
+
+/// Corresponds to struct dyld_cache_local_symbols_entry from dyld_cache_format.h.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct DyldCacheLocalSymbolsEntry32<E: Endian> {
+    dylib_offset: U32<E>,
+    nlist_start_index: U32<E>,
+    nlist_count: U32<E>,
+}
+
+/// Corresponds to struct dyld_cache_local_symbols_entry_64 from dyld_cache_format.h.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct DyldCacheLocalSymbolsEntry64<E: Endian> {
+    dylib_offset: U64<E>,
+    nlist_start_index: U32<E>,
+    nlist_count: U32<E>,
+}
+
+// SAFETY: These structs only consist of byte arrays and have no padding.
+unsafe impl<E: Endian> Pod for DyldCacheLocalSymbolsInfo<E> {}
+unsafe impl<E: Endian> Pod for DyldCacheLocalSymbolsEntry32<E> {}
+unsafe impl<E: Endian> Pod for DyldCacheLocalSymbolsEntry64<E> {}
+
+#[derive(Debug, Clone, Copy)]
+struct LocalSymbolsFormat {
+    endian: Endianness,
+    has_64_bit_entries: bool,
+    is_64: bool,
+}
+
+/// Reads the local symbols for the image at `image_offset` from the local
+/// symbols area of a dyld cache file, whose header is at the start of `data`.
+/// Only symbols in the sections listed in `text_sections` are returned.
+fn dyld_cache_local_symbols<'data, R: ReadRef<'data>>(
+    data: R,
+    format: LocalSymbolsFormat,
+    image_offset: u64,
+    text_sections: &[u8],
+) -> Option<Vec<AdditionalSymbol<'data>>> {
+    let endian = format.endian;
+    let header = macho::DyldCacheHeader::<Endianness>::parse(data).ok()?;
+    let info_offset = header.local_symbols_offset.get(endian);
+    if info_offset == 0 {
+        return None;
+    }
+    let info = data
+        .read_at::<DyldCacheLocalSymbolsInfo<Endianness>>(info_offset)
+        .ok()?;
+    let entries_offset = info_offset + u64::from(info.entries_offset.get(endian));
+    let entries_count = info.entries_count.get(endian) as usize;
+    let (nlist_start_index, nlist_count) = if format.has_64_bit_entries {
+        data.read_slice_at::<DyldCacheLocalSymbolsEntry64<Endianness>>(
+            entries_offset,
+            entries_count,
+        )
+        .ok()?
+        .iter()
+        .find(|entry| entry.dylib_offset.get(endian) == image_offset)
+        .map(|entry| {
+            (
+                entry.nlist_start_index.get(endian),
+                entry.nlist_count.get(endian),
+            )
+        })?
+    } else {
+        data.read_slice_at::<DyldCacheLocalSymbolsEntry32<Endianness>>(
+            entries_offset,
+            entries_count,
+        )
+        .ok()?
+        .iter()
+        .find(|entry| u64::from(entry.dylib_offset.get(endian)) == image_offset)
+        .map(|entry| {
+            (
+                entry.nlist_start_index.get(endian),
+                entry.nlist_count.get(endian),
+            )
+        })?
+    };
+    let strings_start = info_offset + u64::from(info.strings_offset.get(endian));
+    let strings_end = strings_start + u64::from(info.strings_size.get(endian));
+    let strings = StringTable::new(data, strings_start, strings_end);
+    let nlist_offset = info_offset + u64::from(info.nlist_offset.get(endian));
+    if format.is_64 {
+        local_symbols_from_nlists::<macho::Nlist64<Endianness>, R>(
+            data,
+            endian,
+            nlist_offset,
+            nlist_start_index,
+            nlist_count,
+            strings,
+            text_sections,
+        )
+    } else {
+        local_symbols_from_nlists::<macho::Nlist32<Endianness>, R>(
+            data,
+            endian,
+            nlist_offset,
+            nlist_start_index,
+            nlist_count,
+            strings,
+            text_sections,
+        )
     }
 }
 
+fn local_symbols_from_nlists<'data, N: Nlist<Endian = Endianness>, R: ReadRef<'data>>(
+    data: R,
+    endian: Endianness,
+    nlist_offset: u64,
+    nlist_start_index: u32,
+    nlist_count: u32,
+    strings: StringTable<'data, R>,
+    text_sections: &[u8],
+) -> Option<Vec<AdditionalSymbol<'data>>> {
+    let start_offset =
+        nlist_offset + u64::from(nlist_start_index) * std::mem::size_of::<N>() as u64;
+    let nlists = data
+        .read_slice_at::<N>(start_offset, nlist_count as usize)
+        .ok()?;
+    let symbols = nlists
+        .iter()
+        .filter(|nlist| !nlist.is_stab() && nlist.n_type() & macho::N_TYPE == macho::N_SECT)
+        .filter(|nlist| text_sections.contains(&nlist.n_sect()))
+        .filter_map(|nlist| {
+            Some(AdditionalSymbol {
+                address: nlist.n_value(endian).into(),
+                size: 0,
+                name: nlist.name(endian, strings).ok()?,
+                forwarded_to: None,
+            })
+        })
+        .collect();
+    Some(symbols)
+}
+
 struct FileDataAndObject<T: FileContents + 'static>(
     Yoke<ObjectAndMachOData<'static, T>, Box<dyn MakeMachObject<T> + Send + Sync>>,
 );
@@ -398,11 +676,15 @@ impl<T: FileContents + 'static> FileDataAndObject<T> {
 }
 
 impl<T: FileContents + 'static> ObjectSymbolMapOuter<T> for FileDataAndObject<T> {
-    fn make_symbol_map_inner(&self) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error> {
+    fn make_symbol_map_inner(
+        &self,
+        demangle_options: DemangleOptions,
+    ) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error> {
         let ObjectAndMachOData {
             object,
             macho_data,
             addr2line_context,
+            additional_symbols,
         } = self.0.get();
         let (function_starts, function_ends) = compute_function_addresses_macho(macho_data, object);
         let debug_id = debug_id_for_object(object)
@@ -414,24 +696,47 @@ impl<T: FileContents + 'static> ObjectSymbolMapOuter<T> for FileDataAndObject<T>
                 .ok(),
             None,
             debug_id,
+            additional_symbols,
             function_starts.as_deref(),
             function_ends.as_deref(),
             &(),
+            demangle_options,
         );
 
         Ok(symbol_map)
     }
 }
 
+/// Returns [`SymbolFileKind::Dsym`] if the Mach-O header at `offset` has the
+/// `MH_DSYM` file type, and [`SymbolFileKind::MachO`] otherwise.
+fn macho_symbol_file_kind<'data, R: ReadRef<'data>>(data: R, offset: u64) -> SymbolFileKind {
+    let filetype = match FileKind::parse_at(data, offset) {
+        Ok(FileKind::MachO32) => MachHeader32::<Endianness>::parse(data, offset)
+            .ok()
+            .and_then(|header| Some(header.filetype(header.endian().ok()?))),
+        Ok(FileKind::MachO64) => MachHeader64::<Endianness>::parse(data, offset)
+            .ok()
+            .and_then(|header| Some(header.filetype(header.endian().ok()?))),
+        _ => None,
+    };
+    match filetype {
+        Some(macho::MH_DSYM) => SymbolFileKind::Dsym,
+        _ => SymbolFileKind::MachO,
+    }
+}
+
 pub fn get_symbol_map_for_macho<H: FileAndPathHelper>(
     debug_file_location: H::FL,
     file_contents: FileContentsWrapper<H::F>,
     helper: Arc<H>,
+    demangle_options: DemangleOptions,
 ) -> Result<SymbolMap<H>, Error> {
+    let symbol_file_kind = macho_symbol_file_kind(&file_contents, 0);
     let owner = FileDataAndObject::new(Box::new(MachSymbolMapData(file_contents)))?;
-    let symbol_map = ObjectSymbolMap::new(owner)?;
+    let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
     Ok(SymbolMap::new_with_external_file_support(
         debug_file_location,
+        symbol_file_kind,
         Box::new(symbol_map),
         helper,
     ))
@@ -442,14 +747,17 @@ pub fn get_symbol_map_for_fat_archive_member<H: FileAndPathHelper>(
     file_contents: FileContentsWrapper<H::F>,
     member: FatArchiveMember,
     helper: Arc<H>,
+    demangle_options: DemangleOptions,
 ) -> Result<SymbolMap<H>, Error> {
     let (start_offset, range_size) = member.offset_and_size;
+    let symbol_file_kind = macho_symbol_file_kind(&file_contents, start_offset);
     let owner =
         MachOFatArchiveMemberData::new(file_contents, start_offset, range_size, member.arch);
     let owner = FileDataAndObject::new(Box::new(owner))?;
-    let symbol_map = ObjectSymbolMap::new(owner)?;
+    let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
     Ok(SymbolMap::new_with_external_file_support(
         debug_file_location,
+        symbol_file_kind,
         Box::new(symbol_map),
         helper,
     ))
@@ -679,3 +987,230 @@ fn read_uleb128(mut bytes: &[u8]) -> Option<(u64, &[u8])> {
     }
     None
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u64(data: &mut [u8], offset: usize, value: u64) {
+        data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn test_dyld_cache_local_symbols() {
+        // A cache header whose local symbols info is at 0x200, followed by
+        // four nlist_64 entries, two local symbols entries and the strings.
+        // Section 1 is the only text section.
+        let mut data = vec![0; 0x300];
+        data[..16].copy_from_slice(b"dyld_v1  arm64e\0");
+        let info = 0x200;
+        put_u64(&mut data, 0x48, info as u64);
+        put_u32(&mut data, info, 0x20); // nlist_offset
+        put_u32(&mut data, info + 4, 4); // nlist_count
+        put_u32(&mut data, info + 8, 0x80); // strings_offset
+        put_u32(&mut data, info + 12, 0x20); // strings_size
+        put_u32(&mut data, info + 16, 0x60); // entries_offset
+        put_u32(&mut data, info + 20, 2); // entries_count
+
+        let strings = b"\0_first\0_helper\0_stab\0";
+        data[info + 0x80..info + 0x80 + strings.len()].copy_from_slice(strings);
+
+        let nlists = [
+            (1, macho::N_SECT, 1, 0x1_8000_1100),
+            (8, macho::N_SECT, 1, 0x1_8000_5100),
+            (16, macho::N_FUN, 1, 0x1_8000_5200),
+            (16, macho::N_SECT, 2, 0x1_8000_9000),
+        ];
+        for (i, (strx, n_type, n_sect, value)) in nlists.into_iter().enumerate() {
+            let offset = info + 0x20 + i * 16;
+            put_u32(&mut data, offset, strx);
+            data[offset + 4] = n_type;
+            data[offset + 5] = n_sect;
+            put_u64(&mut data, offset + 8, value);
+        }
+
+        let entries = [(0x1000, 0, 1), (0x5000, 1, 3)];
+        for (i, (dylib_offset, start, count)) in entries.into_iter().enumerate() {
+            let offset = info + 0x60 + i * 16;
+            put_u64(&mut data, offset, dylib_offset);
+            put_u32(&mut data, offset + 8, start);
+            put_u32(&mut data, offset + 12, count);
+        }
+
+        let format = LocalSymbolsFormat {
+            endian: Endianness::Little,
+            has_64_bit_entries: true,
+            is_64: true,
+        };
+        let symbols = dyld_cache_local_symbols(&data[..], format, 0x5000, &[1]).unwrap();
+        let symbols: Vec<_> = symbols
+            .iter()
+            .map(|symbol| (symbol.address, symbol.name))
+            .collect();
+        assert_eq!(symbols, vec![(0x1_8000_5100, &b"_helper"[..])]);
+
+        assert!(dyld_cache_local_symbols(&data[..], format, 0x3000, &[1]).is_none());
+    }
+
+    #[test]
+    fn test_dyld_cache_local_symbols_32_bit_entries() {
+        // Older caches without a .symbols subcache have 32 bit dylib offsets in
+        // the local symbols entries, and armv7 caches have 32 bit nlists.
+        let mut data = vec![0; 0x300];
+        data[..16].copy_from_slice(b"dyld_v1   armv7\0");
+        let info = 0x200;
+        put_u64(&mut data, 0x48, info as u64);
+        put_u32(&mut data, info, 0x20); // nlist_offset
+        put_u32(&mut data, info + 4, 3); // nlist_count
+        put_u32(&mut data, info + 8, 0x70); // strings_offset
+        put_u32(&mut data, info + 12, 0x10); // strings_size
+        put_u32(&mut data, info + 16, 0x50); // entries_offset
+        put_u32(&mut data, info + 20, 2); // entries_count
+
+        let strings = b"\0_a\0_b\0_c\0";
+        data[info + 0x70..info + 0x70 + strings.len()].copy_from_slice(strings);
+
+        let nlists = [(1, 0x4100), (4, 0x8100), (7, 0x8200)];
+        for (i, (strx, value)) in nlists.into_iter().enumerate() {
+            let offset = info + 0x20 + i * 12;
+            put_u32(&mut data, offset, strx);
+            data[offset + 4] = macho::N_SECT;
+            data[offset + 5] = 1;
+            put_u32(&mut data, offset + 8, value);
+        }
+
+        let entries = [(0x4000, 0, 1), (0x8000, 1, 2)];
+        for (i, (dylib_offset, start, count)) in entries.into_iter().enumerate() {
+            let offset = info + 0x50 + i * 12;
+            put_u32(&mut data, offset, dylib_offset);
+            put_u32(&mut data, offset + 4, start);
+            put_u32(&mut data, offset + 8, count);
+        }
+
+        let format = LocalSymbolsFormat {
+            endian: Endianness::Little,
+            has_64_bit_entries: false,
+            is_64: false,
+        };
+        let symbols = dyld_cache_local_symbols(&data[..], format, 0x8000, &[1]).unwrap();
+        let symbols: Vec<_> = symbols
+            .iter()
+            .map(|symbol| (symbol.address, symbol.name))
+            .collect();
+        assert_eq!(symbols, vec![(0x8100, &b"_b"[..]), (0x8200, &b"_c"[..])]);
+
+        // Without a local symbols offset in the header, there are no local symbols.
+        put_u64(&mut data, 0x48, 0);
+        assert!(dyld_cache_local_symbols(&data[..], format, 0x8000, &[1]).is_none());
+    }
+
+    #[test]
+    fn test_local_symbols_from_nlists() {
+        let strings = b"\0_skipped\0_ext\0_undef\0_data\0_abs\0_stab\0_last\0";
+        let strx = |name: &[u8]| {
+            strings
+                .windows(name.len())
+                .position(|window| window == name)
+                .unwrap() as u32
+        };
+        let nlists = [
+            (strx(b"_skipped"), macho::N_SECT, 1, 0x1000),
+            (strx(b"_ext"), macho::N_SECT | macho::N_EXT, 1, 0x1100),
+            (strx(b"_undef"), macho::N_UNDF | macho::N_EXT, 0, 0),
+            (strx(b"_data"), macho::N_SECT, 2, 0x2000),
+            (strx(b"_abs"), macho::N_ABS, 1, 0x1200),
+            (strx(b"_stab"), macho::N_FUN, 1, 0x1300),
+            (strx(b"_last"), macho::N_SECT, 1, 0x1400),
+        ];
+        let mut data = vec![0; nlists.len() * 16 + strings.len()];
+        for (i, (strx, n_type, n_sect, value)) in nlists.into_iter().enumerate() {
+            let offset = i * 16;
+            put_u32(&mut data, offset, strx);
+            data[offset + 4] = n_type;
+            data[offset + 5] = n_sect;
+            put_u64(&mut data, offset + 8, value);
+        }
+        let strings_start = nlists.len() * 16;
+        data[strings_start..].copy_from_slice(strings);
+        let data = &data[..];
+        let string_table = StringTable::new(data, strings_start as u64, data.len() as u64);
+
+        // Skip the first nlist, and only look at section 1.
+        let symbols = local_symbols_from_nlists::<macho::Nlist64<Endianness>, _>(
+            data,
+            Endianness::Little,
+            0,
+            1,
+            6,
+            string_table,
+            &[1],
+        )
+        .unwrap();
+        let symbols: Vec<_> = symbols
+            .iter()
+            .map(|symbol| (symbol.address, symbol.name))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![(0x1100, &b"_ext"[..]), (0x1400, &b"_last"[..])]
+        );
+
+        // The nlists must be inside the data.
+        assert!(local_symbols_from_nlists::<macho::Nlist64<Endianness>, _>(
+            data,
+            Endianness::Little,
+            0,
+            1,
+            100,
+            string_table,
+            &[1],
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_dyld_cache_subcache_suffixes() {
+        let make_header = |mapping_offset: u32| {
+            let mut data = vec![0; 0x300];
+            data[..16].copy_from_slice(b"dyld_v1  arm64e\0");
+            put_u32(&mut data, 0x10, mapping_offset);
+            put_u32(&mut data, 0x188, 0x200); // subcaches_offset
+            put_u32(&mut data, 0x18c, 2); // subcaches_count
+            data
+        };
+
+        // Caches from before macOS 12 have no subcaches.
+        let data = make_header(0x140);
+        let (suffixes, has_symbols_subcache) =
+            dyld_cache_subcache_suffixes(&FileContentsWrapper::new(data)).unwrap();
+        assert!(suffixes.is_empty());
+        assert!(!has_symbols_subcache);
+
+        // macOS 12 and 13 caches only store the UUIDs, so the suffix is guessed.
+        let data = make_header(0x1c8);
+        let (suffixes, has_symbols_subcache) =
+            dyld_cache_subcache_suffixes(&FileContentsWrapper::new(data)).unwrap();
+        assert_eq!(suffixes, vec![vec![".1", ".01"], vec![".2", ".02"]]);
+        assert!(!has_symbols_subcache);
+
+        // Newer caches store the suffixes, and can have a .symbols subcache.
+        let mut data = make_header(0x1d0);
+        data[0x190..0x1a0].copy_from_slice(&[0xab; 16]); // symbols_subcache_uuid
+        for (i, suffix) in [&b".01"[..], &b".dylddata"[..]].into_iter().enumerate() {
+            let offset = 0x200 + i * 56 + 24;
+            data[offset..offset + suffix.len()].copy_from_slice(suffix);
+        }
+        let (suffixes, has_symbols_subcache) =
+            dyld_cache_subcache_suffixes(&FileContentsWrapper::new(data)).unwrap();
+        assert_eq!(suffixes, vec![vec![".01"], vec![".dylddata"]]);
+        assert!(has_symbols_subcache);
+
+        let mut data = make_header(0x1d0);
+        data[..16].copy_from_slice(b"not a dyld cache");
+        assert!(dyld_cache_subcache_suffixes(&FileContentsWrapper::new(data)).is_err());
+    }
+}
@@ -109,6 +109,55 @@ impl MappedPath {
             } => format!("{crate_name}-{version}/{path}"),
         }
     }
+
+    /// Create a URL at which this file can be viewed in a web browser, for
+    /// example `"https://github.com/rust-lang/rust/blob/<rev>/library/std/src/sys/unix/thread.rs"`.
+    ///
+    /// Returns `None` if the file's host is not a known code browser, e.g. for
+    /// git repositories which are hosted somewhere other than GitHub, GitLab
+    /// or googlesource.com, and for crates from registries other than crates.io.
+    pub fn to_web_url(&self) -> Option<String> {
+        match self {
+            MappedPath::Git { repo, path, rev } => {
+                if repo.starts_with("github.com/") {
+                    Some(format!("https://{repo}/blob/{rev}/{path}"))
+                } else if repo.starts_with("gitlab.com/") {
+                    Some(format!("https://{repo}/-/blob/{rev}/{path}"))
+                } else if repo
+                    .split('/')
+                    .next()
+                    .is_some_and(|host| host.ends_with(".googlesource.com"))
+                {
+                    Some(format!("https://{repo}/+/{rev}/{path}"))
+                } else {
+                    None
+                }
+            }
+            MappedPath::Hg { repo, path, rev } => Some(format!("https://{repo}/file/{rev}/{path}")),
+            MappedPath::S3 {
+                bucket,
+                digest,
+                path,
+            } => Some(format!("https://{bucket}.s3.amazonaws.com/{digest}/{path}")),
+            MappedPath::Cargo {
+                registry,
+                crate_name,
+                version,
+                path,
+            } => {
+                // The registry names for crates.io, as used in cargo's source cache
+                // directory. "github.com-..." is the git index, "index.crates.io-..."
+                // is the sparse index.
+                if registry.starts_with("github.com-") || registry.starts_with("index.crates.io-") {
+                    Some(format!(
+                        "https://docs.rs/crate/{crate_name}/{version}/source/{path}"
+                    ))
+                } else {
+                    None
+                }
+            }
+        }
+    }
 }
 
 fn git_path(input: &str) -> IResult<&str, (String, String, String)> {
@@ -396,6 +445,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn web_urls() {
+        let web_url = |s: &str| MappedPath::from_special_path_str(s).unwrap().to_web_url();
+        assert_eq!(
+            web_url("git:github.com/rust-lang/rust:library/std/src/sys/unix/thread.rs:53cb7b09b00cbea8754ffb78e7e3cb521cb8af4b").as_deref(),
+            Some("https://github.com/rust-lang/rust/blob/53cb7b09b00cbea8754ffb78e7e3cb521cb8af4b/library/std/src/sys/unix/thread.rs")
+        );
+        assert_eq!(
+            web_url("git:chromium.googlesource.com/chromium/src:content/gpu/gpu_main.cc:4dac2548d4812df2aa4a90ac1fc8912363f4d59c").as_deref(),
+            Some("https://chromium.googlesource.com/chromium/src/+/4dac2548d4812df2aa4a90ac1fc8912363f4d59c/content/gpu/gpu_main.cc")
+        );
+        assert_eq!(
+            web_url("git:gitlab.com/inkscape/inkscape:src/main.cpp:1.2").as_deref(),
+            Some("https://gitlab.com/inkscape/inkscape/-/blob/1.2/src/main.cpp")
+        );
+        assert_eq!(web_url("git:example.com/some/repo:src/main.c:abcdef"), None);
+        assert_eq!(
+            web_url("hg:hg.mozilla.org/mozilla-central:widget/cocoa/nsAppShell.mm:997f00815e6bc28806b75448c8829f0259d2cb28").as_deref(),
+            Some("https://hg.mozilla.org/mozilla-central/file/997f00815e6bc28806b75448c8829f0259d2cb28/widget/cocoa/nsAppShell.mm")
+        );
+        assert_eq!(
+            web_url("s3:gecko-generated-sources:a5d3747707d6877b0e5cb0a364e3cb9fea8aa4feb6ead138952c2ba46d41045297286385f0e0470146f49403e46bd266e654dfca986de48c230f3a71c2aafed4/ipc/ipdl/PBackgroundChild.cpp:").as_deref(),
+            Some("https://gecko-generated-sources.s3.amazonaws.com/a5d3747707d6877b0e5cb0a364e3cb9fea8aa4feb6ead138952c2ba46d41045297286385f0e0470146f49403e46bd266e654dfca986de48c230f3a71c2aafed4/ipc/ipdl/PBackgroundChild.cpp")
+        );
+        assert_eq!(
+            web_url("cargo:github.com-1ecc6299db9ec823:tokio-1.6.1:src/runtime/task/mod.rs")
+                .as_deref(),
+            Some("https://docs.rs/crate/tokio/1.6.1/source/src/runtime/task/mod.rs")
+        );
+        assert_eq!(
+            web_url("cargo:my-registry-0123456789abcdef:tokio-1.6.1:src/lib.rs"),
+            None
+        );
+    }
+
     fn test_roundtrip(s: &str) {
         let mapped_path = MappedPath::from_special_path_str(s).unwrap();
         let roundtripped = mapped_path.to_special_path_str();
@@ -58,6 +58,11 @@ impl<E: ExtraPathMapper> PathMapper<E> {
         self.cache.insert(raw_path.into(), mapped_path.clone());
         mapped_path
     }
+
+    /// The format-specific mapper which is consulted before the built-in mappings.
+    pub fn extra_mapper(&self) -> Option<&E> {
+        self.extra_mapper.as_ref()
+    }
 }
 
 fn map_rustc_path(input: &str) -> Result<MappedPath, nom::Err<nom::error::Error<&str>>> {
@@ -1,5 +1,6 @@
 #[cfg(feature = "partial_read_stats")]
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::future::Future;
 use std::marker::PhantomData;
@@ -44,12 +45,18 @@ pub trait OptionallySendFuture: Future + Send {}
 impl<T> OptionallySendFuture for T where T: Future + Send {}
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum CandidatePathInfo<FL: FileLocation> {
     SingleFile(FL),
     InDyldCache {
         dyld_cache_path: FL,
         dylib_path: String,
     },
+    /// A file which needs to be downloaded. The URL is turned into a file
+    /// location with [`FileAndPathHelper::location_for_url`]; helpers which
+    /// can't download files can return `None` there, and the candidate is
+    /// skipped.
+    Url(String),
 }
 
 /// An address that can be looked up in a `SymbolMap`.
@@ -265,6 +272,26 @@ impl ElfBuildId {
     pub fn from_bytes(bytes: &[u8]) -> Self {
         Self(bytes.to_owned())
     }
+
+    /// The path of the separate debug file for this build ID, relative to a global
+    /// debug directory such as `/usr/lib/debug`.
+    ///
+    /// Distributions install debug files from debuginfo packages at paths of the form
+    /// `/usr/lib/debug/.build-id/ab/cdef1234.debug`, where `ab` are the first two hex
+    /// characters of the build ID and `cdef1234` are the remaining characters. For
+    /// this example, this method returns `.build-id/ab/cdef1234.debug`.
+    ///
+    /// Returns `None` if the build ID has less than two bytes.
+    ///
+    /// See <https://sourceware.org/gdb/onlinedocs/gdb/Separate-Debug-Files.html>.
+    pub fn debug_file_path_in_debug_dir(&self) -> Option<String> {
+        if self.0.len() < 2 {
+            return None;
+        }
+        let build_id = self.to_string();
+        let (two_chars, rest) = build_id.split_at(2);
+        Some(format!(".build-id/{two_chars}/{rest}.debug"))
+    }
 }
 
 impl FromStr for ElfBuildId {
@@ -302,9 +329,22 @@ pub struct LibraryInfo {
     pub code_id: Option<CodeId>,
     pub path: Option<String>,
     pub arch: Option<String>,
+    /// The address at which the library is loaded in the process, i.e. the
+    /// address which corresponds to relative address zero. This is not used for
+    /// finding symbol files; it only exists for [`LibraryInfo::relative_address`].
+    pub base_address: Option<u64>,
 }
 
 impl LibraryInfo {
+    /// Converts an address in the process's address space into an address
+    /// relative to the library's base address, for use in [`LookupAddress::Relative`].
+    ///
+    /// Returns `None` if `base_address` is not set, or if the address is not
+    /// in the 4GB range after the base address.
+    pub fn relative_address(&self, address: u64) -> Option<u32> {
+        u32::try_from(address.checked_sub(self.base_address?)?).ok()
+    }
+
     /// Fill all `None` fields on this object with the corresponding fields from `other`.
     ///
     /// This should only be called if some minimal matching has been established, for
@@ -332,6 +372,9 @@ impl LibraryInfo {
         if self.arch.is_none() && other.arch.is_some() {
             self.arch.clone_from(&other.arch);
         }
+        if self.base_address.is_none() && other.base_address.is_some() {
+            self.base_address = other.base_address;
+        }
     }
 }
 
@@ -373,7 +416,13 @@ pub trait FileAndPathHelper {
         info: &LibraryInfo,
     ) -> FileAndPathHelperResult<Vec<CandidatePathInfo<Self::FL>>>;
 
-    /// TODO
+    /// Return the locations of the dyld shared caches which should be searched
+    /// for system libraries, in the order in which they should be tried.
+    ///
+    /// `arch` is the architecture of the requested library, e.g. `"arm64e"`, if
+    /// it is known. In that case only the caches which can contain libraries of
+    /// this architecture should be returned, because opening a cache is expensive.
+    /// If `arch` is `None`, return all candidate caches.
     fn get_dyld_shared_cache_paths(
         &self,
         arch: Option<&str>,
@@ -398,17 +447,55 @@ pub trait FileAndPathHelper {
         Ok(Vec::new())
     }
 
+    /// Called for ELF files without debug info, to find a separate debug file
+    /// with the same build ID, for example in `/usr/lib/debug/.build-id/` or on
+    /// a debuginfod server. The candidates are tried in order, and only files
+    /// with a matching build ID are used.
+    fn get_candidate_paths_for_debug_file_by_build_id(
+        &self,
+        _original_file_location: &Self::FL,
+        _build_id: &ElfBuildId,
+    ) -> FileAndPathHelperResult<Vec<Self::FL>> {
+        Ok(Vec::new())
+    }
+
     /// This method is the entry point for file access during symbolication.
     /// The implementer needs to return an object which implements the `FileContents` trait.
     /// This method is asynchronous, but once it returns, the file data needs to be
     /// available synchronously because the `FileContents` methods are synchronous.
     /// If there is no file at the requested path, an error should be returned (or in any
     /// other error case).
+    ///
+    /// If loading can take a long time, for example because the file is downloaded,
+    /// the implementation can enforce a timeout and return a [`FileLoadTimeoutError`](crate::FileLoadTimeoutError)
+    /// when it expires. This is then reported as [`Error::FileLoadTimeout`](crate::Error::FileLoadTimeout).
     fn load_file(
         &self,
         location: Self::FL,
     ) -> std::pin::Pin<Box<dyn OptionallySendFuture<Output = FileAndPathHelperResult<Self::F>> + '_>>;
 
+    /// Return a list of additional locations for a source file. These are tried,
+    /// in order, if the source file couldn't be loaded from the location returned
+    /// by [`FileLocation::location_for_source_file`]. This can be used to fetch
+    /// source files from a server if they're not present on this machine.
+    fn get_fallback_locations_for_source_file(
+        &self,
+        _debug_file_location: &Self::FL,
+        _source_file_path: &SourceFilePath,
+    ) -> FileAndPathHelperResult<Vec<Self::FL>> {
+        Ok(Vec::new())
+    }
+
+    /// Return the location for a [`CandidatePathInfo::Url`] candidate, which is
+    /// then passed to [`load_file`](FileAndPathHelper::load_file). Return `None`
+    /// if this helper can't load files from this URL.
+    ///
+    /// This lets helpers share the candidate logic for remote files, and keep
+    /// the actual downloading, and any caching, in `load_file`.
+    fn location_for_url(&self, _url: &str) -> Option<Self::FL> {
+        None
+    }
+
     /// Ask the helper to return a SymbolMap if it happens to have one available already.
     fn get_symbol_map_for_library(
         &self,
@@ -508,6 +595,29 @@ pub trait FileLocation: Clone + Display {
     fn location_for_dwo(&self, comp_dir: &str, path: &str) -> Option<Self>;
 
     fn location_for_dwp(&self) -> Option<Self>;
+
+    /// Called on the location of a mach-O binary, to get a location for the DWARF
+    /// file inside its dSYM bundle, usually
+    /// `<binary>.dSYM/Contents/Resources/DWARF/<binary name>`. If the dSYM exists
+    /// and its UUID matches the binary, it is used instead of the binary.
+    fn location_for_dsym(&self) -> Option<Self> {
+        None
+    }
+}
+
+/// Describes how the original source code of a file can be obtained, based on
+/// the source server information (the `srcsrv` stream) in a PDB file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceFileInfo {
+    /// The source file can be downloaded from this URL.
+    Download { url: String },
+    /// Running `command` in the Windows Command shell, with the environment
+    /// variables from `env` set, creates the source file at `target_path`.
+    ExecuteCommand {
+        command: String,
+        env: HashMap<String, String>,
+        target_path: String,
+    },
 }
 
 /// The path of a source file, as found in the debug info.
@@ -671,7 +781,9 @@ pub fn relative_address_base<'data>(object_file: &impl object::Object<'data>) ->
 pub struct SymbolInfo {
     /// The function's address. This is a relative address.
     pub address: u32,
-    /// The function size, in bytes. May have been approximated from neighboring symbols.
+    /// The function size, in bytes. This is best-effort: it comes from explicit
+    /// size information where available (ELF `st_size`, PDB, Breakpad `FUNC`
+    /// records), and is otherwise approximated from the start of the next symbol.
     pub size: Option<u32>,
     /// The function name, demangled.
     pub name: String,
@@ -694,6 +806,23 @@ pub struct AddressInfo {
     pub frames: Option<Vec<FrameDebugInfo>>,
 }
 
+/// A range of instructions which were generated from the same source line,
+/// as described by the line table of the debug info.
+///
+/// At inlined call sites, the file and line are those of the innermost
+/// inlined function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineRange {
+    /// The start address of the range. This is a relative address.
+    pub address: u32,
+    /// The size of the range, in bytes.
+    pub size: u32,
+    /// The source file which generated these instructions.
+    pub file_path: SourceFilePath,
+    /// The line number in `file_path`.
+    pub line: u32,
+}
+
 /// The lookup result from `lookup_sync`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SyncAddressInfo {
@@ -1178,4 +1307,24 @@ mod test {
         let read_len = cursor.read(&mut read_buf[..2]).unwrap();
         assert_eq!(read_len, 0);
     }
+
+    #[test]
+    fn elf_build_id_debug_file_path() {
+        let build_id = ElfBuildId::from_str("63260a3e6e46db57abf718f6a3562c6eedccf269").unwrap();
+        assert_eq!(
+            build_id.debug_file_path_in_debug_dir().as_deref(),
+            Some(".build-id/63/260a3e6e46db57abf718f6a3562c6eedccf269.debug")
+        );
+        assert_eq!(ElfBuildId(vec![0x63]).debug_file_path_in_debug_dir(), None);
+    }
+
+    #[test]
+    fn library_info_relative_address() {
+        let mut info = LibraryInfo::default();
+        assert_eq!(info.relative_address(0x7f00_0000_1234), None);
+        info.base_address = Some(0x7f00_0000_0000);
+        assert_eq!(info.relative_address(0x7f00_0000_1234), Some(0x1234));
+        assert_eq!(info.relative_address(0x7eff_ffff_ffff), None);
+        assert_eq!(info.relative_address(0x7f01_0000_0000), None);
+    }
 }
@@ -3,20 +3,62 @@ use std::sync::Arc;
 
 use debugid::DebugId;
 
+use crate::demangle::{self, DemangleOptions};
 use crate::shared::LookupAddress;
 use crate::{
     AddressInfo, ExternalFileAddressRef, ExternalFileRef, FileAndPathHelper, FileLocation,
-    FrameDebugInfo, FramesLookupResult, SyncAddressInfo,
+    FrameDebugInfo, FramesLookupResult, LineRange, SourceFileInfo, SyncAddressInfo,
 };
 
 pub trait SymbolMapTrait {
     fn debug_id(&self) -> DebugId;
 
+    /// The options which were used to demangle the symbol names returned by
+    /// lookups. [`SymbolMap::lookup_symbol_name`] uses them to match names.
+    fn demangle_options(&self) -> DemangleOptions {
+        DemangleOptions::default()
+    }
+
     fn symbol_count(&self) -> usize;
 
     fn iter_symbols(&self) -> Box<dyn Iterator<Item = (u32, Cow<'_, str>)> + '_>;
 
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        Box::new(
+            self.iter_symbols()
+                .map(|(address, name)| (address, None, name)),
+        )
+    }
+
     fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo>;
+
+    /// Looks up multiple relative addresses, which must be sorted in ascending
+    /// order. Returns one entry per address, in the same order.
+    ///
+    /// Implementations can override this to look up all addresses in a single
+    /// pass over their symbol information.
+    fn lookup_sorted_relative_addresses(&self, addresses: &[u32]) -> Vec<Option<SyncAddressInfo>> {
+        addresses
+            .iter()
+            .map(|&address| self.lookup_sync(LookupAddress::Relative(address)))
+            .collect()
+    }
+
+    fn folded_symbol_names(&self, _symbol_address: u32) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the line table entries which overlap `address..address + size`,
+    /// clipped to that range and sorted by address.
+    fn line_ranges(&self, _address: u32, _size: u32) -> Vec<LineRange> {
+        Vec::new()
+    }
+
+    fn source_file_info(&self, _path: &str, _extraction_base_path: &str) -> Option<SourceFileInfo> {
+        None
+    }
 }
 
 pub trait SymbolMapTraitWithExternalFileSupport<FC>: SymbolMapTrait {
@@ -40,37 +82,96 @@ pub trait GetInnerSymbolMapWithLookupFramesExt<FC> {
 }
 
 enum InnerSymbolMap<FC> {
-    WithoutAddFile(Box<dyn GetInnerSymbolMap + Send + Sync>),
-    WithAddFile(Box<dyn GetInnerSymbolMapWithLookupFramesExt<FC> + Send + Sync>),
+    WithoutAddFile(Arc<dyn GetInnerSymbolMap + Send + Sync>),
+    WithAddFile(Arc<dyn GetInnerSymbolMapWithLookupFramesExt<FC> + Send + Sync>),
     Direct(Arc<dyn SymbolMapTrait + Send + Sync>),
 }
 
+impl<FC> Clone for InnerSymbolMap<FC> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::WithoutAddFile(inner) => Self::WithoutAddFile(inner.clone()),
+            Self::WithAddFile(inner) => Self::WithAddFile(inner.clone()),
+            Self::Direct(inner) => Self::Direct(inner.clone()),
+        }
+    }
+}
+
+/// The kind of file that the symbol information in a [`SymbolMap`] was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SymbolFileKind {
+    /// An ELF binary or debug file. Symbols come from DWARF debug info if present,
+    /// otherwise from the symbol table or from `.gnu_debugdata`.
+    Elf,
+    /// A Mach-O binary, or a member of a fat archive.
+    MachO,
+    /// The DWARF companion file inside a `.dSYM` bundle.
+    Dsym,
+    /// An image inside a dyld shared cache.
+    DyldSharedCache,
+    /// A PE binary (`.exe` / `.dll`).
+    Pe,
+    /// A COFF object file or import library.
+    Coff,
+    /// A PDB file.
+    Pdb,
+    /// A Breakpad `.sym` file.
+    Breakpad,
+    /// A gsym file.
+    Gsym,
+    /// A WebAssembly module.
+    Wasm,
+    /// A jitdump file.
+    Jitdump,
+    /// The symbol map was supplied by the helper, or the kind is otherwise not known.
+    Unknown,
+}
+
 pub struct SymbolMap<H: FileAndPathHelper> {
     debug_file_location: H::FL,
+    symbol_file_kind: SymbolFileKind,
     inner: InnerSymbolMap<H::F>,
     helper: Option<Arc<H>>,
 }
 
+/// Cloning a `SymbolMap` is cheap: the clone shares the parsed symbol
+/// information with the original.
+impl<H: FileAndPathHelper> Clone for SymbolMap<H> {
+    fn clone(&self) -> Self {
+        Self {
+            debug_file_location: self.debug_file_location.clone(),
+            symbol_file_kind: self.symbol_file_kind,
+            inner: self.inner.clone(),
+            helper: self.helper.clone(),
+        }
+    }
+}
+
 impl<H: FileAndPathHelper> SymbolMap<H> {
     pub(crate) fn new_plain(
         debug_file_location: H::FL,
+        symbol_file_kind: SymbolFileKind,
         inner: Box<dyn GetInnerSymbolMap + Send + Sync>,
     ) -> Self {
         Self {
             debug_file_location,
-            inner: InnerSymbolMap::WithoutAddFile(inner),
+            symbol_file_kind,
+            inner: InnerSymbolMap::WithoutAddFile(Arc::from(inner)),
             helper: None,
         }
     }
 
     pub(crate) fn new_with_external_file_support(
         debug_file_location: H::FL,
+        symbol_file_kind: SymbolFileKind,
         inner: Box<dyn GetInnerSymbolMapWithLookupFramesExt<H::F> + Send + Sync>,
         helper: Arc<H>,
     ) -> Self {
         Self {
             debug_file_location,
-            inner: InnerSymbolMap::WithAddFile(inner),
+            symbol_file_kind,
+            inner: InnerSymbolMap::WithAddFile(Arc::from(inner)),
             helper: Some(helper),
         }
     }
@@ -81,12 +182,19 @@ impl<H: FileAndPathHelper> SymbolMap<H> {
     ) -> Self {
         Self {
             debug_file_location,
+            symbol_file_kind: SymbolFileKind::Unknown,
             inner: InnerSymbolMap::Direct(inner),
             helper: None,
         }
     }
 
-    fn inner(&self) -> &dyn SymbolMapTrait {
+    /// Sets the kind of file this symbol map was read from. Symbol maps created
+    /// with [`SymbolMap::with_symbol_map_trait`] start out as [`SymbolFileKind::Unknown`].
+    pub(crate) fn set_symbol_file_kind(&mut self, symbol_file_kind: SymbolFileKind) {
+        self.symbol_file_kind = symbol_file_kind;
+    }
+
+    pub(crate) fn inner(&self) -> &dyn SymbolMapTrait {
         match &self.inner {
             InnerSymbolMap::WithoutAddFile(inner) => inner.get_inner_symbol_map(),
             InnerSymbolMap::WithAddFile(inner) => inner.get_inner_symbol_map().get_as_symbol_map(),
@@ -98,6 +206,11 @@ impl<H: FileAndPathHelper> SymbolMap<H> {
         &self.debug_file_location
     }
 
+    /// The kind of file that the symbols in this map were read from.
+    pub fn symbol_file_kind(&self) -> SymbolFileKind {
+        self.symbol_file_kind
+    }
+
     pub fn debug_id(&self) -> debugid::DebugId {
         self.inner().debug_id()
     }
@@ -106,14 +219,91 @@ impl<H: FileAndPathHelper> SymbolMap<H> {
         self.inner().symbol_count()
     }
 
+    /// Iterate over all symbols in this `SymbolMap`, ordered by address.
+    ///
+    /// This iterator yields the relative address and the name of each symbol.
     pub fn iter_symbols(&self) -> Box<dyn Iterator<Item = (u32, Cow<'_, str>)> + '_> {
         self.inner().iter_symbols()
     }
 
+    /// Like [`SymbolMap::iter_symbols`], but also yields the size of each
+    /// symbol in bytes, if known. This is the same size that a lookup returns
+    /// in [`SymbolInfo::size`](crate::SymbolInfo::size).
+    pub fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        self.inner().iter_symbols_with_sizes()
+    }
+
     pub fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
         self.inner().lookup_sync(address)
     }
 
+    /// Returns the relative address of the symbol with the name `name`.
+    ///
+    /// `name` can either be the raw symbol name, which may be mangled, or the
+    /// demangled name, as returned in [`SymbolInfo::name`](crate::SymbolInfo::name).
+    /// Matches of the raw name are preferred. If multiple symbols match, the
+    /// first match in [`SymbolMap::iter_symbols`] order is returned.
+    ///
+    /// This does a linear scan over all symbols, demangling each of them, so it
+    /// can take a while for large libraries. If you need to look up many names,
+    /// consider building your own index from [`SymbolMap::iter_symbols`].
+    pub fn lookup_symbol_name(&self, name: &str) -> Option<u32> {
+        let demangle_options = self.inner().demangle_options();
+        let mut demangled_match = None;
+        for (address, symbol_name) in self.iter_symbols() {
+            if symbol_name == name {
+                return Some(address);
+            }
+            if demangled_match.is_none()
+                && demangle::demangle_with_options(&symbol_name, &demangle_options) == name
+            {
+                demangled_match = Some(address);
+            }
+        }
+        demangled_match
+    }
+
+    /// Returns all names of the function which starts at `symbol_address`, if
+    /// there is more than one. This happens if the linker merged multiple
+    /// functions with identical code into one ("identical code folding", ICF).
+    ///
+    /// `symbol_address` is the [`SymbolInfo::address`](crate::SymbolInfo::address)
+    /// returned from a lookup. The returned names are demangled and sorted. They
+    /// come from the symbol table (or from the public symbols in a PDB), so they
+    /// may be formatted differently from [`SymbolInfo::name`](crate::SymbolInfo::name),
+    /// for example without argument types. An empty Vec is returned if only a
+    /// single name is known for this address.
+    pub fn folded_symbol_names(&self, symbol_address: u32) -> Vec<String> {
+        self.inner().folded_symbol_names(symbol_address)
+    }
+
+    /// Returns the line table entries for the relative address range
+    /// `address..address + size`, for example for the range of a symbol from
+    /// [`iter_symbols_with_sizes`](Self::iter_symbols_with_sizes).
+    ///
+    /// This is supported for DWARF debug info and Breakpad symbol files. An empty
+    /// Vec is returned for other formats and for ranges without line info.
+    pub fn line_ranges(&self, address: u32, size: u32) -> Vec<LineRange> {
+        self.inner().line_ranges(address, size)
+    }
+
+    /// Returns information about how to obtain the original source code for the
+    /// source file at `path`, where `path` is the raw path from the debug info
+    /// (see [`SourceFilePath::raw_path`](crate::SourceFilePath::raw_path)).
+    ///
+    /// This is currently only supported for PDB files with a `srcsrv` stream.
+    /// If the source is obtained by running a command, `extraction_base_path` is
+    /// the directory under which the command will place the extracted file.
+    pub fn source_file_info(
+        &self,
+        path: &str,
+        extraction_base_path: &str,
+    ) -> Option<SourceFileInfo> {
+        self.inner().source_file_info(path, extraction_base_path)
+    }
+
     pub async fn lookup(&self, address: LookupAddress) -> Option<AddressInfo> {
         let address_info = self.inner().lookup_sync(address)?;
         let symbol = address_info.symbol;
@@ -168,6 +358,59 @@ impl<H: FileAndPathHelper> SymbolMap<H> {
         }
     }
 
+    /// Look up multiple relative addresses at once. The returned Vec has one
+    /// entry for each address in `addresses`, in the same order.
+    ///
+    /// This gives the same results as calling [`SymbolMap::lookup`] for each
+    /// address, but it is faster for large batches: The addresses are sorted
+    /// and deduplicated, and then looked up in a single pass over the symbol
+    /// table. Lookups whose debug info is in an external file (e.g. split DWARF
+    /// or mach-O object files) are done afterwards, grouped by external file, so
+    /// that each external file usually only needs to be loaded once.
+    pub async fn lookup_addresses(&self, addresses: &[u32]) -> Vec<Option<AddressInfo>> {
+        let mut sorted_addresses = addresses.to_vec();
+        sorted_addresses.sort_unstable();
+        sorted_addresses.dedup();
+        let sorted_results = self
+            .inner()
+            .lookup_sorted_relative_addresses(&sorted_addresses);
+
+        let mut results: Vec<Option<AddressInfo>> = vec![None; addresses.len()];
+        let mut external_addresses = Vec::new();
+        for (i, address) in addresses.iter().enumerate() {
+            let Ok(sorted_index) = sorted_addresses.binary_search(address) else {
+                continue;
+            };
+            let Some(address_info) = sorted_results[sorted_index].clone() else {
+                continue;
+            };
+            let frames = match address_info.frames {
+                Some(FramesLookupResult::Available(frames)) => Some(frames),
+                Some(FramesLookupResult::External(external)) => {
+                    external_addresses.push((i, external));
+                    None
+                }
+                None => None,
+            };
+            results[i] = Some(AddressInfo {
+                symbol: address_info.symbol,
+                frames,
+            });
+        }
+
+        // lookup_external keeps the most recent external file cached, so sort
+        // by external file to get the best hit rate.
+        external_addresses.sort_unstable_by(|(_, a), (_, b)| a.cmp(b));
+        for (i, external) in external_addresses {
+            let frames = self.lookup_external(&external).await;
+            if let Some(address_info) = &mut results[i] {
+                address_info.frames = frames;
+            }
+        }
+
+        results
+    }
+
     /// Resolve a debug info lookup for which `SymbolMap::lookup_*` returned a
     /// `FramesLookupResult::External`.
     ///
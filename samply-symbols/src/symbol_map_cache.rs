@@ -0,0 +1,59 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use debugid::DebugId;
+
+use crate::{FileAndPathHelper, SymbolMap};
+
+type CacheKey = (String, DebugId);
+
+/// A least-recently-used cache of symbol maps, keyed by `(debug_name, debug_id)`.
+///
+/// The number of cached symbol maps is expected to be small, so the entries
+/// are kept in a plain list, ordered from least recently used to most recently
+/// used.
+pub struct SymbolMapCache<H: FileAndPathHelper> {
+    capacity: usize,
+    entries: Mutex<VecDeque<(CacheKey, SymbolMap<H>)>>,
+}
+
+impl<H: FileAndPathHelper> SymbolMapCache<H> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        let entries = self.entries.get_mut().unwrap();
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+    }
+
+    /// Returns the cached symbol map for `key` and marks it as most recently used.
+    pub fn get(&self, key: &CacheKey) -> Option<SymbolMap<H>> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries.iter().position(|(k, _)| k == key)?;
+        let entry = entries.remove(index)?;
+        let symbol_map = entry.1.clone();
+        entries.push_back(entry);
+        Some(symbol_map)
+    }
+
+    /// Adds `symbol_map` to the cache, evicting the least recently used entry
+    /// if the cache is full.
+    pub fn insert(&self, key: CacheKey, symbol_map: SymbolMap<H>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|(k, _)| k != &key);
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back((key, symbol_map));
+    }
+}
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::slice;
 use std::sync::{Arc, Mutex};
@@ -12,6 +13,7 @@ use object::{
 use yoke::Yoke;
 use yoke_derive::Yokeable;
 
+use crate::demangle::DemangleOptions;
 use crate::dwarf::convert_frames;
 use crate::path_mapper::PathMapper;
 use crate::shared::{
@@ -22,7 +24,10 @@ use crate::symbol_map::{
     GetInnerSymbolMap, GetInnerSymbolMapWithLookupFramesExt, SymbolMapTrait,
     SymbolMapTraitWithExternalFileSupport,
 };
-use crate::{demangle, Error, ExternalFileSymbolMap, FileContents, SyncAddressInfo};
+use crate::{
+    demangle, Error, ExternalFileSymbolMap, FileContents, LineRange, SourceFilePath,
+    SyncAddressInfo,
+};
 
 enum FullSymbolListEntry<'a, Symbol> {
     /// A synthesized symbol for a function start address that's known
@@ -31,6 +36,10 @@ enum FullSymbolListEntry<'a, Symbol> {
     /// A synthesized symbol for the entry point of the object.
     SynthesizedEntryPoint,
     Symbol(Symbol),
+    AdditionalSymbol(&'a [u8]),
+    /// A PE export which is forwarded to a function in a different DLL, with
+    /// the export name and the forwarder string.
+    ForwardedExport(&'a [u8], &'a [u8]),
     Export(object::Export<'a>),
     EndAddress,
 }
@@ -44,6 +53,15 @@ impl<'a, Symbol: object::ObjectSymbol<'a>> std::fmt::Debug for FullSymbolListEnt
                 .debug_tuple("Symbol")
                 .field(&arg0.name().unwrap())
                 .finish(),
+            Self::AdditionalSymbol(arg0) => f
+                .debug_tuple("AdditionalSymbol")
+                .field(&String::from_utf8_lossy(arg0))
+                .finish(),
+            Self::ForwardedExport(arg0, arg1) => f
+                .debug_tuple("ForwardedExport")
+                .field(&String::from_utf8_lossy(arg0))
+                .field(&String::from_utf8_lossy(arg1))
+                .finish(),
             Self::Export(arg0) => f
                 .debug_tuple("Export")
                 .field(&std::str::from_utf8(arg0.name()).unwrap())
@@ -62,6 +80,13 @@ impl<'a, Symbol: object::ObjectSymbol<'a>> FullSymbolListEntry<'a, Symbol> {
             FullSymbolListEntry::Symbol(symbol) => {
                 String::from_utf8_lossy(symbol.name_bytes().ok()?)
             }
+            FullSymbolListEntry::AdditionalSymbol(name) => String::from_utf8_lossy(name),
+            FullSymbolListEntry::ForwardedExport(name, target) => format!(
+                "{} (forwarded to {})",
+                String::from_utf8_lossy(name),
+                String::from_utf8_lossy(target)
+            )
+            .into(),
             FullSymbolListEntry::Export(export) => String::from_utf8_lossy(export.name()),
         };
         Some(name)
@@ -69,7 +94,10 @@ impl<'a, Symbol: object::ObjectSymbol<'a>> FullSymbolListEntry<'a, Symbol> {
 
     fn counts_as_proper_symbol(&self) -> bool {
         match self {
-            FullSymbolListEntry::Symbol(_) | FullSymbolListEntry::Export(_) => true,
+            FullSymbolListEntry::Symbol(_)
+            | FullSymbolListEntry::AdditionalSymbol(_)
+            | FullSymbolListEntry::ForwardedExport(_, _)
+            | FullSymbolListEntry::Export(_) => true,
             FullSymbolListEntry::EndAddress
             | FullSymbolListEntry::Synthesized
             | FullSymbolListEntry::SynthesizedEntryPoint => false,
@@ -77,16 +105,35 @@ impl<'a, Symbol: object::ObjectSymbol<'a>> FullSymbolListEntry<'a, Symbol> {
     }
 }
 
+/// A function symbol which doesn't come from the object's own symbol tables,
+/// for example from the MiniDebugInfo of an ELF file.
+pub struct AdditionalSymbol<'a> {
+    /// The stated virtual memory address of the function.
+    pub address: u64,
+    /// The size of the function in bytes, or 0 if unknown.
+    pub size: u64,
+    pub name: &'a [u8],
+    /// For PE exports which are forwarded to a different DLL, the forwarder
+    /// string, e.g. `NTDLL.RtlAllocateHeap`. In that case, `address` is the
+    /// address of the forwarder string in the export directory.
+    pub forwarded_to: Option<&'a [u8]>,
+}
+
 struct SymbolList<'a, Symbol> {
     entries: Vec<(u32, FullSymbolListEntry<'a, Symbol>)>,
+    /// The names of symbols which share their address with other symbols, keyed
+    /// by address. Only addresses with more than one distinct name are present.
+    folded_names: HashMap<u32, Vec<String>>,
 }
 
 impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
     pub fn new<'file, O>(
         object_file: &'file O,
         base_address: u64,
+        additional_symbols: &[AdditionalSymbol<'a>],
         function_start_addresses: Option<&[u32]>,
         function_end_addresses: Option<&[u32]>,
+        demangle_options: &DemangleOptions,
     ) -> Self
     where
         'a: 'file,
@@ -127,6 +174,12 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
                         return false;
                     }
 
+                    // Filter out symbols with no name. Wasm files have such symbols
+                    // for functions which aren't listed in the "name" section.
+                    if symbol.name_bytes().map_or(true, |name| name.is_empty()) {
+                        return false;
+                    }
+
                     // Filter out non-Text symbols which don't have a symbol size.
                     match symbol.kind() {
                         SymbolKind::Text => {
@@ -157,7 +210,18 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
                 }),
         );
 
-        // 3. Exports (only used by exe / dll objects)
+        // 3. Additional symbols from outside the object's symbol tables
+        entries.extend(additional_symbols.iter().filter_map(|symbol| {
+            Some((
+                u32::try_from(symbol.address.checked_sub(base_address)?).ok()?,
+                match symbol.forwarded_to {
+                    Some(target) => FullSymbolListEntry::ForwardedExport(symbol.name, target),
+                    None => FullSymbolListEntry::AdditionalSymbol(symbol.name),
+                },
+            ))
+        }));
+
+        // 4. Exports (only used by exe / dll objects)
         if let Ok(exports) = object_file.exports() {
             for export in exports {
                 entries.push((
@@ -167,7 +231,7 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
             }
         }
 
-        // 4. Placeholder symbols based on function start addresses
+        // 5. Placeholder symbols based on function start addresses
         if let Some(function_start_addresses) = function_start_addresses {
             // Use function start addresses with synthesized symbols of the form fun_abcdef
             // as the ultimate fallback.
@@ -181,7 +245,7 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
             );
         }
 
-        // 5. A placeholder symbol for the entry point.
+        // 6. A placeholder symbol for the entry point.
         if let Some(entry_point) = object_file.entry().checked_sub(base_address) {
             entries.push((
                 entry_point as u32,
@@ -189,7 +253,7 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
             ));
         }
 
-        // 6. End addresses from text section ends
+        // 7. End addresses from text section ends
         // These entries serve to "terminate" the last function of each section,
         // so that addresses in the following section are not considered
         // to be part of the last function of that previous section.
@@ -205,7 +269,7 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
                 }),
         );
 
-        // 7. End addresses for sized symbols
+        // 8. End addresses for sized symbols
         // These addresses serve to "terminate" functions symbols.
         entries.extend(
             object_file
@@ -226,8 +290,25 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
                     ))
                 }),
         );
+        entries.extend(
+            additional_symbols
+                .iter()
+                .filter(|symbol| symbol.size != 0)
+                .filter_map(|symbol| {
+                    Some((
+                        u32::try_from(
+                            symbol
+                                .address
+                                .checked_add(symbol.size)?
+                                .checked_sub(base_address)?,
+                        )
+                        .ok()?,
+                        FullSymbolListEntry::EndAddress,
+                    ))
+                }),
+        );
 
-        // 8. End addresses for known functions ends
+        // 9. End addresses for known functions ends
         // These addresses serve to "terminate" functions from function_start_addresses.
         // They come from .eh_frame or .pdata info, which has the function size.
         if let Some(function_end_addresses) = function_end_addresses {
@@ -247,9 +328,43 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
         // We have added entries in the order best to worst, so we keep the "best"
         // symbol for each address.
         entries.sort_by_key(|(address, _)| *address);
+
+        // Before de-duplicating, remember all symbol names for addresses with
+        // multiple symbols. For example, if the linker merged identical functions
+        // ("identical code folding"), the symbol table still contains the names
+        // of all the merged functions at the same address.
+        let mut folded_names: HashMap<u32, Vec<String>> = HashMap::new();
+        let mut group_start = 0;
+        while group_start < entries.len() {
+            let address = entries[group_start].0;
+            let group_len = entries[group_start..]
+                .iter()
+                .take_while(|(addr, _)| *addr == address)
+                .count();
+            let group = &entries[group_start..group_start + group_len];
+            group_start += group_len;
+            if group_len < 2 {
+                continue;
+            }
+            let mut names: Vec<String> = group
+                .iter()
+                .filter(|(_, entry)| entry.counts_as_proper_symbol())
+                .filter_map(|(addr, entry)| entry.name(*addr))
+                .map(|name| demangle::demangle_with_options(&name, demangle_options))
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            if names.len() > 1 {
+                folded_names.insert(address, names);
+            }
+        }
+
         entries.dedup_by_key(|(address, _)| *address);
 
-        Self { entries }
+        Self {
+            entries,
+            folded_names,
+        }
     }
 
     pub fn lookup_relative_address(&self, address: u32) -> Option<(u32, u32, Cow<'a, str>)> {
@@ -273,6 +388,31 @@ impl<'a, Symbol: object::ObjectSymbol<'a> + 'a> SymbolList<'a, Symbol> {
         };
         Some((*start_addr, *end_addr, name))
     }
+
+    /// Like `lookup_relative_address`, but for multiple addresses which are
+    /// sorted in ascending order. Instead of searching the entire list for each
+    /// address, this only searches the part after the previous address's entry.
+    pub fn lookup_sorted_relative_addresses(
+        &self,
+        addresses: &[u32],
+    ) -> Vec<Option<(u32, u32, Cow<'a, str>)>> {
+        // The number of entries whose address is <= the current address.
+        let mut entry_count = 0;
+        addresses
+            .iter()
+            .map(|&address| {
+                entry_count +=
+                    self.entries[entry_count..].partition_point(|&(addr, _)| addr <= address);
+                let (start_addr, entry) = &self.entries[entry_count.checked_sub(1)?];
+                let (end_addr, _next_entry) = self.entries.get(entry_count)?;
+                let name = match entry {
+                    FullSymbolListEntry::EndAddress => return None,
+                    _ => entry.name(*start_addr)?,
+                };
+                Some((*start_addr, *end_addr, name))
+            })
+            .collect()
+    }
 }
 
 // A file range in an object file, such as a segment or a section,
@@ -362,6 +502,7 @@ pub struct ObjectSymbolMapInner<'a, Symbol, FC: FileContents + 'static, DDM> {
     image_base_address: u64,
     dwo_dwarf_maker: &'a DDM,
     cached_external_file: Mutex<Option<ExternalFileSymbolMap<FC>>>,
+    demangle_options: DemangleOptions,
     _phantom: PhantomData<FC>,
 }
 
@@ -404,6 +545,44 @@ where
         }))
     }
 
+    fn lookup_frames(
+        &self,
+        context: Option<&addr2line::Context<gimli::EndianSlice<'a, gimli::RunTimeEndian>>>,
+        path_mapper: &mut PathMapper<()>,
+        svma: u64,
+    ) -> Option<FramesLookupResult> {
+        let Some(context) = context else {
+            return self.frames_lookup_for_object_map_references(svma);
+        };
+        let mut lookup_result = context.find_frames(svma);
+
+        // We use a loop here so that we can retry the lookup with a "continue"
+        // after we've fed the DWP data into the addr2line context.
+        let frames = loop {
+            break match lookup_result {
+                LookupResult::Load { load, continuation } => {
+                    if let Some(dwp) = self.dwp_package.as_ref() {
+                        if let Ok(maybe_cu) = dwp.find_cu(load.dwo_id, &*load.parent) {
+                            use addr2line::LookupContinuation;
+                            prime_skeleton_line_program(context, svma);
+                            lookup_result = continuation.resume(maybe_cu.map(Arc::new));
+                            continue;
+                        }
+                    }
+                    Some(FramesLookupResult::External(
+                        ExternalFileAddressRef::with_split_dwarf_load(&load, svma),
+                    ))
+                }
+                LookupResult::Output(Ok(frame_iter)) => {
+                    convert_frames(frame_iter, path_mapper, &self.demangle_options)
+                        .map(FramesLookupResult::Available)
+                }
+                LookupResult::Output(Err(_)) => None,
+            };
+        };
+        frames.or_else(|| self.frames_lookup_for_object_map_references(svma))
+    }
+
     fn try_lookup_external_impl(
         &self,
         external: &ExternalFileAddressRef,
@@ -430,7 +609,9 @@ where
                         maybe_file_contents,
                     ) => maybe_file_contents?,
                 };
-                let external_file = ExternalFileSymbolMap::new(file_path, file_contents).ok()?;
+                let external_file =
+                    ExternalFileSymbolMap::new(file_path, file_contents, self.demangle_options)
+                        .ok()?;
                 let lookup_result = external_file
                     .lookup(&external.address_in_file)
                     .map(FramesLookupResult::Available);
@@ -475,13 +656,14 @@ where
                                     Arc::new(dwo_dwarf)
                                 });
                             use addr2line::LookupContinuation;
+                            prime_skeleton_line_program(&ctx, *svma);
                             request = ExternalLookupRequest::ReplyIfYouHaveOrTellMeWhatYouNeed;
                             lookup_result = continuation.resume(maybe_dwarf);
                             continue;
                         }
                         LookupResult::Output(Ok(frame_iter)) => {
                             let mut path_mapper = self.path_mapper.lock().unwrap();
-                            convert_frames(frame_iter, &mut path_mapper)
+                            convert_frames(frame_iter, &mut path_mapper, &self.demangle_options)
                                 .map(FramesLookupResult::Available)
                         }
                         LookupResult::Output(Err(_)) => None,
@@ -502,6 +684,10 @@ where
         self.debug_id
     }
 
+    fn demangle_options(&self) -> DemangleOptions {
+        self.demangle_options
+    }
+
     fn symbol_count(&self) -> usize {
         let iter = self.list.entries.iter();
         iter.filter(|&(_, entry)| entry.counts_as_proper_symbol())
@@ -514,6 +700,24 @@ where
         })
     }
 
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        // A symbol ends where the next entry starts, just like in lookup_relative_address.
+        let entries = &self.list.entries;
+        let iter = entries
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, (address, entry))| {
+                let name = entry.name(*address)?;
+                let size = entries
+                    .get(i + 1)
+                    .map(|(next_address, _)| next_address - address);
+                Some((*address, size, name))
+            });
+        Box::new(iter)
+    }
+
     fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
         let (svma, relative_address) = match address {
             LookupAddress::Relative(relative_address) => (
@@ -535,51 +739,89 @@ where
         };
         let (start_addr, end_addr, name) = self.list.lookup_relative_address(relative_address)?;
         let function_size = end_addr - start_addr;
-        let name = demangle::demangle_any(&name);
+        let name = demangle::demangle_with_options(&name, &self.demangle_options);
         let symbol = SymbolInfo {
             address: start_addr,
             size: Some(function_size),
             name,
         };
+        let context = self.context.as_ref().map(|context| context.lock().unwrap());
+        let mut path_mapper = self.path_mapper.lock().unwrap();
+        let frames = self.lookup_frames(context.as_deref(), &mut path_mapper, svma);
+        Some(SyncAddressInfo { symbol, frames })
+    }
 
-        let mut frames = None;
-        if let Some(context) = self.context.as_ref() {
-            let context = context.lock().unwrap();
-            let mut lookup_result = context.find_frames(svma);
-
-            // We use a loop here so that we can retry the lookup with a "continue"
-            // after we've fed the DWP data into the addr2line context.
-            frames = loop {
-                break match lookup_result {
-                    LookupResult::Load { load, continuation } => {
-                        if let Some(dwp) = self.dwp_package.as_ref() {
-                            if let Ok(maybe_cu) = dwp.find_cu(load.dwo_id, &*load.parent) {
-                                use addr2line::LookupContinuation;
-                                lookup_result = continuation.resume(maybe_cu.map(Arc::new));
-                                continue;
-                            }
-                        }
-                        Some(FramesLookupResult::External(
-                            ExternalFileAddressRef::with_split_dwarf_load(&load, svma),
-                        ))
-                    }
-                    LookupResult::Output(Ok(frame_iter)) => {
-                        let mut path_mapper = self.path_mapper.lock().unwrap();
-                        convert_frames(frame_iter, &mut path_mapper)
-                            .map(FramesLookupResult::Available)
-                    }
-                    LookupResult::Output(Err(_)) => {
-                        drop(lookup_result);
-                        drop(context);
-                        self.frames_lookup_for_object_map_references(svma)
+    fn lookup_sorted_relative_addresses(&self, addresses: &[u32]) -> Vec<Option<SyncAddressInfo>> {
+        let symbols = self.list.lookup_sorted_relative_addresses(addresses);
+
+        // Take the locks once for the entire batch.
+        let context = self.context.as_ref().map(|context| context.lock().unwrap());
+        let mut path_mapper = self.path_mapper.lock().unwrap();
+
+        // Consecutive addresses often fall into the same function, so we only
+        // demangle each function name once.
+        let mut previous_symbol: Option<SymbolInfo> = None;
+        addresses
+            .iter()
+            .zip(symbols)
+            .map(|(&relative_address, symbol)| {
+                let (start_addr, end_addr, name) = symbol?;
+                let svma = self
+                    .image_base_address
+                    .checked_add(u64::from(relative_address))?;
+                let symbol = match &previous_symbol {
+                    Some(symbol) if symbol.address == start_addr => symbol.clone(),
+                    _ => {
+                        let symbol = SymbolInfo {
+                            address: start_addr,
+                            size: Some(end_addr - start_addr),
+                            name: demangle::demangle_with_options(&name, &self.demangle_options),
+                        };
+                        previous_symbol = Some(symbol.clone());
+                        symbol
                     }
                 };
-            }
-        }
-        if frames.is_none() {
-            frames = self.frames_lookup_for_object_map_references(svma);
-        }
-        Some(SyncAddressInfo { symbol, frames })
+                let frames = self.lookup_frames(context.as_deref(), &mut path_mapper, svma);
+                Some(SyncAddressInfo { symbol, frames })
+            })
+            .collect()
+    }
+
+    fn folded_symbol_names(&self, symbol_address: u32) -> Vec<String> {
+        self.list
+            .folded_names
+            .get(&symbol_address)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn line_ranges(&self, address: u32, size: u32) -> Vec<LineRange> {
+        let Some(context) = self.context.as_ref() else {
+            return Vec::new();
+        };
+        let start_svma = self.image_base_address + u64::from(address);
+        let end_svma = start_svma + u64::from(size);
+        let context = context.lock().unwrap();
+        let Ok(iter) = context.find_location_range(start_svma, end_svma) else {
+            return Vec::new();
+        };
+        let mut path_mapper = self.path_mapper.lock().unwrap();
+        iter.filter_map(|(range_start, range_len, location)| {
+            // The first range can start before the requested range.
+            let range_end = (range_start + range_len).min(end_svma);
+            let range_start = range_start.max(start_svma);
+            let file = location.file?;
+            let line = location.line?;
+            let mapped_path = path_mapper.map_path(file);
+            Some(LineRange {
+                address: u32::try_from(range_start - self.image_base_address).ok()?,
+                size: u32::try_from(range_end.checked_sub(range_start)?).ok()?,
+                file_path: SourceFilePath::new(file.into(), mapped_path),
+                line,
+            })
+        })
+        .filter(|range| range.size != 0)
+        .collect()
     }
 }
 
@@ -604,7 +846,10 @@ impl<'data, 'map, Symbol: object::ObjectSymbol<'data>> Iterator
 }
 
 pub trait ObjectSymbolMapOuter<FC> {
-    fn make_symbol_map_inner(&self) -> Result<ObjectSymbolMapInnerWrapper<'_, FC>, Error>;
+    fn make_symbol_map_inner(
+        &self,
+        demangle_options: DemangleOptions,
+    ) -> Result<ObjectSymbolMapInnerWrapper<'_, FC>, Error>;
 }
 
 pub struct ObjectSymbolMap<FC: 'static, OSMO: ObjectSymbolMapOuter<FC>>(
@@ -612,10 +857,10 @@ pub struct ObjectSymbolMap<FC: 'static, OSMO: ObjectSymbolMapOuter<FC>>(
 );
 
 impl<FC, OSMO: ObjectSymbolMapOuter<FC> + 'static> ObjectSymbolMap<FC, OSMO> {
-    pub fn new(outer: OSMO) -> Result<Self, Error> {
+    pub fn new(outer: OSMO, demangle_options: DemangleOptions) -> Result<Self, Error> {
         let outer_and_inner = Yoke::<ObjectSymbolMapInnerWrapper<FC>, _>::try_attach_to_cart(
             Box::new(outer),
-            |outer| outer.make_symbol_map_inner(),
+            |outer| outer.make_symbol_map_inner(demangle_options),
         )?;
         Ok(ObjectSymbolMap(outer_and_inner))
     }
@@ -645,14 +890,17 @@ pub struct ObjectSymbolMapInnerWrapper<'data, FC>(
 );
 
 impl<'a, FC: FileContents + 'static> ObjectSymbolMapInnerWrapper<'a, FC> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<'file, O, Symbol, DDM>(
         object_file: &'file O,
         addr2line_context: Option<addr2line::Context<EndianSlice<'a, RunTimeEndian>>>,
         dwp_package: Option<addr2line::gimli::DwarfPackage<EndianSlice<'a, RunTimeEndian>>>,
         debug_id: DebugId,
+        additional_symbols: &[AdditionalSymbol<'a>],
         function_start_addresses: Option<&[u32]>,
         function_end_addresses: Option<&[u32]>,
         dwo_dwarf_maker: &'a DDM,
+        demangle_options: DemangleOptions,
     ) -> Self
     where
         'a: 'file,
@@ -664,8 +912,10 @@ impl<'a, FC: FileContents + 'static> ObjectSymbolMapInnerWrapper<'a, FC> {
         let list = SymbolList::new(
             object_file,
             base_address,
+            additional_symbols,
             function_start_addresses,
             function_end_addresses,
+            &demangle_options,
         );
 
         let inner = ObjectSymbolMapInner {
@@ -679,6 +929,7 @@ impl<'a, FC: FileContents + 'static> ObjectSymbolMapInnerWrapper<'a, FC> {
             svma_file_ranges: SvmaFileRanges::from_object(object_file),
             dwo_dwarf_maker,
             cached_external_file: Mutex::new(None),
+            demangle_options,
             _phantom: PhantomData,
         };
         Self(Box::new(inner))
@@ -733,6 +984,17 @@ where
     }
 }
 
+/// Parses the line program of the skeleton unit containing `svma`, using the
+/// main file's sections.
+///
+/// The line information of split DWARF units lives in the skeleton unit. But
+/// addr2line parses it lazily, and for the files of inlined calls it uses the
+/// DWO's sections for that, which fails and discards all frames. Parsing it
+/// before resuming the lookup with the DWO data avoids this.
+fn prime_skeleton_line_program<R: gimli::Reader>(context: &addr2line::Context<R>, svma: u64) {
+    let _ = context.find_location(svma);
+}
+
 impl ExternalFileAddressRef {
     fn with_split_dwarf_load(load: &SplitDwarfLoad<EndianSlice<RunTimeEndian>>, svma: u64) -> Self {
         let comp_dir = String::from_utf8_lossy(load.comp_dir.unwrap().slice()).to_string();
@@ -0,0 +1,160 @@
+use object::{File, FileKind, ObjectSymbol, SymbolKind};
+use yoke::Yoke;
+use yoke_derive::Yokeable;
+
+use crate::debugid_util::debug_id_for_object;
+use crate::demangle::DemangleOptions;
+use crate::dwarf::Addr2lineContextData;
+use crate::error::Error;
+use crate::shared::{FileAndPathHelper, FileContents, FileContentsWrapper};
+use crate::symbol_map::{SymbolFileKind, SymbolMap};
+use crate::symbol_map_object::{
+    ObjectSymbolMap, ObjectSymbolMapInnerWrapper, ObjectSymbolMapOuter,
+};
+
+/// Creates a symbol map for a WebAssembly module.
+///
+/// Function names come from the "name" custom section, and file / line
+/// information comes from the DWARF custom sections, if present. Addresses are
+/// offsets relative to the start of the code section, which is also what the
+/// DWARF info in Wasm files uses.
+pub fn get_symbol_map_for_wasm<H: FileAndPathHelper>(
+    file_contents: FileContentsWrapper<H::F>,
+    file_location: H::FL,
+    demangle_options: DemangleOptions,
+) -> Result<SymbolMap<H>, Error> {
+    let owner = WasmSymbolMapDataAndObject::new(file_contents)?;
+    let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
+    Ok(SymbolMap::new_plain(
+        file_location,
+        SymbolFileKind::Wasm,
+        Box::new(symbol_map),
+    ))
+}
+
+/// Parses the contents of a "build_id" custom section, which consists of a
+/// LEB128-encoded length followed by the build ID bytes.
+pub fn build_id_from_section_data(data: &[u8]) -> Option<&[u8]> {
+    let mut len: u64 = 0;
+    let mut shift = 0;
+    for (i, byte) in data.iter().enumerate() {
+        if shift >= 64 {
+            return None;
+        }
+        len |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            let rest = &data[i + 1..];
+            let len = usize::try_from(len).ok()?;
+            return rest.get(..len).filter(|build_id| !build_id.is_empty());
+        }
+        shift += 7;
+    }
+    None
+}
+
+/// Returns the start and end addresses of all function bodies, including those
+/// of functions which don't have an entry in the "name" section.
+fn compute_function_addresses_wasm<'data, O: object::Object<'data>>(
+    object_file: &O,
+) -> (Vec<u32>, Vec<u32>) {
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+    for symbol in object_file.symbols() {
+        if symbol.kind() != SymbolKind::Text || symbol.is_undefined() {
+            continue;
+        }
+        let (Ok(start), Ok(end)) = (
+            u32::try_from(symbol.address()),
+            u32::try_from(symbol.address() + symbol.size()),
+        ) else {
+            continue;
+        };
+        starts.push(start);
+        ends.push(end);
+    }
+    (starts, ends)
+}
+
+#[derive(Yokeable)]
+struct WasmObject<'data, T: FileContents> {
+    file_data: &'data FileContentsWrapper<T>,
+    object: File<'data, &'data FileContentsWrapper<T>>,
+    addr2line_context: Addr2lineContextData,
+}
+
+impl<'data, T: FileContents> WasmObject<'data, T> {
+    pub fn new(
+        file_data: &'data FileContentsWrapper<T>,
+        object: File<'data, &'data FileContentsWrapper<T>>,
+    ) -> Self {
+        Self {
+            file_data,
+            object,
+            addr2line_context: Addr2lineContextData::new(),
+        }
+    }
+}
+
+struct WasmSymbolMapDataAndObject<T: FileContents + 'static>(
+    Yoke<WasmObject<'static, T>, Box<FileContentsWrapper<T>>>,
+);
+impl<T: FileContents + 'static> WasmSymbolMapDataAndObject<T> {
+    pub fn new(file_data: FileContentsWrapper<T>) -> Result<Self, Error> {
+        let data_and_object = Yoke::try_attach_to_cart(
+            Box::new(file_data),
+            move |file_data| -> Result<WasmObject<'_, T>, Error> {
+                let object = File::parse(file_data)
+                    .map_err(|e| Error::ObjectParseError(FileKind::Wasm, e))?;
+                Ok(WasmObject::new(file_data, object))
+            },
+        )?;
+        Ok(Self(data_and_object))
+    }
+}
+
+impl<T: FileContents + 'static> ObjectSymbolMapOuter<T> for WasmSymbolMapDataAndObject<T> {
+    fn make_symbol_map_inner(
+        &self,
+        demangle_options: DemangleOptions,
+    ) -> Result<ObjectSymbolMapInnerWrapper<'_, T>, Error> {
+        let WasmObject {
+            file_data,
+            object,
+            addr2line_context,
+        } = &self.0.get();
+        let debug_id = debug_id_for_object(object)
+            .ok_or(Error::InvalidInputError("debug ID cannot be read"))?;
+        let (function_starts, function_ends) = compute_function_addresses_wasm(object);
+        let symbol_map = ObjectSymbolMapInnerWrapper::new(
+            object,
+            addr2line_context
+                .make_context(*file_data, object, None, None)
+                .ok(),
+            None,
+            debug_id,
+            &[],
+            Some(&function_starts),
+            Some(&function_ends),
+            &(),
+            demangle_options,
+        );
+
+        Ok(symbol_map)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn build_id_section() {
+        assert_eq!(
+            build_id_from_section_data(&[3, 0xab, 0xcd, 0xef]),
+            Some(&[0xab, 0xcd, 0xef][..])
+        );
+        assert_eq!(build_id_from_section_data(&[4, 0xab, 0xcd, 0xef]), None);
+        assert_eq!(build_id_from_section_data(&[0]), None);
+        assert_eq!(build_id_from_section_data(&[]), None);
+    }
+}
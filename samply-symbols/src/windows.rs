@@ -1,30 +1,32 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 
 use debugid::DebugId;
 use nom::bytes::complete::{tag, take_until1};
 use nom::combinator::eof;
 use nom::sequence::terminated;
-use object::{File, FileKind};
+use object::read::pe::{ImageNtHeaders, PeFile};
+use object::{File, FileKind, ReadRef};
 use pdb::PDB;
 use pdb_addr2line::pdb;
 use yoke::Yoke;
 use yoke_derive::Yokeable;
 
 use crate::debugid_util::debug_id_for_object;
+use crate::demangle::DemangleOptions;
 use crate::dwarf::Addr2lineContextData;
 use crate::error::{Context, Error};
 use crate::mapped_path::MappedPath;
 use crate::path_mapper::{ExtraPathMapper, PathMapper};
 use crate::shared::{
     FileAndPathHelper, FileContents, FileContentsWrapper, FileLocation, FrameDebugInfo,
-    FramesLookupResult, LookupAddress, SourceFilePath, SymbolInfo,
+    FramesLookupResult, LookupAddress, SourceFileInfo, SourceFilePath, SymbolInfo,
 };
-use crate::symbol_map::{GetInnerSymbolMap, SymbolMap, SymbolMapTrait};
+use crate::symbol_map::{GetInnerSymbolMap, SymbolFileKind, SymbolMap, SymbolMapTrait};
 use crate::symbol_map_object::{
-    ObjectSymbolMap, ObjectSymbolMapInnerWrapper, ObjectSymbolMapOuter,
+    AdditionalSymbol, ObjectSymbolMap, ObjectSymbolMapInnerWrapper, ObjectSymbolMapOuter,
 };
 use crate::{demangle, SyncAddressInfo};
 
@@ -33,6 +35,7 @@ pub async fn load_symbol_map_for_pdb_corresponding_to_binary<H: FileAndPathHelpe
     file_contents: &FileContentsWrapper<H::F>,
     file_location: H::FL,
     helper: &H,
+    demangle_options: DemangleOptions,
 ) -> Result<SymbolMap<H>, Error> {
     use object::Object;
     let pe =
@@ -52,8 +55,12 @@ pub async fn load_symbol_map_for_pdb_corresponding_to_binary<H: FileAndPathHelpe
     let pdb_file = helper
         .load_file(pdb_location)
         .await
-        .map_err(|e| Error::HelperErrorDuringOpenFile(pdb_path_str.to_string(), e))?;
-    let symbol_map = get_symbol_map_for_pdb(FileContentsWrapper::new(pdb_file), file_location)?;
+        .map_err(|e| Error::helper_error_during_open_file(pdb_path_str.to_string(), e))?;
+    let symbol_map = get_symbol_map_for_pdb(
+        FileContentsWrapper::new(pdb_file),
+        file_location,
+        demangle_options,
+    )?;
     if symbol_map.debug_id() != binary_debug_id {
         return Err(Error::UnmatchedDebugId(
             binary_debug_id,
@@ -68,11 +75,13 @@ pub fn get_symbol_map_for_pe<H: FileAndPathHelper>(
     file_kind: FileKind,
     file_location: H::FL,
     helper: Arc<H>,
+    demangle_options: DemangleOptions,
 ) -> Result<SymbolMap<H>, Error> {
     let owner = PeSymbolMapDataAndObject::new(file_contents, file_kind)?;
-    let symbol_map = ObjectSymbolMap::new(owner)?;
+    let symbol_map = ObjectSymbolMap::new(owner, demangle_options)?;
     Ok(SymbolMap::new_with_external_file_support(
         file_location,
+        SymbolFileKind::Pe,
         Box::new(symbol_map),
         helper,
     ))
@@ -116,7 +125,10 @@ impl<T: FileContents + 'static> PeSymbolMapDataAndObject<T> {
 }
 
 impl<T: FileContents + 'static> ObjectSymbolMapOuter<T> for PeSymbolMapDataAndObject<T> {
-    fn make_symbol_map_inner(&self) -> Result<ObjectSymbolMapInnerWrapper<T>, Error> {
+    fn make_symbol_map_inner(
+        &self,
+        demangle_options: DemangleOptions,
+    ) -> Result<ObjectSymbolMapInnerWrapper<T>, Error> {
         let PeObject {
             file_data,
             object,
@@ -125,6 +137,11 @@ impl<T: FileContents + 'static> ObjectSymbolMapOuter<T> for PeSymbolMapDataAndOb
         let debug_id = debug_id_for_object(object)
             .ok_or(Error::InvalidInputError("debug ID cannot be read"))?;
         let (function_starts, function_ends) = compute_function_addresses_pe(object);
+        let forwarded_exports = match object {
+            File::Pe32(pe) => forwarded_exports_pe(pe),
+            File::Pe64(pe) => forwarded_exports_pe(pe),
+            _ => Vec::new(),
+        };
         let symbol_map = ObjectSymbolMapInnerWrapper::new(
             object,
             addr2line_context
@@ -132,9 +149,11 @@ impl<T: FileContents + 'static> ObjectSymbolMapOuter<T> for PeSymbolMapDataAndOb
                 .ok(),
             None,
             debug_id,
+            &forwarded_exports,
             function_starts.as_deref(),
             function_ends.as_deref(),
             &(),
+            demangle_options,
         );
 
         Ok(symbol_map)
@@ -157,6 +176,37 @@ fn compute_function_addresses_pe<'data, O: object::Object<'data>>(
     }
 }
 
+/// Returns a symbol for each forwarded export, i.e. for each export whose
+/// address points at a forwarder string like `NTDLL.RtlAllocateHeap` in the
+/// export directory, rather than at code in this DLL.
+///
+/// The object crate leaves these out of the regular export list. We still want
+/// them in the symbol table so that the forwarder string's address isn't
+/// attributed to an unrelated symbol.
+fn forwarded_exports_pe<'data, Pe: ImageNtHeaders, R: ReadRef<'data>>(
+    pe: &PeFile<'data, Pe, R>,
+) -> Vec<AdditionalSymbol<'data>> {
+    use object::Object;
+    let Ok(Some(export_table)) = pe.export_table() else {
+        return Vec::new();
+    };
+    let image_base = pe.relative_address_base();
+    export_table
+        .name_iter()
+        .filter_map(|(name_pointer, address_index)| {
+            let address = export_table.address_by_index(address_index.into()).ok()?;
+            let target = export_table.forward_string(address).ok()??;
+            Some(AdditionalSymbol {
+                address: image_base + u64::from(address),
+                // Include the nul terminator.
+                size: target.len() as u64 + 1,
+                name: export_table.name_from_pointer(name_pointer).ok()?,
+                forwarded_to: Some(target),
+            })
+        })
+        .collect()
+}
+
 pub fn is_pdb_file<F: FileContents>(file: &FileContentsWrapper<F>) -> bool {
     PDB::open(file).is_ok()
 }
@@ -165,17 +215,46 @@ struct PdbObject<'data, FC: FileContents + 'static> {
     context_data: pdb_addr2line::ContextPdbData<'data, 'data, &'data FileContentsWrapper<FC>>,
     debug_id: DebugId,
     srcsrv_stream: Option<Box<dyn Deref<Target = [u8]> + Send + 'data>>,
+    folded_names: LazyFoldedNames<'data, FC>,
+    demangle_options: DemangleOptions,
+}
+
+/// The folded public function names of a PDB file, computed on first use.
+///
+/// Walking the public symbols is slow for big PDBs, and most consumers never
+/// ask for folded names, so this is not done when the file is opened.
+struct LazyFoldedNames<'data, FC: FileContents + 'static> {
+    file_data: &'data FileContentsWrapper<FC>,
+    demangle_options: DemangleOptions,
+    names: OnceLock<HashMap<u32, Vec<String>>>,
+}
+
+trait FoldedNames {
+    fn folded_names_at(&self, rva: u32) -> Vec<String>;
+}
+
+impl<FC: FileContents + 'static> FoldedNames for LazyFoldedNames<'_, FC> {
+    fn folded_names_at(&self, rva: u32) -> Vec<String> {
+        let names = self.names.get_or_init(|| {
+            // Folded names are optional information, so a PDB whose public
+            // symbols can't be read still gets a working symbol map.
+            PDB::open(self.file_data)
+                .and_then(|mut pdb| folded_public_function_names(&mut pdb, &self.demangle_options))
+                .unwrap_or_default()
+        });
+        names.get(&rva).cloned().unwrap_or_default()
+    }
 }
 
 trait PdbObjectTrait {
-    fn make_pdb_symbol_map(&self) -> Result<PdbSymbolMapInner<'_>, Error>;
+    fn make_pdb_symbol_map(&self) -> Result<Box<dyn SymbolMapTrait + Send + '_>, Error>;
 }
 
 #[derive(Yokeable)]
 pub struct PdbObjectWrapper<'data>(Box<dyn PdbObjectTrait + Send + 'data>);
 
 impl<FC: FileContents + 'static> PdbObjectTrait for PdbObject<'_, FC> {
-    fn make_pdb_symbol_map(&self) -> Result<PdbSymbolMapInner<'_>, Error> {
+    fn make_pdb_symbol_map(&self) -> Result<Box<dyn SymbolMapTrait + Send + '_>, Error> {
         let context = self.make_context()?;
 
         let path_mapper = match &self.srcsrv_stream {
@@ -190,8 +269,10 @@ impl<FC: FileContents + 'static> PdbObjectTrait for PdbObject<'_, FC> {
             context,
             debug_id: self.debug_id,
             path_mapper: Mutex::new(path_mapper),
+            folded_names: &self.folded_names,
+            demangle_options: self.demangle_options,
         };
-        Ok(symbol_map)
+        Ok(Box::new(symbol_map))
     }
 }
 
@@ -237,6 +318,8 @@ struct PdbSymbolMapInner<'object> {
     context: Box<dyn PdbAddr2lineContextTrait + Send + 'object>,
     debug_id: DebugId,
     path_mapper: Mutex<PathMapper<SrcSrvPathMapper<'object>>>,
+    folded_names: &'object (dyn FoldedNames + Sync),
+    demangle_options: DemangleOptions,
 }
 
 impl SymbolMapTrait for PdbSymbolMapInner<'_> {
@@ -244,6 +327,10 @@ impl SymbolMapTrait for PdbSymbolMapInner<'_> {
         self.debug_id
     }
 
+    fn demangle_options(&self) -> DemangleOptions {
+        self.demangle_options
+    }
+
     fn symbol_count(&self) -> usize {
         self.context.function_count()
     }
@@ -259,6 +346,20 @@ impl SymbolMapTrait for PdbSymbolMapInner<'_> {
         Box::new(iter)
     }
 
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        let iter = self.context.functions().map(|f| {
+            let start_rva = f.start_rva;
+            (
+                start_rva,
+                f.end_rva.map(|end_rva| end_rva - start_rva),
+                Cow::Owned(f.name.unwrap_or_else(|| format!("fun_{start_rva:x}"))),
+            )
+        });
+        Box::new(iter)
+    }
+
     fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
         let rva = match address {
             LookupAddress::Relative(rva) => rva,
@@ -276,7 +377,7 @@ impl SymbolMapTrait for PdbSymbolMapInner<'_> {
         let function_frames = self.context.find_frames(rva).ok()??;
         let symbol_address = function_frames.start_rva;
         let symbol_name = match &function_frames.frames.last().unwrap().function {
-            Some(name) => demangle::demangle_any(name),
+            Some(name) => demangle::demangle_with_options(name, &self.demangle_options),
             None => "unknown".to_string(),
         };
         let function_size = function_frames
@@ -310,6 +411,236 @@ impl SymbolMapTrait for PdbSymbolMapInner<'_> {
 
         Some(SyncAddressInfo { symbol, frames })
     }
+
+    fn folded_symbol_names(&self, symbol_address: u32) -> Vec<String> {
+        self.folded_names.folded_names_at(symbol_address)
+    }
+
+    fn source_file_info(&self, path: &str, extraction_base_path: &str) -> Option<SourceFileInfo> {
+        let path_mapper = self.path_mapper.lock().unwrap();
+        path_mapper
+            .extra_mapper()?
+            .source_file_info(path, extraction_base_path)
+    }
+}
+
+/// Returns the demangled names of public function symbols which share their address with
+/// other public function symbols, keyed by RVA. Multiple functions end up at the
+/// same address if the linker folds identical functions (`/OPT:ICF`). The PDB
+/// still has a public symbol for each of the original functions.
+fn folded_public_function_names<'s, S: pdb::Source<'s> + 's>(
+    pdb: &mut PDB<'s, S>,
+    demangle_options: &DemangleOptions,
+) -> Result<HashMap<u32, Vec<String>>, pdb::Error> {
+    let mut names_by_rva = public_function_names(pdb, demangle_options)?;
+    names_by_rva.retain(|_rva, names| names.len() > 1);
+    Ok(names_by_rva)
+}
+
+/// Returns the demangled names of all public function symbols, keyed by RVA.
+/// The names for each RVA are sorted and deduplicated.
+fn public_function_names<'s, S: pdb::Source<'s> + 's>(
+    pdb: &mut PDB<'s, S>,
+    demangle_options: &DemangleOptions,
+) -> Result<HashMap<u32, Vec<String>>, pdb::Error> {
+    use pdb::FallibleIterator;
+
+    let global_symbols = pdb.global_symbols()?;
+    let address_map = pdb.address_map()?;
+    let mut names_by_rva: HashMap<u32, Vec<String>> = HashMap::new();
+    let mut symbol_iter = global_symbols.iter();
+    while let Some(symbol) = symbol_iter.next()? {
+        let Ok(pdb::SymbolData::Public(symbol)) = symbol.parse() else {
+            continue;
+        };
+        if !symbol.function && !symbol.code {
+            continue;
+        }
+        let Some(rva) = symbol.offset.to_rva(&address_map) else {
+            continue;
+        };
+        names_by_rva
+            .entry(rva.0)
+            .or_default()
+            .push(demangle::demangle_with_options(
+                &symbol.name.to_string(),
+                demangle_options,
+            ));
+    }
+    for names in names_by_rva.values_mut() {
+        names.sort_unstable();
+        names.dedup();
+    }
+    Ok(names_by_rva)
+}
+
+/// The signature of the "minimal debug info" feature in the PDB info stream.
+/// Linkers write it for `/DEBUG:FASTLINK` PDBs.
+const PDB_FEATURE_MINIMAL_DEBUG_INFO: u32 = u32::from_le_bytes(*b"MINI");
+
+/// Returns whether the PDB was linked with `/DEBUG:FASTLINK`.
+///
+/// Such PDBs only contain public symbols. The rest of the debug info, i.e.
+/// function names, inlining and line information, stays in the object files,
+/// which are usually only present on the build machine.
+fn is_fastlink_pdb<'s, S: pdb::Source<'s> + 's>(pdb: &mut PDB<'s, S>) -> Result<bool, Error> {
+    let info_stream = pdb
+        .raw_stream(pdb::StreamIndex(1))
+        .context("raw_stream(PDB info)")?;
+    Ok(info_stream.is_some_and(|stream| {
+        pdb_info_feature_signatures(stream.as_slice())
+            .is_some_and(|mut features| features.any(|f| f == PDB_FEATURE_MINIMAL_DEBUG_INFO))
+    }))
+}
+
+/// Returns the feature signatures at the end of the PDB info stream, which
+/// follow the header and the named stream map.
+fn pdb_info_feature_signatures(data: &[u8]) -> Option<impl Iterator<Item = u32> + '_> {
+    let read_u32 = |pos: &mut usize| -> Option<u32> {
+        let bytes = data.get(*pos..(*pos).checked_add(4)?)?;
+        *pos += 4;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    // Skip version, signature, age and GUID.
+    let mut pos = 28;
+    let names_size = read_u32(&mut pos)?;
+    pos = pos.checked_add(names_size as usize)?;
+    let entry_count = read_u32(&mut pos)?;
+    let _capacity = read_u32(&mut pos)?;
+    let present_words = read_u32(&mut pos)?;
+    pos = pos.checked_add(present_words as usize * 4)?;
+    let deleted_words = read_u32(&mut pos)?;
+    pos = pos.checked_add(deleted_words as usize * 4)?;
+    pos = pos.checked_add(entry_count as usize * 8)?;
+    let features = data.get(pos..)?;
+    Some(
+        features
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())),
+    )
+}
+
+/// The parsed public symbols of a PDB which was linked with `/DEBUG:FASTLINK`,
+/// see [`is_fastlink_pdb`].
+///
+/// Following the module references to the object files isn't supported yet.
+struct FastlinkPdbObject {
+    debug_id: DebugId,
+    /// Sorted by RVA.
+    symbols: Vec<(u32, String)>,
+    folded_names: HashMap<u32, Vec<String>>,
+    demangle_options: DemangleOptions,
+}
+
+impl FastlinkPdbObject {
+    fn parse<'s, S: pdb::Source<'s> + 's>(
+        pdb: &mut PDB<'s, S>,
+        debug_id: DebugId,
+        demangle_options: DemangleOptions,
+    ) -> Result<Self, Error> {
+        let names_by_rva = public_function_names(pdb, &demangle_options)
+            .map_err(|_| Error::FastlinkPdbUnsupported)?;
+        let mut symbols: Vec<(u32, String)> = names_by_rva
+            .iter()
+            .map(|(rva, names)| (*rva, names[0].clone()))
+            .collect();
+        if symbols.is_empty() {
+            return Err(Error::FastlinkPdbUnsupported);
+        }
+        symbols.sort_unstable_by_key(|(rva, _)| *rva);
+        let mut folded_names = names_by_rva;
+        folded_names.retain(|_rva, names| names.len() > 1);
+
+        Ok(Self {
+            debug_id,
+            symbols,
+            folded_names,
+            demangle_options,
+        })
+    }
+}
+
+impl PdbObjectTrait for FastlinkPdbObject {
+    fn make_pdb_symbol_map(&self) -> Result<Box<dyn SymbolMapTrait + Send + '_>, Error> {
+        Ok(Box::new(FastlinkPdbSymbolMap(self)))
+    }
+}
+
+/// A symbol map for PDBs which were linked with `/DEBUG:FASTLINK`. It only
+/// knows about the public function symbols, so lookups return symbol names but
+/// no inline frames or line numbers.
+struct FastlinkPdbSymbolMap<'object>(&'object FastlinkPdbObject);
+
+impl FastlinkPdbSymbolMap<'_> {
+    fn symbol_size(&self, index: usize) -> Option<u32> {
+        let (rva, _) = self.0.symbols[index];
+        let (next_rva, _) = self.0.symbols.get(index + 1)?;
+        Some(next_rva - rva)
+    }
+}
+
+impl SymbolMapTrait for FastlinkPdbSymbolMap<'_> {
+    fn debug_id(&self) -> DebugId {
+        self.0.debug_id
+    }
+
+    fn demangle_options(&self) -> DemangleOptions {
+        self.0.demangle_options
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.0.symbols.len()
+    }
+
+    fn iter_symbols(&self) -> Box<dyn Iterator<Item = (u32, Cow<'_, str>)> + '_> {
+        Box::new(
+            self.0
+                .symbols
+                .iter()
+                .map(|(rva, name)| (*rva, Cow::Borrowed(name.as_str()))),
+        )
+    }
+
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        Box::new(
+            self.0
+                .symbols
+                .iter()
+                .enumerate()
+                .map(|(i, (rva, name))| (*rva, self.symbol_size(i), Cow::Borrowed(name.as_str()))),
+        )
+    }
+
+    fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
+        let LookupAddress::Relative(rva) = address else {
+            // See PdbSymbolMapInner::lookup_sync.
+            return None;
+        };
+        let index = match self.0.symbols.binary_search_by_key(&rva, |(rva, _)| *rva) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        let (symbol_rva, name) = &self.0.symbols[index];
+        Some(SyncAddressInfo {
+            symbol: SymbolInfo {
+                address: *symbol_rva,
+                size: self.symbol_size(index),
+                name: name.clone(),
+            },
+            frames: None,
+        })
+    }
+
+    fn folded_symbol_names(&self, symbol_address: u32) -> Vec<String> {
+        self.0
+            .folded_names
+            .get(&symbol_address)
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 
 fn box_stream<'data, T>(stream: T) -> Box<dyn Deref<Target = [u8]> + Send + 'data>
@@ -326,7 +657,7 @@ pub struct PdbObjectWithFileData<T: FileContents + 'static>(
 );
 
 impl<T: FileContents + 'static> PdbObjectWithFileData<T> {
-    fn new(file_data: PdbFileData<T>) -> Result<Self, Error> {
+    fn new(file_data: PdbFileData<T>, demangle_options: DemangleOptions) -> Result<Self, Error> {
         let data_and_object = Yoke::try_attach_to_cart(Box::new(file_data), |file_data| {
             let mut pdb = PDB::open(&file_data.0)?;
             let info = pdb.pdb_information().context("pdb_information")?;
@@ -334,6 +665,11 @@ impl<T: FileContents + 'static> PdbObjectWithFileData<T> {
             let age = dbi.age().unwrap_or(info.age);
             let debug_id = DebugId::from_parts(info.guid, age);
 
+            if is_fastlink_pdb(&mut pdb)? {
+                let pdb_object = FastlinkPdbObject::parse(&mut pdb, debug_id, demangle_options)?;
+                return Ok(PdbObjectWrapper(Box::new(pdb_object)));
+            }
+
             let srcsrv_stream = match pdb.named_stream(b"srcsrv") {
                 Ok(stream) => Some(box_stream(stream)),
                 Err(pdb::Error::StreamNameNotFound | pdb::Error::StreamNotFound(_)) => None,
@@ -347,6 +683,12 @@ impl<T: FileContents + 'static> PdbObjectWithFileData<T> {
                 context_data,
                 debug_id,
                 srcsrv_stream,
+                folded_names: LazyFoldedNames {
+                    file_data: &file_data.0,
+                    demangle_options,
+                    names: OnceLock::new(),
+                },
+                demangle_options,
             };
 
             Ok(PdbObjectWrapper(Box::new(pdb_object)))
@@ -366,7 +708,7 @@ impl<T: FileContents> PdbSymbolMap<T> {
             |outer| -> Result<PdbSymbolMapInnerWrapper<'_>, Error> {
                 let maker = outer.0.get().0.as_ref();
                 let symbol_map = maker.make_pdb_symbol_map()?;
-                Ok(PdbSymbolMapInnerWrapper(Box::new(symbol_map)))
+                Ok(PdbSymbolMapInnerWrapper(symbol_map))
             },
         )?;
         Ok(PdbSymbolMap(Mutex::new(outer_and_inner)))
@@ -391,6 +733,10 @@ impl<T: FileContents> SymbolMapTrait for PdbSymbolMap<T> {
         self.with_inner(|inner| inner.debug_id())
     }
 
+    fn demangle_options(&self) -> DemangleOptions {
+        self.with_inner(|inner| inner.demangle_options())
+    }
+
     fn symbol_count(&self) -> usize {
         self.with_inner(|inner| inner.symbol_count())
     }
@@ -406,19 +752,46 @@ impl<T: FileContents> SymbolMapTrait for PdbSymbolMap<T> {
         Box::new(vec.into_iter().map(|(addr, s)| (addr, Cow::Owned(s))))
     }
 
+    fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        let vec = self.with_inner(|inner| {
+            let vec: Vec<_> = inner
+                .iter_symbols_with_sizes()
+                .map(|(addr, size, s)| (addr, size, s.to_string()))
+                .collect();
+            vec
+        });
+        Box::new(
+            vec.into_iter()
+                .map(|(addr, size, s)| (addr, size, Cow::Owned(s))),
+        )
+    }
+
     fn lookup_sync(&self, address: LookupAddress) -> Option<SyncAddressInfo> {
         self.with_inner(|inner| inner.lookup_sync(address))
     }
+
+    fn folded_symbol_names(&self, symbol_address: u32) -> Vec<String> {
+        self.with_inner(|inner| inner.folded_symbol_names(symbol_address))
+    }
+
+    fn source_file_info(&self, path: &str, extraction_base_path: &str) -> Option<SourceFileInfo> {
+        self.with_inner(|inner| inner.source_file_info(path, extraction_base_path))
+    }
 }
 
 pub fn get_symbol_map_for_pdb<H: FileAndPathHelper>(
     file_contents: FileContentsWrapper<H::F>,
     debug_file_location: H::FL,
+    demangle_options: DemangleOptions,
 ) -> Result<SymbolMap<H>, Error> {
-    let file_data_and_object = PdbObjectWithFileData::new(PdbFileData(file_contents))?;
+    let file_data_and_object =
+        PdbObjectWithFileData::new(PdbFileData(file_contents), demangle_options)?;
     let symbol_map = PdbSymbolMap::new(file_data_and_object)?;
     Ok(SymbolMap::new_plain(
         debug_file_location,
+        SymbolFileKind::Pdb,
         Box::new(symbol_map),
     ))
 }
@@ -462,6 +835,33 @@ impl ExtraPathMapper for SrcSrvPathMapper<'_> {
 }
 
 impl<'a> SrcSrvPathMapper<'a> {
+    pub fn source_file_info(
+        &self,
+        path: &str,
+        extraction_base_path: &str,
+    ) -> Option<SourceFileInfo> {
+        match self
+            .srcsrv_stream
+            .source_for_path(path, extraction_base_path)
+            .ok()??
+        {
+            srcsrv::SourceRetrievalMethod::Download { url } => {
+                Some(SourceFileInfo::Download { url })
+            }
+            srcsrv::SourceRetrievalMethod::ExecuteCommand {
+                command,
+                env,
+                target_path,
+                ..
+            } => Some(SourceFileInfo::ExecuteCommand {
+                command,
+                env,
+                target_path,
+            }),
+            srcsrv::SourceRetrievalMethod::Other { .. } => None,
+        }
+    }
+
     pub fn new(srcsrv_stream: srcsrv::SrcSrvStream<'a>) -> Self {
         let command_is_file_download_with_url_in_var4_and_uncompress_function_in_var5 =
             Self::matches_chrome_gitiles_workaround(&srcsrv_stream);
@@ -638,4 +1038,48 @@ mod test {
             Err(nom::Err::Error(nom::error::Error::new("otherstuff", nom::error::ErrorKind::Eof)))
         );
     }
+
+    fn pdb_info_stream(features: &[u32]) -> Vec<u32> {
+        let mut words = vec![
+            20000404, // version
+            0,        // signature
+            1,        // age
+            0,        // GUID
+            0,
+            0,
+            0,
+            4, // names size
+            u32::from_le_bytes(*b"/na\0"),
+            1, // entry count
+            1, // capacity
+            1, // present bit words
+            1, // present bits
+            0, // deleted bit words
+            0, // name offset
+            5, // stream index
+        ];
+        words.extend_from_slice(features);
+        words
+    }
+
+    fn to_bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn detect_fastlink_feature() {
+        let data = to_bytes(&pdb_info_stream(&[
+            20140508,
+            PDB_FEATURE_MINIMAL_DEBUG_INFO,
+        ]));
+        let features: Vec<u32> = pdb_info_feature_signatures(&data).unwrap().collect();
+        assert_eq!(features, vec![20140508, PDB_FEATURE_MINIMAL_DEBUG_INFO]);
+
+        let data = to_bytes(&pdb_info_stream(&[20140508]));
+        let features: Vec<u32> = pdb_info_feature_signatures(&data).unwrap().collect();
+        assert_eq!(features, vec![20140508]);
+
+        // Truncated streams are not misdetected.
+        assert!(pdb_info_feature_signatures(&data[..40]).is_none());
+    }
 }
@@ -4,9 +4,10 @@ use std::path::{Path, PathBuf};
 
 use samply_symbols::debugid::DebugId;
 use samply_symbols::{
-    self, CandidatePathInfo, CompactSymbolTable, Error, FileAndPathHelper, FileAndPathHelperResult,
-    FileLocation, LibraryInfo, LookupAddress, MultiArchDisambiguator, OptionallySendFuture,
-    SymbolManager, SymbolMap,
+    self, write_breakpad_sym, BreakpadModuleInfo, CandidatePathInfo, CompactSymbolTable,
+    DemangleOptions, ElfBuildId, Error, FileAndPathHelper, FileAndPathHelperResult, FileLocation,
+    FramesLookupResult, LibraryInfo, LookupAddress, MultiArchDisambiguator, OptionallySendFuture,
+    SourceFileInfo, SourceFilePath, SymbolFileKind, SymbolManager, SymbolMap,
 };
 
 async fn get_symbol_map_with_dyld_cache_fallback(
@@ -116,12 +117,36 @@ impl FileLocation for FileLocationType {
         Some(Self(source_file_path.into()))
     }
 
+    fn location_for_dsym(&self) -> Option<Self> {
+        let file_name = self.0.file_name()?;
+        let mut dsym_name = file_name.to_owned();
+        dsym_name.push(".dSYM");
+        Some(Self(
+            self.0
+                .with_file_name(dsym_name)
+                .join("Contents")
+                .join("Resources")
+                .join("DWARF")
+                .join(file_name),
+        ))
+    }
+
     fn location_for_breakpad_symindex(&self) -> Option<Self> {
         Some(Self(self.0.with_extension("symindex")))
     }
 
-    fn location_for_dwo(&self, _comp_dir: &str, _path: &str) -> Option<Self> {
-        None // TODO
+    fn location_for_dwo(&self, comp_dir: &str, path: &str) -> Option<Self> {
+        let comp_dir = Path::new(comp_dir);
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return Some(Self(path.into()));
+        }
+        if comp_dir.is_absolute() {
+            return Some(Self(comp_dir.join(path)));
+        }
+        // Resolve relative paths with respect to the location of the debug file.
+        let base_path = self.0.parent()?;
+        Some(Self(base_path.join(comp_dir).join(path)))
     }
 
     fn location_for_dwp(&self) -> Option<Self> {
@@ -238,6 +263,29 @@ impl FileAndPathHelper for Helper {
             FileLocationType::new("/System/Library/dyld/dyld_shared_cache_x86_64"),
         ])
     }
+
+    fn get_candidate_paths_for_debug_file_by_build_id(
+        &self,
+        _original_file_location: &FileLocationType,
+        build_id: &ElfBuildId,
+    ) -> FileAndPathHelperResult<Vec<FileLocationType>> {
+        Ok(vec![FileLocationType(
+            self.symbol_directory.join(format!("{build_id}.debug")),
+        )])
+    }
+
+    fn get_fallback_locations_for_source_file(
+        &self,
+        _debug_file_location: &FileLocationType,
+        source_file_path: &SourceFilePath,
+    ) -> FileAndPathHelperResult<Vec<FileLocationType>> {
+        // Look for source files with the same file name in the symbol directory.
+        let file_name = Path::new(source_file_path.raw_path()).file_name();
+        Ok(file_name
+            .map(|file_name| FileLocationType(self.symbol_directory.join(file_name)))
+            .into_iter()
+            .collect())
+    }
 }
 
 fn fixtures_dir() -> PathBuf {
@@ -326,6 +374,33 @@ fn successful_pdb_unspecified_id() {
         );
 }
 
+#[test]
+fn pdb_srcsrv_source_file_info() {
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("win64-ci"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(symbol_manager.load_symbol_map_from_location(
+        FileLocationType(fixtures_dir().join("win64-ci").join("firefox.pdb")),
+        None,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        symbol_map.source_file_info(
+            "/builds/worker/checkouts/gecko/toolkit/xre/nsWindowsWMain.cpp",
+            "C:\\Sources"
+        ),
+        Some(SourceFileInfo::Download {
+            url: "https://hg.mozilla.org/mozilla-central/raw-file/1706d4d54ec68fae1280305b70a02cb24c16ff68/toolkit/xre/nsWindowsWMain.cpp".to_string()
+        })
+    );
+    assert_eq!(
+        symbol_map.source_file_info("/not/in/the/srcsrv/stream.cpp", "C:\\Sources"),
+        None
+    );
+}
+
 #[test]
 fn unsuccessful_pdb_wrong_id() {
     let result = futures::executor::block_on(crate::get_table(
@@ -587,3 +662,1502 @@ fn compare_snapshot() {
 
     assert_eq!(output, expected);
 }
+
+#[test]
+fn folded_symbol_names() {
+    // softokn3.dll was linked with identical code folding, so multiple functions
+    // share the same address. The PDB has public symbols for all of them.
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("win64-ci"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(symbol_manager.load_symbol_map_from_location(
+        FileLocationType(fixtures_dir().join("win64-ci").join("softokn3.pdb")),
+        None,
+    ))
+    .unwrap();
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0xf1b0))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.address, 0xf1b0);
+    let folded_names = symbol_map.folded_symbol_names(symbol.address);
+    assert_eq!(
+        folded_names,
+        vec!["C_GetFunctionList", "NSC_GetFunctionList"]
+    );
+    assert_eq!(symbol.name, "NSC_GetFunctionList(CK_FUNCTION_LIST**)");
+
+    // The same works for exports in the binary itself.
+    let symbol_map = futures::executor::block_on(symbol_manager.load_symbol_map_from_location(
+        FileLocationType(fixtures_dir().join("win64-ci").join("firefox.exe")),
+        None,
+    ))
+    .unwrap();
+    assert_eq!(
+        symbol_map.folded_symbol_names(0x8cd0),
+        vec!["TargetEnumDisplayDevicesA", "TargetGetStockObject"]
+    );
+    assert!(symbol_map.folded_symbol_names(0x8ce0).is_empty());
+}
+
+#[test]
+fn fastlink_pdb_falls_back_to_public_symbols() {
+    // Turn softokn3.pdb into a /DEBUG:FASTLINK PDB by replacing its "VC140"
+    // feature signature with "MINI". Lookups should then only return the
+    // public symbol names, without frames.
+    let pdb_path = fixtures_dir().join("win64-ci").join("softokn3.pdb");
+    let vc140 = 20140508u32.to_le_bytes();
+    let mut patched_bytes = std::fs::read(&pdb_path).unwrap();
+    let mut patch_count = 0;
+    for i in 0..patched_bytes.len() - 4 {
+        if patched_bytes[i..i + 4] == vc140 {
+            patched_bytes[i..i + 4].copy_from_slice(b"MINI");
+            patch_count += 1;
+        }
+    }
+    assert_ne!(patch_count, 0);
+
+    let dir = std::env::temp_dir().join(format!("fastlink-pdb-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let patched_pdb_path = dir.join("softokn3.pdb");
+    std::fs::write(&patched_pdb_path, &patched_bytes).unwrap();
+
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(patched_pdb_path), None),
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+    let fastlink_symbol_map = symbol_map.unwrap();
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(pdb_path), None),
+    )
+    .unwrap();
+    assert_eq!(fastlink_symbol_map.debug_id(), symbol_map.debug_id());
+
+    // Find a function for which the full PDB has frames.
+    let (address, info) = symbol_map
+        .iter_symbols()
+        .find_map(|(address, _name)| {
+            let info = symbol_map.lookup_sync(LookupAddress::Relative(address))?;
+            info.frames.is_some().then_some((address, info))
+        })
+        .unwrap();
+    let fastlink_info = fastlink_symbol_map
+        .lookup_sync(LookupAddress::Relative(address))
+        .unwrap();
+    assert_eq!(fastlink_info.symbol.address, info.symbol.address);
+    assert!(!fastlink_info.symbol.name.is_empty());
+    assert!(fastlink_info.frames.is_none());
+
+    // Folded names come from the public symbols, so they're still available.
+    let info = fastlink_symbol_map
+        .lookup_sync(LookupAddress::Relative(0xf1b0))
+        .unwrap();
+    assert_eq!(info.symbol.address, 0xf1b0);
+    assert_eq!(info.symbol.name, "C_GetFunctionList");
+    assert_eq!(
+        fastlink_symbol_map.folded_symbol_names(0xf1b0),
+        vec!["C_GetFunctionList", "NSC_GetFunctionList"]
+    );
+}
+
+#[test]
+fn pe_forwarded_export() {
+    // This is a hand-written DLL which exports Foo and Bar, and forwards its
+    // HeapAlloc export to NTDLL.RtlAllocateHeap.
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("other").join("pe-forwarder"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(
+            FileLocationType(
+                fixtures_dir()
+                    .join("other")
+                    .join("pe-forwarder")
+                    .join("forwarder.dll"),
+            ),
+            None,
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        symbol_map.debug_id(),
+        DebugId::from_breakpad("131211101514171618191A1B1C1D1E1F1").unwrap()
+    );
+
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x1012))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.address, 0x1010);
+    assert_eq!(symbol.name, "Bar");
+
+    // The export address of HeapAlloc points at the forwarder string.
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x2066))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.address, 0x2066);
+    assert_eq!(
+        symbol.name,
+        "HeapAlloc (forwarded to NTDLL.RtlAllocateHeap)"
+    );
+    assert_eq!(symbol.size, Some(22));
+}
+
+#[test]
+fn wasm_name_section() {
+    // This is a hand-written module with three functions. The "name" section
+    // only has names for the first and the third function.
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("other"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(symbol_manager.load_symbol_map_from_location(
+        FileLocationType(fixtures_dir().join("other").join("example.wasm")),
+        None,
+    ))
+    .unwrap();
+    assert_eq!(
+        symbol_map.debug_id(),
+        DebugId::from_breakpad("131211101514171618191A1B1C1D1E1F0").unwrap()
+    );
+    assert_eq!(symbol_map.symbol_count(), 2);
+
+    // Addresses are relative to the start of the code section.
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x3))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "first");
+    assert_eq!(symbol.address, 0x2);
+    assert_eq!(symbol.size, Some(4));
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x8))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "fun_7");
+    assert_eq!(symbol.size, Some(3));
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0xb))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "third");
+    assert_eq!(symbol.size, Some(5));
+    assert_eq!(symbol_map.lookup_sync(LookupAddress::Relative(0x10)), None);
+}
+
+fn check_split_dwarf_lookup(binary_name: &str) {
+    let dir = fixtures_dir().join("other").join("splitdwarf");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(dir.join(binary_name)), None),
+    )
+    .unwrap();
+
+    // 0x113a is the inlined call to add_one inside compute.
+    let address_info =
+        futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(0x113a))).unwrap();
+    assert_eq!(address_info.symbol.name, "compute");
+    let frames = address_info.frames.unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].function.as_deref(), Some("add_one"));
+    assert_eq!(frames[0].line_number, Some(4));
+    assert_eq!(frames[1].function.as_deref(), Some("compute"));
+    assert_eq!(frames[1].line_number, Some(8));
+    for frame in &frames {
+        assert_eq!(
+            frame.file_path.as_ref().unwrap().raw_path(),
+            "./splitdwarf.c"
+        );
+    }
+}
+
+#[test]
+fn split_dwarf_dwo() {
+    // Built with -gsplit-dwarf; the debug info is in splitdwarf-dwo.dwo, which
+    // is referenced relative to the (relative) comp_dir.
+    check_split_dwarf_lookup("splitdwarf-dwo");
+}
+
+#[test]
+fn split_dwarf_dwp() {
+    // Same as above, but the .dwo was packaged into splitdwarf-dwp.dwp.
+    check_split_dwarf_lookup("splitdwarf-dwp");
+}
+
+fn check_compressed_dwarf_lookup(binary_name: &str) {
+    let dir = fixtures_dir().join("other").join("compressed-dwarf");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(dir.join(binary_name)), None),
+    )
+    .unwrap();
+
+    // 0x113a is the inlined call to add_one inside compute.
+    let address_info =
+        futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(0x113a))).unwrap();
+    assert_eq!(address_info.symbol.name, "compute");
+    let frames = address_info.frames.unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].function.as_deref(), Some("add_one"));
+    assert_eq!(frames[0].line_number, Some(9));
+    assert_eq!(frames[1].function.as_deref(), Some("compute"));
+    assert_eq!(frames[1].line_number, Some(13));
+}
+
+#[test]
+fn compressed_dwarf_zstd() {
+    // The debug sections have SHF_COMPRESSED set and use ELFCOMPRESS_ZSTD.
+    check_compressed_dwarf_lookup("compressed-zstd");
+}
+
+#[test]
+fn compressed_dwarf_zdebug() {
+    // The debug sections use the older GNU-style .zdebug_* naming, with zlib.
+    check_compressed_dwarf_lookup("compressed-zdebug");
+}
+
+#[test]
+fn debug_frame_function_starts() {
+    // This binary was stripped of its symbol table, and the functions from
+    // debugframe.c only have unwind info in .debug_frame, not in .eh_frame.
+    let dir = fixtures_dir().join("other").join("debug-frame");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let mut symbol_manager = SymbolManager::with_helper(helper);
+
+    // Without the best effort flag, .debug_frame is not used.
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("debugframe")), None),
+    )
+    .unwrap();
+    assert_eq!(
+        symbol_map.lookup_sync(LookupAddress::Relative(0x1139)),
+        None
+    );
+
+    symbol_manager.set_best_effort_function_ranges(true);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("debugframe")), None),
+    )
+    .unwrap();
+
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x1139))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "fun_1139");
+    assert_eq!(symbol.size, Some(0x6));
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x1140))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "fun_113f");
+    assert_eq!(symbol.address, 0x113f);
+    assert_eq!(symbol.size, Some(0x17));
+}
+
+#[test]
+fn macho_binary_prefers_dsym() {
+    // libmozglue.dylib only has a symbol table; the DWARF is in the dSYM bundle
+    // next to it.
+    let dir = fixtures_dir().join("macos-ci");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("libmozglue.dylib")), None),
+    )
+    .unwrap();
+    assert_eq!(
+        symbol_map.debug_id(),
+        DebugId::from_breakpad("64EC2645330C3A0BA6E4EBCD28A1B5940").unwrap()
+    );
+    assert!(symbol_map
+        .debug_file_location()
+        .0
+        .ends_with("libmozglue.dylib.dSYM/Contents/Resources/DWARF/libmozglue.dylib"));
+
+    let address_info =
+        futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(0x28b84))).unwrap();
+    let frames = address_info.frames.unwrap();
+    let outer_frame = frames.last().unwrap();
+    assert_eq!(
+        outer_frame.file_path.as_ref().unwrap().raw_path(),
+        "/builds/worker/checkouts/gecko/mozglue/baseprofiler/core/ProfilerMarkerPayload.cpp"
+    );
+    assert_eq!(outer_frame.line_number, Some(362));
+}
+
+#[test]
+fn symbol_file_kind() {
+    let dir = fixtures_dir();
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let kind_for_path = |path: PathBuf| {
+        futures::executor::block_on(
+            symbol_manager.load_symbol_map_from_location(FileLocationType(path), None),
+        )
+        .unwrap()
+        .symbol_file_kind()
+    };
+    assert_eq!(
+        kind_for_path(dir.join("win64-ci").join("firefox.pdb")),
+        SymbolFileKind::Pdb
+    );
+    assert_eq!(
+        kind_for_path(dir.join("win64-ci").join("firefox.exe")),
+        SymbolFileKind::Pe
+    );
+    assert_eq!(
+        kind_for_path(dir.join("linux64-ci").join("firefox")),
+        SymbolFileKind::Elf
+    );
+    assert_eq!(
+        kind_for_path(dir.join("macos-ci").join("libsoftokn3.dylib")),
+        SymbolFileKind::MachO
+    );
+    assert_eq!(
+        kind_for_path(
+            dir.join("macos-ci")
+                .join("libmozglue.dylib.dSYM/Contents/Resources/DWARF/libmozglue.dylib")
+        ),
+        SymbolFileKind::Dsym
+    );
+    assert_eq!(
+        kind_for_path(dir.join("other").join("gsym").join("example.gsym")),
+        SymbolFileKind::Gsym
+    );
+    assert_eq!(
+        kind_for_path(dir.join("other").join("example.wasm")),
+        SymbolFileKind::Wasm
+    );
+}
+
+#[test]
+fn macho_binary_with_mismatched_dsym() {
+    // Put the dSYM of libmozglue.dylib next to libsoftokn3.dylib, so that the
+    // dSYM's UUID doesn't match the binary.
+    let dir = std::env::temp_dir().join(format!(
+        "samply-symbols-mismatched-dsym-{}",
+        std::process::id()
+    ));
+    let dwarf_dir = dir
+        .join("libsoftokn3.dylib.dSYM")
+        .join("Contents")
+        .join("Resources")
+        .join("DWARF");
+    std::fs::create_dir_all(&dwarf_dir).unwrap();
+    let fixtures = fixtures_dir().join("macos-ci");
+    std::fs::copy(
+        fixtures.join("libsoftokn3.dylib"),
+        dir.join("libsoftokn3.dylib"),
+    )
+    .unwrap();
+    std::fs::copy(
+        fixtures
+            .join("libmozglue.dylib.dSYM")
+            .join("Contents")
+            .join("Resources")
+            .join("DWARF")
+            .join("libmozglue.dylib"),
+        dwarf_dir.join("libsoftokn3.dylib"),
+    )
+    .unwrap();
+
+    let symbol_manager = SymbolManager::with_helper(Helper {
+        symbol_directory: dir.clone(),
+    });
+    let result = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("libsoftokn3.dylib")), None),
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        matches!(result, Err(samply_symbols::Error::UnmatchedDebugId(..))),
+        "{:?}",
+        result.map(|symbol_map| symbol_map.debug_id())
+    );
+}
+
+#[test]
+fn debug_file_by_build_id() {
+    // The binary was stripped and has no .gnu_debuglink section. Its debug
+    // info is in a separate file which is named after its build ID.
+    let dir = fixtures_dir().join("other").join("build-id");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(dir.join("buildid")), None),
+    )
+    .unwrap();
+
+    let address_info =
+        futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(0x113a))).unwrap();
+    assert_eq!(address_info.symbol.name, "triple");
+    assert_eq!(address_info.symbol.address, 0x1139);
+    let frames = address_info.frames.unwrap();
+    assert_eq!(frames[0].function.as_deref(), Some("triple"));
+    assert_eq!(
+        frames[0].file_path.as_ref().unwrap().raw_path(),
+        "./buildid.c"
+    );
+}
+
+#[test]
+fn source_file_fallback_location() {
+    let dir = fixtures_dir().join("other").join("compressed-dwarf");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let debug_file_location = FileLocationType(dir.join("compressed-zstd"));
+
+    // The path from the build machine doesn't exist here, so the source file is
+    // found via the helper's fallback location.
+    let source = futures::executor::block_on(symbol_manager.load_source_file(
+        &debug_file_location,
+        &SourceFilePath::new("/builds/worker/compressed.c".to_string(), None),
+    ))
+    .unwrap();
+    assert!(source.contains("add_one"));
+
+    assert!(futures::executor::block_on(symbol_manager.load_source_file(
+        &debug_file_location,
+        &SourceFilePath::new("/builds/worker/nonexistent.c".to_string(), None),
+    ))
+    .is_err());
+}
+
+#[test]
+fn elf_symbol_sizes() {
+    let dir = fixtures_dir().join("other").join("compressed-dwarf");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("compressed-zstd")), None),
+    )
+    .unwrap();
+
+    // compute and main have their size in st_size.
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x1150))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "compute");
+    assert_eq!(symbol.address, 0x1139);
+    assert_eq!(symbol.size, Some(27));
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x1154))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "main");
+    assert_eq!(symbol.size, Some(14));
+
+    // frame_dummy has a zero st_size, so its size is approximated from the
+    // start of the next symbol.
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x1134))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "frame_dummy");
+    assert_eq!(symbol.address, 0x1130);
+    assert_eq!(symbol.size, Some(9));
+}
+
+#[cfg(feature = "swift_demangle")]
+#[test]
+fn swift_symbol_names() {
+    // There is no mach-O fixture built with swiftc, so give two exported
+    // functions of libsoftokn3.dylib Swift names of the same length in the
+    // symbol table.
+    let binary_path = fixtures_dir().join("macos-ci").join("libsoftokn3.dylib");
+    let mut data = std::fs::read(&binary_path).unwrap();
+    for (name, swift_name) in [
+        (
+            &b"_NSC_GetFunctionList\0"[..],
+            &b"_$s4main3FooC3baryyF\0"[..],
+        ),
+        (
+            &b"_NSC_GetInterfaceList\0"[..],
+            &b"_$s4main3addyS2i_SitF\0"[..],
+        ),
+    ] {
+        let offset = data
+            .windows(name.len())
+            .position(|window| window == name)
+            .unwrap();
+        data[offset..offset + name.len()].copy_from_slice(swift_name);
+    }
+
+    let dir = std::env::temp_dir().join(format!("swift-symbols-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let patched_path = dir.join("libsoftokn3.dylib");
+    std::fs::write(&patched_path, &data).unwrap();
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(patched_path), None),
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+    let symbol_map = symbol_map.unwrap();
+
+    let symbol_name = |address| {
+        symbol_map
+            .lookup_sync(LookupAddress::Relative(address))
+            .unwrap()
+            .symbol
+            .name
+    };
+    assert_eq!(symbol_name(0x11010), "main.Foo.bar() -> ()");
+    assert_eq!(
+        symbol_name(0x11030),
+        "main.add(Swift.Int, Swift.Int) -> Swift.Int"
+    );
+    assert_eq!(symbol_name(0x11020), "C_GetFunctionList");
+}
+
+#[cfg(feature = "swift_demangle")]
+#[test]
+fn swift_symbol_names_are_not_demangled_outside_macho() {
+    // swift-symbols is an ELF file whose function names look like Swift names.
+    let dir = fixtures_dir().join("other").join("swift");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("swift-symbols")), None),
+    )
+    .unwrap();
+
+    let symbol_name = |address| {
+        symbol_map
+            .lookup_sync(LookupAddress::Relative(address))
+            .unwrap()
+            .symbol
+            .name
+    };
+    assert_eq!(symbol_name(0x1129), "$s4main3FooC3baryyF");
+    assert_eq!(symbol_name(0x112a), "$s4main3FooC5countSivg");
+    assert_eq!(symbol_name(0x1130), "$s4main3addyS2i_SitF");
+}
+
+#[test]
+fn gsym_corrupt_line_table() {
+    // Make every address in example.gsym point to a function info whose line
+    // table has min_delta = i64::MIN and max_delta = i64::MAX, so that computing
+    // the line range overflows.
+    let dir = fixtures_dir().join("other").join("gsym");
+    let mut data = std::fs::read(dir.join("example.gsym")).unwrap();
+    let num_addresses = u32::from_le_bytes(data[16..20].try_into().unwrap()) as usize;
+    // The address offsets are 2 bytes each, the address info offsets start at the
+    // next 4-byte boundary.
+    let addr_info_offsets_start = (48 + num_addresses * 2 + 3) / 4 * 4;
+    let mut line_table = vec![0x80; 9];
+    line_table.push(0x7f); // min_delta: i64::MIN
+    line_table.extend([0xff; 9]);
+    line_table.push(0x00); // max_delta: i64::MAX
+    line_table.extend([1, 4, 0]); // first_line, one special op, end of sequence
+    data.resize((data.len() + 3) / 4 * 4, 0);
+    let function_info_offset = data.len() as u32;
+    data.extend(0x1000u32.to_le_bytes()); // size
+    data.extend(0u32.to_le_bytes()); // name
+    data.extend(1u32.to_le_bytes()); // info type: line table
+    data.extend((line_table.len() as u32).to_le_bytes());
+    data.extend(&line_table);
+    data.extend([0; 8]); // info type: end of list
+    for index in 0..num_addresses {
+        let offset = addr_info_offsets_start + index * 4;
+        data[offset..offset + 4].copy_from_slice(&function_info_offset.to_le_bytes());
+    }
+    let temp_path = std::env::temp_dir().join(format!("corrupt-{}.gsym", std::process::id()));
+    std::fs::write(&temp_path, &data).unwrap();
+
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(temp_path.clone()), None),
+    );
+    let _ = std::fs::remove_file(&temp_path);
+    let symbol_map = symbol_map.unwrap();
+
+    // The lookup must not panic. The function is still found, just without
+    // line information.
+    let address_info = symbol_map.lookup_sync(LookupAddress::Relative(0x113a));
+    assert!(address_info.is_some());
+}
+
+#[test]
+fn gsym_lookup() {
+    // example.gsym was converted from an ELF binary with llvm-gsymutil.
+    let dir = fixtures_dir().join("other").join("gsym");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("example.gsym")), None),
+    )
+    .unwrap();
+    assert_eq!(
+        symbol_map.debug_id(),
+        DebugId::from_breakpad("BF19081853F06CD71766D5B05D49743E0").unwrap()
+    );
+    assert_eq!(symbol_map.symbol_count(), 9);
+
+    // 0x113a is the inlined call to add_one inside compute.
+    let address_info =
+        futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(0x113a))).unwrap();
+    assert_eq!(address_info.symbol.name, "compute");
+    assert_eq!(address_info.symbol.address, 0x1139);
+    assert_eq!(address_info.symbol.size, Some(0x1b));
+    let frames = address_info.frames.unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].function.as_deref(), Some("add_one"));
+    assert_eq!(frames[0].line_number, Some(8));
+    assert_eq!(frames[1].function.as_deref(), Some("compute"));
+    assert_eq!(frames[1].line_number, Some(12));
+    for frame in &frames {
+        assert_eq!(
+            frame.file_path.as_ref().unwrap().raw_path(),
+            "././example.c"
+        );
+    }
+
+    // The line table has rows for both line 14 and line 15 at 0x1150; like
+    // with DWARF, the last row wins.
+    let address_info =
+        futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(0x1150))).unwrap();
+    let frames = address_info.frames.unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].function.as_deref(), Some("compute"));
+    assert_eq!(frames[0].line_number, Some(15));
+}
+
+#[test]
+fn symbol_map_cache() {
+    // Work on copies of the fixtures, so that we can check that cached symbol
+    // maps don't need the file anymore.
+    let dir = std::env::temp_dir().join(format!(
+        "samply-symbols-symbol-map-cache-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["compressed-zstd", "compressed-zdebug"] {
+        std::fs::copy(
+            fixtures_dir()
+                .join("other")
+                .join("compressed-dwarf")
+                .join(name),
+            dir.join(name),
+        )
+        .unwrap();
+    }
+
+    let mut symbol_manager = SymbolManager::with_helper(Helper {
+        symbol_directory: dir.clone(),
+    });
+    symbol_manager.set_symbol_map_cache_capacity(1);
+    let load = |debug_name: &str| {
+        let debug_id = futures::executor::block_on(
+            symbol_manager.load_symbol_map_from_location(
+                FileLocationType(
+                    fixtures_dir()
+                        .join("other")
+                        .join("compressed-dwarf")
+                        .join(debug_name),
+                ),
+                None,
+            ),
+        )
+        .unwrap()
+        .debug_id();
+        futures::executor::block_on(symbol_manager.load_symbol_map(&LibraryInfo {
+            debug_name: Some(debug_name.to_string()),
+            debug_id: Some(debug_id),
+            ..Default::default()
+        }))
+    };
+
+    assert!(load("compressed-zstd").is_ok());
+    std::fs::remove_file(dir.join("compressed-zstd")).unwrap();
+    let symbol_map = load("compressed-zstd").unwrap();
+    assert_eq!(
+        symbol_map
+            .lookup_sync(LookupAddress::Relative(0x113a))
+            .unwrap()
+            .symbol
+            .name,
+        "compute"
+    );
+
+    // Loading a different library evicts the first one.
+    assert!(load("compressed-zdebug").is_ok());
+    assert!(load("compressed-zstd").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn merge_compact_symbol_tables() {
+    fn table(symbols: &[(u32, &str)]) -> CompactSymbolTable {
+        let mut table = CompactSymbolTable {
+            addr: Vec::new(),
+            index: Vec::new(),
+            buffer: Vec::new(),
+        };
+        for (address, name) in symbols {
+            table.addr.push(*address);
+            table.index.push(table.buffer.len() as u32);
+            table.buffer.extend_from_slice(name.as_bytes());
+        }
+        table.index.push(table.buffer.len() as u32);
+        table
+    }
+
+    let merged = table(&[(0x10, "a"), (0x30, "c"), (0x50, "e_x86_64")]).merge(table(&[
+        (0x20, "b"),
+        (0x50, "e_arm64"),
+        (0x60, "f"),
+    ]));
+    let mut output = Vec::new();
+    dump_table(&mut output, merged, true).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&output).unwrap(),
+        "Found 5 symbols.\n10 a\n20 b\n30 c\n50 e_x86_64\n60 f\n"
+    );
+}
+
+#[test]
+fn compact_symbol_table_for_addresses() {
+    let dir = fixtures_dir().join("other").join("compressed-dwarf");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("compressed-zstd")), None),
+    )
+    .unwrap();
+
+    // 0x0 is before the first symbol, 0x1140 and 0x1150 are both in compute,
+    // and 0x1154 is the first byte of main.
+    let table = CompactSymbolTable::from_symbol_map_for_addresses(
+        &symbol_map,
+        &[0x1154, 0x1150, 0x0, 0x1140],
+    );
+    let mut output = Vec::new();
+    dump_table(&mut output, table, true).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&output).unwrap(),
+        "Found 2 symbols.\n1139 compute\n1154 main\n"
+    );
+}
+
+#[test]
+fn mini_debug_info() {
+    // This library is stripped. The internal functions are only listed in the
+    // symbol table of the compressed ELF in the .gnu_debugdata section, and
+    // exported_function is only in .dynsym.
+    let dir = fixtures_dir().join("other").join("minidebuginfo");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("libminidebuginfo.so")), None),
+    )
+    .unwrap();
+    assert_eq!(
+        symbol_map.debug_id(),
+        DebugId::from_breakpad("0EB44C7527F1972F9A72D00DBA5952750").unwrap()
+    );
+
+    let symbol = |address| {
+        symbol_map
+            .lookup_sync(LookupAddress::Relative(address))
+            .unwrap()
+            .symbol
+    };
+    assert_eq!(symbol(0x10fa).name, "internal_helper");
+    assert_eq!(symbol(0x10fa).address, 0x10f9);
+    assert_eq!(symbol(0x10fa).size, Some(5));
+    assert_eq!(symbol(0x1100).name, "another_internal_helper");
+    assert_eq!(symbol(0x1108).name, "exported_function");
+}
+
+#[test]
+fn iter_symbols_with_sizes() {
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("other"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(symbol_manager.load_symbol_map_from_location(
+        FileLocationType(fixtures_dir().join("other").join("example.wasm")),
+        None,
+    ))
+    .unwrap();
+    let symbols: Vec<_> = symbol_map
+        .iter_symbols_with_sizes()
+        .map(|(address, size, name)| (address, size, name.into_owned()))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            (0x0, Some(2), "EntryPoint".to_string()),
+            (0x2, Some(4), "first".to_string()),
+            (0x7, Some(3), "fun_7".to_string()),
+            (0xb, Some(5), "third".to_string()),
+        ]
+    );
+
+    // The sizes should match the sizes returned by lookups.
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("win64-ci"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(symbol_manager.load_symbol_map_from_location(
+        FileLocationType(fixtures_dir().join("win64-ci").join("firefox.pdb")),
+        None,
+    ))
+    .unwrap();
+    assert_eq!(
+        symbol_map.iter_symbols_with_sizes().count(),
+        symbol_map.iter_symbols().count()
+    );
+    for (address, size, _name) in symbol_map.iter_symbols_with_sizes().step_by(50) {
+        let symbol = symbol_map
+            .lookup_sync(LookupAddress::Relative(address))
+            .unwrap()
+            .symbol;
+        assert_eq!(symbol.address, address);
+        assert_eq!(symbol.size, size);
+    }
+}
+
+#[test]
+fn lookup_symbol_name() {
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("android32-ci"),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(
+            FileLocationType(
+                fixtures_dir()
+                    .join("android32-ci")
+                    .join("libmozglue.so.dbg"),
+            ),
+            None,
+        ),
+    )
+    .unwrap();
+
+    let address = symbol_map
+        .lookup_symbol_name("_Z17getLibraryMappingv")
+        .unwrap();
+    assert_eq!(address, 0x68c91);
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(address))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.address, address);
+    assert_eq!(symbol.name, "getLibraryMapping()");
+    assert_eq!(
+        symbol_map.lookup_symbol_name("getLibraryMapping()"),
+        Some(address)
+    );
+    assert_eq!(symbol_map.lookup_symbol_name("getLibraryMapping"), None);
+}
+
+#[test]
+fn demangle_options() {
+    let helper = Helper {
+        symbol_directory: fixtures_dir().join("android32-ci"),
+    };
+    let mut symbol_manager = SymbolManager::with_helper(helper);
+    symbol_manager.set_demangle_options(DemangleOptions {
+        rust: false,
+        cpp: false,
+        keep_rust_hash: false,
+        ocaml: false,
+        swift: false,
+    });
+    let symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(
+            FileLocationType(
+                fixtures_dir()
+                    .join("android32-ci")
+                    .join("libmozglue.so.dbg"),
+            ),
+            None,
+        ),
+    )
+    .unwrap();
+
+    let symbol = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x68c91))
+        .unwrap()
+        .symbol;
+    assert_eq!(symbol.name, "_Z17getLibraryMappingv");
+    assert_eq!(symbol_map.lookup_symbol_name("getLibraryMapping()"), None);
+}
+
+#[test]
+fn coff_object_file() {
+    // An unlinked object file with one function per section. Addresses are
+    // file offsets, because every section in an object file starts at zero.
+    let dir = fixtures_dir().join("other").join("coff");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("example-coff.obj")), None),
+    )
+    .unwrap();
+    assert_eq!(symbol_map.symbol_count(), 3);
+    assert_eq!(
+        symbol_map.iter_symbols_with_sizes().collect::<Vec<_>>(),
+        vec![
+            (0x1a4, Some(5), "internal_helper".into()),
+            (0x1a9, Some(17), "?compute@@YAHH@Z".into()),
+            (0x1cc, Some(20), "main".into()),
+        ]
+    );
+
+    let symbol = |address| {
+        symbol_map
+            .lookup_sync(LookupAddress::Relative(address))
+            .unwrap()
+            .symbol
+    };
+    assert_eq!(symbol(0x1a4).name, "internal_helper");
+    assert_eq!(symbol(0x1b0).name, "compute(int)");
+    assert_eq!(symbol(0x1b0).address, 0x1a9);
+    assert_eq!(
+        symbol_map
+            .lookup_sync(LookupAddress::FileOffset(0x1d0))
+            .unwrap()
+            .symbol
+            .name,
+        "main"
+    );
+    // 0x1ba..0x1cc is unwind info, not code.
+    assert_eq!(symbol_map.lookup_sync(LookupAddress::Relative(0x1c0)), None);
+    assert_eq!(symbol_map.lookup_sync(LookupAddress::Svma(0x0)), None);
+}
+
+#[test]
+fn jitdump_debug_info() {
+    // See make-jitdump.py for the contents of this file.
+    let dir = fixtures_dir().join("other").join("jitdump");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("jit-1234.dump")), None),
+    )
+    .unwrap();
+    assert_eq!(symbol_map.symbol_count(), 2);
+
+    let lookup = |address| {
+        let address_info = symbol_map
+            .lookup_sync(LookupAddress::Relative(address))
+            .unwrap();
+        let frames = match address_info.frames {
+            Some(FramesLookupResult::Available(frames)) => Some(frames),
+            Some(FramesLookupResult::External(_)) => {
+                panic!("jitdump files have no external debug info")
+            }
+            None => None,
+        };
+        (address_info.symbol, frames)
+    };
+
+    // JS:add is at relative address 0x0 and has debug info.
+    for (address, expected_line) in [(0x0, 10), (0x4, 10), (0x18, 11), (0x3f, 12)] {
+        let (symbol, frames) = lookup(address);
+        assert_eq!(symbol.name, "JS:add");
+        assert_eq!(symbol.address, 0);
+        assert_eq!(symbol.size, Some(0x40));
+        let frames = frames.unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].function.as_deref(), Some("JS:add"));
+        assert_eq!(
+            frames[0].file_path.as_ref().map(|p| p.raw_path()),
+            Some("/home/user/app/add.js")
+        );
+        assert_eq!(
+            frames[0].line_number,
+            Some(expected_line),
+            "address {address:#x}"
+        );
+    }
+
+    // JS:noDebugInfo comes right after JS:add, and has no debug info.
+    let (symbol, frames) = lookup(0x48);
+    assert_eq!(symbol.name, "JS:noDebugInfo");
+    assert_eq!(symbol.address, 0x40);
+    assert!(frames.is_none());
+
+    assert!(symbol_map
+        .lookup_sync(LookupAddress::Relative(0x60))
+        .is_none());
+}
+
+#[test]
+fn incremental_jitdump_symbol_map() {
+    use samply_symbols::linux_perf_data::jitdump::JitDumpReader;
+    use samply_symbols::{IncrementalJitDumpSymbolMap, SymbolMapTrait};
+
+    let path = fixtures_dir()
+        .join("other")
+        .join("jitdump")
+        .join("jit-1234.dump");
+    let mut reader = JitDumpReader::new(File::open(&path).unwrap()).unwrap();
+    let incremental_map = IncrementalJitDumpSymbolMap::new(reader.header());
+    let symbol_map = incremental_map.to_symbol_map::<Helper>(FileLocationType(path.clone()));
+
+    // The first record is the debug info record for JS:add, which doesn't add a symbol.
+    let record = reader.next_record().unwrap().unwrap();
+    incremental_map.add_record(&record).unwrap();
+    assert_eq!(symbol_map.symbol_count(), 0);
+    assert!(symbol_map
+        .lookup_sync(LookupAddress::Relative(0x18))
+        .is_none());
+
+    // The code load record for JS:add.
+    let record = reader.next_record().unwrap().unwrap();
+    incremental_map.add_record(&record).unwrap();
+    assert_eq!(symbol_map.symbol_count(), 1);
+    let address_info = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x18))
+        .unwrap();
+    assert_eq!(address_info.symbol.name, "JS:add");
+    let Some(FramesLookupResult::Available(frames)) = address_info.frames else {
+        panic!("Expected debug info for JS:add");
+    };
+    assert_eq!(frames[0].line_number, Some(11));
+    // JS:noDebugInfo hasn't been added yet.
+    assert!(symbol_map
+        .lookup_sync(LookupAddress::Relative(0x48))
+        .is_none());
+
+    // The code load record for JS:noDebugInfo.
+    let record = reader.next_record().unwrap().unwrap();
+    incremental_map.add_record(&record).unwrap();
+    assert!(reader.next_record().unwrap().is_none());
+    let address_info = symbol_map
+        .lookup_sync(LookupAddress::Relative(0x48))
+        .unwrap();
+    assert_eq!(address_info.symbol.name, "JS:noDebugInfo");
+    assert_eq!(address_info.symbol.address, 0x40);
+    assert!(address_info.frames.is_none());
+
+    // The result matches the symbol map for the complete file.
+    let helper = Helper {
+        symbol_directory: path.parent().unwrap().to_path_buf(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let full_symbol_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(path.clone()), None),
+    )
+    .unwrap();
+    assert_eq!(incremental_map.debug_id(), full_symbol_map.debug_id());
+    assert_eq!(
+        symbol_map.iter_symbols().collect::<Vec<_>>(),
+        full_symbol_map.iter_symbols().collect::<Vec<_>>()
+    );
+}
+
+/// A helper which only knows about a symbol server, and which "downloads"
+/// files from it by mapping URLs to files in a local directory.
+struct SymbolServerHelper {
+    server_directory: Option<PathBuf>,
+}
+
+impl FileAndPathHelper for SymbolServerHelper {
+    type F = FileContentsType;
+    type FL = FileLocationType;
+
+    fn get_candidate_paths_for_debug_file(
+        &self,
+        library_info: &LibraryInfo,
+    ) -> FileAndPathHelperResult<Vec<CandidatePathInfo<Self::FL>>> {
+        let (Some(debug_name), Some(debug_id)) = (&library_info.debug_name, library_info.debug_id)
+        else {
+            return Ok(Vec::new());
+        };
+        Ok(vec![CandidatePathInfo::Url(format!(
+            "https://symbols.example.com/{debug_name}/{}/{debug_name}",
+            debug_id.breakpad()
+        ))])
+    }
+
+    fn get_candidate_paths_for_binary(
+        &self,
+        _library_info: &LibraryInfo,
+    ) -> FileAndPathHelperResult<Vec<CandidatePathInfo<Self::FL>>> {
+        Ok(Vec::new())
+    }
+
+    fn get_dyld_shared_cache_paths(
+        &self,
+        _arch: Option<&str>,
+    ) -> FileAndPathHelperResult<Vec<FileLocationType>> {
+        Ok(Vec::new())
+    }
+
+    fn location_for_url(&self, url: &str) -> Option<FileLocationType> {
+        let server_directory = self.server_directory.as_ref()?;
+        let path = url.strip_prefix("https://symbols.example.com/")?;
+        Some(FileLocationType(server_directory.join(path)))
+    }
+
+    fn load_file(
+        &self,
+        location: Self::FL,
+    ) -> std::pin::Pin<Box<dyn OptionallySendFuture<Output = FileAndPathHelperResult<Self::F>> + '_>>
+    {
+        Box::pin(async move {
+            let file = File::open(&location.0)?;
+            let mmap = unsafe { memmap2::MmapOptions::new().map(&file)? };
+            Ok(mmap_to_file_contents(mmap))
+        })
+    }
+}
+
+#[test]
+fn url_candidate_path() {
+    let server_directory = std::env::temp_dir().join(format!(
+        "samply-symbols-url-candidate-{}",
+        std::process::id()
+    ));
+    let debug_id = DebugId::from_breakpad("AA152DEB2D9B76084C4C44205044422E1").unwrap();
+    let file_dir = server_directory
+        .join("firefox.pdb")
+        .join(debug_id.breakpad().to_string());
+    std::fs::create_dir_all(&file_dir).unwrap();
+    std::fs::copy(
+        fixtures_dir().join("win64-ci").join("firefox.pdb"),
+        file_dir.join("firefox.pdb"),
+    )
+    .unwrap();
+    let library_info = LibraryInfo {
+        debug_name: Some("firefox.pdb".to_string()),
+        debug_id: Some(debug_id),
+        ..Default::default()
+    };
+
+    let symbol_manager = SymbolManager::with_helper(SymbolServerHelper {
+        server_directory: Some(server_directory.clone()),
+    });
+    let symbol_map =
+        futures::executor::block_on(symbol_manager.load_symbol_map(&library_info)).unwrap();
+    assert_eq!(symbol_map.debug_id(), debug_id);
+
+    // A helper which can't load URLs reports the skipped URL in its error.
+    let symbol_manager = SymbolManager::with_helper(SymbolServerHelper {
+        server_directory: None,
+    });
+    let err = futures::executor::block_on(symbol_manager.load_symbol_map(&library_info))
+        .err()
+        .unwrap();
+    assert_eq!(err.enum_as_string(), "HelperRefusedUrl");
+    assert!(err
+        .to_string()
+        .contains("https://symbols.example.com/firefox.pdb/"));
+
+    std::fs::remove_dir_all(&server_directory).unwrap();
+}
+
+#[test]
+fn debug_id_for_binary() {
+    let data = std::fs::read(fixtures_dir().join("win64-local").join("mozglue.dll")).unwrap();
+    assert_eq!(
+        samply_symbols::debug_id_for_binary_data(&data[..]),
+        DebugId::from_breakpad("B3CC644ECC086E044C4C44205044422E1").ok()
+    );
+
+    // Look up an ELF binary by its code ID, without knowing its debug ID.
+    let dir = fixtures_dir().join("other").join("build-id");
+    let symbol_manager = SymbolManager::with_helper(Helper {
+        symbol_directory: dir.clone(),
+    });
+    let info = LibraryInfo {
+        name: Some("buildid".to_string()),
+        code_id: "11e0891e3c873aa0b3bfca68b7069aea02615ae1".parse().ok(),
+        ..Default::default()
+    };
+    let debug_id = futures::executor::block_on(symbol_manager.debug_id_for_binary(&info)).unwrap();
+    let data = std::fs::read(dir.join("buildid")).unwrap();
+    assert_eq!(
+        Some(debug_id),
+        samply_symbols::debug_id_for_binary_data(&data[..])
+    );
+    assert_eq!(
+        debug_id.breakpad().to_string(),
+        "1E89E011873CA03AB3BFCA68B7069AEA0"
+    );
+}
+
+#[test]
+fn symbolicate_absolute_address() {
+    let symbol_manager = SymbolManager::with_helper(Helper {
+        symbol_directory: fixtures_dir().join("win64-ci"),
+    });
+    let firefox = LibraryInfo {
+        debug_name: Some("firefox.pdb".to_string()),
+        debug_id: DebugId::from_breakpad("AA152DEB2D9B76084C4C44205044422E1").ok(),
+        ..Default::default()
+    };
+    let other = LibraryInfo {
+        debug_name: Some("other.pdb".to_string()),
+        debug_id: DebugId::from_breakpad("B3CC644ECC086E044C4C44205044422E1").ok(),
+        base_address: Some(0x7ff7_0000_0000),
+        ..Default::default()
+    };
+    let modules = [
+        (firefox, 0x7ff6_0000_0000..0x7ff6_0010_0000),
+        (other, 0x7ff7_0000_1000..0x7ff7_0010_0000),
+    ];
+
+    let address_info =
+        futures::executor::block_on(symbol_manager.symbolicate_address(&modules, 0x7ff6_0003_1fc8))
+            .unwrap();
+    assert_eq!(address_info.symbol.address, 0x31fc0);
+    assert_eq!(
+        address_info.symbol.name,
+        "sandbox::ProcessMitigationsWin32KDispatcher::EnumDisplayMonitors(sandbox::IPCInfo*, sandbox::CountedBuffer*)"
+    );
+
+    // Not covered by any module.
+    assert!(futures::executor::block_on(
+        symbol_manager.symbolicate_address(&modules, 0x7ff6_0010_0000)
+    )
+    .is_none());
+    // Covered by a module whose symbols can't be found.
+    assert!(futures::executor::block_on(
+        symbol_manager.symbolicate_address(&modules, 0x7ff7_0000_2000)
+    )
+    .is_none());
+}
+
+#[test]
+fn symbolicate_absolute_address_reuses_symbol_map() {
+    // Work on a copy of the fixture, so that we can check that the symbol map
+    // doesn't need the file anymore.
+    let dir = std::env::temp_dir().join(format!(
+        "samply-symbols-symbolicate-address-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(
+        fixtures_dir()
+            .join("other")
+            .join("compressed-dwarf")
+            .join("compressed-zstd"),
+        dir.join("compressed-zstd"),
+    )
+    .unwrap();
+
+    let mut symbol_manager = SymbolManager::with_helper(Helper {
+        symbol_directory: dir.clone(),
+    });
+    symbol_manager.set_symbol_map_cache_capacity(1);
+    let debug_id = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("compressed-zstd")), None),
+    )
+    .unwrap()
+    .debug_id();
+    let modules = [(
+        LibraryInfo {
+            debug_name: Some("compressed-zstd".to_string()),
+            debug_id: Some(debug_id),
+            ..Default::default()
+        },
+        0x5555_0000_0000..0x5555_0001_0000,
+    )];
+    let symbolicate = |address| {
+        futures::executor::block_on(symbol_manager.symbolicate_address(&modules, address))
+            .map(|address_info| address_info.symbol.name)
+    };
+
+    assert_eq!(symbolicate(0x5555_0000_113a).as_deref(), Some("compute"));
+    std::fs::remove_file(dir.join("compressed-zstd")).unwrap();
+    assert_eq!(symbolicate(0x5555_0000_1154).as_deref(), Some("main"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn lookup_addresses() {
+    let dir = fixtures_dir().join("other").join("splitdwarf");
+    let helper = Helper {
+        symbol_directory: dir.clone(),
+    };
+    let symbol_manager = SymbolManager::with_helper(helper);
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("splitdwarf-dwo")), None),
+    )
+    .unwrap();
+
+    // Unsorted, with a duplicate and an address outside of any symbol.
+    let addresses = [0x113a, 0x0, 0x1130, 0x113a];
+    let results = futures::executor::block_on(symbol_map.lookup_addresses(&addresses));
+    assert_eq!(results.len(), addresses.len());
+    for (&address, result) in addresses.iter().zip(&results) {
+        let expected =
+            futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(address)));
+        assert_eq!(result, &expected, "address {address:#x}");
+    }
+    assert_eq!(results[1], None);
+    let frames = results[0].as_ref().unwrap().frames.as_ref().unwrap();
+    assert_eq!(frames[0].function.as_deref(), Some("add_one"));
+}
+
+#[test]
+fn lookup_addresses_matches_lookup_for_all_symbols() {
+    let dir = fixtures_dir().join("macos-ci");
+    let symbol_manager = SymbolManager::with_helper(Helper {
+        symbol_directory: dir.clone(),
+    });
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("libsoftokn3.dylib")), None),
+    )
+    .unwrap();
+
+    // The start, the middle and the last byte of every symbol, and the address
+    // right after it, in reverse order.
+    let mut addresses: Vec<u32> = symbol_map
+        .iter_symbols_with_sizes()
+        .flat_map(|(address, size, _)| {
+            let size = size.unwrap_or(1).max(1);
+            [
+                address,
+                address + size / 2,
+                address + size - 1,
+                address + size,
+            ]
+        })
+        .collect();
+    addresses.reverse();
+    assert!(addresses.len() > 1000);
+
+    let results = futures::executor::block_on(symbol_map.lookup_addresses(&addresses));
+    assert_eq!(results.len(), addresses.len());
+    for (&address, result) in addresses.iter().zip(&results) {
+        let expected =
+            futures::executor::block_on(symbol_map.lookup(LookupAddress::Relative(address)));
+        assert_eq!(result, &expected, "address {address:#x}");
+    }
+}
+
+#[test]
+fn breakpad_sym_round_trip_from_dwarf() {
+    let dir = fixtures_dir().join("other").join("compressed-dwarf");
+    let symbol_manager = SymbolManager::with_helper(Helper {
+        symbol_directory: dir.clone(),
+    });
+    let symbol_map = futures::executor::block_on(
+        symbol_manager
+            .load_symbol_map_from_location(FileLocationType(dir.join("compressed-zstd")), None),
+    )
+    .unwrap();
+
+    let sym_path = std::env::temp_dir().join(format!(
+        "samply-symbols-breakpad-round-trip-{}.sym",
+        std::process::id()
+    ));
+    let module_info = BreakpadModuleInfo {
+        os: "Linux",
+        arch: "x86_64",
+        debug_name: "compressed-zstd",
+    };
+    write_breakpad_sym(
+        &symbol_map,
+        &module_info,
+        BufWriter::new(File::create(&sym_path).unwrap()),
+    )
+    .unwrap();
+    let written_map = futures::executor::block_on(
+        symbol_manager.load_symbol_map_from_location(FileLocationType(sym_path.clone()), None),
+    )
+    .unwrap();
+    std::fs::remove_file(&sym_path).unwrap();
+    assert_eq!(written_map.debug_id(), symbol_map.debug_id());
+
+    // The written file has no INLINE records, so each address resolves to a
+    // single frame with the line of the innermost frame of the original.
+    let location = |frames: Option<FramesLookupResult>| match frames {
+        Some(FramesLookupResult::Available(frames)) => frames.first().map(|frame| {
+            (
+                frame.file_path.as_ref().unwrap().raw_path().to_string(),
+                frame.line_number.unwrap(),
+            )
+        }),
+        _ => None,
+    };
+    let mut checked_functions = Vec::new();
+    for (address, size, name) in symbol_map.iter_symbols_with_sizes() {
+        let Some(size) = size else {
+            continue;
+        };
+        if symbol_map.line_ranges(address, size).is_empty() {
+            continue;
+        }
+        for address in address..address + size {
+            let original = symbol_map
+                .lookup_sync(LookupAddress::Relative(address))
+                .unwrap();
+            let written = written_map
+                .lookup_sync(LookupAddress::Relative(address))
+                .unwrap();
+            assert_eq!(written.symbol.name, original.symbol.name);
+            assert_eq!(location(written.frames), location(original.frames));
+        }
+        checked_functions.push(name.to_string());
+    }
+    assert!(checked_functions.contains(&"compute".to_string()));
+    assert!(checked_functions.contains(&"main".to_string()));
+}
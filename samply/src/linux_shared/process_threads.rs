@@ -1,7 +1,8 @@
 use std::collections::hash_map::Entry;
 
 use fxprof_processed_profile::{
-    CategoryHandle, Frame, FrameFlags, FrameInfo, ProcessHandle, Profile, ThreadHandle, Timestamp,
+    CategoryHandle, Frame, FrameFlags, FrameInfo, ProcessHandle, Profile, SourceLocation,
+    ThreadHandle, Timestamp,
 };
 
 use super::thread::Thread;
@@ -76,8 +77,12 @@ impl ProcessThreads {
                     }
                 }
 
-                let thread_handle =
-                    profile.add_thread(self.profile_process, tid as u32, start_time, false);
+                let thread_handle = profile.add_thread(
+                    self.profile_process,
+                    u64::from(tid as u32),
+                    start_time,
+                    false,
+                );
                 if let Some(name) = &name {
                     profile.set_thread_name(thread_handle, name);
                 }
@@ -173,7 +178,7 @@ impl ProcessThreads {
         self.threads_by_tid.entry(tid).or_insert_with(|| {
             let profile_thread = profile.add_thread(
                 self.profile_process,
-                tid as u32,
+                u64::from(tid as u32),
                 Timestamp::from_millis_since_reference(0.0),
                 false,
             );
@@ -219,5 +224,7 @@ pub fn make_thread_label_frame(
         frame: Frame::Label(thread_label),
         category_pair: CategoryHandle::OTHER.into(),
         flags: FrameFlags::empty(),
+        implementation: None,
+        source_location: SourceLocation::default(),
     }
 }
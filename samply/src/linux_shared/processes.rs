@@ -91,11 +91,11 @@ where
                 let fallback_name = format!("<{pid}>");
                 let process_handle = profile.add_process(
                     name.as_deref().unwrap_or(&fallback_name),
-                    pid as u32,
+                    u64::from(pid as u32),
                     start_time,
                 );
                 let main_thread_handle =
-                    profile.add_thread(process_handle, pid as u32, start_time, true);
+                    profile.add_thread(process_handle, u64::from(pid as u32), start_time, true);
                 if let Some(name) = name.as_deref() {
                     profile.set_thread_name(main_thread_handle, name);
                 }
@@ -149,9 +149,9 @@ where
         self.processes_by_pid.entry(pid).or_insert_with(|| {
             let fake_start_time = Timestamp::from_millis_since_reference(0.0);
             let process_handle =
-                profile.add_process(&format!("<{pid}>"), pid as u32, fake_start_time);
+                profile.add_process(&format!("<{pid}>"), u64::from(pid as u32), fake_start_time);
             let main_thread_handle =
-                profile.add_thread(process_handle, pid as u32, fake_start_time, true);
+                profile.add_thread(process_handle, u64::from(pid as u32), fake_start_time, true);
             let main_thread_label_frame = make_thread_label_frame(profile, None, pid, pid);
             let (thread_recycler, jit_function_recycler) = if self.process_recycler.is_some() {
                 (
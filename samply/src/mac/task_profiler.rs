@@ -13,7 +13,7 @@ use framehop::{
 use fxprof_processed_profile::debugid::DebugId;
 use fxprof_processed_profile::{
     CategoryHandle, Frame, FrameFlags, FrameInfo, LibraryInfo, ProcessHandle, Profile,
-    ThreadHandle, Timestamp,
+    SourceLocation, ThreadHandle, Timestamp,
 };
 use mach::mach_types::{thread_act_port_array_t, thread_act_t};
 use mach::message::mach_msg_type_number_t;
@@ -208,9 +208,9 @@ impl TaskProfiler {
                 )
             }
             None => {
-                let profile_process = profile.add_process(&name, pid, start_time);
+                let profile_process = profile.add_process(&name, pid.into(), start_time);
                 let main_thread_handle =
-                    profile.add_thread(profile_process, main_thread_tid, start_time, true);
+                    profile.add_thread(profile_process, main_thread_tid.into(), start_time, true);
                 if let Some(main_thread_name) = &main_thread_name {
                     profile.set_thread_name(main_thread_handle, main_thread_name);
                 }
@@ -262,7 +262,7 @@ impl TaskProfiler {
                         (profile_thread, thread_label_frame)
                     } else {
                         let profile_thread =
-                            profile.add_thread(profile_process, tid, start_time, false);
+                            profile.add_thread(profile_process, tid.into(), start_time, false);
                         profile.set_thread_name(profile_thread, name);
                         let thread_label_frame =
                             make_thread_label_frame(profile, Some(name), pid, tid);
@@ -270,7 +270,7 @@ impl TaskProfiler {
                     }
                 } else {
                     let profile_thread =
-                        profile.add_thread(profile_process, tid, start_time, false);
+                        profile.add_thread(profile_process, tid.into(), start_time, false);
                     if let Some(name) = &name {
                         profile.set_thread_name(profile_thread, name);
                     }
@@ -393,16 +393,24 @@ impl TaskProfiler {
                                 {
                                     profile_thread
                                 } else {
-                                    let profile_thread =
-                                        profile.add_thread(self.profile_process, tid, now, false);
+                                    let profile_thread = profile.add_thread(
+                                        self.profile_process,
+                                        tid.into(),
+                                        now,
+                                        false,
+                                    );
                                     profile.set_thread_name(profile_thread, name);
                                     let thread_label_frame =
                                         make_thread_label_frame(profile, Some(name), self.pid, tid);
                                     (profile_thread, thread_label_frame)
                                 }
                             } else {
-                                let profile_thread =
-                                    profile.add_thread(self.profile_process, tid, now, false);
+                                let profile_thread = profile.add_thread(
+                                    self.profile_process,
+                                    tid.into(),
+                                    now,
+                                    false,
+                                );
                                 if let Some(name) = &name {
                                     profile.set_thread_name(profile_thread, name);
                                 }
@@ -814,5 +822,7 @@ fn make_thread_label_frame(
         frame: Frame::Label(thread_label),
         category_pair: CategoryHandle::OTHER.into(),
         flags: FrameFlags::empty(),
+        implementation: None,
+        source_location: SourceLocation::default(),
     }
 }
@@ -14,6 +14,7 @@ mod profile_json_preparse;
 mod server;
 mod shared;
 
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::BufReader;
@@ -30,8 +31,11 @@ use mac::profiler;
 // To avoid warnings about unused declarations
 #[cfg(target_os = "macos")]
 pub use mac::{kernel_error, thread_act, thread_info};
-use profile_json_preparse::parse_libinfo_map_from_profile_file;
-use server::{start_server_main, PortSelection, ServerProps};
+use profile_json_preparse::parse_libinfo_map_and_summary_from_profile_file;
+use server::{
+    start_server_main, start_server_main_with_profiles, PortSelection, ServerProps, TlsProps,
+    TokenSelection,
+};
 use shared::included_processes::IncludedProcesses;
 use shared::recording_props::{
     CoreClrProfileProps, ProcessLaunchProps, ProfileCreationProps, RecordingMode, RecordingProps,
@@ -100,8 +104,10 @@ enum Action {
 
 #[derive(Debug, Args)]
 struct LoadArgs {
-    /// Path to the file that should be loaded.
-    file: PathBuf,
+    /// Paths to the files that should be loaded. If more than one file is
+    /// given, the server's start page links to all of them.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
 
     #[command(flatten)]
     server_args: ServerArgs,
@@ -288,7 +294,8 @@ struct ServerArgs {
     #[arg(short, long)]
     no_open: bool,
 
-    /// The address to use for the local web server
+    /// The address to use for the local web server. Use 0.0.0.0 to listen on
+    /// all interfaces, e.g. when running inside a container.
     #[arg(long, default_value = "127.0.0.1")]
     address: String,
 
@@ -296,6 +303,30 @@ struct ServerArgs {
     #[arg(short = 'P', long, default_value = "3000+")]
     port: String,
 
+    /// Serve over HTTPS, using the certificate chain in this PEM file.
+    /// Requires samply to be built with the "tls" feature.
+    #[arg(long, requires = "tls_key", value_name = "PATH")]
+    tls_cert: Option<PathBuf>,
+
+    /// The PEM file with the private key for --tls-cert.
+    #[arg(long, requires = "tls_cert", value_name = "PATH")]
+    tls_key: Option<PathBuf>,
+
+    /// Use this token as the secret path prefix of the server's URLs, instead
+    /// of a random one.
+    #[arg(long, conflicts_with = "no_token")]
+    token: Option<String>,
+
+    /// Serve without a secret path prefix. Anyone who can reach the server
+    /// can then access the profile, so only use this on a trusted network.
+    #[arg(long)]
+    no_token: bool,
+
+    /// Accept profile uploads even with --no-token. Without a token, anyone
+    /// who can reach the server can then replace the served profile.
+    #[arg(long, requires = "no_token")]
+    allow_upload_without_token: bool,
+
     /// Print debugging output.
     #[arg(short, long)]
     verbose: bool,
@@ -331,6 +362,14 @@ struct SymbolArgs {
     /// Extra directory containing symbol files, with the directory structure used by simpleperf's scripts
     #[arg(long)]
     simpleperf_binary_cache: Option<PathBuf>,
+
+    /// Binaries which aren't listed in the profile but should be symbolicated, for example dynamically loaded plugins
+    #[arg(long)]
+    extra_lib: Vec<PathBuf>,
+
+    /// Maximum time that loading a single symbol file may take, including downloads, e.g. "90s" or "10m"
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "10m")]
+    symbol_load_timeout: Duration,
 }
 
 #[derive(Debug, Args, Clone)]
@@ -415,29 +454,40 @@ fn main() {
     let opt = Opt::parse();
     match opt.action {
         Action::Load(load_args) => {
-            let profile_filename = &load_args.file;
-            let input_file = match File::open(profile_filename) {
-                Ok(file) => file,
-                Err(err) => {
-                    eprintln!("Could not open file {:?}: {}", load_args.file, err);
-                    std::process::exit(1)
-                }
-            };
+            let server_props = load_args.server_props();
+            let mut libinfo_map = HashMap::new();
+            for profile_filename in &load_args.files {
+                let input_file = match File::open(profile_filename) {
+                    Ok(file) => file,
+                    Err(err) => {
+                        eprintln!("Could not open file {:?}: {}", profile_filename, err);
+                        std::process::exit(1)
+                    }
+                };
 
-            let libinfo_map =
-                match parse_libinfo_map_from_profile_file(input_file, profile_filename) {
-                    Ok(libinfo_map) => libinfo_map,
+                match parse_libinfo_map_and_summary_from_profile_file(input_file, profile_filename)
+                {
+                    Ok((profile_libinfo_map, summary)) => {
+                        if server_props.verbose {
+                            eprintln!("Profile summary for {:?}: {summary}", profile_filename);
+                        }
+                        libinfo_map.extend(profile_libinfo_map);
+                    }
                     Err(err) => {
-                        eprintln!("Could not parse the input file as JSON: {}", err);
+                        eprintln!(
+                            "Could not parse the input file {:?} as JSON: {}",
+                            profile_filename, err
+                        );
                         eprintln!(
                             "If this is a perf.data file, please use `samply import` instead."
                         );
                         std::process::exit(1)
                     }
-                };
-            start_server_main(
-                profile_filename,
-                load_args.server_props(),
+                }
+            }
+            start_server_main_with_profiles(
+                &load_args.files,
+                server_props,
                 load_args.symbol_props(),
                 libinfo_map,
             );
@@ -454,11 +504,15 @@ fn main() {
             convert_file_to_profile(&input_file, &import_args);
             if let Some(server_props) = import_args.server_props() {
                 let profile_filename = &import_args.output;
-                let libinfo_map = profile_json_preparse::parse_libinfo_map_from_profile_file(
-                    File::open(profile_filename).expect("Couldn't open file we just wrote"),
-                    profile_filename,
-                )
-                .expect("Couldn't parse libinfo map from profile file");
+                let (libinfo_map, summary) =
+                    profile_json_preparse::parse_libinfo_map_and_summary_from_profile_file(
+                        File::open(profile_filename).expect("Couldn't open file we just wrote"),
+                        profile_filename,
+                    )
+                    .expect("Couldn't parse libinfo map from profile file");
+                if server_props.verbose {
+                    eprintln!("Profile summary: {summary}");
+                }
                 start_server_main(
                     profile_filename,
                     server_props,
@@ -712,11 +766,34 @@ impl ServerArgs {
             }
         };
 
+        let tls = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert_path), Some(key_path)) => Some(TlsProps {
+                cert_path: cert_path.clone(),
+                key_path: key_path.clone(),
+            }),
+            _ => None,
+        };
+
+        let token = match &self.token {
+            Some(token) => match TokenSelection::try_fixed(token) {
+                Ok(token) => token,
+                Err(e) => {
+                    eprintln!("Invalid token {token:?}: {e}");
+                    std::process::exit(1)
+                }
+            },
+            None if self.no_token => TokenSelection::Disabled,
+            None => TokenSelection::Random,
+        };
+
         ServerProps {
             address,
             port_selection,
             verbose: self.verbose,
             open_in_browser,
+            tls,
+            token,
+            allow_upload_without_token: self.allow_upload_without_token,
         }
     }
 }
@@ -731,6 +808,8 @@ impl SymbolArgs {
             breakpad_symbol_dir: self.breakpad_symbol_dir.clone(),
             breakpad_symbol_cache: self.breakpad_symbol_cache.clone(),
             simpleperf_binary_cache: self.simpleperf_binary_cache.clone(),
+            extra_libs: self.extra_lib.clone(),
+            symbol_load_timeout: Some(self.symbol_load_timeout),
         }
     }
 }
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
@@ -7,6 +8,7 @@ use std::str::FromStr;
 
 use debugid::DebugId;
 use flate2::bufread::GzDecoder;
+use serde::de::{Deserializer, IgnoredAny, SeqAccess, Visitor};
 use serde_derive::Deserialize;
 use wholesym::{CodeId, LibraryInfo};
 
@@ -26,6 +28,82 @@ struct ProfileJsonProcess {
 struct ProfileJsonThread {
     #[serde(default)]
     pub libs: Vec<ProfileJsonLib>,
+    #[serde(default)]
+    pub samples: ProfileJsonTable,
+    #[serde(default)]
+    pub markers: ProfileJsonTable,
+}
+
+/// A samples or markers table. Processed profiles store the row count in
+/// `length`, Gecko profiles store the rows in a `data` array.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct ProfileJsonTable {
+    pub length: Option<usize>,
+    pub data: Option<ElementCount>,
+}
+
+impl ProfileJsonTable {
+    fn len(&self) -> usize {
+        match (self.length, &self.data) {
+            (Some(length), _) => length,
+            (None, Some(ElementCount(count))) => *count,
+            (None, None) => 0,
+        }
+    }
+}
+
+/// The number of elements in a JSON array, without the elements themselves.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+struct ElementCount(usize);
+
+impl<'de> serde::Deserialize<'de> for ElementCount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ElementCountVisitor;
+
+        impl<'de> Visitor<'de> for ElementCountVisitor {
+            type Value = ElementCount;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut count = 0;
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    count += 1;
+                }
+                Ok(ElementCount(count))
+            }
+        }
+
+        deserializer.deserialize_seq(ElementCountVisitor)
+    }
+}
+
+/// Some statistics about a profile, to help diagnose profiles which look
+/// empty in the profiler.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProfileJsonSummary {
+    pub process_count: usize,
+    pub thread_count: usize,
+    pub sample_count: usize,
+    pub marker_count: usize,
+    pub lib_count: usize,
+}
+
+impl fmt::Display for ProfileJsonSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} processes, {} threads, {} samples, {} markers, {} libraries",
+            self.process_count,
+            self.thread_count,
+            self.sample_count,
+            self.marker_count,
+            self.lib_count
+        )
+    }
 }
 
 #[derive(Deserialize, Default, Clone, Debug, PartialEq, Eq)]
@@ -40,10 +118,13 @@ struct ProfileJsonLib {
     pub arch: Option<String>,
 }
 
-pub fn parse_libinfo_map_from_profile_file(
+type LibinfoMap = HashMap<(String, DebugId), LibraryInfo>;
+
+/// Returns the libraries in the profile, and a summary of the profile's contents.
+pub fn parse_libinfo_map_and_summary_from_profile_file(
     file: File,
     filename: &Path,
-) -> Result<HashMap<(String, DebugId), LibraryInfo>, std::io::Error> {
+) -> Result<(LibinfoMap, ProfileJsonSummary), std::io::Error> {
     // Read the profile.json file and parse it as JSON.
     // Build a map (debugName, breakpadID) -> debugPath from the information
     // in profile(\.processes\[\d+\])*(\.threads\[\d+\])?\.libs.
@@ -53,19 +134,26 @@ pub fn parse_libinfo_map_from_profile_file(
     if filename.extension() == Some(&OsString::from("gz")) {
         let decoder = GzDecoder::new(reader);
         let reader = BufReader::new(decoder);
-        parse_libinfo_map_from_profile(reader)
+        parse_libinfo_map_and_summary_from_profile(reader)
     } else {
-        parse_libinfo_map_from_profile(reader)
+        parse_libinfo_map_and_summary_from_profile(reader)
     }
 }
 
-fn parse_libinfo_map_from_profile(
+/// Like [`parse_libinfo_map_and_summary_from_profile_file`], but reads the
+/// uncompressed profile JSON from `reader`.
+pub fn parse_libinfo_map_and_summary_from_profile(
     reader: impl std::io::Read,
-) -> Result<HashMap<(String, DebugId), LibraryInfo>, std::io::Error> {
+) -> Result<(LibinfoMap, ProfileJsonSummary), std::io::Error> {
     let profile: ProfileJsonProcess = serde_json::from_reader(reader)?;
     let mut libinfo_map = HashMap::new();
     add_to_libinfo_map_recursive(&profile, &mut libinfo_map);
-    Ok(libinfo_map)
+    let mut summary = ProfileJsonSummary {
+        lib_count: libinfo_map.len(),
+        ..Default::default()
+    };
+    add_to_summary_recursive(&profile, &mut summary);
+    Ok((libinfo_map, summary))
 }
 
 fn add_libs_to_libinfo_map(
@@ -102,6 +190,7 @@ fn libinfo_map_entry_for_lib(lib: &ProfileJsonLib) -> Option<LibraryInfo> {
         code_id,
         path,
         arch,
+        base_address: None,
     };
     Some(lib_info)
 }
@@ -119,6 +208,18 @@ fn add_to_libinfo_map_recursive(
     }
 }
 
+fn add_to_summary_recursive(profile: &ProfileJsonProcess, summary: &mut ProfileJsonSummary) {
+    summary.process_count += 1;
+    summary.thread_count += profile.threads.len();
+    for thread in &profile.threads {
+        summary.sample_count += thread.samples.len();
+        summary.marker_count += thread.markers.len();
+    }
+    for process in &profile.processes {
+        add_to_summary_recursive(process, summary);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -143,4 +244,37 @@ mod test {
         assert_eq!(p.threads[0].libs[0], ProfileJsonLib::default());
         assert!(p.processes.is_empty());
     }
+
+    #[test]
+    fn profile_json_summary() {
+        // Gecko format: rows are stored in `data` arrays, subprocesses in `processes`.
+        let gecko = r#"{
+            "threads": [{"samples": {"schema": {}, "data": [[1], [2], [3]]}, "markers": {"data": [[1]]}}],
+            "processes": [{"threads": [{"samples": {"data": [[1]]}}, {}]}]
+        }"#;
+        let (_, summary) = parse_libinfo_map_and_summary_from_profile(gecko.as_bytes()).unwrap();
+        assert_eq!(
+            summary,
+            ProfileJsonSummary {
+                process_count: 2,
+                thread_count: 3,
+                sample_count: 4,
+                marker_count: 1,
+                lib_count: 0,
+            }
+        );
+
+        // Processed format: row counts are stored in `length`.
+        let processed = r#"{
+            "libs": [{"debugName": "libc.so.6", "breakpadId": "0123456789ABCDEF0123456789ABCDEF0"}],
+            "threads": [{"samples": {"length": 10, "stack": []}, "markers": {"length": 5}}]
+        }"#;
+        let (libinfo_map, summary) =
+            parse_libinfo_map_and_summary_from_profile(processed.as_bytes()).unwrap();
+        assert_eq!(libinfo_map.len(), 1);
+        assert_eq!(
+            summary.to_string(),
+            "1 processes, 1 threads, 10 samples, 5 markers, 1 libraries"
+        );
+    }
 }
@@ -1,29 +1,39 @@
 use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::net::{IpAddr, SocketAddr};
+use std::future::Future;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::pin::pin;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use futures_util::TryStreamExt;
 use http_body_util::combinators::BoxBody;
-use http_body_util::{BodyExt, Either, StreamBody};
+use http_body_util::{BodyExt, Either, Full, LengthLimitError, Limited, StreamBody};
 use hyper::body::{Bytes, Frame};
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper::{header, Method, Request, Response, StatusCode};
 use hyper_util::rt::TokioIo;
+use hyper_util::server::graceful::GracefulShutdown;
 use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use platform_dirs::AppDirs;
 use rand::RngCore;
 use samply_quota_manager::QuotaManager;
-use tokio::net::TcpListener;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
 use tokio_util::io::ReaderStream;
 use wholesym::debugid::DebugId;
 use wholesym::{LibraryInfo, SymbolManager, SymbolManagerConfig};
 
 use crate::name::SAMPLY_NAME;
+use crate::profile_json_preparse::parse_libinfo_map_and_summary_from_profile;
 use crate::shared;
 use crate::shared::ctrl_c::CtrlC;
 use crate::shared::symbol_manager_observer::SamplySymbolManagerObserver;
@@ -35,6 +45,22 @@ pub struct ServerProps {
     pub port_selection: PortSelection,
     pub verbose: bool,
     pub open_in_browser: bool,
+    /// If set, the server uses HTTPS instead of HTTP.
+    pub tls: Option<TlsProps>,
+    pub token: TokenSelection,
+    /// Whether to accept profile uploads even if the token is disabled. Without
+    /// a token, anyone who can reach the server could replace the profile.
+    pub allow_upload_without_token: bool,
+}
+
+/// The certificate and private key for serving over HTTPS.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "tls"), allow(dead_code))]
+pub struct TlsProps {
+    /// A PEM file with the certificate chain, starting with the server's certificate.
+    pub cert_path: PathBuf,
+    /// A PEM file with the private key for the server's certificate.
+    pub key_path: PathBuf,
 }
 
 #[tokio::main]
@@ -44,11 +70,89 @@ pub async fn start_server_main(
     symbol_props: SymbolProps,
     libinfo_map: HashMap<(String, DebugId), LibraryInfo>,
 ) {
-    start_server(Some(file), props, symbol_props, libinfo_map).await;
+    start_server(&[file.to_owned()], props, symbol_props, libinfo_map).await;
+}
+
+/// Like [`start_server_main`], but serves several profiles from the same server.
+///
+/// Each profile is available at `/{token}/profile/{n}.json`, and the server's
+/// root page links to all of them. `libinfo_map` should contain the libraries
+/// from all profiles.
+#[tokio::main]
+pub async fn start_server_main_with_profiles(
+    files: &[PathBuf],
+    props: ServerProps,
+    symbol_props: SymbolProps,
+    libinfo_map: HashMap<(String, DebugId), LibraryInfo>,
+) {
+    start_server(files, props, symbol_props, libinfo_map).await;
 }
 
 const BAD_CHARS: &AsciiSet = &CONTROLS.add(b':').add(b'/');
 
+/// The maximum size of a profile which can be uploaded with `POST {prefix}/upload`.
+const MAX_UPLOAD_SIZE: usize = 1024 * 1024 * 1024;
+
+/// A profile which was uploaded with `POST {prefix}/upload`. It replaces the
+/// profiles from the command line: it is served at `{prefix}/profile.json` and
+/// `{prefix}/profile/0.json`, and the index page links to it.
+#[derive(Clone, Debug)]
+struct UploadedProfile {
+    /// The request body, as it was uploaded.
+    data: Bytes,
+    /// Whether `data` is gzip-compressed.
+    is_gzipped: bool,
+}
+
+/// The URLs for a profile which was uploaded with `POST {prefix}/upload`.
+#[derive(Clone, Debug)]
+struct UploadUrls {
+    /// The URL at which the uploaded profile is served.
+    profile_url: String,
+    /// The URL which opens the uploaded profile in the profiler.
+    profiler_url: String,
+}
+
+/// The secret token which the server expects as the first path component of
+/// every request URL, except for the index page.
+///
+/// The token makes sure that only someone who knows the URL can access the
+/// profile and the symbols, even if other processes or other machines can
+/// reach the server.
+#[derive(Clone, Debug, Default)]
+pub enum TokenSelection {
+    /// Generate a new random token every time the server starts.
+    #[default]
+    Random,
+    /// Use the given token, for example for automated setups which need to
+    /// know the URL in advance.
+    Fixed(String),
+    /// Don't require a token. Only use this on a trusted network, or behind
+    /// a reverse proxy which does its own access control.
+    Disabled,
+}
+
+impl TokenSelection {
+    /// Creates a [`TokenSelection::Fixed`] if `token` only consists of
+    /// characters which can appear unescaped in a URL path component.
+    pub fn try_fixed(token: &str) -> Result<Self, String> {
+        if token.is_empty() {
+            return Err("The token must not be empty".to_string());
+        }
+        if token == "." || token == ".." {
+            // These would be collapsed as path segments.
+            return Err(format!("The token must not be {token:?}"));
+        }
+        if let Some(c) = token
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && !"-._~".contains(*c))
+        {
+            return Err(format!("The token must not contain the character {c:?}"));
+        }
+        Ok(TokenSelection::Fixed(token.to_string()))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum PortSelection {
     OnePort(u16),
@@ -104,6 +208,10 @@ fn create_symbol_manager_config_and_quota_manager(
         .use_debuginfod(std::env::var("SAMPLY_USE_DEBUGINFOD").is_ok())
         .use_spotlight(true);
 
+    if let Some(timeout) = symbol_props.symbol_load_timeout {
+        config = config.file_load_timeout(timeout);
+    }
+
     let quota_manager = match &symbols_dir {
         Some(symbols_dir) => create_quota_manager(symbols_dir),
         None => None,
@@ -111,6 +219,7 @@ fn create_symbol_manager_config_and_quota_manager(
 
     if let Some(symbols_dir) = symbols_dir {
         config = config.debuginfod_cache_dir_if_not_installed(symbols_dir.join("debuginfod"));
+        config = config.url_cache_dir(symbols_dir.join("url"));
     }
 
     // TODO: Read symbol server config from some kind of config file
@@ -155,45 +264,149 @@ fn create_symbol_manager_config_and_quota_manager(
 }
 
 async fn start_server(
-    profile_filename: Option<&Path>,
+    profile_filenames: &[PathBuf],
+    server_props: ServerProps,
+    symbol_props: SymbolProps,
+    libinfo_map: HashMap<(String, DebugId), LibraryInfo>,
+) {
+    let ctrl_c_receiver = CtrlC::observe_oneshot();
+    let shutdown = async move {
+        let _ = ctrl_c_receiver.await;
+    };
+    let extra_libs = library_infos_for_extra_libs(&symbol_props.extra_libs).await;
+    start_server_with_shutdown(
+        profile_filenames,
+        server_props,
+        symbol_props,
+        libinfo_map,
+        extra_libs,
+        shutdown,
+    )
+    .await;
+}
+
+/// Reads the library information of the binaries passed with `--extra-lib`.
+/// Binaries which can't be read are skipped with a warning.
+async fn library_infos_for_extra_libs(paths: &[PathBuf]) -> Vec<LibraryInfo> {
+    let mut lib_infos = Vec::new();
+    for path in paths {
+        match SymbolManager::library_info_for_binary_at_path(path, None).await {
+            Ok(lib_info) => lib_infos.push(lib_info),
+            Err(e) => eprintln!("Warning: Could not read the extra library {path:?}: {e}"),
+        }
+    }
+    lib_infos
+}
+
+/// Runs the server until `shutdown` completes.
+///
+/// `extra_libs` are registered with the symbol manager in addition to the
+/// libraries in `libinfo_map`. This allows symbolicating addresses in libraries
+/// which aren't listed in the profile, for example dynamically loaded plugins.
+///
+/// Once `shutdown` completes, the server stops accepting new connections and
+/// waits for the requests on existing connections to finish before returning.
+pub async fn start_server_with_shutdown(
+    profile_filenames: &[PathBuf],
     server_props: ServerProps,
     symbol_props: SymbolProps,
     libinfo_map: HashMap<(String, DebugId), LibraryInfo>,
+    extra_libs: Vec<LibraryInfo>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
 ) {
+    let tls_acceptor = match &server_props.tls {
+        Some(tls_props) => match make_tls_acceptor(tls_props) {
+            Ok(tls_acceptor) => Some(tls_acceptor),
+            Err(e) => {
+                eprintln!("Could not set up TLS: {e}");
+                std::process::exit(1)
+            }
+        },
+        None => None,
+    };
     let (listener, addr) = make_listener(server_props.address, server_props.port_selection).await;
 
-    let token = generate_token();
-    let path_prefix = format!("/{token}");
-    let server_origin = format!("http://{addr}");
+    let uploads_allowed = match server_props.token {
+        TokenSelection::Disabled => server_props.allow_upload_without_token,
+        _ => true,
+    };
+    let path_prefix = match server_props.token {
+        TokenSelection::Random => format!("/{}", generate_token()),
+        TokenSelection::Fixed(token) => format!("/{token}"),
+        TokenSelection::Disabled => {
+            eprintln!("Warning: The server does not use a secret path prefix. Anyone who can reach it can access the profile and query symbols.");
+            String::new()
+        }
+    };
+    let scheme = if tls_acceptor.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let server_origin = format!("{scheme}://{}", origin_addr(addr));
     let symbol_server_url = format!("{server_origin}{path_prefix}");
     let mut template_values: HashMap<&'static str, String> = HashMap::new();
     template_values.insert("SERVER_URL", server_origin.clone());
     template_values.insert("PATH_PREFIX", path_prefix.clone());
+    let upload_item = if uploads_allowed {
+        UPLOAD_ITEM.replace("PATH_PREFIX", &path_prefix)
+    } else {
+        String::new()
+    };
+    template_values.insert("UPLOAD_ITEM", upload_item);
 
-    let profiler_url = if profile_filename.is_some() {
-        let profile_url = format!("{symbol_server_url}/profile.json");
-
-        let env_profiler_override = std::env::var("PROFILER_URL").ok();
-        let profiler_origin = match &env_profiler_override {
-            Some(s) => s.trim_end_matches('/'),
-            None => "https://profiler.firefox.com",
-        };
-
-        let encoded_profile_url = utf8_percent_encode(&profile_url, BAD_CHARS).to_string();
+    let env_profiler_override = std::env::var("PROFILER_URL").ok();
+    let profiler_origin = match &env_profiler_override {
+        Some(s) => s.trim_end_matches('/'),
+        None => "https://profiler.firefox.com",
+    };
+    let make_profiler_url = |profile_url: &str| {
+        let encoded_profile_url = utf8_percent_encode(profile_url, BAD_CHARS).to_string();
         let encoded_symbol_server_url =
             utf8_percent_encode(&symbol_server_url, BAD_CHARS).to_string();
-        let profiler_url = format!(
+        format!(
             "{profiler_origin}/from-url/{encoded_profile_url}/?symbolServer={encoded_symbol_server_url}"
-        );
-        template_values.insert("PROFILER_URL", profiler_url.clone());
-        template_values.insert("PROFILE_URL", profile_url);
-        Some(profiler_url)
-    } else {
-        None
+        )
+    };
+
+    // The URLs that should be opened in the browser, or printed if we're not opening the browser.
+    let profiler_urls: Vec<String> = match profile_filenames {
+        [] => vec![],
+        [_] => {
+            let profile_url = format!("{symbol_server_url}/profile.json");
+            let profiler_url = make_profiler_url(&profile_url);
+            template_values.insert("PROFILER_URL", profiler_url.clone());
+            template_values.insert("PROFILE_URL", profile_url);
+            vec![profiler_url]
+        }
+        _ => {
+            let mut profile_list = String::new();
+            let mut profiler_urls = Vec::new();
+            for (index, profile_filename) in profile_filenames.iter().enumerate() {
+                let profile_url = format!("{symbol_server_url}/profile/{index}.json");
+                let profiler_url = make_profiler_url(&profile_url);
+                let name = escape_html(&profile_filename.to_string_lossy());
+                profile_list.push_str(&format!(
+                    "    <li><code>{name}</code>: <a href=\"{profiler_url}\">Open in the profiler UI</a>, <a download href=\"{profile_url}\">Download the raw profile JSON</a></li>\n"
+                ));
+                profiler_urls.push(profiler_url);
+            }
+            template_values.insert("PROFILE_LIST", profile_list);
+            profiler_urls
+        }
     };
 
     let template_values = Arc::new(template_values);
 
+    let upload_urls = uploads_allowed.then(|| {
+        let profile_url = format!("{symbol_server_url}/profile.json");
+        let profiler_url = make_profiler_url(&profile_url);
+        Arc::new(UploadUrls {
+            profile_url,
+            profiler_url,
+        })
+    });
+
     let (config, quota_manager) = create_symbol_manager_config_and_quota_manager(symbol_props);
     let mut symbol_manager = SymbolManager::with_config(config);
     let notifiers = match &quota_manager {
@@ -217,11 +430,11 @@ async fn start_server(
         notifiers,
     ))));
 
-    for lib_info in libinfo_map.into_values() {
+    for lib_info in libinfo_map.into_values().chain(extra_libs) {
         symbol_manager.add_known_library(lib_info);
     }
 
-    if let Some(profile_filename) = profile_filename {
+    for profile_filename in profile_filenames {
         let precog_filename = profile_filename.with_extension("syms.json");
         if let Some(precog_info) =
             shared::symbol_precog::PrecogSymbolInfo::try_load(&precog_filename)
@@ -240,29 +453,48 @@ async fn start_server(
 
     let server = tokio::task::spawn(run_server(
         listener,
+        tls_acceptor,
+        server_props.verbose,
         symbol_manager,
-        profile_filename.map(PathBuf::from),
+        Arc::new(profile_filenames.to_vec()),
         template_values,
         path_prefix,
+        upload_urls,
+        shutdown,
     ));
 
+    if addr.ip().is_unspecified() {
+        eprintln!(
+            "Local server listening on all interfaces at port {}",
+            addr.port()
+        );
+    }
     eprintln!("Local server listening at {server_origin}");
     if !server_props.open_in_browser {
-        if let Some(profiler_url) = &profiler_url {
+        for profiler_url in &profiler_urls {
             println!("{profiler_url}");
         }
     }
     eprintln!("Press Ctrl+C to stop.");
 
     if server_props.open_in_browser {
-        if let Some(profiler_url) = &profiler_url {
-            let _ = opener::open_browser(profiler_url);
+        match profiler_urls.as_slice() {
+            [] => {}
+            [profiler_url] => {
+                let _ = opener::open_browser(profiler_url);
+            }
+            _ => {
+                // Open the index page, which links to all profiles.
+                let _ = opener::open_browser(format!("{server_origin}/"));
+            }
         }
     }
 
     // Run this server until it stops.
-    if let Err(e) = server.await {
-        eprintln!("server error: {e}");
+    match server.await {
+        Ok(Err(e)) => eprintln!("server error: {e}"),
+        Err(e) => eprintln!("server task failed: {e}"),
+        Ok(Ok(())) => {}
     }
 
     if let Some(quota_manager) = quota_manager {
@@ -270,6 +502,17 @@ async fn start_server(
     }
 }
 
+/// Returns the rest of `path` if it starts with the path component(s) in
+/// `prefix`. An empty prefix matches every path.
+fn strip_path_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(prefix)?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
 // Returns a base32 string for 24 random bytes.
 fn generate_token() -> String {
     let mut bytes = [0u8; 24];
@@ -277,12 +520,31 @@ fn generate_token() -> String {
     nix_base32::to_nix_base32(&bytes)
 }
 
+/// Returns the address that the browser should use to reach a server that is
+/// listening on `addr`.
+///
+/// If the server was bound to an unspecified address such as `0.0.0.0` or `::`,
+/// it accepts connections on all interfaces, but the unspecified address itself
+/// is not something one can connect to on all platforms. Use the loopback
+/// address of the same family instead.
+fn origin_addr(addr: SocketAddr) -> SocketAddr {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    SocketAddr::new(ip, addr.port())
+}
+
 async fn make_listener(addr: IpAddr, port_selection: PortSelection) -> (TcpListener, SocketAddr) {
     match port_selection {
         PortSelection::OnePort(port) => {
             let addr = SocketAddr::from((addr, port));
             match TcpListener::bind(&addr).await {
-                Ok(listener) => (listener, addr),
+                Ok(listener) => {
+                    let addr = listener.local_addr().unwrap_or(addr);
+                    (listener, addr)
+                }
                 Err(e) => {
                     eprintln!("Could not bind to port {port}: {e}");
                     std::process::exit(1)
@@ -326,7 +588,27 @@ const TEMPLATE_WITH_PROFILE: &str = r#"
     <li><a download href="PROFILE_URL">Download the raw profile JSON</a></li>
     <li>Obtain symbols by POSTing to <code>PATH_PREFIX/symbolicate/v5</code>, with the format specified by the <a href="https://tecken.readthedocs.io/en/latest/symbolication.html">Mozilla symbolication API documentation</a>.</li>
     <li>Obtain source code by POSTing to <code>PATH_PREFIX/source/v1</code>, with the format specified in this <a href="https://github.com/mstange/profiler-get-symbols/issues/24#issuecomment-989985588">github comment</a>.</li>
-</ul>
+    <li>Obtain disassembled machine code by POSTing to <code>PATH_PREFIX/asm/v1</code>, with a JSON body containing <code>debugName</code>, <code>debugId</code>, <code>startAddress</code> and <code>size</code>.</li>
+UPLOAD_ITEM</ul>
+"#;
+
+const TEMPLATE_WITH_PROFILES: &str = r#"
+<!DOCTYPE html>
+<html lang="en">
+<meta charset="utf-8">
+<title>Profiler Symbol Server</title>
+<body>
+
+<p>This is the profiler symbol server, running at <code>SERVER_URL</code>. It is serving the following profiles:</p>
+<ul>
+PROFILE_LIST</ul>
+
+<p>You can also:</p>
+<ul>
+    <li>Obtain symbols by POSTing to <code>PATH_PREFIX/symbolicate/v5</code>, with the format specified by the <a href="https://tecken.readthedocs.io/en/latest/symbolication.html">Mozilla symbolication API documentation</a>.</li>
+    <li>Obtain source code by POSTing to <code>PATH_PREFIX/source/v1</code>, with the format specified in this <a href="https://github.com/mstange/profiler-get-symbols/issues/24#issuecomment-989985588">github comment</a>.</li>
+    <li>Obtain disassembled machine code by POSTing to <code>PATH_PREFIX/asm/v1</code>, with a JSON body containing <code>debugName</code>, <code>debugId</code>, <code>startAddress</code> and <code>size</code>.</li>
+UPLOAD_ITEM</ul>
 "#;
 
 const TEMPLATE_WITHOUT_PROFILE: &str = r#"
@@ -340,25 +622,54 @@ const TEMPLATE_WITHOUT_PROFILE: &str = r#"
 <ul>
     <li>Obtain symbols by POSTing to <code>PATH_PREFIX/symbolicate/v5</code>, with the format specified by the <a href="https://tecken.readthedocs.io/en/latest/symbolication.html">Mozilla symbolication API documentation</a>.</li>
     <li>Obtain source code by POSTing to <code>PATH_PREFIX/source/v1</code>, with the format specified in this <a href="https://github.com/mstange/profiler-get-symbols/issues/24#issuecomment-989985588">github comment</a>.</li>
-</ul>
+    <li>Obtain disassembled machine code by POSTing to <code>PATH_PREFIX/asm/v1</code>, with a JSON body containing <code>debugName</code>, <code>debugId</code>, <code>startAddress</code> and <code>size</code>.</li>
+UPLOAD_ITEM</ul>
+"#;
+
+/// The index page entry for uploads, which is only shown if uploads are allowed.
+const UPLOAD_ITEM: &str = r#"    <li>Upload a profile by POSTing it, optionally gzip-compressed, to <code>PATH_PREFIX/upload</code>. It then replaces the profiles served by this server.</li>
 "#;
 
+#[allow(clippy::too_many_arguments)]
 async fn run_server(
     listener: TcpListener,
+    tls_acceptor: Option<TlsAcceptor>,
+    verbose: bool,
     symbol_manager: Arc<SymbolManager>,
-    profile_filename: Option<PathBuf>,
+    profile_filenames: Arc<Vec<PathBuf>>,
     template_values: Arc<HashMap<&'static str, String>>,
     path_prefix: String,
+    upload_urls: Option<Arc<UploadUrls>>,
+    shutdown: impl Future<Output = ()>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let mut ctrl_c_receiver = CtrlC::observe_oneshot();
+    let graceful = GracefulShutdown::new();
+    let mut shutdown = pin!(shutdown);
+    let uploaded_profile: Arc<Mutex<Option<UploadedProfile>>> = Arc::default();
+    let (tls_stream_sender, mut tls_stream_receiver) = tokio::sync::mpsc::unbounded_channel();
 
     // We start a loop to continuously accept incoming connections
     loop {
-        let (stream, _) = tokio::select! {
-            stream_and_addr_res = listener.accept() => stream_and_addr_res?,
-            ctrl_c_result = &mut ctrl_c_receiver => {
-                return Ok(ctrl_c_result?);
+        let stream: Box<dyn ServerStream> = tokio::select! {
+            stream_and_addr_res = listener.accept() => {
+                let (stream, _) = stream_and_addr_res?;
+                match &tls_acceptor {
+                    Some(tls_acceptor) => {
+                        // The TLS handshake happens on a separate task, so that a
+                        // slow client doesn't hold up other connections. Once it's
+                        // done, the stream comes back to us through the channel.
+                        spawn_tls_handshake(
+                            tls_acceptor,
+                            stream,
+                            tls_stream_sender.clone(),
+                            verbose,
+                        );
+                        continue;
+                    }
+                    None => Box::new(stream),
+                }
             }
+            Some(tls_stream) = tls_stream_receiver.recv() => tls_stream,
+            () = &mut shutdown => break,
         };
 
         // Use an adapter to access something implementing `tokio::io` traits as if they implement
@@ -366,66 +677,205 @@ async fn run_server(
         let io = TokioIo::new(stream);
 
         let symbol_manager = symbol_manager.clone();
-        let profile_filename = profile_filename.clone();
+        let profile_filenames = profile_filenames.clone();
         let template_values = template_values.clone();
         let path_prefix = path_prefix.clone();
+        let uploaded_profile = uploaded_profile.clone();
+        let upload_urls = upload_urls.clone();
+
+        // Finally, we bind the incoming connection to our service
+        let connection = http1::Builder::new()
+            // `service_fn` converts our function in a `Service`
+            .serve_connection(
+                io,
+                service_fn(move |req| {
+                    symbolication_service(
+                        req,
+                        template_values.clone(),
+                        symbol_manager.clone(),
+                        profile_filenames.clone(),
+                        path_prefix.clone(),
+                        uploaded_profile.clone(),
+                        upload_urls.clone(),
+                    )
+                }),
+            );
+        let connection = graceful.watch(connection);
 
         // Spawn a tokio task to serve multiple connections concurrently
         tokio::task::spawn(async move {
-            // Finally, we bind the incoming connection to our service
-            if let Err(err) = http1::Builder::new()
-                // `service_fn` converts our function in a `Service`
-                .serve_connection(
-                    io,
-                    service_fn(move |req| {
-                        symbolication_service(
-                            req,
-                            template_values.clone(),
-                            symbol_manager.clone(),
-                            profile_filename.clone(),
-                            path_prefix.clone(),
-                        )
-                    }),
-                )
-                .await
-            {
+            if let Err(err) = connection.await {
                 println!("Error serving connection: {:?}", err);
             }
         });
     }
+
+    // Stop accepting new connections, and let the existing connections finish
+    // their in-flight requests.
+    drop(listener);
+    graceful.shutdown().await;
+    Ok(())
+}
+
+/// A connection to a client, either a plain TCP stream or a TLS stream.
+trait ServerStream: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> ServerStream for T {}
+
+#[cfg(feature = "tls")]
+type TlsAcceptor = tokio_rustls::TlsAcceptor;
+
+/// How long a client gets to complete the TLS handshake before we drop the
+/// connection.
+#[cfg(feature = "tls")]
+const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// TLS is not supported without the "tls" feature, so this type has no values.
+#[cfg(not(feature = "tls"))]
+enum TlsAcceptor {}
+
+#[cfg(feature = "tls")]
+fn make_tls_acceptor(tls_props: &TlsProps) -> Result<TlsAcceptor, String> {
+    use tokio_rustls::rustls::crypto::ring::default_provider;
+    use tokio_rustls::rustls::ServerConfig;
+
+    let open = |path: &Path| {
+        std::fs::File::open(path)
+            .map(std::io::BufReader::new)
+            .map_err(|e| format!("Could not open {path:?}: {e}"))
+    };
+    let cert_chain = rustls_pemfile::certs(&mut open(&tls_props.cert_path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Could not read the certificates: {e}"))?;
+    if cert_chain.is_empty() {
+        return Err(format!(
+            "No certificates found in {:?}",
+            tls_props.cert_path
+        ));
+    }
+    let key = rustls_pemfile::private_key(&mut open(&tls_props.key_path)?)
+        .map_err(|e| format!("Could not read the private key: {e}"))?
+        .ok_or_else(|| format!("No private key found in {:?}", tls_props.key_path))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, key)
+        .map_err(|e| e.to_string())?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+#[cfg(not(feature = "tls"))]
+fn make_tls_acceptor(_tls_props: &TlsProps) -> Result<TlsAcceptor, String> {
+    Err("samply was built without TLS support. Rebuild it with the \"tls\" feature.".to_string())
+}
+
+#[cfg(feature = "tls")]
+fn spawn_tls_handshake(
+    tls_acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    tls_stream_sender: UnboundedSender<Box<dyn ServerStream>>,
+    verbose: bool,
+) {
+    let tls_acceptor = tls_acceptor.clone();
+    tokio::task::spawn(async move {
+        let handshake = tls_acceptor.accept(stream);
+        match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, handshake).await {
+            Ok(Ok(tls_stream)) => {
+                // This only fails if the server has stopped.
+                let _ = tls_stream_sender.send(Box::new(tls_stream));
+            }
+            Ok(Err(err)) => {
+                if verbose {
+                    eprintln!("Error during TLS handshake: {err}");
+                }
+            }
+            Err(_) => {
+                if verbose {
+                    eprintln!("TLS handshake timed out");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(feature = "tls"))]
+fn spawn_tls_handshake(
+    tls_acceptor: &TlsAcceptor,
+    _stream: TcpStream,
+    _tls_stream_sender: UnboundedSender<Box<dyn ServerStream>>,
+    _verbose: bool,
+) {
+    match *tls_acceptor {}
 }
 
 async fn symbolication_service(
     req: Request<hyper::body::Incoming>,
     template_values: Arc<HashMap<&'static str, String>>,
     symbol_manager: Arc<SymbolManager>,
-    profile_filename: Option<PathBuf>,
+    profile_filenames: Arc<Vec<PathBuf>>,
     path_prefix: String,
+    uploaded_profile: Arc<Mutex<Option<UploadedProfile>>>,
+    upload_urls: Option<Arc<UploadUrls>>,
 ) -> Result<Response<Either<String, BoxBody<Bytes, std::io::Error>>>, hyper::Error> {
-    let has_profile = profile_filename.is_some();
     let method = req.method();
     let path = req.uri().path();
     let mut response = Response::new(Either::Left(String::new()));
 
-    let Some(path_without_prefix) = path.strip_prefix(&path_prefix) else {
-        // The secret prefix was not part of the URL. Do not send CORS headers.
-        match (method, path) {
-            (&Method::GET, "/") => {
-                response.headers_mut().insert(
-                    header::CONTENT_TYPE,
-                    header::HeaderValue::from_static("text/html"),
-                );
-                let template = match has_profile {
-                    true => TEMPLATE_WITH_PROFILE,
-                    false => TEMPLATE_WITHOUT_PROFILE,
-                };
-                *response.body_mut() =
-                    Either::Left(substitute_template(template, &template_values));
-            }
-            _ => {
-                *response.status_mut() = StatusCode::NOT_FOUND;
-            }
+    // The index page and the monitoring endpoints don't require the secret
+    // prefix. Apart from the index page, they must not expose anything which
+    // would allow deriving it. Do not send CORS headers for these.
+    match (method, path) {
+        (&Method::GET, "/") => {
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("text/html"),
+            );
+            let has_upload = uploaded_profile.lock().unwrap().is_some();
+            let body = match (&upload_urls, has_upload) {
+                (Some(upload_urls), true) => {
+                    let mut template_values = (*template_values).clone();
+                    template_values.insert("PROFILE_URL", upload_urls.profile_url.clone());
+                    template_values.insert("PROFILER_URL", upload_urls.profiler_url.clone());
+                    substitute_template(TEMPLATE_WITH_PROFILE, &template_values)
+                }
+                _ => {
+                    let template = match profile_filenames.len() {
+                        0 => TEMPLATE_WITHOUT_PROFILE,
+                        1 => TEMPLATE_WITH_PROFILE,
+                        _ => TEMPLATE_WITH_PROFILES,
+                    };
+                    substitute_template(template, &template_values)
+                }
+            };
+            *response.body_mut() = Either::Left(body);
+            return Ok(response);
+        }
+        (&Method::GET, "/health") => {
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("text/plain"),
+            );
+            *response.body_mut() = Either::Left("OK".to_string());
+            return Ok(response);
         }
+        (&Method::GET, "/version") => {
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            );
+            let version = serde_json::json!({ "version": env!("CARGO_PKG_VERSION") });
+            *response.body_mut() = Either::Left(version.to_string());
+            return Ok(response);
+        }
+        _ => {}
+    }
+
+    let Some(path_without_prefix) = strip_path_prefix(path, &path_prefix) else {
+        // The secret prefix was not part of the URL. Do not send CORS headers.
+        *response.status_mut() = StatusCode::NOT_FOUND;
         return Ok(response);
     };
 
@@ -438,7 +888,37 @@ async fn symbolication_service(
         header::HeaderValue::from_static("*"),
     );
 
-    match (method, path_without_prefix, profile_filename) {
+    let requested_profile_index = match *method {
+        Method::GET => profile_index_for_path(path_without_prefix),
+        _ => None,
+    };
+    if let Some(index) = requested_profile_index {
+        let uploaded = uploaded_profile.lock().unwrap().clone();
+        if let Some(uploaded) = uploaded {
+            // The uploaded profile replaces all profiles from the command line.
+            if index != 0 {
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                return Ok(response);
+            }
+            if uploaded.is_gzipped {
+                response.headers_mut().insert(
+                    header::CONTENT_ENCODING,
+                    header::HeaderValue::from_static("gzip"),
+                );
+            }
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json; charset=UTF-8"),
+            );
+            let body = Full::new(uploaded.data).map_err(|never| match never {});
+            *response.body_mut() = Either::Right(body.boxed());
+            return Ok(response);
+        }
+    }
+
+    let requested_profile = requested_profile_index.and_then(|index| profile_filenames.get(index));
+
+    match (method, path_without_prefix, requested_profile) {
         (&Method::OPTIONS, _, _) => {
             // https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/OPTIONS
             *response.status_mut() = StatusCode::NO_CONTENT;
@@ -471,7 +951,7 @@ async fn symbolication_service(
                 );
             }
         }
-        (&Method::GET, "/profile.json", Some(profile_filename)) => {
+        (&Method::GET, _, Some(profile_filename)) => {
             if profile_filename.extension() == Some(OsStr::new("gz")) {
                 response.headers_mut().insert(
                     header::CONTENT_ENCODING,
@@ -485,7 +965,7 @@ async fn symbolication_service(
 
             // Stream the file. This follows the send_file example from the hyper repo.
             // https://github.com/hyperium/hyper/blob/7206fe30302937075c51c16a69d1eb3bbce6a671/examples/send_file.rs
-            let file = tokio::fs::File::open(&profile_filename)
+            let file = tokio::fs::File::open(profile_filename)
                 .await
                 .expect("couldn't open profile file");
 
@@ -495,12 +975,40 @@ async fn symbolication_service(
             let stream_body = StreamBody::new(reader_stream.map_ok(Frame::data));
             *response.body_mut() = Either::Right(stream_body.boxed());
         }
+        (&Method::POST, "/upload", _) => match &upload_urls {
+            None => {
+                // Uploads are not allowed without a token, unless the user opted in.
+                *response.status_mut() = StatusCode::FORBIDDEN;
+            }
+            Some(upload_urls) => match read_uploaded_profile(req).await {
+                Ok(uploaded) => {
+                    // Parsing a big profile takes a while, so don't block the runtime.
+                    let symbol_manager = symbol_manager.clone();
+                    let data = uploaded.data.clone();
+                    let is_gzipped = uploaded.is_gzipped;
+                    let _ = tokio::task::spawn_blocking(move || {
+                        add_libs_from_uploaded_profile(&data, is_gzipped, &symbol_manager)
+                    })
+                    .await;
+                    *uploaded_profile.lock().unwrap() = Some(uploaded);
+                    eprintln!(
+                        "Received an uploaded profile. It replaces the previously served profiles:"
+                    );
+                    println!("{}", upload_urls.profiler_url);
+                    *response.status_mut() = StatusCode::NO_CONTENT;
+                }
+                Err(status) => {
+                    *response.status_mut() = status;
+                }
+            },
+        },
         (&Method::POST, path, _) => {
             response.headers_mut().insert(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
             );
             let path = path.to_string();
+            let use_gzip = accepts_gzip(req.headers());
             // Await the full body to be concatenated into a `Collected<Bytes>`.
             let full_body = req.into_body().collect().await?;
             // Convert the `Collected<Bytes>` into a `String`.
@@ -508,7 +1016,23 @@ async fn symbolication_service(
                 String::from_utf8(full_body.to_bytes().to_vec()).expect("invalid utf-8");
             let response_json = symbol_manager.query_json_api(&path, &full_body).await;
 
-            *response.body_mut() = Either::Left(response_json);
+            response.headers_mut().insert(
+                header::VARY,
+                header::HeaderValue::from_static("Accept-Encoding"),
+            );
+            match use_gzip.then(|| gzip_compress(response_json.as_bytes())) {
+                Some(Ok(compressed)) => {
+                    response.headers_mut().insert(
+                        header::CONTENT_ENCODING,
+                        header::HeaderValue::from_static("gzip"),
+                    );
+                    let body = Full::new(Bytes::from(compressed)).map_err(|never| match never {});
+                    *response.body_mut() = Either::Right(body.boxed());
+                }
+                _ => {
+                    *response.body_mut() = Either::Left(response_json);
+                }
+            }
         }
         _ => {
             *response.status_mut() = StatusCode::NOT_FOUND;
@@ -518,6 +1042,115 @@ async fn symbolication_service(
     Ok(response)
 }
 
+/// Reads the body of a `POST {prefix}/upload` request, and rejects it with
+/// `413 Payload Too Large` if it exceeds [`MAX_UPLOAD_SIZE`].
+async fn read_uploaded_profile(
+    req: Request<hyper::body::Incoming>,
+) -> Result<UploadedProfile, StatusCode> {
+    let content_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if content_length.is_some_and(|len| len > MAX_UPLOAD_SIZE as u64) {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    let has_gzip_encoding = req
+        .headers()
+        .get(header::CONTENT_ENCODING)
+        .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"gzip"));
+    let data = match Limited::new(req.into_body(), MAX_UPLOAD_SIZE)
+        .collect()
+        .await
+    {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => return Err(StatusCode::PAYLOAD_TOO_LARGE),
+        Err(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+    if data.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Also detect gzip from the magic bytes, for clients which upload a
+    // .json.gz file without setting Content-Encoding.
+    let is_gzipped = has_gzip_encoding || data.starts_with(&[0x1f, 0x8b]);
+    Ok(UploadedProfile { data, is_gzipped })
+}
+
+/// Registers the libraries listed in an uploaded profile with the symbol
+/// manager, so that symbol requests for these libraries can find their files.
+///
+/// The profile is served as uploaded even if it can't be parsed, in which case
+/// no libraries are registered.
+fn add_libs_from_uploaded_profile(data: &[u8], is_gzipped: bool, symbol_manager: &SymbolManager) {
+    let result = if is_gzipped {
+        parse_libinfo_map_and_summary_from_profile(GzDecoder::new(data))
+    } else {
+        parse_libinfo_map_and_summary_from_profile(data)
+    };
+    if let Ok((libinfo_map, _summary)) = result {
+        for lib_info in libinfo_map.into_values() {
+            symbol_manager.add_known_library(lib_info);
+        }
+    }
+}
+
+/// Returns the index of the profile that should be served for the given
+/// request path.
+///
+/// `/profile.json` refers to the first profile, and `/profile/{n}.json` refers
+/// to the n-th profile, starting at zero.
+fn profile_index_for_path(path: &str) -> Option<usize> {
+    if path == "/profile.json" {
+        return Some(0);
+    }
+    let index = path.strip_prefix("/profile/")?.strip_suffix(".json")?;
+    index.parse::<usize>().ok()
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns whether the Accept-Encoding request header lists gzip as an
+/// acceptable encoding.
+fn accepts_gzip(headers: &header::HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            if !name.eq_ignore_ascii_case("gzip") && name != "*" {
+                return false;
+            }
+            // "gzip;q=0" means that gzip is not acceptable.
+            !parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            })
+        })
+}
+
+fn gzip_compress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
 fn substitute_template(template: &str, template_values: &HashMap<&'static str, String>) -> String {
     let mut s = template.to_string();
     for (key, value) in template_values {
@@ -525,3 +1158,328 @@ fn substitute_template(template: &str, template_values: &HashMap<&'static str, S
     }
     s
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn shuts_down_when_requested() {
+        let props = ServerProps {
+            address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port_selection: PortSelection::OnePort(0),
+            verbose: false,
+            open_in_browser: false,
+            tls: None,
+            token: TokenSelection::Random,
+            allow_upload_without_token: false,
+        };
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server = start_server_with_shutdown(
+            &[],
+            props,
+            SymbolProps::default(),
+            HashMap::new(),
+            Vec::new(),
+            async move {
+                let _ = shutdown_receiver.await;
+            },
+        );
+        shutdown_sender.send(()).unwrap();
+        server.await;
+    }
+
+    #[tokio::test]
+    async fn symbolicates_extra_libs() {
+        let lib_path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/other/example-linux");
+        let extra_libs =
+            library_infos_for_extra_libs(&[lib_path, PathBuf::from("/nonexistent")]).await;
+        assert_eq!(extra_libs.len(), 1);
+        let symbol_manager = SymbolManager::with_config(SymbolManagerConfig::new());
+        for lib_info in extra_libs {
+            symbol_manager.add_known_library(lib_info);
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::task::spawn(run_server(
+            listener,
+            None,
+            false,
+            Arc::new(symbol_manager),
+            Arc::new(vec![]),
+            Arc::new(HashMap::new()),
+            "/token".to_string(),
+            None,
+            async move {
+                let _ = shutdown_receiver.await;
+            },
+        ));
+
+        let body = r#"{"memoryMap":[["example-linux","BE4E976C325246EE9D6B7847A670B2A90"]],"stacks":[[[0,4438]]]}"#;
+        let request = format!(
+            "POST /token/symbolicate/v5 HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let response = send_request(addr, &request).await;
+        assert!(response.contains(r#""function":"main""#), "{response}");
+
+        shutdown_sender.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    async fn send_request(addr: SocketAddr, request: impl AsRef<[u8]>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_ref()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    fn test_upload_urls() -> Arc<UploadUrls> {
+        Arc::new(UploadUrls {
+            profile_url: "http://localhost/token/profile.json".to_string(),
+            profiler_url: "https://profiler.example/from-url/uploaded".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn upload_profile() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::task::spawn(run_server(
+            listener,
+            None,
+            false,
+            Arc::new(SymbolManager::with_config(SymbolManagerConfig::new())),
+            Arc::new(vec![PathBuf::from("a.json"), PathBuf::from("b.json")]),
+            Arc::new(HashMap::new()),
+            "/token".to_string(),
+            Some(test_upload_urls()),
+            async move {
+                let _ = shutdown_receiver.await;
+            },
+        ));
+        let get = |path: &str| {
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        };
+
+        let body = r#"{"meta":{"version":24},"threads":[]}"#;
+        let upload = |path: &str| {
+            format!(
+                "POST {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        };
+
+        // Uploads need the secret prefix.
+        let response = send_request(addr, &upload("/upload")).await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+
+        let response = send_request(addr, &upload("/token/upload")).await;
+        assert!(response.starts_with("HTTP/1.1 204"), "{response}");
+
+        let response = send_request(addr, get("/token/profile.json")).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(!response.contains("content-encoding"), "{response}");
+        assert!(response.ends_with(body), "{response}");
+
+        // The upload replaces all profiles from the command line.
+        let response = send_request(addr, get("/token/profile/0.json")).await;
+        assert!(response.ends_with(body), "{response}");
+        let response = send_request(addr, get("/token/profile/1.json")).await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+        let response = send_request(addr, get("/")).await;
+        assert!(
+            response.contains(&test_upload_urls().profiler_url),
+            "{response}"
+        );
+        assert!(!response.contains("a.json"), "{response}");
+
+        let response = send_request(
+            addr,
+            &format!(
+                "POST /token/upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                MAX_UPLOAD_SIZE + 1
+            ),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+
+        shutdown_sender.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_uploads_if_not_allowed() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::task::spawn(run_server(
+            listener,
+            None,
+            false,
+            Arc::new(SymbolManager::with_config(SymbolManagerConfig::new())),
+            Arc::new(vec![]),
+            Arc::new(HashMap::new()),
+            String::new(),
+            None,
+            async move {
+                let _ = shutdown_receiver.await;
+            },
+        ));
+
+        let body = r#"{"meta":{"version":24},"threads":[]}"#;
+        let response = send_request(
+            addr,
+            format!(
+                "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            ),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+
+        let response = send_request(
+            addr,
+            "GET /profile.json HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+
+        shutdown_sender.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn symbolicates_libs_from_uploaded_profile() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::task::spawn(run_server(
+            listener,
+            None,
+            false,
+            Arc::new(SymbolManager::with_config(SymbolManagerConfig::new())),
+            Arc::new(vec![]),
+            Arc::new(HashMap::new()),
+            "/token".to_string(),
+            Some(test_upload_urls()),
+            async move {
+                let _ = shutdown_receiver.await;
+            },
+        ));
+
+        let symbolicate = || async {
+            let body = r#"{"memoryMap":[["example-linux","BE4E976C325246EE9D6B7847A670B2A90"]],"stacks":[[[0,4438]]]}"#;
+            send_request(
+                addr,
+                format!(
+                    "POST /token/symbolicate/v5 HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                ),
+            )
+            .await
+        };
+
+        // The library isn't known before the upload.
+        let response = symbolicate().await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(!response.contains(r#""function":"main""#), "{response}");
+
+        let lib_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/other/example-linux")
+            .canonicalize()
+            .unwrap();
+        let profile = serde_json::json!({
+            "meta": { "version": 24 },
+            "libs": [{
+                "debugName": "example-linux",
+                "debugPath": lib_path,
+                "name": "example-linux",
+                "path": lib_path,
+                "breakpadId": "BE4E976C325246EE9D6B7847A670B2A90",
+            }],
+            "threads": [],
+        });
+        let body = gzip_compress(profile.to_string().as_bytes()).unwrap();
+        let mut upload = format!(
+            "POST /token/upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        upload.extend_from_slice(&body);
+        let response = send_request(addr, upload).await;
+        assert!(response.starts_with("HTTP/1.1 204"), "{response}");
+
+        let response = symbolicate().await;
+        assert!(response.contains(r#""function":"main""#), "{response}");
+
+        shutdown_sender.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn profile_paths() {
+        assert_eq!(profile_index_for_path("/profile.json"), Some(0));
+        assert_eq!(profile_index_for_path("/profile/0.json"), Some(0));
+        assert_eq!(profile_index_for_path("/profile/1.json"), Some(1));
+        assert_eq!(profile_index_for_path("/profile/x.json"), None);
+        assert_eq!(profile_index_for_path("/profile/1"), None);
+        assert_eq!(profile_index_for_path("/symbolicate/v5"), None);
+    }
+
+    #[test]
+    fn accept_encoding_negotiation() {
+        fn accepts(value: &str) -> bool {
+            let mut headers = header::HeaderMap::new();
+            headers.insert(
+                header::ACCEPT_ENCODING,
+                header::HeaderValue::from_str(value).unwrap(),
+            );
+            accepts_gzip(&headers)
+        }
+        assert!(accepts("gzip"));
+        assert!(accepts("gzip, deflate, br"));
+        assert!(accepts("br;q=1.0, GZIP;q=0.5"));
+        assert!(accepts("*"));
+        assert!(!accepts("deflate, br"));
+        assert!(!accepts("gzip;q=0"));
+        assert!(!accepts("identity"));
+        assert!(!accepts_gzip(&header::HeaderMap::new()));
+    }
+
+    #[test]
+    fn path_prefixes() {
+        assert_eq!(
+            strip_path_prefix("/abc/profile.json", "/abc"),
+            Some("/profile.json")
+        );
+        assert_eq!(strip_path_prefix("/abc", "/abc"), Some(""));
+        assert_eq!(strip_path_prefix("/abcd/profile.json", "/abc"), None);
+        assert_eq!(strip_path_prefix("/profile.json", "/abc"), None);
+        assert_eq!(
+            strip_path_prefix("/profile.json", ""),
+            Some("/profile.json")
+        );
+    }
+
+    #[test]
+    fn fixed_tokens() {
+        assert!(matches!(
+            TokenSelection::try_fixed("my-token_1.0~"),
+            Ok(TokenSelection::Fixed(token)) if token == "my-token_1.0~"
+        ));
+        assert!(TokenSelection::try_fixed("").is_err());
+        assert!(TokenSelection::try_fixed("a/b").is_err());
+        assert!(TokenSelection::try_fixed("a?b").is_err());
+        assert!(TokenSelection::try_fixed(".").is_err());
+        assert!(TokenSelection::try_fixed("..").is_err());
+        assert!(TokenSelection::try_fixed("...").is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 use fxprof_processed_profile::{
     CategoryHandle, Frame, FrameFlags, FrameInfo, MarkerFieldFormat, MarkerFieldSchema,
-    MarkerLocation, MarkerSchema, MarkerTiming, ProcessHandle, Profile, StaticSchemaMarker,
-    StringHandle, ThreadHandle, Timestamp,
+    MarkerLocation, MarkerSchema, MarkerTiming, ProcessHandle, Profile, SourceLocation,
+    StaticSchemaMarker, StringHandle, ThreadHandle, Timestamp,
 };
 
 use crate::shared::context_switch::ThreadContextSwitchData;
@@ -119,6 +119,8 @@ impl Cpus {
             frame: Frame::Label(idle_string),
             category_pair: CategoryHandle::OTHER.into(),
             flags: FrameFlags::empty(),
+            implementation: None,
+            source_location: SourceLocation::default(),
         };
         Self {
             start_time,
@@ -140,7 +142,7 @@ impl Cpus {
     pub fn get_mut(&mut self, cpu: usize, profile: &mut Profile) -> &mut Cpu {
         while self.cpus.len() <= cpu {
             let i = self.cpus.len();
-            let thread = profile.add_thread(self.process_handle, i as u32, self.start_time, false);
+            let thread = profile.add_thread(self.process_handle, i as u64, self.start_time, false);
             let name = format!("CPU {i}");
             profile.set_thread_name(thread, &name);
             self.cpus
@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use fxprof_processed_profile::{CategoryPairHandle, Frame, FrameFlags, FrameInfo};
+use fxprof_processed_profile::{CategoryPairHandle, Frame, FrameFlags, FrameInfo, SourceLocation};
 
 use super::jit_category_manager::{JsFrame, JsName};
 use super::lib_mappings::{AndroidArtInfo, LibMappingsHierarchy};
@@ -206,6 +206,8 @@ impl<I: Iterator<Item = SecondPassFrameInfo>> Iterator for ConvertedStackIter<I>
             frame: location,
             category_pair: category,
             flags: FrameFlags::empty(),
+            implementation: None,
+            source_location: SourceLocation::default(),
         };
 
         // Work around an imperfection in Spidermonkey's stack frames.
@@ -244,6 +246,8 @@ impl<I: Iterator<Item = SecondPassFrameInfo>> Iterator for ConvertedStackIter<I>
                 frame: Frame::Label(js_name),
                 category_pair: category,
                 flags: FrameFlags::IS_JS,
+                implementation: None,
+                source_location: SourceLocation::default(),
             };
             let buffered_frame = std::mem::replace(&mut frame_info, prepended_js_frame);
             self.pending_frame_info = Some(buffered_frame);
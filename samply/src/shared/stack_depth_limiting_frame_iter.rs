@@ -1,5 +1,5 @@
 use fxprof_processed_profile::{
-    CategoryPairHandle, Frame, FrameFlags, FrameInfo, Profile, StringHandle,
+    CategoryPairHandle, Frame, FrameFlags, FrameInfo, Profile, SourceLocation, StringHandle,
 };
 
 /// Returns `Some((start_index, count))` if part of the stack should be elided
@@ -124,6 +124,8 @@ impl<I: Iterator<Item = FrameInfo>> Iterator for StackDepthLimitingFrameIter<I>
                     frame,
                     category_pair: self.category,
                     flags: FrameFlags::empty(),
+                    implementation: None,
+                    source_location: SourceLocation::default(),
                 });
             }
             StackDepthLimitingFrameIterState::NoMoreElision { index } => {
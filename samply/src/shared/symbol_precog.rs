@@ -335,7 +335,7 @@ pub fn presymbolicate(profile: &fxprof_processed_profile::Profile, precog_output
         .use_spotlight(true)
         // .verbose(true)
         .respect_nt_symbol_path(true);
-    let mut symbol_manager = wholesym::SymbolManager::with_config(config);
+    let symbol_manager = wholesym::SymbolManager::with_config(config);
 
     for (lib, rvas) in profile.lib_used_rva_iter() {
         // Add the library to the symbol manager with all the info, so that load_symbol_map can find it later
@@ -345,6 +345,7 @@ pub fn presymbolicate(profile: &fxprof_processed_profile::Profile, precog_output
             debug_path: Some(lib.debug_path.clone()),
             debug_id: Some(lib.debug_id),
             arch: lib.arch.clone(),
+            base_address: None,
             debug_name: Some(lib.debug_name.clone()),
             code_id: lib
                 .code_id
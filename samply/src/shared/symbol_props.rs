@@ -1,6 +1,7 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SymbolProps {
     /// Extra directories containing symbol files
     pub symbol_dir: Vec<PathBuf>,
@@ -16,4 +17,8 @@ pub struct SymbolProps {
     pub breakpad_symbol_cache: Option<PathBuf>,
     /// Extra directory containing symbol files, with the directory structure used by simpleperf's scripts
     pub simpleperf_binary_cache: Option<PathBuf>,
+    /// Binaries which aren't listed in the profile but should be symbolicated
+    pub extra_libs: Vec<PathBuf>,
+    /// Maximum time that loading a single symbol file may take, including downloads
+    pub symbol_load_timeout: Option<Duration>,
 }
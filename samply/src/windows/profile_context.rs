@@ -6,7 +6,7 @@ use fxprof_processed_profile::{
     CategoryColor, CategoryHandle, CounterHandle, CpuDelta, Frame, FrameFlags, FrameInfo,
     LibraryHandle, LibraryInfo, Marker, MarkerFieldFormat, MarkerFieldSchema, MarkerHandle,
     MarkerLocation, MarkerSchema, MarkerTiming, ProcessHandle, Profile, SamplingInterval,
-    StaticSchemaMarker, StringHandle, ThreadHandle, Timestamp,
+    SourceLocation, StaticSchemaMarker, StringHandle, ThreadHandle, Timestamp,
 };
 use shlex::Shlex;
 use wholesym::PeCodeId;
@@ -763,10 +763,10 @@ impl ProfileContext {
 
         let timestamp = self.timestamp_converter.convert_time(timestamp_raw);
         let name = self.make_process_name(&image_file_name, &cmdline);
-        let process_handle = self.profile.add_process(&name, pid, timestamp);
-        let main_thread_handle = self
-            .profile
-            .add_thread(process_handle, pid, timestamp, true);
+        let process_handle = self.profile.add_process(&name, pid.into(), timestamp);
+        let main_thread_handle =
+            self.profile
+                .add_thread(process_handle, pid.into(), timestamp, true);
         let main_thread_label_frame =
             make_thread_label_frame(&mut self.profile, Some(&name), pid, pid);
         let (thread_recycler, jit_function_recycler) = if self.process_recycler.is_some() {
@@ -823,10 +823,10 @@ impl ProfileContext {
                     main_thread_label_frame,
                 )
             } else {
-                let process_handle = self.profile.add_process(&name, pid, timestamp);
+                let process_handle = self.profile.add_process(&name, pid.into(), timestamp);
                 let main_thread_handle =
                     self.profile
-                        .add_thread(process_handle, pid, timestamp, true);
+                        .add_thread(process_handle, pid.into(), timestamp, true);
                 let main_thread_label_frame =
                     make_thread_label_frame(&mut self.profile, Some(&name), pid, pid);
                 (process_handle, main_thread_handle, main_thread_label_frame)
@@ -914,7 +914,7 @@ impl ProfileContext {
             let thread_label_frame =
                 make_thread_label_frame(&mut self.profile, Some(thread_name), pid, tid);
             process.main_thread_label_frame = thread_label_frame.clone();
-            self.profile.set_thread_tid(thread_handle, tid);
+            self.profile.set_thread_tid(thread_handle, tid.into());
             let thread = Thread::new(name, true, thread_handle, thread_label_frame, pid, tid);
             self.threads.add(tid, timestamp_raw, thread);
             self.thread_handles
@@ -929,7 +929,7 @@ impl ProfileContext {
 
         let thread_handle = self
             .profile
-            .add_thread(process.handle, tid, timestamp, false);
+            .add_thread(process.handle, tid.into(), timestamp, false);
         let thread_label_frame =
             make_thread_label_frame(&mut self.profile, name.as_deref(), pid, tid);
         if let Some(name) = name.as_deref() {
@@ -970,7 +970,7 @@ impl ProfileContext {
             let thread_label_frame =
                 make_thread_label_frame(&mut self.profile, Some(thread_name), pid, tid);
             process.main_thread_label_frame = thread_label_frame.clone();
-            self.profile.set_thread_tid(thread_handle, tid);
+            self.profile.set_thread_tid(thread_handle, tid.into());
             let thread = Thread::new(name, true, thread_handle, thread_label_frame, pid, tid);
             self.threads.add(tid, timestamp_raw, thread);
             self.thread_handles
@@ -999,7 +999,7 @@ impl ProfileContext {
 
         let thread_handle = self
             .profile
-            .add_thread(process.handle, tid, timestamp, false);
+            .add_thread(process.handle, tid.into(), timestamp, false);
         let thread_label_frame =
             make_thread_label_frame(&mut self.profile, name.as_deref(), pid, tid);
         if let Some(name) = name.as_deref() {
@@ -2233,6 +2233,8 @@ pub fn make_thread_label_frame(
         frame: Frame::Label(thread_label),
         category_pair: CategoryHandle::OTHER.into(),
         flags: FrameFlags::empty(),
+        implementation: None,
+        source_location: SourceLocation::default(),
     }
 }
 
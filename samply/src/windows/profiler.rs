@@ -193,11 +193,15 @@ pub fn start_recording(
 
     // then fire up the server for the profiler front end, if not save-only
     if let Some(server_props) = server_props {
-        let libinfo_map = crate::profile_json_preparse::parse_libinfo_map_from_profile_file(
-            File::open(&output_file).expect("Couldn't open file we just wrote"),
-            &output_file,
-        )
-        .expect("Couldn't parse libinfo map from profile file");
+        let (libinfo_map, summary) =
+            crate::profile_json_preparse::parse_libinfo_map_and_summary_from_profile_file(
+                File::open(&output_file).expect("Couldn't open file we just wrote"),
+                &output_file,
+            )
+            .expect("Couldn't parse libinfo map from profile file");
+        if server_props.verbose {
+            eprintln!("Profile summary: {summary}");
+        }
 
         start_server_main(&output_file, server_props, symbol_props, libinfo_map);
     }
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use samply_symbols::DemangleOptions;
 use symsrv::{parse_nt_symbol_path, NtSymbolPathEntry};
 
 /// The configuration of a [`SymbolManager`](crate::SymbolManager).
@@ -21,6 +23,12 @@ pub struct SymbolManagerConfig {
     pub(crate) debuginfod_servers: Vec<(String, PathBuf)>,
     pub(crate) extra_symbol_directories: Vec<PathBuf>,
     pub(crate) simpleperf_binary_cache_directories: Vec<PathBuf>,
+    pub(crate) demangle_options: DemangleOptions,
+    pub(crate) symbol_map_cache_capacity: usize,
+    pub(crate) best_effort_function_ranges: bool,
+    pub(crate) source_file_url_templates: Vec<String>,
+    pub(crate) url_cache_dir: Option<PathBuf>,
+    pub(crate) file_load_timeout: Option<Duration>,
 }
 
 impl SymbolManagerConfig {
@@ -184,4 +192,70 @@ impl SymbolManagerConfig {
         self.simpleperf_binary_cache_directories.push(dir.into());
         self
     }
+
+    /// Set which kinds of symbol names are demangled. By default, Rust and C++
+    /// names are demangled, and the hash suffix of Rust names is removed.
+    pub fn demangle_options(mut self, demangle_options: DemangleOptions) -> Self {
+        self.demangle_options = demangle_options;
+        self
+    }
+
+    /// Set how many symbol maps [`SymbolManager::load_symbol_map`](crate::SymbolManager::load_symbol_map)
+    /// keeps in memory, so that repeated requests for the same library don't
+    /// need to load and parse the symbol file again. The least recently used
+    /// symbol map is evicted first.
+    ///
+    /// The default is 0, i.e. no symbol maps are cached.
+    pub fn symbol_map_cache_capacity(mut self, capacity: usize) -> Self {
+        self.symbol_map_cache_capacity = capacity;
+        self
+    }
+
+    /// Whether to synthesize `fun_<address>` symbols from the `.debug_frame`
+    /// unwind info of stripped ELF binaries, which have no symbol table. This
+    /// is off by default.
+    pub fn best_effort_function_ranges(mut self, enabled: bool) -> Self {
+        self.best_effort_function_ranges = enabled;
+        self
+    }
+
+    /// Add a URL template for downloading source files which can't be found on
+    /// this machine, for example on CI-built binaries. In the template, `{path}`
+    /// is replaced with the source file path from the debug info, with backslashes
+    /// replaced by forward slashes and without leading slashes.
+    ///
+    /// Example: `"https://ci.example.com/sources/{path}"`
+    ///
+    /// This method can be called multiple times; the templates will be tried in
+    /// the order of those calls.
+    pub fn source_file_url_template(mut self, url_template: impl Into<String>) -> Self {
+        self.source_file_url_templates.push(url_template.into());
+        self
+    }
+
+    /// Set a directory to cache files in which are downloaded from plain URLs,
+    /// for example source files from a [`source_file_url_template`] server.
+    /// A file downloaded from `https://example.com/dir/file.c` is stored at
+    /// `<dir>/example.com/dir/file.c`, and is not downloaded again while it
+    /// exists.
+    ///
+    /// Without a cache directory, these files are downloaded into memory every
+    /// time they're needed.
+    ///
+    /// [`source_file_url_template`]: SymbolManagerConfig::source_file_url_template
+    pub fn url_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.url_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Set the maximum time that loading a single file may take, including
+    /// any downloads. Loads which take longer fail with
+    /// [`Error::FileLoadTimeout`](crate::Error::FileLoadTimeout), so that a
+    /// stalled download doesn't block symbolication forever.
+    ///
+    /// By default, there is no timeout.
+    pub fn file_load_timeout(mut self, timeout: Duration) -> Self {
+        self.file_load_timeout = Some(timeout);
+        self
+    }
 }
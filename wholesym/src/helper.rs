@@ -8,7 +8,8 @@ use bytes::Bytes;
 use debugid::DebugId;
 use samply_symbols::{
     CandidatePathInfo, CodeId, ElfBuildId, FileAndPathHelper, FileAndPathHelperResult,
-    FileLocation, LibraryInfo, OptionallySendFuture, PeCodeId, SymbolMapTrait,
+    FileLoadTimeoutError, FileLocation, LibraryInfo, OptionallySendFuture, PeCodeId,
+    SourceFilePath, SymbolMapTrait,
 };
 use symsrv::{SymsrvDownloader, SymsrvObserver};
 use uuid::Uuid;
@@ -55,7 +56,7 @@ pub enum WholesymFileLocation {
     BreakpadSymindexFile(String),
     DebuginfodDebugFile(ElfBuildId),
     DebuginfodExecutable(ElfBuildId),
-    UrlForSourceFile(String),
+    Url(String),
     VdsoLoadedIntoThisProcess,
 }
 
@@ -103,7 +104,7 @@ impl FileLocation for WholesymFileLocation {
                     // SECURITY: This URL is referred to by a debug file on the local file system.
                     // We trust the contents of these files, and we allow them to refer to
                     // arbitrary URLs.
-                    return Some(Self::UrlForSourceFile(source_file_path.to_owned()));
+                    return Some(Self::Url(source_file_path.to_owned()));
                 }
                 let source_file_path = Path::new(source_file_path);
                 if source_file_path.is_absolute() {
@@ -127,6 +128,26 @@ impl FileLocation for WholesymFileLocation {
         }
     }
 
+    fn location_for_dsym(&self) -> Option<Self> {
+        // Only look for dSYM bundles right next to local binaries.
+        match self {
+            Self::LocalFile(binary_path) => {
+                let file_name = binary_path.file_name()?;
+                let mut dsym_name = file_name.to_owned();
+                dsym_name.push(".dSYM");
+                Some(Self::LocalFile(
+                    binary_path
+                        .with_file_name(dsym_name)
+                        .join("Contents")
+                        .join("Resources")
+                        .join("DWARF")
+                        .join(file_name),
+                ))
+            }
+            _ => None,
+        }
+    }
+
     fn location_for_breakpad_symindex(&self) -> Option<Self> {
         match self {
             Self::BreakpadSymbolServerFile(rel_path) | Self::LocalBreakpadFile(rel_path) => {
@@ -379,13 +400,35 @@ impl Helper {
                 .get_file_no_download(&rel_path)
                 .await
                 .ok_or("Not found on breakpad symbol server")?,
-            WholesymFileLocation::UrlForSourceFile(url) => {
-                let download = self
-                    .downloader
-                    .initiate_download(&url, Some(self.observer.clone()))
-                    .await?;
-                let bytes = download.download_to_memory(None).await?;
-                return Ok(WholesymFileContents::Bytes(bytes.into()));
+            WholesymFileLocation::Url(url) => {
+                let cache_path = self
+                    .config
+                    .url_cache_dir
+                    .as_deref()
+                    .and_then(|cache_dir| cache_path_for_url(cache_dir, &url));
+                let is_cached = match &cache_path {
+                    Some(cache_path) => self.check_file_exists(cache_path).await,
+                    None => false,
+                };
+                match cache_path {
+                    Some(cache_path) if is_cached => cache_path,
+                    Some(cache_path) => {
+                        let download = self
+                            .downloader
+                            .initiate_download(&url, Some(self.observer.clone()))
+                            .await?;
+                        download.download_to_file(&cache_path, None).await?;
+                        cache_path
+                    }
+                    None => {
+                        let download = self
+                            .downloader
+                            .initiate_download(&url, Some(self.observer.clone()))
+                            .await?;
+                        let bytes = download.download_to_memory(None).await?;
+                        return Ok(WholesymFileContents::Bytes(bytes.into()));
+                    }
+                }
             }
             WholesymFileLocation::SymsrvFile(filename, hash) => {
                 self.symsrv_downloader
@@ -467,6 +510,60 @@ impl Helper {
             info.absorb(known_info);
         }
     }
+
+    /// Returns the locations where a separate debug file for `build_id` might
+    /// be found: in `/usr/lib/debug/.build-id/`, and on a debuginfod server.
+    fn debug_file_locations_for_build_id(
+        &self,
+        build_id: &ElfBuildId,
+    ) -> Vec<WholesymFileLocation> {
+        let mut paths = Vec::new();
+
+        if let Some(rel_path) = build_id.debug_file_path_in_debug_dir() {
+            paths.push(WholesymFileLocation::LocalFile(
+                Path::new("/usr/lib/debug").join(rel_path),
+            ));
+        }
+
+        if self.debuginfod_downloader.is_some() {
+            paths.push(WholesymFileLocation::DebuginfodDebugFile(
+                build_id.to_owned(),
+            ));
+        }
+
+        paths
+    }
+}
+
+/// Returns the path at which the file for `url` is stored in `cache_dir`, for
+/// example `<cache_dir>/example.com/dir/file.c` for `https://example.com/dir/file.c`.
+/// Returns `None` for URLs which don't map to a file path unambiguously, e.g.
+/// because they have a query string.
+fn cache_path_for_url(cache_dir: &Path, url: &str) -> Option<PathBuf> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    if rest.contains(['?', '#']) || rest.ends_with('/') {
+        return None;
+    }
+    let mut path = cache_dir.to_owned();
+    let mut segment_count = 0;
+    for segment in rest.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return None,
+            _ => {}
+        }
+        // Replace characters which aren't allowed in file names on all platforms,
+        // such as the ':' before a port number.
+        path.push(segment.replace(
+            |c: char| !c.is_ascii_alphanumeric() && !"-._~".contains(c),
+            "_",
+        ));
+        segment_count += 1;
+    }
+    // We need at least a host name and a file name.
+    (segment_count >= 2).then_some(path)
 }
 
 impl FileAndPathHelper for Helper {
@@ -484,6 +581,23 @@ impl FileAndPathHelper for Helper {
 
         let mut got_dsym = false;
 
+        // If the binary is available locally, it is tried at the end of this
+        // list, and loading it looks for its debug file by build ID, see
+        // get_candidate_paths_for_debug_file_by_build_id. Only try the build ID
+        // locations here if that won't happen, so that they aren't tried twice.
+        let binary_is_local = info.path.as_ref().is_some_and(|path| {
+            let path = Path::new(path);
+            self.config
+                .redirect_paths
+                .get(path)
+                .map_or(path, PathBuf::as_path)
+                .is_file()
+        });
+        let elf_build_id = match &info.code_id {
+            Some(CodeId::ElfBuildId(build_id)) if !binary_is_local => Some(build_id),
+            _ => None,
+        };
+
         if let (Some(debug_path), Some(debug_name)) = (&info.debug_path, &info.debug_name) {
             if let Some(debug_id) = info.debug_id {
                 // First, see if we can find a dSYM file for the binary.
@@ -562,13 +676,10 @@ impl FileAndPathHelper for Helper {
 
         // Find debuginfo in /usr/lib/debug/.build-id/ etc.
         // <https://sourceware.org/gdb/onlinedocs/gdb/Separate-Debug-Files.html>
-        if let Some(CodeId::ElfBuildId(build_id)) = &info.code_id {
-            let build_id = build_id.to_string();
-            if build_id.len() > 2 {
-                let (two_chars, rest) = build_id.split_at(2);
-                let path = format!("/usr/lib/debug/.build-id/{two_chars}/{rest}.debug");
+        if let Some(build_id) = elf_build_id {
+            if let Some(rel_path) = build_id.debug_file_path_in_debug_dir() {
                 paths.push(CandidatePathInfo::SingleFile(
-                    WholesymFileLocation::LocalFile(PathBuf::from(path)),
+                    WholesymFileLocation::LocalFile(Path::new("/usr/lib/debug").join(rel_path)),
                 ));
             }
         }
@@ -626,8 +737,8 @@ impl FileAndPathHelper for Helper {
         }
 
         if !might_be_fake_jit_file(&info) {
-            if let (Some(_debuginfod_symbol_cache), Some(CodeId::ElfBuildId(build_id))) =
-                (self.debuginfod_downloader.as_ref(), &info.code_id)
+            if let (Some(_debuginfod_symbol_cache), Some(build_id)) =
+                (self.debuginfod_downloader.as_ref(), elf_build_id)
             {
                 paths.push(CandidatePathInfo::SingleFile(
                     WholesymFileLocation::DebuginfodDebugFile(build_id.to_owned()),
@@ -816,12 +927,30 @@ impl FileAndPathHelper for Helper {
         Ok(get_dyld_shared_cache_paths(arch))
     }
 
+    fn location_for_url(&self, url: &str) -> Option<WholesymFileLocation> {
+        // Only plain HTTP(S) downloads are supported; they go through the same
+        // downloader (and observer) as the symbol server downloads.
+        if url.starts_with("https://") || url.starts_with("http://") {
+            Some(WholesymFileLocation::Url(url.to_owned()))
+        } else {
+            None
+        }
+    }
+
     fn load_file(
         &self,
         location: WholesymFileLocation,
     ) -> std::pin::Pin<Box<dyn OptionallySendFuture<Output = FileAndPathHelperResult<Self::F>> + '_>>
     {
-        Box::pin(self.load_file_impl(location))
+        let Some(timeout) = self.config.file_load_timeout else {
+            return Box::pin(self.load_file_impl(location));
+        };
+        Box::pin(async move {
+            match tokio::time::timeout(timeout, self.load_file_impl(location)).await {
+                Ok(result) => result,
+                Err(_) => Err(Box::new(FileLoadTimeoutError { timeout }) as _),
+            }
+        })
     }
 
     fn get_candidate_paths_for_supplementary_debug_file(
@@ -848,22 +977,36 @@ impl FileAndPathHelper for Helper {
             // machine, not on this machine.
         }
 
-        let build_id = sup_file_build_id.to_string();
-        if build_id.len() > 2 {
-            let (two_chars, rest) = build_id.split_at(2);
-            let path = format!("/usr/lib/debug/.build-id/{two_chars}/{rest}.debug");
-            paths.push(WholesymFileLocation::LocalFile(PathBuf::from(path)));
-
-            if self.debuginfod_downloader.is_some() {
-                paths.push(WholesymFileLocation::DebuginfodDebugFile(
-                    sup_file_build_id.to_owned(),
-                ));
-            }
-        }
+        paths.extend(self.debug_file_locations_for_build_id(sup_file_build_id));
 
         Ok(paths)
     }
 
+    fn get_candidate_paths_for_debug_file_by_build_id(
+        &self,
+        _original_file_location: &WholesymFileLocation,
+        build_id: &ElfBuildId,
+    ) -> FileAndPathHelperResult<Vec<WholesymFileLocation>> {
+        Ok(self.debug_file_locations_for_build_id(build_id))
+    }
+
+    fn get_fallback_locations_for_source_file(
+        &self,
+        _debug_file_location: &WholesymFileLocation,
+        source_file_path: &SourceFilePath,
+    ) -> FileAndPathHelperResult<Vec<WholesymFileLocation>> {
+        let path = source_file_path.raw_path().replace('\\', "/");
+        let path = path.trim_start_matches('/');
+        // SECURITY: The URL templates come from the configuration, not from the
+        // debug file, so we only request URLs on servers the user has chosen.
+        Ok(self
+            .config
+            .source_file_url_templates
+            .iter()
+            .map(|template| WholesymFileLocation::Url(template.replace("{path}", path)))
+            .collect())
+    }
+
     fn get_symbol_map_for_library(
         &self,
         info: &LibraryInfo,
@@ -886,39 +1029,43 @@ impl FileAndPathHelper for Helper {
 /// For example, the architecture might have been derived from the mach-O
 /// header of an object that was found in memory (e.g. the dyld images list
 /// of a profiled process).
+///
+/// If the architecture is known, only the caches for that architecture are
+/// returned, so that we don't spend time opening caches which can't contain
+/// the object. If it is unknown, all caches are returned, with the caches for
+/// the architecture of this machine first.
 fn get_dyld_shared_cache_paths(arch: Option<&str>) -> Vec<WholesymFileLocation> {
-    let mut vec = Vec::new();
-
-    let mut add_entries_in_dir = |dir: &str| {
-        let mut add_entry_for_arch = |arch: &str| {
-            let path = format!("{dir}/dyld_shared_cache_{arch}");
-            vec.push(WholesymFileLocation::LocalFile(PathBuf::from(path)));
-        };
-        match arch {
-            None => {
-                // Try all known architectures.
-                add_entry_for_arch("arm64e");
-                add_entry_for_arch("x86_64h");
-                add_entry_for_arch("x86_64");
-            }
-            Some("x86_64") => {
-                // x86_64 binaries can be either in the x86_64 or in the x86_64h cache.
-                add_entry_for_arch("x86_64h");
-                add_entry_for_arch("x86_64");
-            }
-            Some(arch) => {
-                // Use the cache that matches the CPU architecture of the object file.
-                add_entry_for_arch(arch);
-            }
-        }
-    };
-
-    // macOS 13+:
-    add_entries_in_dir("/System/Volumes/Preboot/Cryptexes/OS/System/Library/dyld");
-    // macOS 11 until macOS 13:
-    add_entries_in_dir("/System/Library/dyld");
+    let arches = dyld_shared_cache_arches(arch);
+    let dirs = [
+        // macOS 13+:
+        "/System/Volumes/Preboot/Cryptexes/OS/System/Library/dyld",
+        // macOS 11 until macOS 13:
+        "/System/Library/dyld",
+    ];
+    dirs.iter()
+        .flat_map(|dir| {
+            arches.iter().map(move |arch| {
+                WholesymFileLocation::LocalFile(PathBuf::from(format!(
+                    "{dir}/dyld_shared_cache_{arch}"
+                )))
+            })
+        })
+        .collect()
+}
 
-    vec
+/// The architecture suffixes of the dyld shared caches which can contain an
+/// object of the given architecture, in the order in which they should be tried.
+fn dyld_shared_cache_arches(arch: Option<&str>) -> Vec<&str> {
+    match arch {
+        // x86_64 binaries can be either in the x86_64 or in the x86_64h cache.
+        Some("x86_64") => vec!["x86_64h", "x86_64"],
+        // Use the cache that matches the CPU architecture of the object file.
+        Some(arch) => vec![arch],
+        // Try all known architectures, starting with the native one. On Apple
+        // Silicon, x86_64 processes running under Rosetta use the x86_64 cache.
+        None if cfg!(target_arch = "x86_64") => vec!["x86_64h", "x86_64", "arm64e"],
+        None => vec!["arm64e", "x86_64h", "x86_64"],
+    }
 }
 
 /// Used to filter out files like `jitted-12345-12.so`, to avoid hammering debuginfod servers.
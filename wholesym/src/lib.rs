@@ -149,9 +149,10 @@ pub use config::SymbolManagerConfig;
 pub use download_error::DownloadError;
 pub use samply_symbols;
 pub use samply_symbols::{
-    AddressInfo, CodeId, ElfBuildId, Error, ExternalFileAddressInFileRef, ExternalFileAddressRef,
-    ExternalFileRef, ExternalFileSymbolMap, FrameDebugInfo, FramesLookupResult, LibraryInfo,
-    LookupAddress, MappedPath, MultiArchDisambiguator, PeCodeId, SourceFilePath, SymbolInfo,
+    AddressInfo, CodeId, DemangleOptions, ElfBuildId, Error, ExternalFileAddressInFileRef,
+    ExternalFileAddressRef, ExternalFileRef, ExternalFileSymbolMap, FileLoadTimeoutError,
+    FrameDebugInfo, FramesLookupResult, LibraryInfo, LookupAddress, MappedPath,
+    MultiArchDisambiguator, PeCodeId, SourceFileInfo, SourceFilePath, SymbolFileKind, SymbolInfo,
     SyncAddressInfo,
 };
 pub use symbol_manager::{SymbolFileOrigin, SymbolManager, SymbolMap};
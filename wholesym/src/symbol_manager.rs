@@ -1,11 +1,13 @@
 use std::borrow::Cow;
+use std::ops::Range;
 use std::path::Path;
 use std::sync::Arc;
 
 use debugid::DebugId;
 use samply_symbols::{
     self, AddressInfo, Error, ExternalFileAddressInFileRef, ExternalFileAddressRef, FrameDebugInfo,
-    LibraryInfo, LookupAddress, MultiArchDisambiguator, SymbolMapTrait, SyncAddressInfo,
+    LibraryInfo, LookupAddress, MultiArchDisambiguator, SourceFileInfo, SymbolFileKind,
+    SymbolMapTrait, SyncAddressInfo,
 };
 
 use crate::config::SymbolManagerConfig;
@@ -109,6 +111,11 @@ impl SymbolMap {
         SymbolFileOrigin(self.0.debug_file_location().clone())
     }
 
+    /// The kind of file that the symbol information in this `SymbolMap` was read from.
+    pub fn symbol_file_kind(&self) -> SymbolFileKind {
+        self.0.symbol_file_kind()
+    }
+
     /// The Debug ID of the binary that is described by the symbol information in this `SymbolMap`.
     pub fn debug_id(&self) -> debugid::DebugId {
         self.0.debug_id()
@@ -125,6 +132,43 @@ impl SymbolMap {
     pub fn iter_symbols(&self) -> Box<dyn Iterator<Item = (u32, Cow<'_, str>)> + '_> {
         self.0.iter_symbols()
     }
+
+    /// Iterate over all symbols in this `SymbolMap`, including their sizes.
+    ///
+    /// This iterator yields the relative address, the size in bytes (if known),
+    /// and the name of each symbol.
+    pub fn iter_symbols_with_sizes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (u32, Option<u32>, Cow<'_, str>)> + '_> {
+        self.0.iter_symbols_with_sizes()
+    }
+
+    /// Returns the relative address of the symbol with the given name, which
+    /// can be the raw (possibly mangled) name or the demangled name.
+    ///
+    /// This does a linear scan over all symbols, so it can be slow for large
+    /// libraries.
+    pub fn lookup_symbol_name(&self, name: &str) -> Option<u32> {
+        self.0.lookup_symbol_name(name)
+    }
+
+    /// Returns all names of the function which starts at `symbol_address`, if
+    /// the linker merged multiple identical functions into one ("identical code
+    /// folding"). Returns an empty Vec if only a single name is known.
+    pub fn folded_symbol_names(&self, symbol_address: u32) -> Vec<String> {
+        self.0.folded_symbol_names(symbol_address)
+    }
+
+    /// Returns information about how to obtain the original source code for the
+    /// source file with the raw path `path`. This uses the `srcsrv` stream of
+    /// PDB files, and returns `None` for other formats.
+    pub fn source_file_info(
+        &self,
+        path: &str,
+        extraction_base_path: &str,
+    ) -> Option<SourceFileInfo> {
+        self.0.source_file_info(path, extraction_base_path)
+    }
 }
 
 pub struct ExternalFileSymbolMap(samply_symbols::ExternalFileSymbolMap<WholesymFileContents>);
@@ -153,8 +197,14 @@ pub struct SymbolManager {
 impl SymbolManager {
     /// Create a new `SymbolManager` with the given config.
     pub fn with_config(config: SymbolManagerConfig) -> Self {
+        let demangle_options = config.demangle_options;
+        let symbol_map_cache_capacity = config.symbol_map_cache_capacity;
+        let best_effort_function_ranges = config.best_effort_function_ranges;
         let helper = Helper::with_config(config);
-        let symbol_manager = samply_symbols::SymbolManager::with_helper(helper);
+        let mut symbol_manager = samply_symbols::SymbolManager::with_helper(helper);
+        symbol_manager.set_demangle_options(demangle_options);
+        symbol_manager.set_symbol_map_cache_capacity(symbol_map_cache_capacity);
+        symbol_manager.set_best_effort_function_ranges(best_effort_function_ranges);
         Self { symbol_manager }
     }
 
@@ -236,7 +286,7 @@ impl SymbolManager {
     /// `(debug_name, debug_id)` pairs, so there needs to be some stored auxiliary
     /// information which allows us to find the right debug files for the request.
     /// The list of "known libraries" is this auxiliary information.
-    pub fn add_known_library(&mut self, lib_info: LibraryInfo) {
+    pub fn add_known_library(&self, lib_info: LibraryInfo) {
         self.symbol_manager.helper().add_known_lib(lib_info);
     }
 
@@ -268,6 +318,20 @@ impl SymbolManager {
         Ok(SymbolMap(self.symbol_manager.load_symbol_map(&info).await?))
     }
 
+    /// Symbolicate an address in the address space of a process, given the modules
+    /// of that process and the address ranges they're mapped at.
+    ///
+    /// See [`samply_symbols::SymbolManager::symbolicate_address`] for details.
+    pub async fn symbolicate_address(
+        &self,
+        modules: &[(LibraryInfo, Range<u64>)],
+        absolute_address: u64,
+    ) -> Option<AddressInfo> {
+        self.symbol_manager
+            .symbolicate_address(modules, absolute_address)
+            .await
+    }
+
     /// Manually load and return an external file with additional debug info.
     /// This is a lower-level alternative to [`lookup_external`](SymbolMap::lookup_external)
     /// and can be used if more control over caching is desired.
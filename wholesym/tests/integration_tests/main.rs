@@ -108,6 +108,132 @@ fn exe() {
     assert_eq!(info.arch.as_deref(), Some("x86_64"));
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn debug_file_by_build_id() {
+    // The binary is stripped and has no .gnu_debuglink, so its debug file can
+    // only be found by build ID.
+    let dir = fixtures_dir().join("other").join("build-id");
+    let config = wholesym::SymbolManagerConfig::default().redirect_path_for_testing(
+        "/usr/lib/debug/.build-id/11/e0891e3c873aa0b3bfca68b7069aea02615ae1.debug",
+        dir.join("11e0891e3c873aa0b3bfca68b7069aea02615ae1.debug"),
+    );
+    let symbol_manager = wholesym::SymbolManager::with_config(config);
+    let symbol_map = symbol_manager
+        .load_symbol_map_for_binary_at_path(&dir.join("buildid"), None)
+        .await
+        .unwrap();
+    let address_info = symbol_map
+        .lookup(LookupAddress::Relative(0x113a))
+        .await
+        .unwrap();
+    assert_eq!(address_info.symbol.name, "triple");
+    let frames = address_info.frames.unwrap();
+    assert_eq!(
+        frames[0].file_path.as_ref().unwrap().raw_path(),
+        "./buildid.c"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn stalled_download_times_out() {
+    // A server which accepts connections but never responds.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let server_url = format!("http://{}", listener.local_addr().unwrap());
+    let cache_dir =
+        std::env::temp_dir().join(format!("wholesym-stalled-download-{}", std::process::id()));
+    let timeout = std::time::Duration::from_millis(200);
+    let config = wholesym::SymbolManagerConfig::default()
+        .breakpad_symbols_server(server_url, &cache_dir)
+        .file_load_timeout(timeout);
+    let symbol_manager = wholesym::SymbolManager::with_config(config);
+    let result = symbol_manager
+        .load_symbol_map(
+            "stalled.pdb",
+            DebugId::from_breakpad("AA152DEB2D9B76084C4C44205044422E1").unwrap(),
+        )
+        .await;
+    let _ = std::fs::remove_dir_all(&cache_dir);
+    let errors = match result {
+        Err(wholesym::Error::NoSuccessfulCandidate(errors)) => errors,
+        Err(e) => panic!("Unexpected error: {e}"),
+        Ok(_) => panic!("Loading from a stalled server should fail"),
+    };
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, wholesym::Error::FileLoadTimeout(_, t) if *t == timeout)),
+        "{errors:?}"
+    );
+    drop(listener);
+}
+
+#[cfg(feature = "api")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn source_file_url_is_cached() {
+    let dir = fixtures_dir().join("other").join("compressed-dwarf");
+    let source = std::fs::read_to_string(dir.join("compressed.c")).unwrap();
+
+    // A server which serves the source file for exactly one request.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let server_url = format!("http://{}", listener.local_addr().unwrap());
+    let served_source = source.clone();
+    let server = std::thread::spawn(move || {
+        use std::io::{BufRead, Write};
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = std::io::BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{served_source}",
+            served_source.len()
+        )
+        .unwrap();
+    });
+
+    // Load the binary from a directory without the source file, so that the
+    // source file isn't found next to it.
+    let temp_dir = std::env::temp_dir().join(format!("wholesym-url-cache-{}", std::process::id()));
+    let binary_path = temp_dir.join("compressed-zstd");
+    let cache_dir = temp_dir.join("cache");
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    std::fs::copy(dir.join("compressed-zstd"), &binary_path).unwrap();
+
+    let config = wholesym::SymbolManagerConfig::default()
+        .source_file_url_template(format!("{server_url}/{{path}}"))
+        .url_cache_dir(&cache_dir);
+    let symbol_manager = wholesym::SymbolManager::with_config(config);
+    let info = wholesym::SymbolManager::library_info_for_binary_at_path(&binary_path, None)
+        .await
+        .unwrap();
+    let request = format!(
+        r#"{{"debugName":"{}","debugId":"{}","moduleOffset":"0x113a","file":"./compressed.c"}}"#,
+        info.debug_name.as_deref().unwrap(),
+        info.debug_id.unwrap().breakpad()
+    );
+    symbol_manager.add_known_library(info);
+
+    // The first request downloads the file, the second one finds it in the cache.
+    let mut responses = Vec::new();
+    for _ in 0..2 {
+        responses.push(symbol_manager.query_json_api("/source/v1", &request).await);
+    }
+    let cached_path = cache_dir
+        .join(server_url.trim_start_matches("http://").replace(':', "_"))
+        .join("compressed.c");
+    let cached_source = std::fs::read_to_string(cached_path);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    assert_eq!(cached_source.unwrap(), source);
+    for response in responses {
+        assert!(response.contains(r#""source":"#), "{response}");
+        assert!(response.contains("add_one"), "{response}");
+    }
+    server.join().unwrap();
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn dwz_symbolication() {
     let ls_dir = fixtures_dir().join("other").join("ls-linux");